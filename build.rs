@@ -4,6 +4,7 @@ use std::env;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
+use std::process::Command;
 
 const FIELD_SIZE: usize = 256;
 
@@ -148,6 +149,28 @@ fn write_tables() {
     write_table!(2D => f, mul_table_high, "MUL_TABLE_HIGH", "u8");
 }
 
+// Compiles the kernel of the `cuda` module to PTX, which the driver
+// compiles for the GPU at hand when the module is loaded. `NVCC` sets the
+// path of the compiler.
+fn compile_cuda_kernel() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/cuda.cu");
+    println!("cargo:rerun-if-env-changed=NVCC");
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let nvcc = env::var("NVCC").unwrap_or_else(|_| "nvcc".to_string());
+    let status = Command::new(nvcc)
+        .args(["--ptx", "-O3", "src/cuda.cu", "-o"])
+        .arg(Path::new(&out_dir).join("cuda.ptx"))
+        .status()
+        .expect("cannot run nvcc, set NVCC to its path");
+    assert!(status.success(), "nvcc cannot compile src/cuda.cu");
+}
+
 fn main() {
     write_tables();
+
+    if env::var_os("CARGO_FEATURE_CUDA").is_some() {
+        compile_cuda_kernel();
+    }
 }
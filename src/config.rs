@@ -0,0 +1,101 @@
+//! Configuration of codecs, e.g. to record in metadata files
+//! exactly how a stripe was encoded.
+
+use crate::errors::Error;
+use crate::{galois_16, galois_8, Field, MatrixKind, ReedSolomon};
+
+/// The field a codec operates over.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FieldKind {
+    /// GF(2^8), see `galois_8`.
+    Galois8,
+    /// GF(2^16), see `galois_16`.
+    Galois16,
+}
+
+impl FieldKind {
+    /// Returns the polynomial the field is constructed with,
+    /// see `galois_8::POLYNOMIAL` and `galois_16::POLYNOMIAL`.
+    pub const fn polynomial(self) -> u32 {
+        match self {
+            Self::Galois8 => galois_8::POLYNOMIAL,
+            Self::Galois16 => galois_16::POLYNOMIAL,
+        }
+    }
+}
+
+/// A field with a `FieldKind`, i.e. one of the fields provided by this crate.
+pub trait KnownField: Field {
+    const KIND: FieldKind;
+}
+
+impl KnownField for galois_8::Field {
+    const KIND: FieldKind = FieldKind::Galois8;
+}
+
+impl KnownField for galois_16::Field {
+    const KIND: FieldKind = FieldKind::Galois16;
+}
+
+/// The configuration of a codec, from which an identical codec can be built.
+///
+/// With the `serde` feature enabled, the configuration can be serialized
+/// and deserialized, e.g. to store it alongside the shards.
+///
+/// The inversion cache limit is not part of the configuration, as it
+/// does not affect the encoding.
+///
+/// # Example
+///
+/// ```
+/// use reed_solomon_erasure::galois_8::ReedSolomon;
+/// use reed_solomon_erasure::{FieldKind, MatrixKind};
+///
+/// let r = ReedSolomon::builder(10, 4)
+///     .matrix_kind(MatrixKind::Cauchy)
+///     .build()
+///     .unwrap();
+///
+/// let config = r.config().unwrap();
+/// assert_eq!(FieldKind::Galois8, config.field);
+/// assert_eq!(MatrixKind::Cauchy, config.matrix_kind);
+///
+/// assert_eq!(r, config.build().unwrap());
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReedSolomonConfig {
+    /// The field the codec operates over.
+    pub field: FieldKind,
+    /// The polynomial the field is constructed with,
+    /// checked against `field` when building the codec.
+    pub polynomial: u32,
+    /// The number of data shards.
+    pub data_shards: usize,
+    /// The number of parity shards.
+    pub parity_shards: usize,
+    /// The construction of the encoding matrix, ignored if not `systematic`.
+    pub matrix_kind: MatrixKind,
+    /// Whether the data is stored as is in the data shards.
+    pub systematic: bool,
+}
+
+impl ReedSolomonConfig {
+    /// Builds the codec described by the configuration.
+    ///
+    /// Returns `Error::IncompatibleField` if `field` is not `F`, or if
+    /// `polynomial` is not the polynomial of `field`.
+    ///
+    /// Returns the same errors as `ReedSolomonBuilder::build` otherwise.
+    pub fn build<F: KnownField>(&self) -> Result<ReedSolomon<F>, Error> {
+        if self.field != F::KIND || self.polynomial != self.field.polynomial() {
+            return Err(Error::IncompatibleField);
+        }
+
+        ReedSolomon::builder(self.data_shards, self.parity_shards)
+            .matrix_kind(self.matrix_kind)
+            .systematic(self.systematic)
+            .build()
+    }
+}
@@ -0,0 +1,82 @@
+//! Content-addressed shard IDs over GF(2^8).
+//!
+//! The ID of a shard is the hash of a fingerprint of the codec geometry
+//! followed by the shard bytes, so identical shards of stripes encoded with
+//! the same codec share an ID, while shards of different geometries do not,
+//! even if their bytes happen to be equal. This allows storing shards in
+//! content-addressed backends and checking shards fetched by ID.
+//!
+//! The hash function is provided by the caller via `ContentHash`.
+
+use crate::format;
+use crate::galois_8::ReedSolomon;
+
+/// A hash function deriving IDs from shard contents,
+/// e.g. a wrapper around SHA-256.
+pub trait ContentHash {
+    /// The type of the IDs.
+    type Id: PartialEq;
+
+    /// Hashes the concatenation of `chunks`.
+    fn hash(&self, chunks: &[&[u8]]) -> Self::Id;
+}
+
+/// Returns the fingerprint of the geometry of `codec`, which identifies
+/// the data shard count, the parity shard count and the encoding matrix.
+///
+/// The format is stable across versions: a version byte of 1, the data
+/// shard count and the parity shard count as 32-bit little-endian
+/// integers, then the parity rows of the encoding matrix.
+/// See `format::GEOMETRY_FINGERPRINT_VERSION`.
+pub fn geometry_fingerprint(codec: &ReedSolomon) -> Vec<u8> {
+    let mut fingerprint = vec![format::GEOMETRY_FINGERPRINT_VERSION];
+    fingerprint.extend_from_slice(&(codec.data_shard_count() as u32).to_le_bytes());
+    fingerprint.extend_from_slice(&(codec.parity_shard_count() as u32).to_le_bytes());
+
+    for row in codec.get_parity_rows() {
+        fingerprint.extend_from_slice(row);
+    }
+
+    fingerprint
+}
+
+/// Derives the content-addressed ID of `shard` of a stripe of `codec`.
+///
+/// # Example
+///
+/// ```
+/// use reed_solomon_erasure::content::{self, ContentHash};
+/// use reed_solomon_erasure::galois_8::ReedSolomon;
+///
+/// struct Fnv;
+///
+/// impl ContentHash for Fnv {
+///     type Id = u64;
+///
+///     fn hash(&self, chunks: &[&[u8]]) -> u64 {
+///         chunks.iter().flat_map(|x| x.iter()).fold(0xcbf2_9ce4_8422_2325, |h, &b| {
+///             (h ^ u64::from(b)).wrapping_mul(0x100_0000_01b3)
+///         })
+///     }
+/// }
+///
+/// let r = ReedSolomon::new(3, 2).unwrap();
+/// let id = content::shard_id(&Fnv, &r, &[1, 2, 3]);
+///
+/// assert!(content::verify_shard(&Fnv, &r, &id, &[1, 2, 3]));
+/// assert!(!content::verify_shard(&Fnv, &r, &id, &[1, 2, 4]));
+/// ```
+pub fn shard_id<H: ContentHash>(hasher: &H, codec: &ReedSolomon, shard: &[u8]) -> H::Id {
+    hasher.hash(&[&geometry_fingerprint(codec), shard])
+}
+
+/// Checks that `shard`, e.g. as fetched by `id` from a content-addressed
+/// backend, has the ID `id` for `codec`.
+pub fn verify_shard<H: ContentHash>(
+    hasher: &H,
+    codec: &ReedSolomon,
+    id: &H::Id,
+    shard: &[u8],
+) -> bool {
+    shard_id(hasher, codec, shard) == *id
+}
@@ -1,14 +1,33 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::iter;
+use std::marker::PhantomData;
+use std::ops::{DerefMut, Range};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use smallvec::SmallVec;
 
+use crate::config::{KnownField, ReedSolomonConfig};
+use crate::crc32c::Crc32c;
 use crate::errors::Error;
 use crate::errors::SBSError;
+use crate::executor::{Executor, ScopedThreads};
+use crate::fft::{self, AdditiveFft};
+use crate::format;
 
-use crate::inversion_tree::InversionTree;
+use crate::inversion_tree::{InversionTree, DEFAULT_INDICES_LIMIT};
 use crate::matrix::Matrix;
+use crate::platform::Platform;
+#[cfg(feature = "shadow")]
+use crate::shadow::{self, Shadow};
+#[cfg(feature = "metrics")]
+use crate::stats::{Counters, Stats};
 
 use super::Field;
+use super::RangeFetch;
+use super::RangeStore;
 use super::ReconstructShard;
 
 // /// Parameters for parallelism.
@@ -50,6 +69,12 @@ use super::ReconstructShard;
 /// where encoding packet by packet as you receive them may be more efficient
 /// than waiting for N packets then encode them all at once.
 ///
+/// Data shards which arrive out of order, e.g. from parallel fetches,
+/// can be fed as they come with `encode_data_shard`.
+///
+/// When the data shards are fed in order, `SbsNotStarted` keeps track of
+/// the same state in its types instead, so misuse does not compile.
+///
 /// # Example
 ///
 /// ```
@@ -89,7 +114,31 @@ use super::ReconstructShard;
 #[derive(PartialEq, Debug)]
 pub struct ShardByShard<'a, F: 'a + Field> {
     codec: &'a ReedSolomon<F>,
+    // the lowest index of the data shards not applied to the parity yet
     cur_input: usize,
+    applied: SmallVec<[bool; 32]>,
+    applied_count: usize,
+    digest: Option<Digest<F>>,
+}
+
+/// Checksum of the data shards fed to a `ShardByShard`.
+struct Digest<F: Field> {
+    crc: Crc32c,
+    update: fn(&mut Crc32c, &[F::Elem]),
+}
+
+impl<F: Field> PartialEq for Digest<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.crc == other.crc
+    }
+}
+
+impl<F: Field> std::fmt::Debug for Digest<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Digest")
+            .field("crc", &self.crc)
+            .finish_non_exhaustive()
+    }
 }
 
 impl<'a, F: 'a + Field> ShardByShard<'a, F> {
@@ -98,12 +147,21 @@ impl<'a, F: 'a + Field> ShardByShard<'a, F> {
         ShardByShard {
             codec,
             cur_input: 0,
+            applied: smallvec::smallvec![false; codec.data_shard_count],
+            applied_count: 0,
+            digest: None,
         }
     }
 
     /// Checks if the parity shards are ready to use.
     pub fn parity_ready(&self) -> bool {
-        self.cur_input == self.codec.data_shard_count
+        self.applied_count == self.codec.data_shard_count
+    }
+
+    /// Checks if the data shard indexed by `i_data` was applied to the
+    /// parity shards.
+    pub fn is_applied(&self, i_data: usize) -> bool {
+        self.applied.get(i_data).copied().unwrap_or(false)
     }
 
     /// Resets the bookkeeping data.
@@ -114,11 +172,11 @@ impl<'a, F: 'a + Field> ShardByShard<'a, F> {
     /// Returns `SBSError::LeftoverShards` when there are shards encoded
     /// but parity shards are not ready to use.
     pub fn reset(&mut self) -> Result<(), SBSError> {
-        if self.cur_input > 0 && !self.parity_ready() {
+        if self.applied_count > 0 && !self.parity_ready() {
             return Err(SBSError::LeftoverShards);
         }
 
-        self.cur_input = 0;
+        self.reset_force();
 
         Ok(())
     }
@@ -126,15 +184,62 @@ impl<'a, F: 'a + Field> ShardByShard<'a, F> {
     /// Resets the bookkeeping data without checking.
     pub fn reset_force(&mut self) {
         self.cur_input = 0;
+        for x in &mut self.applied {
+            *x = false;
+        }
+        self.applied_count = 0;
+
+        if let Some(ref mut digest) = self.digest {
+            digest.crc = Crc32c::new();
+        }
     }
 
-    /// Returns the current input shard index.
+    /// Returns the current input shard index, i.e. the lowest index of
+    /// the data shards not applied yet, which `encode` and `encode_sep` use.
     pub fn cur_input_index(&self) -> usize {
         self.cur_input
     }
 
-    fn incre_cur_input(&mut self) {
-        self.cur_input += 1;
+    fn mark_applied(&mut self, i_data: usize) {
+        self.applied[i_data] = true;
+        self.applied_count += 1;
+
+        while self.cur_input < self.applied.len() && self.applied[self.cur_input] {
+            self.cur_input += 1;
+        }
+    }
+
+    // The first data shard applied overwrites the parity shards,
+    // the others are added onto them.
+    fn code_data_shard<U: AsMut<[F::Elem]>>(
+        &mut self,
+        i_data: usize,
+        data: &[F::Elem],
+        parity: &mut [U],
+    ) {
+        let first = self.applied_count == 0;
+        let parity_rows = self.codec.get_parity_rows();
+
+        for (row, output) in parity_rows.iter().zip(parity.iter_mut()) {
+            if first {
+                self.codec.mul_slice(row[i_data], data, output.as_mut());
+            } else {
+                self.codec.mul_slice_add(row[i_data], data, output.as_mut());
+            }
+        }
+
+        let coded = data.len() * parity.len();
+        self.codec
+            .record(data.len(), if first { coded } else { 0 }, coded);
+
+        self.update_digest(data);
+        self.mark_applied(i_data);
+    }
+
+    fn update_digest(&mut self, data: &[F::Elem]) {
+        if let Some(ref mut digest) = self.digest {
+            (digest.update)(&mut digest.crc, data);
+        }
     }
 
     fn sbs_encode_checks<U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
@@ -193,9 +298,9 @@ impl<'a, F: 'a + Field> ShardByShard<'a, F> {
         let shards = shards.as_mut();
         self.sbs_encode_checks(shards)?;
 
-        self.codec.encode_single(self.cur_input, shards).unwrap();
+        let (data, parity) = shards.split_at_mut(self.codec.data_shard_count);
+        self.code_data_shard(self.cur_input, data[self.cur_input].as_ref(), parity);
 
-        self.incre_cur_input();
         Ok(())
     }
 
@@ -210,177 +315,1988 @@ impl<'a, F: 'a + Field> ShardByShard<'a, F> {
     ) -> Result<(), SBSError> {
         self.sbs_encode_sep_checks(data, parity)?;
 
-        self.codec
-            .encode_single_sep(self.cur_input, data[self.cur_input].as_ref(), parity)
-            .unwrap();
+        self.code_data_shard(self.cur_input, data[self.cur_input].as_ref(), parity);
+
+        Ok(())
+    }
+
+    /// Constructs the parity shards partially using the data shard indexed
+    /// by `i_data`, which can be fed in any order, e.g. as parallel fetches
+    /// complete.
+    ///
+    /// Returns `SBSError::TooManyCalls` when all input data shards
+    /// have already been applied.
+    ///
+    /// Returns `SBSError::OutOfOrder` when the bookkeeping struct keeps
+    /// a checksum, see `with_digest`, and `i_data` is not
+    /// `cur_input_index`, as the checksum is of the data shards in order.
+    ///
+    /// Returns `SBSError::RSError(Error::InvalidIndex)` when `i_data` is
+    /// not a data shard index, or the data shard was already applied.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[macro_use] extern crate reed_solomon_erasure;
+    /// # fn main () {
+    /// use reed_solomon_erasure::galois_8::{ReedSolomon, ShardByShard};
+    ///
+    /// let r = ReedSolomon::new(3, 2).unwrap();
+    /// let mut sbs = ShardByShard::new(&r);
+    ///
+    /// let data = shards!([0, 1], [2, 3], [4, 5]);
+    /// let mut parity = shards!([0, 0], [0, 0]);
+    ///
+    /// for &i in &[2, 0, 1] {
+    ///     sbs.encode_data_shard(i, &data[i], &mut parity).unwrap();
+    /// }
+    /// assert!(sbs.parity_ready());
+    ///
+    /// let mut shards = data;
+    /// shards.extend(parity);
+    /// assert!(r.verify(&shards).unwrap());
+    /// # }
+    /// ```
+    pub fn encode_data_shard<U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &mut self,
+        i_data: usize,
+        data: &[F::Elem],
+        parity: &mut [U],
+    ) -> Result<(), SBSError> {
+        if self.parity_ready() {
+            return Err(SBSError::TooManyCalls);
+        }
+        if self.digest.is_some() && i_data != self.cur_input {
+            return Err(SBSError::OutOfOrder);
+        }
+
+        let internal_checks = |sbs: &Self, parity: &mut [U]| {
+            let codec = sbs.codec;
+            check_slice_index!(data => codec, i_data);
+            if sbs.applied[i_data] {
+                return Err(Error::InvalidIndex);
+            }
+            check_piece_count!(parity => codec, parity);
+            check_slices!(multi => parity, single => data, index => i_data);
+
+            Ok(())
+        };
+        internal_checks(self, parity).map_err(SBSError::RSError)?;
+
+        self.code_data_shard(i_data, data, parity);
 
-        self.incre_cur_input();
         Ok(())
     }
 }
 
-/// Reed-Solomon erasure code encoder/decoder.
-///
-/// # Common error handling
-///
-/// ## For `encode`, `encode_shards`, `verify`, `verify_shards`, `reconstruct`, `reconstruct_data`, `reconstruct_shards`, `reconstruct_data_shards`
-///
-/// Return `Error::TooFewShards` or `Error::TooManyShards`
-/// when the number of provided shards
-/// does not match the codec's one.
-///
-/// Return `Error::EmptyShard` when the first shard provided is
-/// of zero length.
-///
-/// Return `Error::IncorrectShardSize` when the provided shards
-/// are of different lengths.
-///
-/// ## For `reconstruct`, `reconstruct_data`, `reconstruct_shards`, `reconstruct_data_shards`
-///
-/// Return `Error::TooFewShardsPresent` when there are not
-/// enough shards for reconstruction.
-///
-/// Return `Error::InvalidShardFlags` when the number of flags does not match
-/// the total number of shards.
-///
-/// # Variants of encoding methods
-///
-/// ## `sep`
-///
-/// Methods ending in `_sep` takes an immutable reference to data shards,
-/// and a mutable reference to parity shards.
-///
-/// They are useful as they do not need to borrow the data shards mutably,
-/// and other work that only needs read-only access to data shards can be done
-/// in parallel/concurrently during the encoding.
-///
-/// Following is a table of all the `sep` variants
-///
-/// | not `sep` | `sep` |
-/// | --- | --- |
-/// | `encode_single` | `encode_single_sep` |
-/// | `encode`        | `encode_sep` |
-///
-/// The `sep` variants do similar checks on the provided data shards and
-/// parity shards.
-///
-/// Return `Error::TooFewDataShards`, `Error::TooManyDataShards`,
-/// `Error::TooFewParityShards`, or `Error::TooManyParityShards` when applicable.
-///
-/// ## `single`
-///
-/// Methods containing `single` facilitate shard by shard encoding, where
-/// the parity shards are partially constructed using one data shard at a time.
-/// See `ShardByShard` struct for more details on how shard by shard encoding
-/// can be useful.
-///
-/// They are prone to **misuse**, and it is recommended to use the `ShardByShard`
-/// bookkeeping struct instead for shard by shard encoding.
-///
-/// The ones that are also `sep` are **ESPECIALLY** prone to **misuse**.
-/// Only use them when you actually need the flexibility.
-///
-/// Following is a table of all the shard by shard variants
-///
-/// | all shards at once | shard by shard |
-/// | --- | --- |
-/// | `encode` | `encode_single` |
-/// | `encode_sep` | `encode_single_sep` |
-///
-/// The `single` variants do similar checks on the provided data shards and parity shards,
-/// and also do index check on `i_data`.
+/// The size of the chunks `ShardByShard::encode_from_reader` reads at a time.
+const SBS_READ_CHUNK_LEN: usize = 64 * 1024;
+
+impl<'a, F: 'a + Field<Elem = u8>> ShardByShard<'a, F> {
+    /// Creates a new instance of the bookkeeping struct which also keeps
+    /// a CRC-32C checksum of the data shards as they are encoded.
+    ///
+    /// This gives the checksum of the data, i.e. the data shards one after
+    /// another, in the same pass as the parity, see `finalize_with_digest`.
+    pub fn with_digest(codec: &'a ReedSolomon<F>) -> Self {
+        Self {
+            codec,
+            cur_input: 0,
+            applied: smallvec::smallvec![false; codec.data_shard_count],
+            applied_count: 0,
+            digest: Some(Digest {
+                crc: Crc32c::new(),
+                update: Crc32c::update,
+            }),
+        }
+    }
+
+    /// Constructs the parity shards partially using the current input data
+    /// shard, reading it from `reader` chunk by chunk, e.g. as it arrives
+    /// over the network, rather than requiring it to be buffered in full.
+    ///
+    /// Exactly as many bytes as the length of the parity shards are read.
+    /// The data shard is not kept, so it has to be stored separately if
+    /// needed, e.g. by wrapping `reader` in an adapter which copies what is read.
+    ///
+    /// Returns an error of kind `io::ErrorKind::InvalidInput` wrapping
+    /// an `SBSError` when `encode_sep` would return that `SBSError`, and
+    /// the errors of `reader` otherwise, e.g. of kind
+    /// `io::ErrorKind::UnexpectedEof` if the data shard is too short.
+    ///
+    /// If `reader` fails, the parity shards are left partially updated,
+    /// so the stripe has to be restarted after `reset_force`.
+    pub fn encode_from_reader<R: io::Read, U: AsRef<[u8]> + AsMut<[u8]>>(
+        &mut self,
+        mut reader: R,
+        parity: &mut [U],
+    ) -> io::Result<()> {
+        if self.parity_ready() {
+            return Err(SBSError::TooManyCalls.into());
+        }
+
+        let codec = self.codec;
+        let internal_checks = |parity: &mut [U]| {
+            check_piece_count!(parity => codec, parity);
+            check_slices!(multi => parity);
+
+            Ok(())
+        };
+        internal_checks(parity).map_err(SBSError::RSError)?;
+
+        let i_data = self.cur_input;
+        let first = self.applied_count == 0;
+        let parity_rows = self.codec.get_parity_rows();
+        let shard_len = parity[0].as_ref().len();
+
+        let mut buffer = vec![0; shard_len.min(SBS_READ_CHUNK_LEN)];
+        let mut offset = 0;
+        while offset < shard_len {
+            let chunk = &mut buffer[..SBS_READ_CHUNK_LEN.min(shard_len - offset)];
+            reader.read_exact(chunk)?;
+
+            for (row, output) in parity_rows.iter().zip(parity.iter_mut()) {
+                let output = &mut output.as_mut()[offset..offset + chunk.len()];
+                if first {
+                    codec.mul_slice(row[i_data], chunk, output);
+                } else {
+                    codec.mul_slice_add(row[i_data], chunk, output);
+                }
+            }
+
+            let coded = chunk.len() * parity.len();
+            self.codec
+                .record(chunk.len(), if first { coded } else { 0 }, coded);
+            self.update_digest(chunk);
+
+            offset += chunk.len();
+        }
+
+        self.mark_applied(i_data);
+        Ok(())
+    }
+
+    /// Returns the CRC-32C checksum of the data shards, and resets the
+    /// bookkeeping data and the checksum for the next stripe.
+    ///
+    /// Returns `SBSError::LeftoverShards` when the parity shards are not
+    /// ready to use, and `SBSError::NoDigest` if the bookkeeping struct
+    /// was not created via `with_digest`.
+    pub fn finalize_with_digest(&mut self) -> Result<u32, SBSError> {
+        let crc = match &self.digest {
+            Some(digest) => digest.crc.value(),
+            None => return Err(SBSError::NoDigest),
+        };
+        if !self.parity_ready() {
+            return Err(SBSError::LeftoverShards);
+        }
+
+        self.reset_force();
+
+        Ok(crc)
+    }
+}
+
+/// A stripe encoded shard by shard in order, before any data shard
+/// was fed, see `SbsNotStarted::feed`.
 ///
-/// Return `Error::InvalidIndex` if `i_data >= data_shard_count`.
+/// This is the first state of a typed counterpart of `ShardByShard`,
+/// whose states consume each other:
 ///
-/// # Encoding behaviour
-/// ## For `encode`
+/// ```text
+/// SbsNotStarted --feed--> SbsFeeding --feed--> ... --feed--> SbsDone
+///       ^                     |                                 |
+///       +-------abort---------+-------------reset---------------+
+/// ```
 ///
-/// You do not need to clear the parity shards beforehand, as the methods
-/// will overwrite them completely.
+/// Each `feed` applies the next data shard, so no data shard can be fed
+/// twice, and `SbsDone` has no `feed`, so the misuses `ShardByShard`
+/// reports as `SBSError::TooManyCalls`, `SBSError::LeftoverShards` and
+/// `SBSError::OutOfOrder` do not compile:
 ///
-/// ## For `encode_single`, `encode_single_sep`
+/// ```compile_fail
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// # fn main () {
+/// use reed_solomon_erasure::galois_8::{ReedSolomon, SbsFed, SbsNotStarted};
 ///
-/// Calling them with `i_data` being `0` will overwrite the parity shards
-/// completely. If you are using the methods correctly, then you do not need
-/// to clear the parity shards beforehand.
+/// let r = ReedSolomon::new(1, 1).unwrap();
+/// let mut parity = shards!([0, 0]);
 ///
-/// # Variants of verifying methods
+/// let done = match SbsNotStarted::new(&r).feed(&[1, 2], &mut parity) {
+///     Ok(SbsFed::Done(done)) => done,
+///     _ => unreachable!(),
+/// };
+/// done.feed(&[3, 4], &mut parity);
+/// # }
+/// ```
 ///
-/// `verify` allocate sa buffer on the heap of the same size
-/// as the parity shards, and encode the input once using the buffer to store
-/// the computed parity shards, then check if the provided parity shards
-/// match the computed ones.
+/// # Example
 ///
-/// `verify_with_buffer`, allows you to provide
-/// the buffer to avoid making heap allocation(s) for the buffer in every call.
+/// ```
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// # fn main () {
+/// use reed_solomon_erasure::galois_8::{ReedSolomon, SbsFed, SbsNotStarted};
 ///
-/// The `with_buffer` variants also guarantee that the buffer contains the correct
-/// parity shards if the result is `Ok(_)` (i.e. it does not matter whether the
-/// verification passed or not, as long as the result is not an error, the buffer
-/// will contain the correct parity shards after the call).
+/// let r = ReedSolomon::new(3, 2).unwrap();
 ///
-/// Following is a table of all the `with_buffer` variants
+/// let data = shards!([0, 1], [2, 3], [4, 5]);
+/// let mut parity = shards!([0, 0], [0, 0]);
 ///
-/// | not `with_buffer` | `with_buffer` |
-/// | --- | --- |
-/// | `verify` | `verify_with_buffer` |
+/// let mut fed = SbsNotStarted::new(&r).feed(&data[0], &mut parity).unwrap();
+/// let done = loop {
+///     match fed {
+///         SbsFed::Feeding(feeding) => {
+///             let i = feeding.next_index();
+///             fed = feeding.feed(&data[i], &mut parity).unwrap();
+///         }
+///         SbsFed::Done(done) => break done,
+///     }
+/// };
 ///
-/// The `with_buffer` variants also check the dimensions of the buffer and return
-/// `Error::TooFewBufferShards`, `Error::TooManyBufferShards`, `Error::EmptyShard`,
-/// or `Error::IncorrectShardSize` when applicable.
+/// let mut shards = data;
+/// shards.extend(parity);
+/// assert!(r.verify(&shards).unwrap());
 ///
-#[derive(Debug)]
-pub struct ReedSolomon<F: Field> {
-    data_shard_count: usize,
-    parity_shard_count: usize,
-    total_shard_count: usize,
-    matrix: Matrix<F>,
-    tree: InversionTree<F>,
+/// // on to the next stripe
+/// let not_started = done.reset();
+/// # }
+/// ```
+#[derive(PartialEq, Debug)]
+pub struct SbsNotStarted<'a, F: 'a + Field>(ShardByShard<'a, F>);
+
+/// A stripe encoded shard by shard in order, with some but not all of
+/// its data shards fed, see `SbsNotStarted`.
+#[derive(PartialEq, Debug)]
+pub struct SbsFeeding<'a, F: 'a + Field>(ShardByShard<'a, F>);
+
+/// A stripe encoded shard by shard in order, with all of its data shards
+/// fed, so its parity shards are ready to use, see `SbsNotStarted`.
+#[derive(PartialEq, Debug)]
+pub struct SbsDone<'a, F: 'a + Field>(ShardByShard<'a, F>);
+
+/// The state of a stripe after a data shard was fed, see `SbsNotStarted`.
+#[derive(PartialEq, Debug)]
+pub enum SbsFed<'a, F: 'a + Field> {
+    /// More data shards are to be fed.
+    Feeding(SbsFeeding<'a, F>),
+    /// All data shards were fed.
+    Done(SbsDone<'a, F>),
 }
 
-impl<F: Field> Clone for ReedSolomon<F> {
-    fn clone(&self) -> Self {
-        Self::new(self.data_shard_count, self.parity_shard_count)
-            .expect("basic checks already passed as precondition of existence of self")
+// Applies the next data shard of `sbs`, leaving it untouched on error.
+fn sbs_feed<'a, F: 'a + Field, U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+    mut sbs: ShardByShard<'a, F>,
+    data: &[F::Elem],
+    parity: &mut [U],
+) -> Result<SbsFed<'a, F>, (ShardByShard<'a, F>, Error)> {
+    let codec = sbs.codec;
+    let internal_checks = |parity: &mut [U]| {
+        check_piece_count!(parity => codec, parity);
+        check_slices!(multi => parity, single => data, index => sbs.cur_input);
+
+        Ok(())
+    };
+    if let Err(e) = internal_checks(parity) {
+        return Err((sbs, e));
     }
+
+    sbs.code_data_shard(sbs.cur_input, data, parity);
+
+    Ok(if sbs.parity_ready() {
+        SbsFed::Done(SbsDone(sbs))
+    } else {
+        SbsFed::Feeding(SbsFeeding(sbs))
+    })
 }
 
-impl<F: Field> PartialEq for ReedSolomon<F> {
-    fn eq(&self, rhs: &Self) -> bool {
-        self.data_shard_count == rhs.data_shard_count
-            && self.parity_shard_count == rhs.parity_shard_count
+impl<'a, F: 'a + Field> SbsNotStarted<'a, F> {
+    pub fn new(codec: &'a ReedSolomon<F>) -> Self {
+        Self(ShardByShard::new(codec))
+    }
+
+    /// Constructs the parity shards partially using the first data shard,
+    /// overwriting them.
+    ///
+    /// Returns `Error::TooFewParityShards` or `Error::TooManyParityShards`
+    /// when the number of parity shards does not match the codec, and
+    /// `Error::EmptyShard` or `Error::IncorrectShardSize` when the shards
+    /// are empty or not all of the same length, along with this state,
+    /// in which case nothing is touched.
+    pub fn feed<U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        self,
+        data: &[F::Elem],
+        parity: &mut [U],
+    ) -> Result<SbsFed<'a, F>, (Self, Error)> {
+        sbs_feed(self.0, data, parity).map_err(|(sbs, e)| (Self(sbs), e))
     }
 }
 
-impl<F: Field> ReedSolomon<F> {
-    // AUDIT
-    //
-    // Error detection responsibilities
-    //
-    // Terminologies and symbols:
-    //   X =A, B, C=> Y: X delegates error checking responsibilities A, B, C to Y
-    //   X:= A, B, C: X needs to handle responsibilities A, B, C
-    //
-    // Encode methods
-    //
-    // `encode_single`:=
-    //   - check index `i_data` within range [0, data shard count)
-    //   - check length of `slices` matches total shard count exactly
-    //   - check consistency of length of individual slices
-    // `encode_single_sep`:=
+impl<'a, F: 'a + Field<Elem = u8>> SbsNotStarted<'a, F> {
+    /// Creates a stripe which also keeps a CRC-32C checksum of the data
+    /// shards as they are fed, see `SbsDone::digest`.
+    pub fn with_digest(codec: &'a ReedSolomon<F>) -> Self {
+        Self(ShardByShard::with_digest(codec))
+    }
+}
+
+impl<'a, F: 'a + Field> SbsFeeding<'a, F> {
+    /// Returns the index of the data shard the next `feed` applies.
+    pub fn next_index(&self) -> usize {
+        self.0.cur_input_index()
+    }
+
+    /// Constructs the parity shards partially using the next data shard,
+    /// see `SbsNotStarted::feed`.
+    pub fn feed<U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        self,
+        data: &[F::Elem],
+        parity: &mut [U],
+    ) -> Result<SbsFed<'a, F>, (Self, Error)> {
+        sbs_feed(self.0, data, parity).map_err(|(sbs, e)| (Self(sbs), e))
+    }
+
+    /// Abandons the stripe, whose parity shards are left partially updated.
+    pub fn abort(mut self) -> SbsNotStarted<'a, F> {
+        self.0.reset_force();
+        SbsNotStarted(self.0)
+    }
+}
+
+impl<'a, F: 'a + Field> SbsDone<'a, F> {
+    /// Returns the CRC-32C checksum of the data shards, or `None` if the
+    /// stripe was not created via `SbsNotStarted::with_digest`.
+    pub fn digest(&self) -> Option<u32> {
+        self.0.digest.as_ref().map(|digest| digest.crc.value())
+    }
+
+    /// Starts the next stripe.
+    pub fn reset(mut self) -> SbsNotStarted<'a, F> {
+        self.0.reset_force();
+        SbsNotStarted(self.0)
+    }
+}
+
+/// Bookkeeper for shard by shard reconstruction.
+///
+/// The shards to reconstruct from are chosen up front, e.g. with
+/// `ReedSolomon::repair_plan`, which fixes the decode matrix. Each shard
+/// can then be pushed as soon as it arrives, in any order, and its
+/// contribution to the reconstructed shards is added right away, so the
+/// reconstruction is finished as soon as the last shard arrives. This
+/// overlaps the latency of fetching the shards with the decoding work.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// # fn main () {
+/// use reed_solomon_erasure::galois_8::{IncrementalDecoder, ReedSolomon};
+///
+/// let r = ReedSolomon::new(2, 2).unwrap();
+///
+/// let mut shards = shards!([0, 1], [2, 3], [0, 0], [0, 0]);
+/// r.encode(&mut shards).unwrap();
+///
+/// // rebuild the first data shard from the second data shard
+/// // and the last parity shard
+/// let mut decoder = IncrementalDecoder::new(&r, &[1, 3], &[0]).unwrap();
+/// let mut outputs = vec![vec![0u8; 2]];
+///
+/// decoder.push(3, &shards[3], &mut outputs).unwrap();
+/// assert!(!decoder.is_done());
+/// decoder.push(1, &shards[1], &mut outputs).unwrap();
+/// assert!(decoder.is_done());
+///
+/// assert_eq!(shards[0], outputs[0]);
+/// # }
+/// ```
+#[derive(PartialEq, Debug)]
+pub struct IncrementalDecoder<'a, F: 'a + Field> {
+    codec: &'a ReedSolomon<F>,
+    sources: SmallVec<[usize; 32]>,
+    targets: SmallVec<[usize; 32]>,
+    /// `coefficients[i][j]` is the coefficient of source `j` in target `i`.
+    coefficients: Vec<SmallVec<[F::Elem; 32]>>,
+    received: SmallVec<[bool; 32]>,
+    received_count: usize,
+}
+
+impl<'a, F: 'a + Field> IncrementalDecoder<'a, F> {
+    /// Creates a new instance of the bookkeeping struct, which reconstructs
+    /// the shards `targets` from the shards `sources`.
+    ///
+    /// Returns `Error::TooFewShardsPresent` or `Error::TooManyShards` if
+    /// `sources` does not contain exactly `data_shard_count` indices.
+    ///
+    /// Returns `Error::InvalidIndex` if an index is not a shard index,
+    /// is repeated, or is both in `sources` and `targets`.
+    pub fn new(
+        codec: &'a ReedSolomon<F>,
+        sources: &[usize],
+        targets: &[usize],
+    ) -> Result<Self, Error> {
+        if sources.len() < codec.data_shard_count {
+            return Err(Error::TooFewShardsPresent);
+        }
+        if sources.len() > codec.data_shard_count {
+            return Err(Error::TooManyShards {
+                max: codec.data_shard_count,
+            });
+        }
+
+        let mut used = vec![false; codec.total_shard_count];
+        for &i in sources.iter().chain(targets) {
+            check_slice_index!(all => codec, i);
+            if used[i] {
+                return Err(Error::InvalidIndex);
+            }
+            used[i] = true;
+        }
+
+        let mut sources: SmallVec<[usize; 32]> = SmallVec::from_slice(sources);
+        sources.sort_unstable();
+        let invalid_indices: SmallVec<[usize; 32]> = (0..codec.total_shard_count)
+            .filter(|i| !sources.contains(i))
+            .collect();
+
+        let data_decode_matrix = codec.get_data_decode_matrix(&sources, &invalid_indices)?;
+
+        let coefficients = targets
+            .iter()
+            .map(|&target| {
+                let row = codec.matrix.get_row(target);
+                (0..codec.data_shard_count)
+                    .map(|j| {
+                        row.iter().enumerate().fold(F::zero(), |acc, (c, &x)| {
+                            F::add(acc, F::mul(x, data_decode_matrix.get(c, j)))
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            codec,
+            received: smallvec::smallvec![false; sources.len()],
+            sources,
+            targets: SmallVec::from_slice(targets),
+            coefficients,
+            received_count: 0,
+        })
+    }
+
+    /// Returns the indices of the shards to reconstruct, in the order
+    /// of the outputs passed to `push`.
+    pub fn targets(&self) -> &[usize] {
+        &self.targets
+    }
+
+    /// Checks if all of the sources were pushed, i.e. the reconstructed
+    /// shards are ready to use.
+    pub fn is_done(&self) -> bool {
+        self.received_count == self.sources.len()
+    }
+
+    /// Resets the bookkeeping data, e.g. to reconstruct the same
+    /// shards of the next stripe.
+    pub fn reset(&mut self) {
+        for x in &mut self.received {
+            *x = false;
+        }
+        self.received_count = 0;
+    }
+
+    /// Adds the contribution of the shard at `index` to the reconstructed
+    /// shards in `outputs`, which are fully overwritten once all of the
+    /// sources were pushed.
+    ///
+    /// Returns `SBSError::TooManyCalls` when all of the sources were
+    /// already pushed.
+    ///
+    /// Returns `SBSError::RSError(Error::InvalidIndex)` when `index` is
+    /// not one of the sources, or was already pushed.
+    ///
+    /// Returns `SBSError::RSError(Error::TooFewBufferShards)` or
+    /// `SBSError::RSError(Error::TooManyBufferShards)` when the number of
+    /// outputs does not match the number of targets.
+    ///
+    /// Returns `SBSError::RSError(Error::EmptyShard)` or
+    /// `SBSError::RSError(Error::IncorrectShardSize)` when the shard is
+    /// empty or its length does not match the length of the outputs.
+    pub fn push<U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &mut self,
+        index: usize,
+        shard: &[F::Elem],
+        outputs: &mut [U],
+    ) -> Result<(), SBSError> {
+        if self.is_done() {
+            return Err(SBSError::TooManyCalls);
+        }
+
+        let internal_checks = |sbs: &Self, outputs: &mut [U]| {
+            let position = sbs
+                .sources
+                .iter()
+                .position(|&i| i == index)
+                .filter(|&j| !sbs.received[j])
+                .ok_or(Error::InvalidIndex)?;
+
+            if outputs.len() < sbs.targets.len() {
+                return Err(Error::TooFewBufferShards);
+            }
+            if outputs.len() > sbs.targets.len() {
+                return Err(Error::TooManyBufferShards);
+            }
+            if shard.is_empty() {
+                return Err(Error::EmptyShard);
+            }
+            for output in outputs.iter() {
+                check_slices!(single => output, single => shard);
+            }
+
+            Ok(position)
+        };
+
+        let position = internal_checks(self, outputs).map_err(SBSError::RSError)?;
+
+        for (coefficients, output) in self.coefficients.iter().zip(outputs.iter_mut()) {
+            if self.received_count == 0 {
+                self.codec
+                    .mul_slice(coefficients[position], shard, output.as_mut());
+            } else {
+                self.codec
+                    .mul_slice_add(coefficients[position], shard, output.as_mut());
+            }
+        }
+
+        let coded = shard.len() * outputs.len();
+        self.codec.record(
+            shard.len(),
+            if self.received_count == 0 { coded } else { 0 },
+            coded,
+        );
+
+        self.received[position] = true;
+        self.received_count += 1;
+
+        Ok(())
+    }
+}
+
+/// Bookkeeping struct to decode a sequence of stripes from shards
+/// arriving in any order, e.g. from several network transports.
+///
+/// Shards are pushed tagged with a fragment index, which is the stripe index
+/// multiplied by the total shard count plus the index of the shard in the stripe.
+/// A stripe is decoded as soon as enough of its shards are present,
+/// and stripes are handed out in order. Shards of stripes which were
+/// already handed out are ignored.
+///
+/// Transports may deliver the same shard more than once, e.g. on retransmits.
+/// Copies identical to the one buffered are ignored. Copies pushed via
+/// `push_verified`, i.e. whose checksum was checked by the caller, are
+/// preferred over copies pushed via `push`. When two unverified copies of a
+/// shard conflict, neither can be trusted, so the shard is treated as missing
+/// unless a verified copy arrives later. When two verified copies conflict,
+/// the first one is kept.
+///
+/// This does no I/O, so it can be driven from a blocking loop as well as
+/// from an async stream adapter of any runtime.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// # use reed_solomon_erasure::*;
+/// # fn main () {
+/// use reed_solomon_erasure::galois_8::{ReedSolomon, StreamDecoder};
+///
+/// let r = ReedSolomon::new(2, 1).unwrap();
+///
+/// let mut stripes = vec![
+///     shards!([0, 1], [2, 3], [0, 0]),
+///     shards!([4, 5], [6, 7], [0, 0]),
+/// ];
+/// for stripe in stripes.iter_mut() {
+///     r.encode(stripe).unwrap();
+/// }
+///
+/// let mut decoder = StreamDecoder::new(&r);
+///
+/// // the second stripe arrives first
+/// decoder.push(4, stripes[1][1].clone()).unwrap();
+/// decoder.push(5, stripes[1][2].clone()).unwrap();
+/// assert_eq!(None, decoder.next_block());
+///
+/// // the first stripe is missing its first data shard
+/// decoder.push(1, stripes[0][1].clone()).unwrap();
+/// decoder.push(2, stripes[0][2].clone()).unwrap();
+///
+/// assert_eq!(Some(vec![0, 1, 2, 3]), decoder.next_block());
+/// assert_eq!(Some(vec![4, 5, 6, 7]), decoder.next_block());
+/// assert_eq!(None, decoder.next_block());
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct StreamDecoder<'a, F: 'a + Field> {
+    codec: &'a ReedSolomon<F>,
+    pending: BTreeMap<usize, Vec<ShardCopy<F::Elem>>>,
+    next_stripe: usize,
+}
+
+/// The copy of a shard buffered by `StreamDecoder`.
+#[derive(PartialEq, Debug, Clone)]
+enum ShardCopy<E> {
+    Missing,
+    Unverified(Vec<E>),
+    Verified(Vec<E>),
+    /// Two unverified copies conflicted, only a verified copy is accepted.
+    Conflicting,
+}
+
+impl<E> ShardCopy<E> {
+    const fn as_shard(&self) -> Option<&Vec<E>> {
+        match self {
+            Self::Unverified(shard) | Self::Verified(shard) => Some(shard),
+            Self::Missing | Self::Conflicting => None,
+        }
+    }
+
+    fn into_shard(self) -> Option<Vec<E>> {
+        match self {
+            Self::Unverified(shard) | Self::Verified(shard) => Some(shard),
+            Self::Missing | Self::Conflicting => None,
+        }
+    }
+}
+
+impl<'a, F: 'a + Field> StreamDecoder<'a, F> {
+    /// Creates a new instance of the bookkeeping struct.
+    pub fn new(codec: &'a ReedSolomon<F>) -> Self {
+        Self {
+            codec,
+            pending: BTreeMap::new(),
+            next_stripe: 0,
+        }
+    }
+
+    /// Buffers a shard until its stripe can be decoded.
+    ///
+    /// Returns `Error::EmptyShard` if the shard is of zero length.
+    ///
+    /// Returns `Error::IncorrectShardSize` if the shard is not of the same
+    /// length as the shards already buffered for its stripe.
+    pub fn push(&mut self, fragment_index: usize, shard: Vec<F::Elem>) -> Result<(), Error> {
+        self.push_copy(fragment_index, shard, false)
+    }
+
+    /// Buffers a shard whose checksum was checked by the caller until its
+    /// stripe can be decoded, preferring it over unverified copies.
+    ///
+    /// Returns the same errors as `push`.
+    pub fn push_verified(
+        &mut self,
+        fragment_index: usize,
+        shard: Vec<F::Elem>,
+    ) -> Result<(), Error> {
+        self.push_copy(fragment_index, shard, true)
+    }
+
+    fn push_copy(
+        &mut self,
+        fragment_index: usize,
+        shard: Vec<F::Elem>,
+        verified: bool,
+    ) -> Result<(), Error> {
+        let total_shard_count = self.codec.total_shard_count;
+        let stripe = fragment_index / total_shard_count;
+
+        if shard.is_empty() {
+            return Err(Error::EmptyShard);
+        }
+
+        // the stripe was already handed out
+        if stripe < self.next_stripe {
+            return Ok(());
+        }
+
+        let shards = self
+            .pending
+            .entry(stripe)
+            .or_insert_with(|| vec![ShardCopy::Missing; total_shard_count]);
+
+        if let Some(present) = shards.iter().find_map(ShardCopy::as_shard) {
+            if present.len() != shard.len() {
+                return Err(Error::IncorrectShardSize {
+                    shard: fragment_index % total_shard_count,
+                    expected: present.len(),
+                    actual: shard.len(),
+                });
+            }
+        }
+
+        let copy = &mut shards[fragment_index % total_shard_count];
+        let new_copy = match (&*copy, verified) {
+            (ShardCopy::Missing, false) => ShardCopy::Unverified(shard),
+            (ShardCopy::Missing | ShardCopy::Unverified(_) | ShardCopy::Conflicting, true) => {
+                ShardCopy::Verified(shard)
+            }
+            (ShardCopy::Unverified(present), false) if *present != shard => ShardCopy::Conflicting,
+            // identical copies, copies conflicting with a verified one,
+            // and unverified copies of conflicting ones are ignored
+            _ => return Ok(()),
+        };
+        *copy = new_copy;
+
+        Ok(())
+    }
+
+    /// Returns the data of the next stripe, with the data shards concatenated,
+    /// or `None` if not enough of its shards were pushed yet.
+    pub fn next_block(&mut self) -> Option<Vec<F::Elem>> {
+        let data_shard_count = self.codec.data_shard_count;
+
+        match self.pending.get(&self.next_stripe) {
+            Some(shards)
+                if shards.iter().filter_map(ShardCopy::as_shard).count() >= data_shard_count => {}
+            _ => return None,
+        }
+
+        let mut shards: Vec<_> = self
+            .pending
+            .remove(&self.next_stripe)
+            .unwrap()
+            .into_iter()
+            .map(ShardCopy::into_shard)
+            .collect();
+        self.next_stripe += 1;
+
+        let shard_len = shards.iter().flatten().next().unwrap().len();
+        let mut data = vec![vec![F::zero(); shard_len]; data_shard_count];
+        self.codec
+            .decode_data(&mut shards, &mut data)
+            .expect("enough shards of equal length present; qed");
+
+        Some(data.concat())
+    }
+
+    /// Returns the index of the next stripe to be handed out.
+    pub const fn next_stripe_index(&self) -> usize {
+        self.next_stripe
+    }
+
+    /// Returns the number of stripes with shards buffered.
+    ///
+    /// A non-zero count once the input is exhausted means
+    /// some stripes could not be decoded.
+    pub fn pending_stripe_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Reed-Solomon erasure code encoder/decoder.
+///
+/// # Common error handling
+///
+/// ## For `encode`, `encode_shards`, `verify`, `verify_shards`, `reconstruct`, `reconstruct_data`, `reconstruct_shards`, `reconstruct_data_shards`
+///
+/// Return `Error::TooFewShards` or `Error::TooManyShards`
+/// when the number of provided shards
+/// does not match the codec's one.
+///
+/// Return `Error::EmptyShard` when the first shard provided is
+/// of zero length.
+///
+/// Return `Error::IncorrectShardSize` when the provided shards
+/// are of different lengths.
+///
+/// ## For `reconstruct`, `reconstruct_data`, `reconstruct_shards`, `reconstruct_data_shards`
+///
+/// Return `Error::TooFewShardsPresent` when there are not
+/// enough shards for reconstruction.
+///
+/// Return `Error::InvalidShardFlags` when the number of flags does not match
+/// the total number of shards.
+///
+/// # Variants of encoding methods
+///
+/// ## `sep`
+///
+/// Methods ending in `_sep` takes an immutable reference to data shards,
+/// and a mutable reference to parity shards.
+///
+/// They are useful as they do not need to borrow the data shards mutably,
+/// and other work that only needs read-only access to data shards can be done
+/// in parallel/concurrently during the encoding.
+///
+/// Following is a table of all the `sep` variants
+///
+/// | not `sep` | `sep` |
+/// | --- | --- |
+/// | `encode_single` | `encode_single_sep` |
+/// | `encode`        | `encode_sep` |
+///
+/// The `sep` variants do similar checks on the provided data shards and
+/// parity shards.
+///
+/// Return `Error::TooFewDataShards`, `Error::TooManyDataShards`,
+/// `Error::TooFewParityShards`, or `Error::TooManyParityShards` when applicable.
+///
+/// ## `single`
+///
+/// Methods containing `single` facilitate shard by shard encoding, where
+/// the parity shards are partially constructed using one data shard at a time.
+/// See `ShardByShard` struct for more details on how shard by shard encoding
+/// can be useful.
+///
+/// They are prone to **misuse**, and it is recommended to use the `ShardByShard`
+/// bookkeeping struct instead for shard by shard encoding.
+///
+/// The ones that are also `sep` are **ESPECIALLY** prone to **misuse**.
+/// Only use them when you actually need the flexibility.
+///
+/// Following is a table of all the shard by shard variants
+///
+/// | all shards at once | shard by shard |
+/// | --- | --- |
+/// | `encode` | `encode_single` |
+/// | `encode_sep` | `encode_single_sep` |
+///
+/// The `single` variants do similar checks on the provided data shards and parity shards,
+/// and also do index check on `i_data`.
+///
+/// Return `Error::InvalidIndex` if `i_data >= data_shard_count`.
+///
+/// # Encoding behaviour
+/// ## For `encode`
+///
+/// You do not need to clear the parity shards beforehand, as the methods
+/// will overwrite them completely.
+///
+/// ## For `encode_single`, `encode_single_sep`
+///
+/// Calling them with `i_data` being `0` will overwrite the parity shards
+/// completely. If you are using the methods correctly, then you do not need
+/// to clear the parity shards beforehand.
+///
+/// # Variants of verifying methods
+///
+/// `verify` allocate sa buffer on the heap of the same size
+/// as the parity shards, and encode the input once using the buffer to store
+/// the computed parity shards, then check if the provided parity shards
+/// match the computed ones.
+///
+/// `verify_with_buffer`, allows you to provide
+/// the buffer to avoid making heap allocation(s) for the buffer in every call.
+///
+/// The `with_buffer` variants also guarantee that the buffer contains the correct
+/// parity shards if the result is `Ok(_)` (i.e. it does not matter whether the
+/// verification passed or not, as long as the result is not an error, the buffer
+/// will contain the correct parity shards after the call).
+///
+/// Following is a table of all the `with_buffer` variants
+///
+/// | not `with_buffer` | `with_buffer` |
+/// | --- | --- |
+/// | `verify` | `verify_with_buffer` |
+///
+/// The `with_buffer` variants also check the dimensions of the buffer and return
+/// `Error::TooFewBufferShards`, `Error::TooManyBufferShards`, `Error::EmptyShard`,
+/// or `Error::IncorrectShardSize` when applicable.
+///
+#[derive(Debug)]
+pub struct ReedSolomon<F: Field> {
+    data_shard_count: usize,
+    parity_shard_count: usize,
+    total_shard_count: usize,
+    matrix: Arc<Matrix<F>>,
+    data_mixing: Option<DataMixing<F>>,
+    small_decoder: Option<Arc<SmallDecoder<F>>>,
+    fft: Option<Arc<AdditiveFft<F>>>,
+    tree: InversionTree<F>,
+    precomputed_erasures: usize,
+    max_working_memory: Option<usize>,
+    platform: Option<Platform>,
+    #[cfg(feature = "metrics")]
+    counters: Counters,
+    #[cfg(feature = "shadow")]
+    shadow: shadow::Slot<F>,
+}
+
+/// Matrices mapping the data to the first `data_shard_count` shards
+/// of a non-systematic codec, and back.
+#[derive(PartialEq, Debug)]
+struct DataMixing<F: Field> {
+    mix: Arc<Matrix<F>>,
+    unmix: Arc<Matrix<F>>,
+}
+
+impl<F: Field> Clone for DataMixing<F> {
+    fn clone(&self) -> Self {
+        Self {
+            mix: Arc::clone(&self.mix),
+            unmix: Arc::clone(&self.unmix),
+        }
+    }
+}
+
+/// The maximum data shard count of codecs using `SmallDecoder`.
+const SMALL_DECODER_MAX_DATA_SHARDS: usize = 4;
+
+/// The maximum total shard count of codecs using `SmallDecoder`.
+const SMALL_DECODER_MAX_SHARDS: usize = 8;
+
+/// Decode matrices of a small codec, e.g. 2+1 or 4+2 as common for
+/// packet FEC, for every set of `data_shard_count` shards.
+///
+/// The matrices are computed when the codec is created, so decoding
+/// needs neither a matrix inversion nor a lookup in the inversion tree,
+/// which takes a lock.
+#[derive(PartialEq, Debug)]
+struct SmallDecoder<F: Field> {
+    /// The decode matrices indexed by the bitmask of the shards decoded from.
+    matrices: Vec<Option<Arc<Matrix<F>>>>,
+}
+
+impl<F: Field> SmallDecoder<F> {
+    /// Returns `None` if the codec is too large to compute every matrix up front.
+    fn new(matrix: &Matrix<F>, data_shards: usize, total_shards: usize) -> Option<Self> {
+        if data_shards > SMALL_DECODER_MAX_DATA_SHARDS || total_shards > SMALL_DECODER_MAX_SHARDS {
+            return None;
+        }
+
+        let matrices = (0..1usize << total_shards)
+            .map(|mask| {
+                if mask.count_ones() as usize != data_shards {
+                    return None;
+                }
+
+                let mut sub_matrix = Matrix::new(data_shards, data_shards);
+                for (sub_matrix_row, valid_index) in (0..total_shards)
+                    .filter(|&i| mask & (1 << i) != 0)
+                    .enumerate()
+                {
+                    for c in 0..data_shards {
+                        sub_matrix.set(sub_matrix_row, c, matrix.get(valid_index, c));
+                    }
+                }
+
+                sub_matrix.invert().ok().map(Arc::new)
+            })
+            .collect();
+
+        Some(Self { matrices })
+    }
+
+    fn get(&self, valid_indices: &[usize]) -> Option<Arc<Matrix<F>>> {
+        let mask = valid_indices.iter().fold(0, |mask, &i| mask | (1 << i));
+
+        self.matrices[mask].clone()
+    }
+}
+
+/// Which shards a reconstruction used and which it rebuilt,
+/// as returned by `reconstruct_with_report`.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct ReconstructReport {
+    /// The indices of the shards the rebuilt shards were computed from,
+    /// in ascending order. Empty if nothing was rebuilt.
+    pub used: Vec<usize>,
+    /// The indices of the shards which were missing and rebuilt,
+    /// in ascending order.
+    pub rebuilt: Vec<usize>,
+}
+
+/// What `verify_with_policy` does when the parity shards do not match
+/// the data shards.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MismatchPolicy {
+    /// Only reports which parity shards mismatch, like `verify`.
+    ReportOnly,
+    /// Re-derives each shard in turn from the others, and keeps the
+    /// re-derivation if it is the only one which makes the stripe
+    /// consistent, i.e. locates a single corrupted shard.
+    Rederive,
+    /// Searches for the smallest set of shards which, once re-derived
+    /// from the others, makes the stripe consistent, up to half the parity
+    /// shard count, the most errors the code can correct. Costs up to one
+    /// reconstruction per candidate set.
+    Correct,
+    /// Searches like `Correct`, but for up to `max_corrupted` shards, and
+    /// only reports the candidate sets without overwriting anything, e.g.
+    /// to investigate a stripe beyond the correction capability of the code.
+    ///
+    /// The search is capped at one less than the parity shard count, as any
+    /// set of that many shards re-derives into a consistent stripe. It costs
+    /// up to one reconstruction per set of at most `max_corrupted` shards,
+    /// which stays tractable for up to about 4 of them.
+    Identify {
+        /// The most shards a candidate set may hold.
+        max_corrupted: usize,
+    },
+}
+
+/// How `verify_with_policy` resolved the stripe.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Resolution {
+    /// The parity shards match the data shards.
+    Consistent,
+    /// The parity shards mismatch, and nothing was attempted.
+    Reported,
+    /// The shards in `VerifyReport::candidates` were corrupted and
+    /// were overwritten with their re-derivation.
+    Corrected,
+    /// Several sets of shards could be the corrupted ones, listed in
+    /// `VerifyReport::candidates`. Nothing was touched.
+    Ambiguous,
+    /// The shards in `VerifyReport::candidates` are the only set which
+    /// could be the corrupted one, as found by `MismatchPolicy::Identify`.
+    /// Nothing was touched.
+    Identified,
+    /// No set of shards within the correction capability of the policy
+    /// could be the corrupted one. Nothing was touched.
+    Uncorrectable,
+}
+
+/// The outcome of `verify_with_policy` and the evidence it is based on.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct VerifyReport {
+    /// How the stripe was resolved.
+    pub resolution: Resolution,
+    /// The indices of the parity shards which mismatch the data shards,
+    /// in ascending order.
+    pub mismatched_parity: Vec<usize>,
+    /// The sets of shard indices whose re-derivation makes the stripe
+    /// consistent, each in ascending order. Empty unless the policy
+    /// searched for corrupted shards.
+    pub candidates: Vec<Vec<usize>>,
+}
+
+impl VerifyReport {
+    /// Returns `true` if the stripe is consistent, either as provided
+    /// or after correction.
+    pub const fn is_consistent(&self) -> bool {
+        matches!(
+            self.resolution,
+            Resolution::Consistent | Resolution::Corrected
+        )
+    }
+}
+
+/// The work a call would do, as returned by `encode_plan` and
+/// `reconstruct_plan`, e.g. to admission-control large repairs.
+///
+/// The counts are the ones recorded by `ReedSolomon::stats` with the
+/// `metrics` feature enabled.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct WorkPlan {
+    /// The number of field element multiplications on shard data.
+    pub mul_ops: u64,
+    /// The number of bytes of shards read.
+    pub bytes_read: u64,
+    /// The number of bytes of shards written.
+    pub bytes_written: u64,
+    /// An upper bound on the number of bytes allocated for decode matrices,
+    /// which are not allocated when found in the inversion cache.
+    pub scratch_bytes: u64,
+}
+
+impl WorkPlan {
+    /// Estimates the duration of the work at a throughput of
+    /// `mul_ops_per_sec` multiplications per second, e.g. as measured with
+    /// `ReedSolomon::stats` over a previous call.
+    ///
+    /// Saturates to `Duration::MAX`, e.g. for a throughput of 0 measured
+    /// before any work was done, unless there is no work to do.
+    pub fn duration(&self, mul_ops_per_sec: u64) -> Duration {
+        if self.mul_ops == 0 {
+            return Duration::ZERO;
+        }
+
+        Duration::try_from_secs_f64(self.mul_ops as f64 / mul_ops_per_sec as f64)
+            .unwrap_or(Duration::MAX)
+    }
+}
+
+/// The number of bytes across all shards which `code_some_slices` works on
+/// at a time, so that the blocks of the outputs stay in the L2 cache while
+/// the blocks of the inputs are added onto them.
+const CACHE_BLOCK_BYTES: usize = 256 * 1024;
+
+/// The number of bytes of each shard processed between two reports of
+/// `encode_with_progress` and `reconstruct_with_progress`.
+const PROGRESS_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// The most submatrices of the encoding matrix `ReedSolomon::new_with_matrix`
+/// inverts to check that every set of data shard count shards can be
+/// reconstructed from, which takes well under a second.
+pub const MAX_CHECKED_SUBMATRICES: usize = 1 << 16;
+
+/// An event reported to the callback of `encode_with_progress` and
+/// `reconstruct_with_progress`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Progress {
+    /// `processed` bytes of the shards read by the call were processed,
+    /// out of `total`, counted like `WorkPlan::bytes_read`.
+    Bytes {
+        /// The number of bytes processed so far.
+        processed: u64,
+        /// The number of bytes processed by the whole call.
+        total: u64,
+    },
+    /// The shard at the given index was written in full.
+    ShardCompleted(usize),
+}
+
+/// Reusable buffers for `reconstruct_with_scratch`.
+///
+/// The scratch holds the data decode matrix of the last reconstruction,
+/// which is reused as long as the same shards are missing, and the buffers
+/// to compute a new one otherwise. It adapts to the codec it is used with,
+/// allocating only when used with a codec of a different data shard count.
+#[derive(Debug)]
+pub struct ReconstructScratch<F: Field> {
+    // encoding matrix of the codec `work` was computed for
+    matrix: Option<Arc<Matrix<F>>>,
+    // the data decode matrix on the right of an identity matrix
+    work: Matrix<F>,
+    present: Vec<bool>,
+    valid_indices: Vec<usize>,
+    invalid_indices: Vec<usize>,
+}
+
+impl<F: Field> ReconstructScratch<F> {
+    /// Creates the scratch buffers for `codec`.
+    pub fn new(codec: &ReedSolomon<F>) -> Self {
+        let data_shards = codec.data_shard_count;
+        let total_shards = codec.total_shard_count;
+
+        Self {
+            matrix: None,
+            work: Matrix::new(data_shards, 2 * data_shards),
+            present: Vec::with_capacity(total_shards),
+            valid_indices: Vec::with_capacity(data_shards),
+            invalid_indices: Vec::with_capacity(total_shards),
+        }
+    }
+
+    fn prepare(&mut self, codec: &ReedSolomon<F>) {
+        if matches!(self.matrix, Some(ref matrix) if Arc::ptr_eq(matrix, &codec.matrix)) {
+            return;
+        }
+
+        if self.work.row_count() != codec.data_shard_count {
+            *self = Self::new(codec);
+        }
+
+        self.matrix = Some(Arc::clone(&codec.matrix));
+        self.invalid_indices.clear();
+    }
+
+    fn is_decoded(&self) -> bool {
+        !self.invalid_indices.is_empty()
+            && self
+                .present
+                .iter()
+                .enumerate()
+                .filter(|(_, &present)| !present)
+                .map(|(i, _)| i)
+                .eq(self.invalid_indices.iter().copied())
+    }
+
+    fn decode(&mut self, matrix: &Matrix<F>, data_shards: usize) {
+        self.valid_indices.clear();
+        self.invalid_indices.clear();
+        for (i, &present) in self.present.iter().enumerate() {
+            if !present {
+                self.invalid_indices.push(i);
+            } else if self.valid_indices.len() < data_shards {
+                self.valid_indices.push(i);
+            }
+        }
+
+        for (r, &valid_index) in self.valid_indices.iter().enumerate() {
+            for c in 0..data_shards {
+                self.work.set(r, c, matrix.get(valid_index, c));
+                self.work.set(
+                    r,
+                    data_shards + c,
+                    if r == c { F::one() } else { F::zero() },
+                );
+            }
+        }
+
+        self.work.gaussian_elim().unwrap();
+    }
+}
+
+/// The coefficients reconstructing the missing shards of one pattern of
+/// missing shards, as returned by `ReedSolomon::decode_plan`.
+///
+/// Applying a plan to a stripe neither looks up the inversion cache nor
+/// selects the shards to decode from, so stripes with the same shards
+/// missing, e.g. the degraded reads of a failed disk, are reconstructed
+/// at the cost of the multiplications alone. Each missing shard is
+/// computed from the present shards directly, parity shards included.
+///
+/// The plan is independent of the codec it was created with, which need
+/// not be kept around. With the `metrics` feature, its work is not
+/// recorded by `ReedSolomon::stats`.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// # use reed_solomon_erasure::galois_8::ReedSolomon;
+/// # fn main () {
+/// let r = ReedSolomon::new(3, 2).unwrap();
+/// let plan = r.decode_plan(&[true, false, true, true, false]).unwrap();
+///
+/// let mut shards = shards!([0, 1, 2], [3, 4, 5], [6, 7, 8], [0, 0, 0], [0, 0, 0]);
+/// r.encode(&mut shards).unwrap();
+/// let expect = shards.clone();
+///
+/// shards[1] = vec![0; 3];
+/// shards[4] = vec![0; 3];
+/// plan.reconstruct(&mut shards).unwrap();
+/// assert_eq!(expect, shards);
+/// # }
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct DecodePlan<F: Field> {
+    data_shard_count: usize,
+    total_shard_count: usize,
+    present: Vec<bool>,
+    valid_indices: Vec<usize>,
+    // the missing shards in ascending order, and the coefficients of each
+    // over the shards of `valid_indices`
+    rebuilt: Vec<usize>,
+    rows: Vec<Vec<F::Elem>>,
+    platform: Option<Platform>,
+}
+
+impl<F: Field> DecodePlan<F> {
+    /// Returns the flags of the shards which are present, as passed to
+    /// `ReedSolomon::decode_plan`.
+    pub fn present(&self) -> &[bool] {
+        &self.present
+    }
+
+    /// Reconstructs the missing shards of `shards`, overwriting them.
+    ///
+    /// `shards` holds every shard of the stripe, and the ones flagged
+    /// missing by the plan are only written.
+    ///
+    /// Returns `Error::TooFewShards` or `Error::TooManyShards` if the number
+    /// of shards does not match the codec the plan was created with.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct<T: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        shards: &mut [T],
+    ) -> Result<(), Error> {
+        self.reconstruct_internal(shards, false)
+    }
+
+    /// Reconstructs only the missing data shards of `shards`, see
+    /// `reconstruct`.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_data<T: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        shards: &mut [T],
+    ) -> Result<(), Error> {
+        self.reconstruct_internal(shards, true)
+    }
+
+    fn reconstruct_internal<T: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        shards: &mut [T],
+        data_only: bool,
+    ) -> Result<(), Error> {
+        check_piece_count!(all => self, shards);
+        check_slices!(multi => shards);
+
+        for (&i, row) in self.rebuilt.iter().zip(&self.rows) {
+            if data_only && i >= self.data_shard_count {
+                break;
+            }
+
+            // the missing shard is split off from the present ones
+            let (before, rest) = shards.split_at_mut(i);
+            let (output, after) = rest.split_first_mut().expect("i < total shard count; qed");
+            let output = output.as_mut();
+
+            for (j, (&valid_index, &c)) in self.valid_indices.iter().zip(row).enumerate() {
+                let input = if valid_index < i {
+                    before[valid_index].as_ref()
+                } else {
+                    after[valid_index - i - 1].as_ref()
+                };
+
+                if j == 0 {
+                    mul_slice::<F>(self.platform, c, input, output);
+                } else {
+                    mul_slice_add::<F>(self.platform, c, input, output);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Read-only snapshot of a `ReedSolomon` codec.
+///
+/// A frozen codec only holds the immutable state of the codec, i.e. the
+/// shard counts and the encoding matrix, and none of the lazily populated
+/// inversion cache or the lock guarding it. This makes it cheap to share
+/// with worker processes, e.g. in a prefork server architecture, where
+/// pages that are never written to stay shared after forking.
+///
+/// Each worker then calls `thaw` to get a full codec, which gets its own
+/// inversion cache that is populated lazily as usual, after computing the
+/// matrices of `ReedSolomonBuilder::precomputed_erasures`.
+///
+/// # Example
+///
+/// ```
+/// use reed_solomon_erasure::galois_8::ReedSolomon;
+///
+/// let r = ReedSolomon::new(3, 2).unwrap();
+/// let frozen = r.freeze();
+///
+/// // in a worker
+/// let worker_codec = frozen.thaw();
+/// assert_eq!(r, worker_codec);
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct FrozenCodec<F: Field> {
+    data_shard_count: usize,
+    parity_shard_count: usize,
+    matrix: Arc<Matrix<F>>,
+    data_mixing: Option<DataMixing<F>>,
+    small_decoder: Option<Arc<SmallDecoder<F>>>,
+    fft: Option<Arc<AdditiveFft<F>>>,
+    inversion_cache_limit: usize,
+    inversion_cache_policy: InversionCachePolicy,
+    precomputed_erasures: usize,
+    max_working_memory: Option<usize>,
+    platform: Option<Platform>,
+}
+
+impl<F: Field> FrozenCodec<F> {
+    pub const fn data_shard_count(&self) -> usize {
+        self.data_shard_count
+    }
+
+    pub const fn parity_shard_count(&self) -> usize {
+        self.parity_shard_count
+    }
+
+    pub const fn total_shard_count(&self) -> usize {
+        self.data_shard_count + self.parity_shard_count
+    }
+
+    /// Creates a full codec out of the snapshot, with an empty inversion cache.
+    ///
+    /// The encoding matrix is shared with the snapshot rather than copied.
+    pub fn thaw(&self) -> ReedSolomon<F> {
+        let codec = ReedSolomon {
+            data_shard_count: self.data_shard_count,
+            parity_shard_count: self.parity_shard_count,
+            total_shard_count: self.total_shard_count(),
+            matrix: Arc::clone(&self.matrix),
+            data_mixing: self.data_mixing.clone(),
+            small_decoder: self.small_decoder.clone(),
+            fft: self.fft.clone(),
+            tree: InversionTree::with_policy(
+                self.data_shard_count,
+                self.parity_shard_count,
+                self.inversion_cache_limit,
+                self.inversion_cache_policy,
+            ),
+            precomputed_erasures: self.precomputed_erasures,
+            max_working_memory: self.max_working_memory,
+            platform: self.platform,
+            #[cfg(feature = "metrics")]
+            counters: Counters::default(),
+            #[cfg(feature = "shadow")]
+            shadow: shadow::Slot::default(),
+        };
+        codec.precompute_inversions(self.precomputed_erasures);
+
+        codec
+    }
+}
+
+/// Multiplies with the kernel of `platform`, or the detected one if `None`,
+/// see `Field::mul_slice_on`.
+pub fn mul_slice<F: Field>(
+    platform: Option<Platform>,
+    c: F::Elem,
+    input: &[F::Elem],
+    out: &mut [F::Elem],
+) {
+    match platform {
+        Some(platform) => F::mul_slice_on(platform, c, input, out),
+        None => F::mul_slice(c, input, out),
+    }
+}
+
+/// Multiplies with the kernel of `platform`, or the detected one if `None`,
+/// see `Field::mul_slice_add_on`.
+pub fn mul_slice_add<F: Field>(
+    platform: Option<Platform>,
+    c: F::Elem,
+    input: &[F::Elem],
+    out: &mut [F::Elem],
+) {
+    match platform {
+        Some(platform) => F::mul_slice_add_on(platform, c, input, out),
+        None => F::mul_slice_add(c, input, out),
+    }
+}
+
+// Whether every value of `slice` is an element of the field, see
+// `Field::is_elem`.
+fn all_elems<F: Field>(slice: &[F::Elem]) -> bool {
+    slice.iter().all(|&x| F::is_elem(x))
+}
+
+// Returns `Error::InvalidElement` if a present shard holds a value which
+// is not an element of the field.
+fn check_present_elems<F: Field, T: ReconstructShard<F>>(shards: &mut [T]) -> Result<(), Error> {
+    for shard in shards {
+        if let Some(slice) = shard.get() {
+            if !all_elems::<F>(slice) {
+                return Err(Error::InvalidElement);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn push_u32(bytes: &mut Vec<u8>, x: usize) {
+    bytes.extend_from_slice(&(x as u32).to_le_bytes());
+}
+
+fn take_u32(bytes: &mut &[u8]) -> Result<usize, Error> {
+    if bytes.len() < 4 {
+        return Err(Error::InvalidInversionCache);
+    }
+    let (x, rest) = bytes.split_at(4);
+    *bytes = rest;
+    Ok(u32::from_le_bytes([x[0], x[1], x[2], x[3]]) as usize)
+}
+
+// Advances `indices` to the next combination of its length out of
+// `0..n` in lexicographic order, returns `false` after the last one.
+fn next_combination(indices: &mut [usize], n: usize) -> bool {
+    let k = indices.len();
+    for i in (0..k).rev() {
+        if indices[i] < n - k + i {
+            indices[i] += 1;
+            for j in i + 1..k {
+                indices[j] = indices[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+/// Construction of the encoding matrix of a codec.
+///
+/// Codecs built with different constructions produce different parity
+/// and cannot decode each other's shards.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatrixKind {
+    /// Vandermonde matrix multiplied by the inverse of its top square,
+    /// as used by Backblaze's Java implementation. This is the default.
+    Vandermonde,
+    /// Identity matrix on top of a Cauchy matrix, as used by
+    /// e.g. klauspost/reedsolomon's `WithCauchyMatrix` option.
+    Cauchy,
+    /// Reed-Solomon code over the novel polynomial basis of Lin, Chung
+    /// and Han, which can be encoded and decoded with an additive FFT in
+    /// `O(n log n)` rather than `O(n^2)` multiplications per element.
+    ///
+    /// The codec uses the FFT whenever it takes fewer multiplications
+    /// than the encoding matrix, e.g. for hundreds of shards, and the
+    /// matrix otherwise, which produces the same shards.
+    ///
+    /// As the data shard count is rounded up to a power of 2 internally,
+    /// fewer parity shards are supported: the total shard count may be at
+    /// most `F::ORDER` minus the padding, e.g. 100 + 128 over GF(2^8).
+    /// With a power of 2 of data shards the code is the same as `Vandermonde`.
+    ///
+    /// Requires `F::nth` to be additive, `nth(a ^ b) == add(nth(a), nth(b))`,
+    /// which holds for all fields of this crate.
+    AdditiveFft,
+}
+
+/// Eviction policy of the inversion cache, which holds the decode
+/// matrices of previous reconstructions.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum InversionCachePolicy {
+    /// Evicts the least used entries once the total number of invalid
+    /// indices keying them reaches `ReedSolomonBuilder::inversion_cache_limit`.
+    /// This is the default.
+    LeastUsed,
+    /// Keeps the approximate memory used by the cache within `budget_bytes`,
+    /// evicting the entries with the fewest uses per byte first.
+    ///
+    /// The size of an entry grows with the square of the data shard count,
+    /// so entries of wide codecs cost more of the budget and have to be
+    /// used more often to displace several entries of small ones.
+    /// Entries larger than the whole budget are not cached.
+    Weighted {
+        /// The memory budget of the cache in bytes.
+        budget_bytes: usize,
+    },
+    /// Keeps at most `max_entries` entries, evicting the least recently
+    /// used entry first.
+    ///
+    /// Unlike `LeastUsed`, which favours entries used often in the past,
+    /// this lets the cache follow failure patterns which change over time.
+    /// A `max_entries` of zero disables the cache.
+    LeastRecentlyUsed {
+        /// The maximum number of cached entries.
+        max_entries: usize,
+    },
+    /// Caches nothing, inverting a matrix on every reconstruction which
+    /// needs one, see `ReedSolomonBuilder::inversion_cache`.
+    Disabled,
+}
+
+/// Builder for `ReedSolomon` codecs with non-default configuration.
+///
+/// `ReedSolomon::new(data_shards, parity_shards)` is equivalent to
+/// `ReedSolomon::builder(data_shards, parity_shards).build()`.
+///
+/// # Example
+///
+/// ```
+/// use reed_solomon_erasure::galois_8::ReedSolomon;
+///
+/// let r = ReedSolomon::builder(10, 4)
+///     .inversion_cache_limit(64)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(10, r.data_shard_count());
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ReedSolomonBuilder<F: Field> {
+    data_shards: usize,
+    parity_shards: usize,
+    inversion_cache_limit: usize,
+    inversion_cache_policy: InversionCachePolicy,
+    inversion_cache: bool,
+    matrix_kind: MatrixKind,
+    systematic: bool,
+    max_working_memory: Option<usize>,
+    platform: Option<Platform>,
+    calibrate_platform: bool,
+    precomputed_erasures: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ReedSolomonBuilder<F> {
+    /// Creates a builder with the default configuration.
+    pub const fn new(data_shards: usize, parity_shards: usize) -> Self {
+        Self {
+            data_shards,
+            parity_shards,
+            inversion_cache_limit: DEFAULT_INDICES_LIMIT,
+            inversion_cache_policy: InversionCachePolicy::LeastUsed,
+            inversion_cache: true,
+            matrix_kind: MatrixKind::Vandermonde,
+            systematic: true,
+            max_working_memory: None,
+            platform: None,
+            calibrate_platform: false,
+            precomputed_erasures: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the limit on the total number of invalid indices keying
+    /// the decode matrices cached in the inversion tree.
+    ///
+    /// Least used entries are evicted once the limit is reached.
+    /// Only applies to `InversionCachePolicy::LeastUsed`.
+    ///
+    /// Default is 254.
+    #[must_use]
+    pub const fn inversion_cache_limit(mut self, limit: usize) -> Self {
+        self.inversion_cache_limit = limit;
+        self
+    }
+
+    /// Sets the eviction policy of the inversion cache.
+    ///
+    /// Default is `InversionCachePolicy::LeastUsed`.
+    #[must_use]
+    pub const fn inversion_cache_policy(mut self, policy: InversionCachePolicy) -> Self {
+        self.inversion_cache_policy = policy;
+        self
+    }
+
+    /// Sets whether decode matrices are cached at all.
+    ///
+    /// Without the cache, every reconstruction with missing data shards
+    /// inverts a matrix, which suits one-shot tools and memory-constrained
+    /// environments. This overrides `inversion_cache_policy` with
+    /// `InversionCachePolicy::Disabled`.
+    ///
+    /// Default is `true`.
+    #[must_use]
+    pub const fn inversion_cache(mut self, enabled: bool) -> Self {
+        self.inversion_cache = enabled;
+        self
+    }
+
+    /// Sets the construction of the encoding matrix.
+    ///
+    /// Default is `MatrixKind::Vandermonde`.
+    #[must_use]
+    pub const fn matrix_kind(mut self, kind: MatrixKind) -> Self {
+        self.matrix_kind = kind;
+        self
+    }
+
+    /// Sets whether the data is stored as is in the data shards.
+    ///
+    /// A non-systematic codec codes every shard from all of the data,
+    /// so no single shard contains the data in plain. The data must then
+    /// be encoded with `encode_data` and recovered with `decode_data`,
+    /// which always goes through a matrix inversion when data shards are missing.
+    ///
+    /// The encoding matrix of a non-systematic codec is always built from
+    /// a Vandermonde matrix, `matrix_kind` is ignored, and one fewer shard
+    /// than `F::ORDER` is supported. At least two data shards are required,
+    /// since with a single data shard every shard would be a plain copy of it.
+    ///
+    /// Default is `true`.
+    #[must_use]
+    pub const fn systematic(mut self, systematic: bool) -> Self {
+        self.systematic = systematic;
+        self
+    }
+
+    /// Sets the approximate number of bytes across all shards which
+    /// `encode`, `encode_sep`, `reconstruct` and `reconstruct_data` work on
+    /// at a time, e.g. to repair huge stripes in a container with a tight
+    /// memory limit.
+    ///
+    /// Longer stripes are processed one column window at a time, like
+    /// `encode_chunked` and `reconstruct_chunked` do, which produces the
+    /// same shards. Each window holds at least one element of each shard.
+    ///
+    /// Default is no limit.
+    #[must_use]
+    pub const fn max_working_memory(mut self, bytes: usize) -> Self {
+        self.max_working_memory = Some(bytes);
+        self
+    }
+
+    /// Forces the SIMD kernel used to multiply shards, e.g. to benchmark
+    /// the kernels against each other or to rule one out while debugging.
+    ///
+    /// Only affects fields with several kernels, i.e. GF(2^8) and GF(2^16).
+    ///
+    /// Default is the fastest kernel the CPU supports, see `Platform::detect`.
+    #[must_use]
+    pub const fn platform(mut self, platform: Platform) -> Self {
+        self.platform = Some(platform);
+        self
+    }
+
+    /// Sets whether `build` picks the kernel which is the fastest on this
+    /// CPU by running a microbenchmark, see `Platform::calibrate`, rather
+    /// than going by the instruction set extensions the CPU supports.
+    ///
+    /// The benchmark takes a few milliseconds, so codecs which are built
+    /// often should rather be derived from a calibrated one, e.g. with
+    /// `freeze` and `thaw`, which keep its platform. Ignored if `platform`
+    /// is set.
+    ///
+    /// Default is `false`.
+    #[must_use]
+    pub const fn calibrate_platform(mut self, calibrate: bool) -> Self {
+        self.calibrate_platform = calibrate;
+        self
+    }
+
+    /// Sets the number of missing shards up to which `build` computes and
+    /// caches the decode matrices of every pattern of missing shards, so
+    /// the first reconstructions need not invert any matrix.
+    ///
+    /// With 1, the matrices of all single erasures are computed, with 2
+    /// those of all double erasures as well, and so on, up to the parity
+    /// shard count. Their number grows quickly with the shard count and
+    /// the cache limit and policy still apply, see `inversion_cache_limit`.
+    /// Codecs small enough to compute every matrix anyway ignore this.
+    ///
+    /// Clones of the codec and codecs thawed from `freeze` compute the
+    /// matrices again, so they start with the same cache.
+    ///
+    /// Default is 0.
+    #[must_use]
+    pub const fn precomputed_erasures(mut self, max_erasures: usize) -> Self {
+        self.precomputed_erasures = max_erasures;
+        self
+    }
+
+    /// Creates the codec.
+    ///
+    /// Returns `Error::TooFewDataShards` if `data_shards == 0`, or if
+    /// `data_shards == 1` for a non-systematic codec.
+    ///
+    /// Returns `Error::TooFewParityShards` if `parity_shards == 0`.
+    ///
+    /// Returns `Error::TooManyShards` if `data_shards + parity_shards > F::ORDER`,
+    /// if `data_shards + parity_shards == F::ORDER` for a non-systematic codec,
+    /// or if the padded data shards do not leave room for the parity shards
+    /// for `MatrixKind::AdditiveFft`.
+    ///
+    /// Returns `Error::UnsupportedPlatform` if the CPU does not support
+    /// the platform set with `platform`.
+    pub fn build(mut self) -> Result<ReedSolomon<F>, Error> {
+        self.check_shard_counts()?;
+        match self.platform {
+            Some(platform) if !platform.is_supported() => {
+                return Err(Error::UnsupportedPlatform);
+            }
+            None if self.calibrate_platform => {
+                self.platform = Some(Platform::calibrate::<F>());
+            }
+            _ => {}
+        }
+
+        let total_shards = self.data_shards + self.parity_shards;
+
+        if self.systematic {
+            if self.matrix_kind == MatrixKind::AdditiveFft {
+                let max = fft::max_shards::<F>(self.data_shards);
+                if total_shards > max {
+                    return Err(Error::TooManyShards { max });
+                }
+            }
+
+            let matrix =
+                ReedSolomon::build_matrix(self.matrix_kind, self.data_shards, total_shards);
+
+            Ok(self.build_with_matrix(matrix, None))
+        } else {
+            // With a single data shard every row of the non-systematic
+            // matrix is `[1]`, i.e. every shard is a plain copy of the data.
+            if self.data_shards == 1 {
+                return Err(Error::TooFewDataShards);
+            }
+            if total_shards == F::ORDER {
+                return Err(Error::TooManyShards { max: F::ORDER - 1 });
+            }
+
+            let (matrix, data_mixing) =
+                ReedSolomon::build_non_systematic_matrix(self.data_shards, total_shards);
+
+            Ok(self.build_with_matrix(matrix, Some(data_mixing)))
+        }
+    }
+
+    const fn check_shard_counts(&self) -> Result<(), Error> {
+        if self.data_shards == 0 {
+            return Err(Error::TooFewDataShards);
+        }
+        if self.parity_shards == 0 {
+            return Err(Error::TooFewParityShards);
+        }
+        match self.data_shards.checked_add(self.parity_shards) {
+            Some(total_shards) if total_shards <= F::ORDER => Ok(()),
+            _ => Err(Error::TooManyShards { max: F::ORDER }),
+        }
+    }
+
+    fn build_with_matrix(
+        self,
+        matrix: Matrix<F>,
+        data_mixing: Option<DataMixing<F>>,
+    ) -> ReedSolomon<F> {
+        let data_shards = self.data_shards;
+        let parity_shards = self.parity_shards;
+
+        let small_decoder =
+            SmallDecoder::new(&matrix, data_shards, data_shards + parity_shards).map(Arc::new);
+
+        let fft = (self.matrix_kind == MatrixKind::AdditiveFft && data_mixing.is_none())
+            .then(|| Arc::new(AdditiveFft::new(data_shards, parity_shards)));
+
+        let codec = ReedSolomon {
+            data_shard_count: data_shards,
+            parity_shard_count: parity_shards,
+            total_shard_count: data_shards + parity_shards,
+            matrix: Arc::new(matrix),
+            data_mixing,
+            small_decoder,
+            fft,
+            tree: InversionTree::with_policy(
+                data_shards,
+                parity_shards,
+                self.inversion_cache_limit,
+                if self.inversion_cache {
+                    self.inversion_cache_policy
+                } else {
+                    InversionCachePolicy::Disabled
+                },
+            ),
+            precomputed_erasures: self.precomputed_erasures,
+            max_working_memory: self.max_working_memory,
+            platform: self.platform,
+            #[cfg(feature = "metrics")]
+            counters: Counters::default(),
+            #[cfg(feature = "shadow")]
+            shadow: shadow::Slot::default(),
+        };
+        codec.precompute_inversions(self.precomputed_erasures);
+
+        codec
+    }
+}
+
+impl<F: KnownField> ReedSolomon<F> {
+    /// Returns the configuration of the codec, from which an identical
+    /// codec can be built, see `ReedSolomonConfig`.
+    ///
+    /// Returns `Error::InvalidMatrix` if the codec was created with a custom
+    /// encoding matrix, e.g. by `new_with_matrix` or `shorten`.
+    pub fn config(&self) -> Result<ReedSolomonConfig, Error> {
+        let config = |matrix_kind, systematic| ReedSolomonConfig {
+            field: F::KIND,
+            polynomial: F::KIND.polynomial(),
+            data_shards: self.data_shard_count,
+            parity_shards: self.parity_shard_count,
+            matrix_kind,
+            systematic,
+        };
+
+        if self.data_mixing.is_some() {
+            return Ok(config(MatrixKind::Vandermonde, false));
+        }
+        if self.fft.is_some() {
+            return Ok(config(MatrixKind::AdditiveFft, true));
+        }
+
+        [MatrixKind::Vandermonde, MatrixKind::Cauchy]
+            .iter()
+            .find(|&&kind| {
+                let matrix =
+                    Self::build_matrix(kind, self.data_shard_count, self.total_shard_count);
+                (0..self.total_shard_count).all(|i| matrix.get_row(i) == self.matrix.get_row(i))
+            })
+            .map(|&kind| config(kind, true))
+            .ok_or(Error::InvalidMatrix)
+    }
+}
+
+impl<F: Field> Clone for ReedSolomon<F> {
+    fn clone(&self) -> Self {
+        #[cfg(feature = "shadow")]
+        {
+            let mut codec = self.freeze().thaw();
+            codec.shadow = self.shadow.clone();
+            codec
+        }
+        #[cfg(not(feature = "shadow"))]
+        self.freeze().thaw()
+    }
+}
+
+impl<F: Field> PartialEq for ReedSolomon<F> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.data_shard_count == rhs.data_shard_count
+            && self.parity_shard_count == rhs.parity_shard_count
+            && (0..self.total_shard_count).all(|i| self.matrix.get_row(i) == rhs.matrix.get_row(i))
+    }
+}
+
+impl<F: Field> ReedSolomon<F> {
+    // AUDIT
+    //
+    // Error detection responsibilities
+    //
+    // Terminologies and symbols:
+    //   X =A, B, C=> Y: X delegates error checking responsibilities A, B, C to Y
+    //   X:= A, B, C: X needs to handle responsibilities A, B, C
+    //
+    // Encode methods
+    //
+    // `encode_single`:=
+    //   - check index `i_data` within range [0, data shard count)
+    //   - check length of `slices` matches total shard count exactly
+    //   - check consistency of length of individual slices
+    // `encode_single_sep`:=
     //   - check index `i_data` within range [0, data shard count)
     //   - check length of `parity` matches parity shard count exactly
     //   - check consistency of length of individual parity slices
     //   - check length of `single_data` matches length of first parity slice
+    // `update_parity`:=
+    //   - check index `i_data` within range [0, data shard count)
+    //   - check length of `parity` matches parity shard count exactly
+    //   - check length of `new_parity` matches parity shard count exactly
+    //   - check consistency of length of individual parity slices
+    //   - check consistency of length of individual new parity slices
+    //   - check length of first new parity slice matches length of first parity slice
+    //   - check length of `old_data` and `new_data` match length of first parity slice
+    // `update_range`:=
+    //   - check `new_data` is not empty
+    //   - check shard length of `backend` is not zero
+    //   - check range lies within the data shards
+    //   - check length of ranges read from `backend` matches the requested length
     // `encode`:=
     //   - check length of `slices` matches total shard count exactly
     //   - check consistency of length of individual slices
+    // `encode_with_progress` =ALL=> `encode_chunked_internal`
+    // `encode_cancellable` =ALL=> `encode_chunked_internal`
+    // `encode_chunked` =ALL=> `encode_chunked_internal`
+    // `encode_chunked_internal`:=
+    //   - check length of `slices` matches total shard count exactly
+    //   - check consistency of length of individual slices
+    //
+    //   Passes control to encode for each chunk of the shards
+    //
+    // `encode_batch`:=
+    //   - check length of each stripe matches total shard count exactly
+    //   - check consistency of length of individual slices of each stripe
     // `encode_sep`:=
     //   - check length of `data` matches data shard count exactly
     //   - check length of `parity` matches parity shard count exactly
     //   - check consistency of length of individual data slices
-    //   - check consistency of length of individual parity slices
-    //   - check length of first parity slice matches length of first data slice
+    //   - check consistency of length of individual parity slices
+    //   - check length of first parity slice matches length of first data slice
+    // `encode_data`:=
+    //   - check length of `data` matches data shard count exactly
+    //   - check length of `shards` matches total shard count exactly
+    //   - check consistency of length of individual data slices
+    //   - check consistency of length of individual slices
+    //   - check length of first slice matches length of first data slice
+    //
+    //   Mixes the data into the data shards then passes control to encode_sep
+    //
+    // `encode_parity_from`:=
+    //   - check length of `data` matches data shard count exactly
+    //   - check `first_parity` plus length of `parity` matches parity shard count exactly
+    //   - check consistency of length of individual data slices
+    //   - check consistency of length of individual parity slices
+    //   - check length of first parity slice matches length of first data slice
+    // `encode_sep_vectored`:=
+    //   - check length of `data` matches data shard count exactly
+    //   - check length of `parity` matches parity shard count exactly
+    //   - check consistency of length of individual data slices
+    //   - check total length of the chunks of each parity shard matches length of first data slice
     //
     // Verify methods
     //
@@ -396,297 +2312,3296 @@ impl<F: Field> ReedSolomon<F> {
     //   - check consistency of length of individual slices
     //   - check consistency of length of individual slices in buffer
     //   - check length of first slice in buffer matches length of first slice
+    // `audit_sample`:=
+    //   - check length of `slices` matches total shard count exactly
+    //   - check consistency of length of individual slices
+    //   - check each position in `sample_positions` is within range [0, slice length)
+    // `verify_punctured`:=
+    //   - check length of `punctured` matches total shard count exactly
+    //   - check no data shard is marked punctured
+    //   - check length of `slices` matches count of shards not punctured exactly
+    //   - check consistency of length of individual slices
+    // `verify_with_policy`:=
+    //   - check length of `slices` matches total shard count exactly
+    //   - check consistency of length of individual slices
+    //
+    //   Passes control to reconstruct for each candidate set of corrupted shards
     //
     // Reconstruct methods
     //
     // `reconstruct` =ALL=> `reconstruct_internal`
+    //   or, beyond `max_working_memory`, =ALL=> `reconstruct_chunked_internal`
     // `reconstruct_data`=ALL=> `reconstruct_internal`
+    //   or, beyond `max_working_memory`, =ALL=> `reconstruct_chunked_internal`
+    // `reconstruct_with_report` =ALL=> `reconstruct_internal`
+    // `reconstruct_data_with_report` =ALL=> `reconstruct_internal`
     // `reconstruct_internal`:=
     //   - check length of `slices` matches total shard count exactly
     //   - check consistency of length of individual slices
     //   - check length of `slice_present` matches length of `slices`
+    // `reconstruct_with_scratch` =ALL=> `reconstruct_with_scratch_internal`
+    // `reconstruct_data_with_scratch` =ALL=> `reconstruct_with_scratch_internal`
+    // `reconstruct_with_scratch_internal`:=
+    //   - check length of `shards` matches total shard count exactly
+    //   - check consistency of length of individual slices
+    //
+    // `reconstruct_with_progress` =ALL=> `reconstruct_chunked_internal`
+    // `reconstruct_data_with_progress` =ALL=> `reconstruct_chunked_internal`
+    // `reconstruct_cancellable` =ALL=> `reconstruct_chunked_internal`
+    // `reconstruct_data_cancellable` =ALL=> `reconstruct_chunked_internal`
+    // `reconstruct_chunked` =ALL=> `reconstruct_chunked_internal`
+    // `reconstruct_data_chunked` =ALL=> `reconstruct_chunked_internal`
+    // `reconstruct_chunked_internal`:=
+    //   - check length of `shards` matches total shard count exactly
+    //   - check consistency of length of individual slices
+    //
+    //   Passes control to reconstruct_internal for each chunk of the shards
+    //
+    // `reconstruct_parallel` =ALL=> `reconstruct_parallel_internal`
+    // `reconstruct_data_parallel` =ALL=> `reconstruct_parallel_internal`
+    // `reconstruct_parallel_internal`:=
+    //   - check length of `shards` matches total shard count exactly
+    //   - check consistency of length of individual slices
+    //
+    //   Passes control to reconstruct_internal for each byte range
+    //
+    // `reconstruct_range` =ALL=> `reconstruct_range_internal`
+    // `reconstruct_data_range` =ALL=> `reconstruct_range_internal`
+    // `reconstruct_range_internal`:=
+    //   - check length of `shards` matches total shard count exactly
+    //   - check consistency of length of individual slices
+    //   - check length of `present` matches total shard count exactly
+    //   - check `range` lies within the slices and is not empty
+    //
+    //   Passes control to reconstruct_internal for the range
+    //
+    // `reconstruct_interleaved`:=
+    //   - check length of `shards` matches total shard count exactly
+    //   - check consistency of length of individual slices
+    //   - check `depth` is not zero
+    //   - check each erasure is within the shards
+    //   - check each sub-stripe has at most parity shard count erased segments
+    //
+    //   Passes control to reconstruct for each sub-stripe with erased segments
+    // `decode_data`:=
+    //   - check length of `data` matches data shard count exactly
+    //   - check consistency of length of individual data slices
+    //   - check length of first data slice matches length of reconstructed data shards
+    //
+    //   Passes control to reconstruct_data before unmixing the data shards
+    //
+    // `reshard`:=
+    //   - check length of `shards` matches total shard count exactly
+    //
+    //   Passes control to reconstruct_data, and decode_data if the data is mixed,
+    //   then to encode_data of `target` if its data is mixed
+    //
+    // Plan methods
+    //
+    // `reconstruct_plan` =ALL=> `reconstruct_plan_internal`
+    // `reconstruct_data_plan` =ALL=> `reconstruct_plan_internal`
+    // `reconstruct_plan_internal`:=
+    //   - check length of `present` matches total shard count exactly
+    //   - check number of present shards is at least data shard count
+    // `repair_plan`:=
+    //   - check length of `present` matches total shard count exactly
+    //   - check each wanted index within range [0, total shard count)
+    //   - check number of present shards is at least data shard count if a wanted shard is missing
+
+    pub(crate) fn get_parity_rows(&self) -> SmallVec<[&[F::Elem]; 32]> {
+        let mut parity_rows = SmallVec::with_capacity(self.parity_shard_count);
+        let matrix = &self.matrix;
+        for i in self.data_shard_count..self.total_shard_count {
+            parity_rows.push(matrix.get_row(i));
+        }
+
+        parity_rows
+    }
+
+    fn build_matrix(kind: MatrixKind, data_shards: usize, total_shards: usize) -> Matrix<F> {
+        match kind {
+            MatrixKind::Vandermonde => {
+                let vandermonde = Matrix::vandermonde(total_shards, data_shards);
+
+                let top = vandermonde.sub_matrix(0, 0, data_shards, data_shards);
+
+                vandermonde.multiply(&top.invert().unwrap())
+            }
+            MatrixKind::Cauchy => Matrix::cauchy(total_shards, data_shards),
+            MatrixKind::AdditiveFft => {
+                let parity_shards = total_shards - data_shards;
+                let fft = AdditiveFft::<F>::new(data_shards, parity_shards);
+
+                // encoding the unit vectors yields the columns of the parity rows
+                let mut data = vec![vec![F::zero(); data_shards]; data_shards];
+                for (i, data) in data.iter_mut().enumerate() {
+                    data[i] = F::one();
+                }
+                let mut parity = vec![vec![F::zero(); data_shards]; parity_shards];
+                fft.encode(None, &data, &mut parity);
+
+                let mut matrix = Matrix::new(total_shards, data_shards);
+                for i in 0..data_shards {
+                    matrix.set(i, i, F::one());
+                }
+                for (i, row) in parity.iter().enumerate() {
+                    for (c, &x) in row.iter().enumerate() {
+                        matrix.set(data_shards + i, c, x);
+                    }
+                }
+
+                matrix
+            }
+        }
+    }
+
+    /// Creates a new instance of Reed-Solomon erasure code encoder/decoder.
+    ///
+    /// Returns `Error::TooFewDataShards` if `data_shards == 0`.
+    ///
+    /// Returns `Error::TooFewParityShards` if `parity_shards == 0`.
+    ///
+    /// Returns `Error::TooManyShards` if `data_shards + parity_shards > F::ORDER`.
+    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self, Error> {
+        Self::builder(data_shards, parity_shards).build()
+    }
+
+    /// Creates a new instance of Reed-Solomon erasure code encoder/decoder
+    /// using the provided parity rows of the encoding matrix, e.g. to stay
+    /// compatible with shards produced by another implementation.
+    ///
+    /// `parity_rows[i][j]` is the coefficient of data shard `j` in parity shard `i`.
+    ///
+    /// The rows are checked to allow reconstruction from any `data_shards`
+    /// shards, which requires inverting every such submatrix of the
+    /// encoding matrix. There are `C(data_shards + parity_shards, data_shards)`
+    /// of them, so codecs with more than `MAX_CHECKED_SUBMATRICES` are
+    /// refused, see `new_with_matrix_unchecked` for those.
+    ///
+    /// Returns `Error::TooFewDataShards` if `data_shards == 0`.
+    ///
+    /// Returns `Error::TooFewParityShards` if `parity_shards == 0`.
+    ///
+    /// Returns `Error::TooManyShards` if `data_shards + parity_shards > F::ORDER`,
+    /// or if there are more than `MAX_CHECKED_SUBMATRICES` submatrices to
+    /// check, with the most shards for which there are not.
+    ///
+    /// Returns `Error::TooFewParityShards` or `Error::TooManyParityShards`
+    /// if the number of rows does not match `parity_shards`.
+    ///
+    /// Returns `Error::InvalidMatrix` if a row is not of length `data_shards`,
+    /// or if some set of `data_shards` shards cannot be reconstructed from.
+    pub fn new_with_matrix<T: AsRef<[F::Elem]>>(
+        data_shards: usize,
+        parity_shards: usize,
+        parity_rows: &[T],
+    ) -> Result<Self, Error> {
+        Self::with_parity_rows(data_shards, parity_shards, parity_rows, true)
+    }
+
+    /// Creates a codec from the provided parity rows like `new_with_matrix`,
+    /// without checking that every set of `data_shards` shards can be
+    /// reconstructed from, e.g. for matrices known to be MDS which are
+    /// too large to check.
+    ///
+    /// Reconstruction from a set of shards whose submatrix is singular
+    /// fails with `Error::InvalidMatrix`.
+    ///
+    /// Returns the errors of `new_with_matrix`, except for those of
+    /// the reconstructibility check.
+    pub fn new_with_matrix_unchecked<T: AsRef<[F::Elem]>>(
+        data_shards: usize,
+        parity_shards: usize,
+        parity_rows: &[T],
+    ) -> Result<Self, Error> {
+        Self::with_parity_rows(data_shards, parity_shards, parity_rows, false)
+    }
+
+    fn with_parity_rows<T: AsRef<[F::Elem]>>(
+        data_shards: usize,
+        parity_shards: usize,
+        parity_rows: &[T],
+        checked: bool,
+    ) -> Result<Self, Error> {
+        let builder = Self::builder(data_shards, parity_shards);
+        builder.check_shard_counts()?;
+
+        if parity_rows.len() < parity_shards {
+            return Err(Error::TooFewParityShards);
+        }
+        if parity_rows.len() > parity_shards {
+            return Err(Error::TooManyParityShards);
+        }
+
+        let total_shards = data_shards + parity_shards;
+
+        let mut matrix = Matrix::new(total_shards, data_shards);
+        for i in 0..data_shards {
+            matrix.set(i, i, F::one());
+        }
+        for (i, row) in parity_rows.iter().enumerate() {
+            let row = row.as_ref();
+            if row.len() != data_shards {
+                return Err(Error::InvalidMatrix);
+            }
+            for (c, &x) in row.iter().enumerate() {
+                matrix.set(data_shards + i, c, x);
+            }
+        }
+
+        if checked {
+            let max = Self::max_checked_shards(data_shards);
+            if total_shards > max {
+                return Err(Error::TooManyShards { max });
+            }
+            if !Self::is_reconstructible(&matrix, data_shards, total_shards) {
+                return Err(Error::InvalidMatrix);
+            }
+        }
+
+        Ok(builder.build_with_matrix(matrix, None))
+    }
+
+    fn build_non_systematic_matrix(
+        data_shards: usize,
+        total_shards: usize,
+    ) -> (Matrix<F>, DataMixing<F>) {
+        // Unlike `Matrix::vandermonde`, zero is skipped, so that no row
+        // is a unit vector, i.e. no shard is a plain copy of a data shard.
+        let mut vandermonde = Matrix::new(total_shards, data_shards);
+        for r in 0..total_shards {
+            let r_a = F::nth(r + 1);
+            for c in 0..data_shards {
+                vandermonde.set(r, c, F::exp(r_a, c));
+            }
+        }
+
+        // The shards are the Vandermonde matrix applied to the data, which
+        // equals the systematic matrix below applied to the data mixed by `top`.
+        let top = vandermonde.sub_matrix(0, 0, data_shards, data_shards);
+        let top_inv = top.invert().unwrap();
+
+        let matrix = vandermonde.multiply(&top_inv);
+
+        let data_mixing = DataMixing {
+            mix: Arc::new(top),
+            unmix: Arc::new(top_inv),
+        };
+
+        (matrix, data_mixing)
+    }
+
+    /// Creates a codec with `parity_shards` parity shards whose encoding
+    /// matrix extends the one of this codec, e.g. to increase durability
+    /// of stored stripes later on.
+    ///
+    /// The existing parity shards stay correct for the returned codec,
+    /// so only the new ones need to be computed, see `encode_parity_from`.
+    /// The inversion cache limit, the working memory limit and the platform
+    /// are carried over.
+    ///
+    /// Returns `Error::TooFewParityShards` if `parity_shards` is smaller than
+    /// the parity shard count, `Error::TooManyShards` if the shard count
+    /// exceeds the field order, or the limit of `MatrixKind::AdditiveFft`
+    /// for codecs built with it, and `Error::InvalidMatrix` if the encoding
+    /// matrix was provided to `new_with_matrix` and cannot be extended.
+    pub fn grow_parity(&self, parity_shards: usize) -> Result<Self, Error> {
+        if parity_shards < self.parity_shard_count {
+            return Err(Error::TooFewParityShards);
+        }
+
+        let mut builder = Self::builder(self.data_shard_count, parity_shards)
+            .inversion_cache_limit(self.tree.indices_limit())
+            .inversion_cache_policy(self.tree.policy())
+            .systematic(self.data_mixing.is_none());
+        builder.max_working_memory = self.max_working_memory;
+        builder.platform = self.platform;
+        builder.check_shard_counts()?;
+
+        let total_shards = self.data_shard_count + parity_shards;
+
+        // The rows of all the matrices built in here only depend on the row
+        // index and the data shard count, so the smaller matrix is a prefix
+        // of the larger one when it was built in the same way.
+        let candidates = if self.data_mixing.is_some() {
+            if total_shards == F::ORDER {
+                return Err(Error::TooManyShards { max: F::ORDER - 1 });
+            }
+
+            let (matrix, data_mixing) =
+                Self::build_non_systematic_matrix(self.data_shard_count, total_shards);
+
+            vec![(matrix, Some(data_mixing))]
+        } else if self.fft.is_some() {
+            let max = fft::max_shards::<F>(self.data_shard_count);
+            if total_shards > max {
+                return Err(Error::TooManyShards { max });
+            }
+
+            builder = builder.matrix_kind(MatrixKind::AdditiveFft);
+            let matrix =
+                Self::build_matrix(MatrixKind::AdditiveFft, self.data_shard_count, total_shards);
+
+            vec![(matrix, None)]
+        } else {
+            [MatrixKind::Vandermonde, MatrixKind::Cauchy]
+                .iter()
+                .map(|&kind| {
+                    (
+                        Self::build_matrix(kind, self.data_shard_count, total_shards),
+                        None,
+                    )
+                })
+                .collect()
+        };
+
+        candidates
+            .into_iter()
+            .find(|(matrix, _)| {
+                (0..self.total_shard_count).all(|i| matrix.get_row(i) == self.matrix.get_row(i))
+            })
+            .map(|(matrix, data_mixing)| builder.build_with_matrix(matrix, data_mixing))
+            .ok_or(Error::InvalidMatrix)
+    }
+
+    /// Creates a shortened codec, where the data shards at the positions
+    /// `absent_data_shards` are permanently absent and taken to be zero,
+    /// e.g. to store small objects in a fixed 10+4 layout.
+    ///
+    /// The data shards of the returned codec are the remaining data shards
+    /// in order, and its parity shards are the ones of this codec computed
+    /// with zeros in place of the absent data shards. No matrix is inverted,
+    /// as the columns of the absent data shards are simply left out of the
+    /// encoding matrix. The inversion cache limit, the working memory
+    /// limit and the platform are carried over.
+    ///
+    /// Returns `Error::InvalidIndex` if a position is not a data shard index
+    /// or is repeated, `Error::TooFewDataShards` if all data shards are
+    /// absent, and `Error::InvalidMatrix` if the codec is not systematic.
+    pub fn shorten(&self, absent_data_shards: &[usize]) -> Result<Self, Error> {
+        if self.data_mixing.is_some() {
+            return Err(Error::InvalidMatrix);
+        }
+
+        let mut absent = vec![false; self.data_shard_count];
+        for &i in absent_data_shards {
+            if i >= self.data_shard_count || absent[i] {
+                return Err(Error::InvalidIndex);
+            }
+            absent[i] = true;
+        }
+
+        let present: SmallVec<[usize; 32]> =
+            (0..self.data_shard_count).filter(|&i| !absent[i]).collect();
+
+        let mut builder = Self::builder(present.len(), self.parity_shard_count)
+            .inversion_cache_limit(self.tree.indices_limit())
+            .inversion_cache_policy(self.tree.policy());
+        builder.max_working_memory = self.max_working_memory;
+        builder.platform = self.platform;
+        builder.check_shard_counts()?;
+
+        let data_shards = present.len();
+
+        let mut matrix = Matrix::new(data_shards + self.parity_shard_count, data_shards);
+        for i in 0..data_shards {
+            matrix.set(i, i, F::one());
+        }
+        for (i, row) in self.get_parity_rows().iter().enumerate() {
+            for (c, &j) in present.iter().enumerate() {
+                matrix.set(data_shards + i, c, row[j]);
+            }
+        }
+
+        Ok(builder.build_with_matrix(matrix, None))
+    }
+
+    // The most shards for which there are at most `MAX_CHECKED_SUBMATRICES`
+    // sets of `data_shards` shards, i.e. submatrices `is_reconstructible`
+    // inverts.
+    const fn max_checked_shards(data_shards: usize) -> usize {
+        let max_submatrices = MAX_CHECKED_SUBMATRICES as u64;
+        // C(total_shards, data_shards)
+        let mut submatrices = 1_u64;
+        let mut total_shards = data_shards;
+        while total_shards < F::ORDER {
+            let next = submatrices * (total_shards as u64 + 1)
+                / (total_shards as u64 + 1 - data_shards as u64);
+            if next > max_submatrices {
+                break;
+            }
+            submatrices = next;
+            total_shards += 1;
+        }
+        total_shards
+    }
+
+    /// Checks that every `data_shards` rows of `matrix` form an invertible matrix.
+    fn is_reconstructible(matrix: &Matrix<F>, data_shards: usize, total_shards: usize) -> bool {
+        // start with the first `data_shards` rows, then step through
+        // the combinations in lexicographic order
+        let mut rows: Vec<usize> = (0..data_shards).collect();
+        let mut sub_matrix: Matrix<F> = Matrix::new(data_shards, data_shards);
+
+        loop {
+            for (sub_matrix_row, &row) in rows.iter().enumerate() {
+                for c in 0..data_shards {
+                    sub_matrix.set(sub_matrix_row, c, matrix.get(row, c));
+                }
+            }
+            if sub_matrix.invert().is_err() {
+                return false;
+            }
+
+            // find the rightmost row which can still be moved down
+            match (0..data_shards)
+                .rev()
+                .find(|&i| rows[i] < total_shards - data_shards + i)
+            {
+                Some(i) => {
+                    rows[i] += 1;
+                    for j in i + 1..data_shards {
+                        rows[j] = rows[j - 1] + 1;
+                    }
+                }
+                None => return true,
+            }
+        }
+    }
+
+    /// Creates a builder for a codec with non-default configuration,
+    /// see `ReedSolomonBuilder`.
+    pub const fn builder(data_shards: usize, parity_shards: usize) -> ReedSolomonBuilder<F> {
+        ReedSolomonBuilder::new(data_shards, parity_shards)
+    }
+
+    pub fn data_shard_count(&self) -> usize {
+        self.data_shard_count
+    }
+
+    pub fn parity_shard_count(&self) -> usize {
+        self.parity_shard_count
+    }
+
+    pub fn total_shard_count(&self) -> usize {
+        self.total_shard_count
+    }
+
+    /// Returns the parity rows of the encoding matrix, where `rows[i][j]` is
+    /// the coefficient of data shard `j` in parity shard `i`.
+    ///
+    /// For a systematic codec, passing the rows to `new_with_matrix`
+    /// gives a codec compatible with this one, e.g. to persist the rows
+    /// alongside the shards to guarantee they stay decodable. For a
+    /// non-systematic codec, the rows apply to the first `data_shard_count`
+    /// shards rather than to the data, see `encoding_matrix`.
+    pub fn parity_matrix(&self) -> Vec<Vec<F::Elem>> {
+        self.get_parity_rows()
+            .into_iter()
+            .map(<[F::Elem]>::to_vec)
+            .collect()
+    }
+
+    /// Returns the full encoding matrix, where `rows[i][j]` is the
+    /// coefficient of data element `j` in shard `i`.
+    ///
+    /// For a systematic codec, the first `data_shard_count` rows form the
+    /// identity matrix and the remaining rows are those of `parity_matrix`.
+    /// For a non-systematic codec, the data mixing is included, so the
+    /// matrix maps the data as passed to `encode_data` to every shard.
+    pub fn encoding_matrix(&self) -> Vec<Vec<F::Elem>> {
+        let mixed = self
+            .data_mixing
+            .as_ref()
+            .map(|mixing| self.matrix.multiply(&mixing.mix));
+        let matrix = mixed.as_ref().unwrap_or(&self.matrix);
+
+        (0..self.total_shard_count)
+            .map(|i| matrix.get_row(i).to_vec())
+            .collect()
+    }
+
+    /// Returns the totals of the work done by the codec since its creation
+    /// or the last call to `reset_stats`.
+    ///
+    /// Taking the difference of the totals before and after a call gives
+    /// the work done by that call, e.g. to compare the write amplification
+    /// of different geometries.
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> Stats {
+        self.counters.snapshot()
+    }
+
+    /// Resets the totals returned by `stats`.
+    #[cfg(feature = "metrics")]
+    pub fn reset_stats(&self) {
+        self.counters.reset();
+    }
+
+    /// Registers a secondary implementation to compare the codec against,
+    /// or unregisters it if `shadow` is `None`.
+    ///
+    /// `encode_sep`, and with it `encode`, and every reconstruction also
+    /// run the secondary implementation on the same shards, and report
+    /// the shards whose outputs differ to `Shadow::divergence`. The outputs
+    /// of the codec are the ones kept. Reconstructions compare only the
+    /// shards they rebuild, and the secondary implementation is given the
+    /// shards the codec reconstructs from.
+    ///
+    /// Clones of the codec share the secondary implementation, codecs
+    /// thawed from a `FrozenCodec` do not.
+    #[cfg(feature = "shadow")]
+    pub fn set_shadow(&mut self, shadow: Option<Arc<dyn Shadow<F>>>) {
+        self.shadow = shadow::Slot(shadow);
+    }
+
+    /// Records work done for `stats`, counted in field elements.
+    #[cfg(feature = "metrics")]
+    #[inline]
+    fn record(&self, elems_read: usize, elems_written: usize, mul_ops: usize) {
+        let elem_size = std::mem::size_of::<F::Elem>();
+        self.counters
+            .record(elems_read * elem_size, elems_written * elem_size, mul_ops);
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    #[allow(clippy::unused_self)]
+    #[inline]
+    const fn record(&self, _elems_read: usize, _elems_written: usize, _mul_ops: usize) {}
+
+    /// Returns the platform the codec was built with, see
+    /// `ReedSolomonBuilder::platform`, or `None` if it detects the fastest one.
+    pub const fn platform(&self) -> Option<Platform> {
+        self.platform
+    }
+
+    pub(crate) const fn is_systematic(&self) -> bool {
+        self.data_mixing.is_none()
+    }
+
+    fn mul_slice(&self, c: F::Elem, input: &[F::Elem], out: &mut [F::Elem]) {
+        mul_slice::<F>(self.platform, c, input, out);
+    }
+
+    fn mul_slice_add(&self, c: F::Elem, input: &[F::Elem], out: &mut [F::Elem]) {
+        mul_slice_add::<F>(self.platform, c, input, out);
+    }
+
+    /// Takes a read-only snapshot of the codec, see `FrozenCodec`.
+    pub fn freeze(&self) -> FrozenCodec<F> {
+        FrozenCodec {
+            data_shard_count: self.data_shard_count,
+            parity_shard_count: self.parity_shard_count,
+            matrix: Arc::clone(&self.matrix),
+            data_mixing: self.data_mixing.clone(),
+            small_decoder: self.small_decoder.clone(),
+            fft: self.fft.clone(),
+            inversion_cache_limit: self.tree.indices_limit(),
+            inversion_cache_policy: self.tree.policy(),
+            precomputed_erasures: self.precomputed_erasures,
+            max_working_memory: self.max_working_memory,
+            platform: self.platform,
+        }
+    }
+
+    /// Exports the keys of the decode matrices cached in the inversion tree.
+    ///
+    /// Only the sets of missing shard indices are recorded, as the decode
+    /// matrices are fully determined by them and the codec configuration.
+    /// The format is independent of the field element representation and
+    /// is stable across versions.
+    ///
+    /// Codecs with at most 4 data shards and 8 shards in total have every
+    /// decode matrix computed when they are created, so they never cache any.
+    ///
+    /// See `import_inversion_cache`.
+    pub fn export_inversion_cache(&self) -> Vec<u8> {
+        let keys = self.tree.cached_indices();
+
+        let mut bytes = Vec::new();
+        bytes.push(format::INVERSION_CACHE_VERSION);
+        push_u32(&mut bytes, F::ORDER);
+        push_u32(&mut bytes, self.data_shard_count);
+        push_u32(&mut bytes, self.parity_shard_count);
+        push_u32(&mut bytes, keys.len());
+        for invalid_indices in keys {
+            push_u32(&mut bytes, invalid_indices.len());
+            for index in invalid_indices {
+                push_u32(&mut bytes, index);
+            }
+        }
+
+        bytes
+    }
+
+    /// Computes and caches the decode matrices recorded by
+    /// `export_inversion_cache`, so they need not be computed
+    /// on the first reconstruction involving the same missing shards.
+    ///
+    /// Matrices which are already cached are left as is, and the cache
+    /// limit of the codec is respected.
+    ///
+    /// Returns `Error::InvalidInversionCache` if `bytes` is malformed or
+    /// was exported from a codec with a different field or shard counts,
+    /// in which case the cache is left untouched.
+    pub fn import_inversion_cache(&self, bytes: &[u8]) -> Result<(), Error> {
+        let keys = self.parse_inversion_cache(bytes)?;
+
+        for invalid_indices in keys {
+            let valid_indices: SmallVec<[usize; 32]> = (0..self.total_shard_count)
+                .filter(|i| !invalid_indices.contains(i))
+                .take(self.data_shard_count)
+                .collect();
+
+            self.get_data_decode_matrix(&valid_indices, &invalid_indices)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn parse_inversion_cache(&self, bytes: &[u8]) -> Result<Vec<Vec<usize>>, Error> {
+        let (&version, mut rest) = bytes.split_first().ok_or(Error::InvalidInversionCache)?;
+
+        // the order is truncated by `push_u32` for GF(2^64)
+        if version != format::INVERSION_CACHE_VERSION
+            || take_u32(&mut rest)? != F::ORDER as u32 as usize
+            || take_u32(&mut rest)? != self.data_shard_count
+            || take_u32(&mut rest)? != self.parity_shard_count
+        {
+            return Err(Error::InvalidInversionCache);
+        }
+
+        let key_count = take_u32(&mut rest)?;
+        let mut keys = Vec::new();
+        for _ in 0..key_count {
+            let len = take_u32(&mut rest)?;
+            // at least one shard must be missing and the rest must be
+            // enough to reconstruct from
+            if len == 0 || len > self.parity_shard_count {
+                return Err(Error::InvalidInversionCache);
+            }
+
+            let mut invalid_indices = Vec::with_capacity(len);
+            for _ in 0..len {
+                let index = take_u32(&mut rest)?;
+                // indices must be strictly increasing and in range
+                if index >= self.total_shard_count
+                    || matches!(invalid_indices.last(), Some(&last) if index <= last)
+                {
+                    return Err(Error::InvalidInversionCache);
+                }
+                invalid_indices.push(index);
+            }
+            keys.push(invalid_indices);
+        }
+
+        if !rest.is_empty() {
+            return Err(Error::InvalidInversionCache);
+        }
+
+        Ok(keys)
+    }
+
+    // Codes long shards one cache-sized block of every shard at a time,
+    // rather than streaming each input through all of the outputs, which
+    // would go out to memory for every input once the outputs exceed the cache.
+    fn code_some_slices<T: AsRef<[F::Elem]>, U: AsMut<[F::Elem]>>(
+        &self,
+        matrix_rows: &[&[F::Elem]],
+        inputs: &[T],
+        outputs: &mut [U],
+    ) {
+        if outputs.is_empty() {
+            return;
+        }
+
+        let shard_len = inputs[0].as_ref().len();
+        let block_len = Self::cache_block_len(self.data_shard_count + outputs.len());
+        if shard_len <= block_len {
+            for i_input in 0..self.data_shard_count {
+                self.code_single_slice(matrix_rows, i_input, inputs[i_input].as_ref(), outputs);
+            }
+            return;
+        }
+
+        let mut offset = 0;
+        while offset < shard_len {
+            let end = shard_len.min(offset + block_len);
+
+            let mut output_blocks: SmallVec<[&mut [F::Elem]; 32]> = outputs
+                .iter_mut()
+                .map(|output| &mut output.as_mut()[offset..end])
+                .collect();
+            for i_input in 0..self.data_shard_count {
+                let input_block = &inputs[i_input].as_ref()[offset..end];
+                self.code_single_slice(matrix_rows, i_input, input_block, &mut output_blocks);
+            }
+
+            offset = end;
+        }
+    }
+
+    // The number of elements of each of `shards` shards which fit into
+    // `CACHE_BLOCK_BYTES`, in whole cache lines so that the vectorized
+    // kernels are not left with a tail in every block.
+    const fn cache_block_len(shards: usize) -> usize {
+        let bytes = CACHE_BLOCK_BYTES / shards / 64 * 64;
+        Self::elems_in(if bytes == 0 { 64 } else { bytes })
+    }
+
+    // Computes all of the parity shards, with the additive FFT
+    // where it takes fewer multiplications than the encoding matrix.
+    fn code_parity<T: AsRef<[F::Elem]>, U: AsMut<[F::Elem]>>(
+        &self,
+        parity_rows: &[&[F::Elem]],
+        data: &[T],
+        parity: &mut [U],
+    ) {
+        match &self.fft {
+            Some(fft) if fft.encode_cost() < self.data_shard_count * self.parity_shard_count => {
+                let shard_len = data[0].as_ref().len();
+                let mul_ops = fft.encode(self.platform, data, parity);
+                self.record(
+                    self.data_shard_count * shard_len,
+                    self.parity_shard_count * shard_len,
+                    mul_ops,
+                );
+            }
+            _ => self.code_some_slices(parity_rows, data, parity),
+        }
+    }
+
+    fn code_single_slice<U: AsMut<[F::Elem]>>(
+        &self,
+        matrix_rows: &[&[F::Elem]],
+        i_input: usize,
+        input: &[F::Elem],
+        outputs: &mut [U],
+    ) {
+        if outputs.is_empty() {
+            return;
+        }
+
+        let coded = input.len() * outputs.len();
+        self.record(input.len(), if i_input == 0 { coded } else { 0 }, coded);
+
+        // all rows at once, so that fields with fused kernels
+        // pass over the input only once for several of them
+        let coefficients: SmallVec<[F::Elem; 32]> = matrix_rows[..outputs.len()]
+            .iter()
+            .map(|row| row[i_input])
+            .collect();
+        let mut outputs: SmallVec<[&mut [F::Elem]; 32]> =
+            outputs.iter_mut().map(AsMut::as_mut).collect();
+
+        let platform = self.platform.unwrap_or_else(Platform::detect);
+        if i_input == 0 {
+            F::mul_slice_rows_on(platform, &coefficients, input, &mut outputs);
+        } else {
+            F::mul_slice_add_rows_on(platform, &coefficients, input, &mut outputs);
+        }
+    }
+
+    fn check_some_slices_with_buffer<T, U>(
+        &self,
+        matrix_rows: &[&[F::Elem]],
+        inputs: &[T],
+        to_check: &[T],
+        buffer: &mut [U],
+    ) -> bool
+    where
+        T: AsRef<[F::Elem]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        self.code_some_slices(matrix_rows, inputs, buffer);
+
+        let at_least_one_mismatch_present = buffer
+            .iter_mut()
+            .enumerate()
+            .map(|(i, expected_parity_shard)| {
+                expected_parity_shard.as_ref() == to_check[i].as_ref()
+            })
+            .any(|x| !x); // find the first false (some slice is different from the expected one)
+        !at_least_one_mismatch_present
+    }
+
+    /// Constructs all shards from the data.
+    ///
+    /// For a systematic codec this copies the data into the data shards
+    /// then constructs the parity shards, see `encode_sep`. For a
+    /// non-systematic codec every shard is coded.
+    ///
+    /// The shards of a non-systematic codec can be verified and reconstructed
+    /// as usual, with the coded shards taking the place of the data shards.
+    pub fn encode_data<T, U>(&self, data: &[T], shards: &mut [U]) -> Result<(), Error>
+    where
+        T: AsRef<[F::Elem]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        check_piece_count!(data => self, data);
+        check_piece_count!(all => self, shards);
+        check_slices!(multi => data, multi => shards);
+
+        let (coded, parity) = shards.split_at_mut(self.data_shard_count);
+
+        match self.data_mixing {
+            Some(ref data_mixing) => {
+                let mix_rows: SmallVec<[&[F::Elem]; 32]> = (0..self.data_shard_count)
+                    .map(|i| data_mixing.mix.get_row(i))
+                    .collect();
+
+                self.code_some_slices(&mix_rows, data, coded);
+            }
+            None => {
+                for (c, d) in coded.iter_mut().zip(data) {
+                    c.as_mut().copy_from_slice(d.as_ref());
+                }
+            }
+        }
+
+        self.encode_sep(coded, parity)
+    }
+
+    /// Reconstructs the data shards if needed, then writes the data into `data`.
+    ///
+    /// This is the inverse of `encode_data`, see `reconstruct_data`
+    /// for the requirements on `shards`.
+    pub fn decode_data<T, U>(&self, shards: &mut [T], data: &mut [U]) -> Result<(), Error>
+    where
+        T: ReconstructShard<F>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        check_piece_count!(data => self, data);
+        check_slices!(multi => data);
+
+        self.reconstruct_data(shards)?;
+
+        let coded: SmallVec<[&[F::Elem]; 32]> = shards[..self.data_shard_count]
+            .iter_mut()
+            .map(|shard| &*shard.get().expect("data shards reconstructed; qed"))
+            .collect();
+
+        check_slices!(multi => coded, single => data[0]);
+
+        match self.data_mixing {
+            Some(ref data_mixing) => {
+                let unmix_rows: SmallVec<[&[F::Elem]; 32]> = (0..self.data_shard_count)
+                    .map(|i| data_mixing.unmix.get_row(i))
+                    .collect();
+
+                self.code_some_slices(&unmix_rows, &coded, data);
+            }
+            None => {
+                for (d, c) in data.iter_mut().zip(coded) {
+                    d.as_mut().copy_from_slice(c);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts a stripe of this codec into a stripe of `target`, e.g. when
+    /// expanding a cluster from 6+3 to 10+4.
+    ///
+    /// The data, i.e. the data shards of this codec one after another, is
+    /// split into the data shards of `target`, the last of which is padded
+    /// with zeros. Missing data shards are reconstructed first, like in
+    /// `reconstruct_data`, so `shards` is modified in place.
+    ///
+    /// Work already done is reused where possible: the data shards are only
+    /// copied, and when both codecs have the same data shard count, the
+    /// present parity shards of this codec whose encoding matrix row also
+    /// occurs in `target` are copied instead of recomputed. With the default
+    /// matrix this is the case for the first parity shards when only the
+    /// parity shard count changes.
+    ///
+    /// Returns the `data shard count + parity shard count` shards of `target`.
+    pub fn reshard<T: ReconstructShard<F>>(
+        &self,
+        shards: &mut [T],
+        target: &Self,
+    ) -> Result<Vec<Vec<F::Elem>>, Error> {
+        check_piece_count!(all => self, shards);
+
+        let parity_present: SmallVec<[bool; 32]> = shards[self.data_shard_count..]
+            .iter()
+            .map(|shard| shard.len().is_some())
+            .collect();
+
+        self.reconstruct_data(shards)?;
+
+        let shard_len = shards[0].len().expect("data shards reconstructed; qed");
+
+        let unmixed = match self.data_mixing {
+            Some(_) => {
+                let mut data = vec![vec![F::zero(); shard_len]; self.data_shard_count];
+                self.decode_data(shards, &mut data)?;
+                Some(data)
+            }
+            None => None,
+        };
+
+        let (data, parity) = shards.split_at_mut(self.data_shard_count);
+
+        let source: SmallVec<[&[F::Elem]; 32]> = unmixed.as_ref().map_or_else(
+            || {
+                data.iter_mut()
+                    .map(|shard| &*shard.get().expect("data shards reconstructed; qed"))
+                    .collect()
+            },
+            |data| data.iter().map(Vec::as_slice).collect(),
+        );
+
+        let data_len = self.data_shard_count * shard_len;
+        let new_len = data_len.div_ceil(target.data_shard_count);
+
+        let mut elems = source
+            .iter()
+            .flat_map(|shard| shard.iter().copied())
+            .chain(iter::repeat(F::zero()));
+        let new_data: Vec<Vec<F::Elem>> = (0..target.data_shard_count)
+            .map(|_| elems.by_ref().take(new_len).collect())
+            .collect();
+
+        if target.data_mixing.is_some() {
+            let mut new_shards = vec![vec![F::zero(); new_len]; target.total_shard_count];
+            target.encode_data(&new_data, &mut new_shards)?;
+
+            return Ok(new_shards);
+        }
+
+        let mut new_shards = new_data;
+        new_shards.resize(target.total_shard_count, vec![F::zero(); new_len]);
+
+        {
+            let (new_data, new_parity) = new_shards.split_at_mut(target.data_shard_count);
+
+            let reusable =
+                self.data_shard_count == target.data_shard_count && self.data_mixing.is_none();
+            let source_rows = self.get_parity_rows();
+
+            let mut rows: SmallVec<[&[F::Elem]; 32]> = SmallVec::new();
+            let mut outputs: SmallVec<[&mut Vec<F::Elem>; 32]> = SmallVec::new();
+
+            for (row, output) in target.get_parity_rows().into_iter().zip(new_parity) {
+                let reused = source_rows
+                    .iter()
+                    .zip(parity.iter_mut())
+                    .zip(&parity_present)
+                    .find(|((source_row, _), &present)| reusable && present && **source_row == row)
+                    .and_then(|((_, shard), _)| shard.get());
+
+                if let Some(shard) = reused {
+                    output.copy_from_slice(shard);
+                } else {
+                    rows.push(row);
+                    outputs.push(output);
+                }
+            }
+
+            target.code_some_slices(&rows, new_data, &mut outputs);
+        }
+
+        Ok(new_shards)
+    }
+
+    /// Constructs the parity shards partially using only the data shard
+    /// indexed by `i_data`.
+    ///
+    /// The slots where the parity shards sit at will be overwritten.
+    ///
+    /// # Warning
+    ///
+    /// You must apply this method on the data shards in strict sequential order (0..data shard count),
+    /// otherwise the parity shards will be incorrect.
+    ///
+    /// It is recommended to use the `ShardByShard` bookkeeping struct instead of this method directly.
+    pub fn encode_single<T, U>(&self, i_data: usize, mut shards: T) -> Result<(), Error>
+    where
+        T: AsRef<[U]> + AsMut<[U]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        let slices = shards.as_mut();
+
+        check_slice_index!(data => self, i_data);
+        check_piece_count!(all=> self, slices);
+        check_slices!(multi => slices);
+
+        // Get the slice of output buffers.
+        let (mut_input, output) = slices.split_at_mut(self.data_shard_count);
+
+        let input = mut_input[i_data].as_ref();
+
+        self.encode_single_sep(i_data, input, output)
+    }
+
+    /// Constructs the parity shards partially using only the data shard provided.
+    ///
+    /// The data shard must match the index `i_data`.
+    ///
+    /// The slots where the parity shards sit at will be overwritten.
+    ///
+    /// # Warning
+    ///
+    /// You must apply this method on the data shards in strict sequential order (0..data shard count),
+    /// otherwise the parity shards will be incorrect.
+    ///
+    /// It is recommended to use the `ShardByShard` bookkeeping struct instead of this method directly.
+    pub fn encode_single_sep<U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        i_data: usize,
+        single_data: &[F::Elem],
+        parity: &mut [U],
+    ) -> Result<(), Error> {
+        check_slice_index!(data => self, i_data);
+        check_piece_count!(parity => self, parity);
+        check_slices!(multi => parity, single => single_data, index => i_data);
+
+        let parity_rows = self.get_parity_rows();
+
+        // Do the coding.
+        self.code_single_slice(&parity_rows, i_data, single_data, parity);
+
+        Ok(())
+    }
+
+    /// Computes the parity shards for a new version of the data shard
+    /// indexed by `i_data`, from the parity shards of the old version.
+    ///
+    /// Only the changed data shard is read, as the parity is updated by the
+    /// difference between `old_data` and `new_data`. The new parity shards
+    /// are written into `new_parity` rather than over `parity`, so the old
+    /// version of the stripe stays decodable until the caller commits the
+    /// new version, e.g. in a transactional storage engine. Updates of
+    /// several data shards are chained by passing the new parity shards
+    /// of one update as the old ones of the next.
+    pub fn update_parity<T, U>(
+        &self,
+        i_data: usize,
+        old_data: &[F::Elem],
+        new_data: &[F::Elem],
+        parity: &[T],
+        new_parity: &mut [U],
+    ) -> Result<(), Error>
+    where
+        T: AsRef<[F::Elem]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        check_slice_index!(data => self, i_data);
+        check_piece_count!(parity => self, parity);
+        check_piece_count!(parity => self, new_parity);
+        check_slices!(multi => parity, multi => new_parity);
+        check_slices!(multi => parity, single => old_data, index => i_data);
+        check_slices!(multi => parity, single => new_data, index => i_data);
+
+        let parity_rows = self.get_parity_rows();
+
+        let len = old_data.len();
+        let parity_count = self.parity_shard_count;
+        self.record(
+            len * (2 + parity_count),
+            len * parity_count,
+            2 * len * parity_count,
+        );
+
+        for ((row, old), new) in parity_rows.iter().zip(parity).zip(new_parity.iter_mut()) {
+            let new = new.as_mut();
+            new.copy_from_slice(old.as_ref());
+
+            // the field has characteristic 2, so adding the old data
+            // again removes it from the parity
+            self.mul_slice_add(row[i_data], old_data, new);
+            self.mul_slice_add(row[i_data], new_data, new);
+        }
+
+        Ok(())
+    }
+
+    /// Overwrites the data of stripe `stripe` of `backend` starting at
+    /// `offset` with `new_data`, updating the parity shards accordingly,
+    /// e.g. for a small write to a large stripe.
+    ///
+    /// The data shards of the stripe are taken as laid out back to back,
+    /// so `offset` counts elements from the start of the first data shard,
+    /// and the range may span several data shards. Only the old data of the
+    /// range and the parity over the span of affected offsets are read, and
+    /// the parity is updated by the difference between the old and the new
+    /// data, like `update_parity`.
+    ///
+    /// Everything is read before anything is written, so if a read fails,
+    /// the stripe is left untouched. The data is then written before the
+    /// parity. If a write fails, the stripe is left inconsistent, and
+    /// has to be encoded again once `backend` recovers.
+    ///
+    /// Returns an error of kind `io::ErrorKind::InvalidInput` wrapping
+    /// `Error::EmptyShard` if `new_data` is empty or the shards of the
+    /// stripe are, `Error::InvalidRange` if the range does not lie within
+    /// the data shards, and `Error::IncorrectShardSize` if `backend`
+    /// returns a range that is not of the requested length. The errors of
+    /// `backend` are returned as is.
+    pub fn update_range<B: RangeStore<F>>(
+        &self,
+        stripe: usize,
+        offset: usize,
+        new_data: &[F::Elem],
+        backend: &mut B,
+    ) -> io::Result<()> {
+        if new_data.is_empty() {
+            return Err(Error::EmptyShard.into());
+        }
+
+        let shard_len = backend.shard_len(stripe)?;
+        if shard_len == 0 {
+            return Err(Error::EmptyShard.into());
+        }
+        match offset.checked_add(new_data.len()) {
+            Some(end) if end <= self.data_shard_count * shard_len => {}
+            _ => return Err(Error::InvalidRange.into()),
+        }
+
+        // The parts of the range in each data shard, as the data shard,
+        // the offset in the shard, the offset in `new_data` and the length.
+        let mut parts: SmallVec<[(usize, usize, usize, usize); 32]> = SmallVec::new();
+        let mut pos = 0;
+        while pos < new_data.len() {
+            let within = (offset + pos) % shard_len;
+            let len = (shard_len - within).min(new_data.len() - pos);
+            parts.push(((offset + pos) / shard_len, within, pos, len));
+            pos += len;
+        }
+
+        let start = parts.iter().map(|x| x.1).min().unwrap();
+        let end = parts.iter().map(|x| x.1 + x.3).max().unwrap();
+
+        let mut read = |shard: usize, offset: usize, len: usize| -> io::Result<_> {
+            let range = backend.read(stripe, shard, offset, len)?;
+            if range.len() != len {
+                return Err(Error::IncorrectShardSize {
+                    shard,
+                    expected: len,
+                    actual: range.len(),
+                }
+                .into());
+            }
+            Ok(range)
+        };
+
+        let mut old_data = Vec::with_capacity(parts.len());
+        for &(i, at, _, len) in &parts {
+            old_data.push(read(i, at, len)?);
+        }
+        let mut parity = Vec::with_capacity(self.parity_shard_count);
+        for i in self.data_shard_count..self.total_shard_count {
+            parity.push(read(i, start, end - start)?);
+        }
+
+        let parity_rows = self.get_parity_rows();
+        for (&(i, at, pos, len), old) in parts.iter().zip(&old_data) {
+            for (row, parity) in parity_rows.iter().zip(parity.iter_mut()) {
+                let parity = &mut parity[at - start..at - start + len];
+
+                // the field has characteristic 2, so adding the old data
+                // again removes it from the parity
+                self.mul_slice_add(row[i], old, parity);
+                self.mul_slice_add(row[i], &new_data[pos..pos + len], parity);
+            }
+        }
+
+        let parity_len = (end - start) * self.parity_shard_count;
+        self.record(
+            2 * new_data.len() + parity_len,
+            new_data.len() + parity_len,
+            2 * new_data.len() * self.parity_shard_count,
+        );
+
+        for &(i, at, pos, len) in &parts {
+            backend.write(stripe, i, at, &new_data[pos..pos + len])?;
+        }
+        for (i, parity) in parity.iter().enumerate() {
+            backend.write(stripe, self.data_shard_count + i, start, parity)?;
+        }
+
+        Ok(())
+    }
+
+    /// Constructs the parity shards.
+    ///
+    /// The slots where the parity shards sit at will be overwritten.
+    pub fn encode<T, U>(&self, mut shards: T) -> Result<(), Error>
+    where
+        T: AsRef<[U]> + AsMut<[U]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        let slices: &mut [U] = shards.as_mut();
+
+        check_piece_count!(all => self, slices);
+        check_slices!(multi => slices);
+
+        // Get the slice of output buffers.
+        let (input, output) = slices.split_at_mut(self.data_shard_count);
+
+        self.encode_sep(&*input, output)
+    }
+
+    /// Constructs the parity shards like `encode`, reporting the progress
+    /// to `progress`, e.g. to display a progress bar for long shards.
+    ///
+    /// The shards are encoded one megabyte of each shard at a time, with a
+    /// `Progress::Bytes` reported after each, then a
+    /// `Progress::ShardCompleted` for each parity shard.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[macro_use] extern crate reed_solomon_erasure;
+    /// # fn main () {
+    /// use reed_solomon_erasure::galois_8::ReedSolomon;
+    /// use reed_solomon_erasure::Progress;
+    ///
+    /// let r = ReedSolomon::new(2, 1).unwrap();
+    /// let mut shards = shards!([0, 1], [2, 3], [0, 0]);
+    ///
+    /// let mut events = Vec::new();
+    /// r.encode_with_progress(&mut shards, |x| events.push(x)).unwrap();
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         Progress::Bytes { processed: 4, total: 4 },
+    ///         Progress::ShardCompleted(2),
+    ///     ],
+    ///     events
+    /// );
+    /// # }
+    /// ```
+    pub fn encode_with_progress<T, U, P>(&self, mut shards: T, progress: P) -> Result<(), Error>
+    where
+        T: AsRef<[U]> + AsMut<[U]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+        P: FnMut(Progress),
+    {
+        self.encode_chunked_internal(
+            shards.as_mut(),
+            Self::elems_in(PROGRESS_CHUNK_BYTES),
+            progress,
+            None,
+        )
+    }
+
+    /// Constructs the parity shards like `encode`, giving up with
+    /// `Error::Cancelled` once `cancel` is set, e.g. when a node is
+    /// asked to shut down during a background repair.
+    ///
+    /// `cancel` is checked before each megabyte of each shard, see
+    /// `encode_with_progress`. The parity shards are only partially
+    /// constructed when the call is cancelled.
+    pub fn encode_cancellable<T, U>(&self, mut shards: T, cancel: &AtomicBool) -> Result<(), Error>
+    where
+        T: AsRef<[U]> + AsMut<[U]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        self.encode_chunked_internal(
+            shards.as_mut(),
+            Self::elems_in(PROGRESS_CHUNK_BYTES),
+            |_| {},
+            Some(cancel),
+        )
+    }
+
+    /// Constructs the parity shards like `encode`, one horizontal chunk
+    /// of the stripe at a time, so the working set stays within about
+    /// `chunk_bytes` across all shards regardless of the shard length,
+    /// e.g. to keep it within the CPU cache for very long shards.
+    ///
+    /// Each chunk holds at least one element of each shard.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[macro_use] extern crate reed_solomon_erasure;
+    /// # fn main () {
+    /// use reed_solomon_erasure::galois_8::ReedSolomon;
+    ///
+    /// let r = ReedSolomon::new(2, 1).unwrap();
+    /// let mut shards = shards!([0, 1, 2, 3], [4, 5, 6, 7], [0, 0, 0, 0]);
+    ///
+    /// // 3 bytes across the 3 shards, i.e. 1 byte of each shard at a time
+    /// r.encode_chunked(&mut shards, 3).unwrap();
+    ///
+    /// assert!(r.verify(&shards).unwrap());
+    /// # }
+    /// ```
+    pub fn encode_chunked<T, U>(&self, mut shards: T, chunk_bytes: usize) -> Result<(), Error>
+    where
+        T: AsRef<[U]> + AsMut<[U]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        let chunk_len = Self::elems_in(chunk_bytes / self.total_shard_count);
+        self.encode_chunked_internal(shards.as_mut(), chunk_len, |_| {}, None)
+    }
+
+    // The number of elements of each shard to process at a time to stay
+    // within `max_working_memory`, if shards of `shard_len` elements exceed it.
+    fn window_len(&self, shard_len: usize) -> Option<usize> {
+        let window_len = Self::elems_in(self.max_working_memory? / self.total_shard_count);
+        (shard_len > window_len).then_some(window_len)
+    }
+
+    // The number of elements in `bytes` of a shard, at least one.
+    const fn elems_in(bytes: usize) -> usize {
+        let elems = bytes / std::mem::size_of::<F::Elem>();
+        if elems == 0 {
+            1
+        } else {
+            elems
+        }
+    }
+
+    fn encode_chunked_internal<U, P>(
+        &self,
+        slices: &mut [U],
+        chunk_len: usize,
+        mut progress: P,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<(), Error>
+    where
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+        P: FnMut(Progress),
+    {
+        check_piece_count!(all => self, slices);
+        check_slices!(multi => slices);
+
+        let shard_len = slices[0].as_ref().len();
+        let elem_size = std::mem::size_of::<F::Elem>();
+        let total = (self.data_shard_count * shard_len * elem_size) as u64;
+
+        let mut offset = 0;
+        while offset < shard_len {
+            if cancel.is_some_and(|x| x.load(Ordering::Relaxed)) {
+                return Err(Error::Cancelled);
+            }
+
+            let end = shard_len.min(offset + chunk_len);
+
+            let mut sub_stripe: SmallVec<[&mut [F::Elem]; 32]> = slices
+                .iter_mut()
+                .map(|slice| &mut slice.as_mut()[offset..end])
+                .collect();
+            self.encode(&mut sub_stripe[..])?;
+
+            offset = end;
+            progress(Progress::Bytes {
+                processed: (self.data_shard_count * offset * elem_size) as u64,
+                total,
+            });
+        }
+
+        for i in self.data_shard_count..self.total_shard_count {
+            progress(Progress::ShardCompleted(i));
+        }
+
+        Ok(())
+    }
+
+    /// Constructs the parity shards of many stripes in one call.
+    ///
+    /// This is equivalent to calling `encode` on each stripe, but the
+    /// encoding matrix rows are only looked up once, which matters when
+    /// there are many stripes of small shards. The stripes may have
+    /// different shard lengths.
+    ///
+    /// All stripes are checked before any is encoded, so if the method
+    /// returns an `Error`, then nothing is touched.
+    pub fn encode_batch<S, U>(&self, stripes: &mut [S]) -> Result<(), Error>
+    where
+        S: AsRef<[U]> + AsMut<[U]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        for stripe in stripes.iter() {
+            let slices: &[U] = stripe.as_ref();
+
+            check_piece_count!(all => self, slices);
+            check_slices!(multi => slices);
+        }
+
+        let parity_rows = self.get_parity_rows();
+
+        for stripe in stripes.iter_mut() {
+            let (input, output) = stripe.as_mut().split_at_mut(self.data_shard_count);
+
+            self.code_parity(&parity_rows, input, output);
+        }
+
+        Ok(())
+    }
+
+    /// Constructs the parity shards using a read-only view into the
+    /// data shards.
+    ///
+    /// The slots where the parity shards sit at will be overwritten.
+    pub fn encode_sep<T: AsRef<[F::Elem]>, U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        data: &[T],
+        parity: &mut [U],
+    ) -> Result<(), Error> {
+        check_piece_count!(data => self, data);
+        check_piece_count!(parity => self, parity);
+        check_slices!(multi => data, multi => parity);
+
+        let parity_rows = self.get_parity_rows();
+
+        // Do the coding.
+        let shard_len = data[0].as_ref().len();
+        match self.window_len(shard_len) {
+            Some(window_len) => {
+                let mut offset = 0;
+                while offset < shard_len {
+                    let end = shard_len.min(offset + window_len);
+
+                    let data_window: SmallVec<[&[F::Elem]; 32]> =
+                        data.iter().map(|x| &x.as_ref()[offset..end]).collect();
+                    let mut parity_window: SmallVec<[&mut [F::Elem]; 32]> = parity
+                        .iter_mut()
+                        .map(|x| &mut x.as_mut()[offset..end])
+                        .collect();
+                    self.code_parity(&parity_rows, &data_window, &mut parity_window);
+
+                    offset = end;
+                }
+            }
+            None => self.code_parity(&parity_rows, data, parity),
+        }
+
+        #[cfg(feature = "shadow")]
+        self.shadow
+            .check_encode(self.data_shard_count, data, parity);
+
+        Ok(())
+    }
+
+    /// Constructs only the parity shards from index `first_parity` onwards,
+    /// e.g. the new ones of a codec returned by `grow_parity`.
+    ///
+    /// `parity` holds the parity shards `first_parity..parity shard count`.
+    ///
+    /// Returns `Error::TooFewParityShards` or `Error::TooManyParityShards`
+    /// if the length of `parity` does not match.
+    pub fn encode_parity_from<T, U>(
+        &self,
+        first_parity: usize,
+        data: &[T],
+        parity: &mut [U],
+    ) -> Result<(), Error>
+    where
+        T: AsRef<[F::Elem]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        check_piece_count!(data => self, data);
+        if first_parity + parity.len() < self.parity_shard_count {
+            return Err(Error::TooFewParityShards);
+        }
+        if first_parity + parity.len() > self.parity_shard_count {
+            return Err(Error::TooManyParityShards);
+        }
+        check_slices!(multi => data, multi => parity);
+
+        let parity_rows = self.get_parity_rows();
+
+        self.code_some_slices(&parity_rows[first_parity..], data, parity);
+
+        Ok(())
+    }
+
+    /// Constructs the parity shards using a read-only view into the
+    /// data shards, writing each parity shard into a list of
+    /// non-contiguous buffers.
+    ///
+    /// Each item of `parity` is the list of chunks (e.g. `IoSliceMut`s or
+    /// pre-carved packet buffers) making up one parity shard. The chunks are
+    /// filled in order, so the parity shard is their concatenation.
+    ///
+    /// Returns `Error::IncorrectShardSize` when the total length of
+    /// the chunks of any parity shard does not match the length of
+    /// the data shards. In which case, nothing is touched.
+    pub fn encode_sep_vectored<T, V, U>(&self, data: &[T], parity: &mut [V]) -> Result<(), Error>
+    where
+        T: AsRef<[F::Elem]>,
+        V: AsMut<[U]>,
+        U: DerefMut<Target = [F::Elem]>,
+    {
+        check_piece_count!(data => self, data);
+        check_piece_count!(parity => self, parity);
+        check_slices!(multi => data);
+
+        let shard_len = data[0].as_ref().len();
+
+        for (i, chunks) in parity.iter_mut().enumerate() {
+            let total_len: usize = chunks.as_mut().iter().map(|chunk| chunk.len()).sum();
+            if total_len != shard_len {
+                return Err(Error::IncorrectShardSize {
+                    shard: i,
+                    expected: shard_len,
+                    actual: total_len,
+                });
+            }
+        }
+
+        let parity_rows = self.get_parity_rows();
+
+        let parity_count = self.parity_shard_count;
+        self.record(
+            shard_len * self.data_shard_count,
+            shard_len * parity_count,
+            shard_len * self.data_shard_count * parity_count,
+        );
+
+        for (i_row, chunks) in parity.iter_mut().enumerate() {
+            let mut offset = 0;
+
+            for chunk in chunks.as_mut().iter_mut() {
+                let chunk: &mut [F::Elem] = &mut *chunk;
+                if chunk.is_empty() {
+                    continue;
+                }
+                let end = offset + chunk.len();
+
+                for i_input in 0..self.data_shard_count {
+                    let input = &data[i_input].as_ref()[offset..end];
+
+                    if i_input == 0 {
+                        self.mul_slice(parity_rows[i_row][i_input], input, chunk);
+                    } else {
+                        self.mul_slice_add(parity_rows[i_row][i_input], input, chunk);
+                    }
+                }
+
+                offset = end;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Constructs the parity shards using a read-only view into the
+    /// data shards, where the last data shard may be shorter than the others.
+    ///
+    /// The missing tail of the last data shard is treated as zero padding,
+    /// so the parity shards are still of the full shard length, which is
+    /// the length of the other data shards and of the parity shards.
+    ///
+    /// Returns the true length of the last data shard, which is needed by
+    /// `verify_padded`, `reconstruct_padded` and `reconstruct_data_padded`.
+    ///
+    /// Returns `Error::IncorrectShardSize` when a data shard other than
+    /// the last one does not have the full shard length, or when the last
+    /// data shard is longer than the full shard length.
+    pub fn encode_sep_padded<T: AsRef<[F::Elem]>, U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        data: &[T],
+        parity: &mut [U],
+    ) -> Result<usize, Error> {
+        check_piece_count!(data => self, data);
+        check_piece_count!(parity => self, parity);
+        check_slices!(multi => parity);
+
+        let shard_len = parity[0].as_ref().len();
+        let (last, full) = data.split_last().expect("at least one data shard; qed");
+        for (i, shard) in full.iter().enumerate() {
+            if shard.as_ref().len() != shard_len {
+                return Err(Error::IncorrectShardSize {
+                    shard: i,
+                    expected: shard_len,
+                    actual: shard.as_ref().len(),
+                });
+            }
+        }
+        let last_len = last.as_ref().len();
+        if last_len > shard_len {
+            return Err(Error::IncorrectShardSize {
+                shard: full.len(),
+                expected: shard_len,
+                actual: last_len,
+            });
+        }
+
+        let parity_rows = self.get_parity_rows();
+
+        let data_len = shard_len * (self.data_shard_count - 1) + last_len;
+        let parity_count = self.parity_shard_count;
+        self.record(data_len, shard_len * parity_count, data_len * parity_count);
+
+        for (i_row, output) in parity.iter_mut().enumerate() {
+            let output = output.as_mut();
+
+            for i_input in 0..self.data_shard_count {
+                let input = data[i_input].as_ref();
+                let len = input.len();
+
+                if i_input == 0 {
+                    for x in &mut output[len..] {
+                        *x = F::zero();
+                    }
+                }
+                if len == 0 {
+                    continue;
+                }
+
+                if i_input == 0 {
+                    self.mul_slice(parity_rows[i_row][i_input], input, &mut output[..len]);
+                } else {
+                    self.mul_slice_add(parity_rows[i_row][i_input], input, &mut output[..len]);
+                }
+            }
+        }
+
+        Ok(last_len)
+    }
+
+    /// Checks if the parity shards are correct.
+    ///
+    /// This is a wrapper of `verify_with_buffer`.
+    pub fn verify<T: AsRef<[F::Elem]>>(&self, slices: &[T]) -> Result<bool, Error> {
+        check_piece_count!(all => self, slices);
+        check_slices!(multi => slices);
+
+        let slice_len = slices[0].as_ref().len();
+
+        let mut buffer: SmallVec<[Vec<F::Elem>; 32]> =
+            SmallVec::with_capacity(self.parity_shard_count);
+
+        for _ in 0..self.parity_shard_count {
+            buffer.push(vec![F::zero(); slice_len]);
+        }
+
+        self.verify_with_buffer(slices, &mut buffer)
+    }
+
+    /// Checks if the parity shards are correct at the given element
+    /// positions only.
+    ///
+    /// This costs a fraction of `verify` when few positions are sampled,
+    /// at the price of only detecting corruption that covers at least one
+    /// of them. Sampling positions at random gives probabilistic detection
+    /// suitable for periodic audits of large amounts of data.
+    ///
+    /// Returns `Error::InvalidSamplePosition` if any position is not
+    /// within the shards.
+    pub fn audit_sample<T: AsRef<[F::Elem]>>(
+        &self,
+        slices: &[T],
+        sample_positions: &[usize],
+    ) -> Result<bool, Error> {
+        check_piece_count!(all => self, slices);
+        check_slices!(multi => slices);
+
+        let slice_len = slices[0].as_ref().len();
+        if sample_positions.iter().any(|&pos| pos >= slice_len) {
+            return Err(Error::InvalidSamplePosition);
+        }
+
+        let (data, parity) = slices.split_at(self.data_shard_count);
+        let parity_rows = self.get_parity_rows();
+
+        let samples = sample_positions.len();
+        self.record(
+            samples * self.total_shard_count,
+            0,
+            samples * self.data_shard_count * self.parity_shard_count,
+        );
+
+        for &pos in sample_positions {
+            for (parity_row, parity_shard) in parity_rows.iter().zip(parity) {
+                let expected = data
+                    .iter()
+                    .zip(parity_row.iter())
+                    .fold(F::zero(), |acc, (shard, &coef)| {
+                        F::add(acc, F::mul(coef, shard.as_ref()[pos]))
+                    });
+
+                if expected != parity_shard.as_ref()[pos] {
+                    return Ok(false);
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Checks if the transmitted parity shards of a punctured stripe
+    /// are correct.
+    ///
+    /// A punctured stripe leaves out some of the parity shards by design,
+    /// e.g. to trade redundancy for bandwidth. `punctured` marks those shards
+    /// and `slices` holds only the remaining ones, in order. Unlike a missing
+    /// shard, a punctured shard is not expected, so only the transmitted
+    /// parity shards are checked.
+    ///
+    /// Returns `Error::InvalidShardFlags` if the length of `punctured` does
+    /// not match the total shard count, `Error::InvalidPuncturing` if a data
+    /// shard is marked punctured, and `Error::TooFewShards` or
+    /// `Error::TooManyShards` if the number of slices does not match
+    /// the number of shards not punctured.
+    pub fn verify_punctured<T: AsRef<[F::Elem]>>(
+        &self,
+        slices: &[T],
+        punctured: &[bool],
+    ) -> Result<bool, Error> {
+        if punctured.len() != self.total_shard_count {
+            return Err(Error::InvalidShardFlags);
+        }
+        if punctured[..self.data_shard_count].iter().any(|&x| x) {
+            return Err(Error::InvalidPuncturing);
+        }
+
+        let transmitted = punctured.iter().filter(|&&x| !x).count();
+        if slices.len() < transmitted {
+            return Err(Error::TooFewShards);
+        }
+        if slices.len() > transmitted {
+            return Err(Error::TooManyShards { max: transmitted });
+        }
+        check_slices!(multi => slices);
+
+        let (data, to_check) = slices.split_at(self.data_shard_count);
+        if to_check.is_empty() {
+            return Ok(true);
+        }
+
+        let parity_rows: SmallVec<[&[F::Elem]; 32]> = self
+            .get_parity_rows()
+            .into_iter()
+            .zip(&punctured[self.data_shard_count..])
+            .filter(|(_, &x)| !x)
+            .map(|(row, _)| row)
+            .collect();
+
+        let slice_len = slices[0].as_ref().len();
+        let mut buffer: SmallVec<[Vec<F::Elem>; 32]> = SmallVec::with_capacity(to_check.len());
+
+        for _ in 0..to_check.len() {
+            buffer.push(vec![F::zero(); slice_len]);
+        }
+
+        Ok(self.check_some_slices_with_buffer(&parity_rows, data, to_check, &mut buffer))
+    }
+
+    /// Checks if the parity shards are correct.
+    pub fn verify_with_buffer<T, U>(&self, slices: &[T], buffer: &mut [U]) -> Result<bool, Error>
+    where
+        T: AsRef<[F::Elem]>,
+        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
+    {
+        check_piece_count!(all => self, slices);
+        check_piece_count!(parity_buf => self, buffer);
+        check_slices!(multi => slices, multi => buffer);
+
+        let data = &slices[0..self.data_shard_count];
+        let to_check = &slices[self.data_shard_count..];
+
+        let parity_rows = self.get_parity_rows();
+
+        Ok(self.check_some_slices_with_buffer(&parity_rows, data, to_check, buffer))
+    }
+
+    /// Checks if the parity shards are correct, where the last data shard
+    /// may be shorter than the others, see `encode_sep_padded`.
+    ///
+    /// `last_len` is the true length of the last data shard.
+    pub fn verify_padded<T: AsRef<[F::Elem]>>(
+        &self,
+        slices: &[T],
+        last_len: usize,
+    ) -> Result<bool, Error> {
+        check_piece_count!(all => self, slices);
+
+        let (data, parity) = slices.split_at(self.data_shard_count);
+        check_slices!(multi => parity);
+
+        if data[self.data_shard_count - 1].as_ref().len() != last_len {
+            return Err(Error::IncorrectShardSize {
+                shard: self.data_shard_count - 1,
+                expected: last_len,
+                actual: data[self.data_shard_count - 1].as_ref().len(),
+            });
+        }
+
+        let shard_len = parity[0].as_ref().len();
+
+        let mut buffer: SmallVec<[Vec<F::Elem>; 32]> =
+            SmallVec::with_capacity(self.parity_shard_count);
+
+        for _ in 0..self.parity_shard_count {
+            buffer.push(vec![F::zero(); shard_len]);
+        }
+
+        self.encode_sep_padded(data, &mut buffer)?;
+
+        Ok(buffer
+            .iter()
+            .zip(parity.iter())
+            .all(|(expected, actual)| expected.as_slice() == actual.as_ref()))
+    }
+
+    /// Checks if the parity shards are correct, and resolves a mismatch
+    /// according to `policy`.
+    ///
+    /// Unlike `verify`, which only tells whether the stripe is consistent,
+    /// the report lists the mismatching parity shards and, for the policies
+    /// searching for corrupted shards, the candidate sets found. The shards
+    /// are only overwritten if the resolution is `Resolution::Corrected`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[macro_use] extern crate reed_solomon_erasure;
+    /// # fn main () {
+    /// use reed_solomon_erasure::galois_8::ReedSolomon;
+    /// use reed_solomon_erasure::{MismatchPolicy, Resolution};
+    ///
+    /// let r = ReedSolomon::new(3, 2).unwrap();
+    ///
+    /// let mut shards = shards!([0, 1], [2, 3], [4, 5], [0, 0], [0, 0]);
+    /// r.encode(&mut shards).unwrap();
+    /// let expect = shards.clone();
+    ///
+    /// shards[1][0] ^= 1;
+    ///
+    /// let report = r.verify_with_policy(&mut shards, MismatchPolicy::Rederive).unwrap();
+    ///
+    /// assert_eq!(Resolution::Corrected, report.resolution);
+    /// assert_eq!(vec![vec![1]], report.candidates);
+    /// assert_eq!(expect, shards);
+    /// # }
+    /// ```
+    pub fn verify_with_policy<T: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        slices: &mut [T],
+        policy: MismatchPolicy,
+    ) -> Result<VerifyReport, Error> {
+        check_piece_count!(all => self, slices);
+        check_slices!(multi => slices);
+
+        let mismatched_parity = self.mismatched_parity(slices);
+
+        let mut report = VerifyReport {
+            resolution: Resolution::Consistent,
+            mismatched_parity,
+            candidates: Vec::new(),
+        };
+        if report.mismatched_parity.is_empty() {
+            return Ok(report);
+        }
+
+        let max_errors = match policy {
+            MismatchPolicy::ReportOnly => {
+                report.resolution = Resolution::Reported;
+                return Ok(report);
+            }
+            MismatchPolicy::Rederive => 1,
+            MismatchPolicy::Correct => self.parity_shard_count / 2,
+            MismatchPolicy::Identify { max_corrupted } => {
+                max_corrupted.min(self.parity_shard_count - 1)
+            }
+        };
+
+        // the shards re-derived for the last candidate set
+        let mut work: Vec<(Vec<F::Elem>, bool)> = slices
+            .iter()
+            .map(|slice| (slice.as_ref().to_vec(), true))
+            .collect();
+        let mut rederived = Vec::new();
+
+        for errors in 1..=max_errors {
+            let mut erased: SmallVec<[usize; 32]> = (0..errors).collect();
+
+            loop {
+                for &i in &erased {
+                    work[i].1 = false;
+                }
+                self.reconstruct(&mut work)?;
+
+                let consistent = {
+                    let view: SmallVec<[&[F::Elem]; 32]> =
+                        work.iter().map(|(shard, _)| shard.as_slice()).collect();
+                    self.mismatched_parity(&view).is_empty()
+                };
+                if consistent {
+                    if report.candidates.is_empty() {
+                        rederived = erased.iter().map(|&i| work[i].0.clone()).collect();
+                    }
+                    report.candidates.push(erased.to_vec());
+                }
+
+                for &i in &erased {
+                    work[i].0.copy_from_slice(slices[i].as_ref());
+                    work[i].1 = true;
+                }
+
+                if !next_combination(&mut erased, self.total_shard_count) {
+                    break;
+                }
+            }
+
+            if !report.candidates.is_empty() {
+                break;
+            }
+        }
+
+        report.resolution = match report.candidates.len() {
+            0 => Resolution::Uncorrectable,
+            1 if matches!(policy, MismatchPolicy::Identify { .. }) => Resolution::Identified,
+            1 => {
+                for (&i, shard) in report.candidates[0].iter().zip(rederived) {
+                    slices[i].as_mut().copy_from_slice(&shard);
+                }
+                Resolution::Corrected
+            }
+            _ => Resolution::Ambiguous,
+        };
+
+        Ok(report)
+    }
+
+    fn mismatched_parity<T: AsRef<[F::Elem]>>(&self, slices: &[T]) -> Vec<usize> {
+        let slice_len = slices[0].as_ref().len();
+        let mut buffer: SmallVec<[Vec<F::Elem>; 32]> =
+            SmallVec::with_capacity(self.parity_shard_count);
+
+        for _ in 0..self.parity_shard_count {
+            buffer.push(vec![F::zero(); slice_len]);
+        }
+
+        let (data, parity) = slices.split_at(self.data_shard_count);
+        self.code_some_slices(&self.get_parity_rows(), data, &mut buffer);
+
+        buffer
+            .iter()
+            .zip(parity)
+            .enumerate()
+            .filter(|(_, (expected, actual))| expected.as_slice() != actual.as_ref())
+            .map(|(i, _)| self.data_shard_count + i)
+            .collect()
+    }
+
+    /// Reconstructs all shards.
+    ///
+    /// The shards marked not present are only overwritten when no error
+    /// is detected. All provided shards must have the same length.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    ///
+    /// `reconstruct`, `reconstruct_data`, `reconstruct_shards`,
+    /// `reconstruct_data_shards` share the same core code base.
+    pub fn reconstruct<T: ReconstructShard<F>>(&self, slices: &mut [T]) -> Result<(), Error> {
+        match self.reconstruct_window_len(slices) {
+            Some(window_len) => {
+                self.reconstruct_chunked_internal(slices, false, window_len, |_| {}, None)
+            }
+            None => self.reconstruct_internal(slices, false, None),
+        }
+    }
+
+    /// Reconstructs only the data shards.
+    ///
+    /// The shards marked not present are only overwritten when no error
+    /// is detected. All provided shards must have the same length.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    ///
+    /// `reconstruct`, `reconstruct_data`, `reconstruct_shards`,
+    /// `reconstruct_data_shards` share the same core code base.
+    pub fn reconstruct_data<T: ReconstructShard<F>>(&self, slices: &mut [T]) -> Result<(), Error> {
+        match self.reconstruct_window_len(slices) {
+            Some(window_len) => {
+                self.reconstruct_chunked_internal(slices, true, window_len, |_| {}, None)
+            }
+            None => self.reconstruct_internal(slices, true, None),
+        }
+    }
+
+    // The window length for reconstructing `slices`, see `window_len`,
+    // going by the length of the first present shard.
+    fn reconstruct_window_len<T: ReconstructShard<F>>(&self, slices: &[T]) -> Option<usize> {
+        slices
+            .iter()
+            .find_map(ReconstructShard::len)
+            .and_then(|shard_len| self.window_len(shard_len))
+    }
+
+    /// Reconstructs all shards like `reconstruct`, returning which shards
+    /// were used for decoding and which were rebuilt, e.g. for auditing repairs.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_with_report<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+    ) -> Result<ReconstructReport, Error> {
+        let mut report = ReconstructReport::default();
+        self.reconstruct_internal(slices, false, Some(&mut report))?;
+        Ok(report)
+    }
+
+    /// Reconstructs only the data shards like `reconstruct_data`, returning
+    /// which shards were used for decoding and which were rebuilt, see
+    /// `reconstruct_with_report`. Missing parity shards are not rebuilt,
+    /// so they are not part of the report.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_data_with_report<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+    ) -> Result<ReconstructReport, Error> {
+        let mut report = ReconstructReport::default();
+        self.reconstruct_internal(slices, true, Some(&mut report))?;
+        Ok(report)
+    }
+
+    /// Reconstructs all shards like `reconstruct`, checking the result
+    /// against the present shards not used for decoding, and retrying with
+    /// other subsets of the present shards until the stripe is consistent.
+    ///
+    /// The present shards left out are tried in sets of increasing size,
+    /// always keeping at least one present shard more than the data shard
+    /// count to check the result against. The shards left out of the first
+    /// consistent stripe found are overwritten with their re-derivation, and
+    /// their indices are returned in ascending order, empty if the present
+    /// shards were consistent.
+    ///
+    /// With exactly `data_shard_count` shards present, nothing can be
+    /// checked, and this is the same as `reconstruct`.
+    ///
+    /// Returns `Error::InconsistentShards` if no subset gives a consistent
+    /// stripe. This means if the method returns an `Error`, then nothing is
+    /// touched.
+    ///
+    /// Costs up to one reconstruction and verification per subset, which
+    /// stays tractable for a few corrupted shards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[macro_use] extern crate reed_solomon_erasure;
+    /// # fn main () {
+    /// use reed_solomon_erasure::galois_8::ReedSolomon;
+    ///
+    /// let r = ReedSolomon::new(3, 3).unwrap();
+    ///
+    /// let mut shards = shards!([0, 1], [2, 3], [4, 5], [0, 0], [0, 0], [0, 0]);
+    /// r.encode(&mut shards).unwrap();
+    /// let expect = shards.clone();
+    ///
+    /// let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+    /// shards[0] = None;
+    /// shards[2].as_mut().unwrap()[1] ^= 1;
+    ///
+    /// assert_eq!(vec![2], r.reconstruct_verified(&mut shards).unwrap());
+    /// assert_eq!(expect, shards.into_iter().flatten().collect::<Vec<_>>());
+    /// # }
+    /// ```
+    pub fn reconstruct_verified<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+    ) -> Result<Vec<usize>, Error> {
+        check_piece_count!(all => self, slices);
+        check_present_elems::<F, _>(slices)?;
+
+        let mut shard_len = None;
+        for (i, shard) in slices.iter().enumerate() {
+            if let Some(len) = shard.len() {
+                if len == 0 {
+                    return Err(Error::EmptyShard);
+                }
+                if let Some(old_len) = shard_len {
+                    if len != old_len {
+                        return Err(Error::IncorrectShardSize {
+                            shard: i,
+                            expected: old_len,
+                            actual: len,
+                        });
+                    }
+                }
+                shard_len = Some(len);
+            }
+        }
+
+        let present: Vec<usize> = (0..self.total_shard_count)
+            .filter(|&i| !slices[i].is_empty())
+            .collect();
+        if present.len() < self.data_shard_count {
+            return Err(Error::TooFewShardsPresent);
+        }
+        if present.len() == self.data_shard_count {
+            self.reconstruct(slices)?;
+            return Ok(Vec::new());
+        }
+        let shard_len = shard_len.expect("at least one shard present; qed");
+
+        let mut work: Vec<(Vec<F::Elem>, bool)> = slices
+            .iter_mut()
+            .map(|slice| {
+                slice.get().map_or_else(
+                    || (vec![F::zero(); shard_len], false),
+                    |slice| (slice.to_vec(), true),
+                )
+            })
+            .collect();
+        // the present shards left out of the current attempt
+        let mut excluded = Vec::new();
+        let mut found = false;
+
+        'search: for count in 0..present.len() - self.data_shard_count {
+            let mut picks: SmallVec<[usize; 32]> = (0..count).collect();
+
+            loop {
+                excluded.clear();
+                excluded.extend(picks.iter().map(|&p| present[p]));
+                for &i in &excluded {
+                    work[i].1 = false;
+                }
+                self.reconstruct(&mut work)?;
+
+                let consistent = {
+                    let view: SmallVec<[&[F::Elem]; 32]> =
+                        work.iter().map(|(shard, _)| shard.as_slice()).collect();
+                    self.mismatched_parity(&view).is_empty()
+                };
+                if consistent {
+                    found = true;
+                    break 'search;
+                }
+
+                for &i in &excluded {
+                    work[i]
+                        .0
+                        .copy_from_slice(slices[i].get().expect("present shard; qed"));
+                    work[i].1 = true;
+                }
+
+                if !next_combination(&mut picks, present.len()) {
+                    break;
+                }
+            }
+        }
+
+        if !found {
+            return Err(Error::InconsistentShards);
+        }
+
+        for (i, slice) in slices.iter_mut().enumerate() {
+            if present.binary_search(&i).is_err() {
+                if let Err(Err(e)) = slice.get_or_initialize(shard_len) {
+                    return Err(e.at_shard(i));
+                }
+            }
+        }
+        for (i, (slice, (shard, _))) in slices.iter_mut().zip(&work).enumerate() {
+            if present.binary_search(&i).is_err() || excluded.contains(&i) {
+                slice
+                    .get()
+                    .expect("shard initialized above; qed")
+                    .copy_from_slice(shard);
+            }
+        }
+
+        Ok(excluded)
+    }
+
+    /// Reconstructs all shards like `reconstruct`, reporting the progress
+    /// to `progress`, e.g. to display a progress bar for long shards.
+    ///
+    /// The shards are reconstructed one megabyte of each shard at a time,
+    /// with a `Progress::Bytes` reported after each, then a
+    /// `Progress::ShardCompleted` for each rebuilt shard. Nothing is
+    /// reported if no shard is missing.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_with_progress<T: ReconstructShard<F>, P: FnMut(Progress)>(
+        &self,
+        slices: &mut [T],
+        progress: P,
+    ) -> Result<(), Error> {
+        self.reconstruct_chunked_internal(
+            slices,
+            false,
+            Self::elems_in(PROGRESS_CHUNK_BYTES),
+            progress,
+            None,
+        )
+    }
+
+    /// Reconstructs only the data shards like `reconstruct_data`,
+    /// reporting the progress to `progress`, see `reconstruct_with_progress`.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_data_with_progress<T: ReconstructShard<F>, P: FnMut(Progress)>(
+        &self,
+        slices: &mut [T],
+        progress: P,
+    ) -> Result<(), Error> {
+        self.reconstruct_chunked_internal(
+            slices,
+            true,
+            Self::elems_in(PROGRESS_CHUNK_BYTES),
+            progress,
+            None,
+        )
+    }
+
+    /// Reconstructs all shards like `reconstruct`, giving up with
+    /// `Error::Cancelled` once `cancel` is set, e.g. when a node is
+    /// asked to shut down during a background repair.
+    ///
+    /// `cancel` is checked before each megabyte of each shard, see
+    /// `reconstruct_with_progress`.
+    ///
+    /// If the method returns an `Error` other than `Error::Cancelled`,
+    /// then nothing is touched, and likewise if `cancel` is already set
+    /// when the method is called. When the call is cancelled later on, the
+    /// shards marked not present are only partially reconstructed, and
+    /// have to be discarded.
+    pub fn reconstruct_cancellable<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+        cancel: &AtomicBool,
+    ) -> Result<(), Error> {
+        self.reconstruct_chunked_internal(
+            slices,
+            false,
+            Self::elems_in(PROGRESS_CHUNK_BYTES),
+            |_| {},
+            Some(cancel),
+        )
+    }
+
+    /// Reconstructs only the data shards like `reconstruct_data`, giving
+    /// up with `Error::Cancelled` once `cancel` is set,
+    /// see `reconstruct_cancellable`.
+    pub fn reconstruct_data_cancellable<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+        cancel: &AtomicBool,
+    ) -> Result<(), Error> {
+        self.reconstruct_chunked_internal(
+            slices,
+            true,
+            Self::elems_in(PROGRESS_CHUNK_BYTES),
+            |_| {},
+            Some(cancel),
+        )
+    }
+
+    /// Reconstructs all shards like `reconstruct`, one horizontal chunk
+    /// of the stripe at a time, so the working set stays within about
+    /// `chunk_bytes` across all shards regardless of the shard length,
+    /// see `encode_chunked`.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_chunked<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+        chunk_bytes: usize,
+    ) -> Result<(), Error> {
+        let chunk_len = Self::elems_in(chunk_bytes / self.total_shard_count);
+        self.reconstruct_chunked_internal(slices, false, chunk_len, |_| {}, None)
+    }
+
+    /// Reconstructs only the data shards like `reconstruct_data`, one
+    /// horizontal chunk of the stripe at a time, see `reconstruct_chunked`.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_data_chunked<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+        chunk_bytes: usize,
+    ) -> Result<(), Error> {
+        let chunk_len = Self::elems_in(chunk_bytes / self.total_shard_count);
+        self.reconstruct_chunked_internal(slices, true, chunk_len, |_| {}, None)
+    }
+
+    fn reconstruct_chunked_internal<T: ReconstructShard<F>, P: FnMut(Progress)>(
+        &self,
+        shards: &mut [T],
+        data_only: bool,
+        chunk_len: usize,
+        mut progress: P,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<(), Error> {
+        check_piece_count!(all => self, shards);
+        check_present_elems::<F, _>(shards)?;
+
+        let mut shard_len = None;
+        let mut present: SmallVec<[bool; 32]> = SmallVec::with_capacity(self.total_shard_count);
+
+        for (i, shard) in shards.iter().enumerate() {
+            if let Some(len) = shard.len() {
+                if len == 0 {
+                    return Err(Error::EmptyShard);
+                }
+                if let Some(old_len) = shard_len.filter(|&old_len| len != old_len) {
+                    return Err(Error::IncorrectShardSize {
+                        shard: i,
+                        expected: old_len,
+                        actual: len,
+                    });
+                }
+                shard_len = Some(len);
+            }
+            present.push(shard.len().is_some());
+        }
+
+        let number_present = present.iter().filter(|&&x| x).count();
+        if number_present == self.total_shard_count {
+            return Ok(());
+        }
+        if number_present < self.data_shard_count {
+            return Err(Error::TooFewShardsPresent);
+        }
+
+        let is_cancelled = || cancel.is_some_and(|x| x.load(Ordering::Relaxed));
+        if is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        let shard_len = shard_len.expect("at least one shard present; qed");
+        let to_rebuild = |i: usize| !present[i] && (!data_only || i < self.data_shard_count);
+
+        let mut slices: SmallVec<[&mut [F::Elem]; 32]> =
+            SmallVec::with_capacity(self.total_shard_count);
+        for (i, shard) in shards.iter_mut().enumerate() {
+            if data_only && i >= self.data_shard_count && !present[i] {
+                slices.push(&mut [][..]);
+                continue;
+            }
+
+            match shard.get_or_initialize(shard_len) {
+                Ok(x) | Err(Ok(x)) => slices.push(x),
+                Err(Err(e)) => return Err(e.at_shard(i)),
+            }
+        }
+
+        let elem_size = std::mem::size_of::<F::Elem>();
+        let total = (self.data_shard_count * shard_len * elem_size) as u64;
+
+        let mut offset = 0;
+        while offset < shard_len {
+            if offset > 0 && is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+
+            let end = shard_len.min(offset + chunk_len);
+
+            let mut sub_stripe: SmallVec<[_; 32]> = slices
+                .iter_mut()
+                .zip(&present)
+                .map(|(slice, &present)| {
+                    let sub_slice: &mut [F::Elem] = if slice.is_empty() {
+                        &mut []
+                    } else {
+                        &mut slice[offset..end]
+                    };
+                    (sub_slice, present)
+                })
+                .collect();
+            self.reconstruct_internal(&mut sub_stripe, data_only, None)?;
+
+            offset = end;
+            progress(Progress::Bytes {
+                processed: (self.data_shard_count * offset * elem_size) as u64,
+                total,
+            });
+        }
+
+        for i in (0..self.total_shard_count).filter(|&i| to_rebuild(i)) {
+            progress(Progress::ShardCompleted(i));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the work `encode` would do on shards of `shard_len` elements,
+    /// without doing it.
+    pub const fn encode_plan(&self, shard_len: usize) -> WorkPlan {
+        let elem_size = std::mem::size_of::<F::Elem>() as u64;
+        let shard_len = shard_len as u64;
+        let data_shard_count = self.data_shard_count as u64;
+        let parity_shard_count = self.parity_shard_count as u64;
+
+        WorkPlan {
+            mul_ops: data_shard_count * parity_shard_count * shard_len,
+            bytes_read: data_shard_count * shard_len * elem_size,
+            bytes_written: parity_shard_count * shard_len * elem_size,
+            scratch_bytes: 0,
+        }
+    }
+
+    /// Returns the work `reconstruct` would do on shards of `shard_len`
+    /// elements, where `present` marks the shards which are present,
+    /// without doing it.
+    ///
+    /// Returns `Error::InvalidShardFlags` if the length of `present` does not
+    /// match the total shard count, and `Error::TooFewShardsPresent` if too
+    /// few shards are present.
+    pub fn reconstruct_plan(&self, present: &[bool], shard_len: usize) -> Result<WorkPlan, Error> {
+        self.reconstruct_plan_internal(present, shard_len, false)
+    }
+
+    /// Returns the work `reconstruct_data` would do, see `reconstruct_plan`.
+    pub fn reconstruct_data_plan(
+        &self,
+        present: &[bool],
+        shard_len: usize,
+    ) -> Result<WorkPlan, Error> {
+        self.reconstruct_plan_internal(present, shard_len, true)
+    }
+
+    fn reconstruct_plan_internal(
+        &self,
+        present: &[bool],
+        shard_len: usize,
+        data_only: bool,
+    ) -> Result<WorkPlan, Error> {
+        if present.len() != self.total_shard_count {
+            return Err(Error::InvalidShardFlags);
+        }
 
-    fn get_parity_rows(&self) -> SmallVec<[&[F::Elem]; 32]> {
-        let mut parity_rows = SmallVec::with_capacity(self.parity_shard_count);
-        let matrix = &self.matrix;
-        for i in self.data_shard_count..self.total_shard_count {
-            parity_rows.push(matrix.get_row(i));
+        let number_present = present.iter().filter(|&&x| x).count();
+        if number_present == self.total_shard_count {
+            return Ok(WorkPlan::default());
+        }
+        if number_present < self.data_shard_count {
+            return Err(Error::TooFewShardsPresent);
         }
 
-        parity_rows
-    }
+        let (data, parity) = present.split_at(self.data_shard_count);
+        let missing_data = data.iter().filter(|&&x| !x).count() as u64;
+        let missing_parity = if data_only {
+            0
+        } else {
+            parity.iter().filter(|&&x| !x).count() as u64
+        };
 
-    fn build_matrix(data_shards: usize, total_shards: usize) -> Matrix<F> {
-        let vandermonde = Matrix::vandermonde(total_shards, data_shards);
+        let elem_size = std::mem::size_of::<F::Elem>() as u64;
+        let shard_len = shard_len as u64;
+        let data_shard_count = self.data_shard_count as u64;
 
-        let top = vandermonde.sub_matrix(0, 0, data_shards, data_shards);
+        // the inputs are only read when there is something to code
+        let coded_outputs = u64::from(missing_data > 0) + u64::from(missing_parity > 0);
 
-        vandermonde.multiply(&top.invert().unwrap())
+        Ok(WorkPlan {
+            mul_ops: data_shard_count * (missing_data + missing_parity) * shard_len,
+            bytes_read: coded_outputs * data_shard_count * shard_len * elem_size,
+            bytes_written: (missing_data + missing_parity) * shard_len * elem_size,
+            // the square submatrix, the augmented matrix and the inverse
+            scratch_bytes: 4 * data_shard_count * data_shard_count * elem_size,
+        })
     }
 
-    /// Creates a new instance of Reed-Solomon erasure code encoder/decoder.
+    /// Reconstructs all shards like `reconstruct`, using `scratch` instead
+    /// of the inversion cache.
     ///
-    /// Returns `Error::TooFewDataShards` if `data_shards == 0`.
+    /// This performs no heap allocation, except when initializing missing
+    /// shards of types such as `Option<Vec<_>>`, and when `scratch` was
+    /// created for a codec of a different data shard count. The decode
+    /// matrix is only computed again when other shards are missing than in
+    /// the previous call with the same scratch and codec.
     ///
-    /// Returns `Error::TooFewParityShards` if `parity_shards == 0`.
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_with_scratch<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+        scratch: &mut ReconstructScratch<F>,
+    ) -> Result<(), Error> {
+        self.reconstruct_with_scratch_internal(slices, scratch, false)
+    }
+
+    /// Reconstructs only the data shards like `reconstruct_data`, using
+    /// `scratch` instead of the inversion cache, see
+    /// `reconstruct_with_scratch`.
     ///
-    /// Returns `Error::TooManyShards` if `data_shards + parity_shards > F::ORDER`.
-    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self, Error> {
-        if data_shards == 0 {
-            return Err(Error::TooFewDataShards);
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_data_with_scratch<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+        scratch: &mut ReconstructScratch<F>,
+    ) -> Result<(), Error> {
+        self.reconstruct_with_scratch_internal(slices, scratch, true)
+    }
+
+    fn reconstruct_with_scratch_internal<T: ReconstructShard<F>>(
+        &self,
+        shards: &mut [T],
+        scratch: &mut ReconstructScratch<F>,
+        data_only: bool,
+    ) -> Result<(), Error> {
+        check_piece_count!(all => self, shards);
+        check_present_elems::<F, _>(shards)?;
+
+        let data_shard_count = self.data_shard_count;
+
+        scratch.prepare(self);
+        scratch.present.clear();
+
+        let mut shard_len = None;
+
+        for (i, shard) in shards.iter().enumerate() {
+            if let Some(len) = shard.len() {
+                if len == 0 {
+                    return Err(Error::EmptyShard);
+                }
+                if let Some(old_len) = shard_len.filter(|&old_len| len != old_len) {
+                    return Err(Error::IncorrectShardSize {
+                        shard: i,
+                        expected: old_len,
+                        actual: len,
+                    });
+                }
+                shard_len = Some(len);
+            }
+            scratch.present.push(shard.len().is_some());
         }
-        if parity_shards == 0 {
-            return Err(Error::TooFewParityShards);
+
+        let number_present = scratch.present.iter().filter(|&&x| x).count();
+        if number_present == self.total_shard_count {
+            return Ok(());
         }
-        if data_shards + parity_shards > F::ORDER {
-            return Err(Error::TooManyShards);
+        if number_present < data_shard_count {
+            return Err(Error::TooFewShardsPresent);
         }
 
-        let total_shards = data_shards + parity_shards;
+        let shard_len = shard_len.expect("at least one shard present; qed");
 
-        let matrix = Self::build_matrix(data_shards, total_shards);
+        for (i, shard) in shards.iter_mut().enumerate() {
+            if !scratch.present[i] && (i < data_shard_count || !data_only) {
+                if let Err(Err(e)) = shard.get_or_initialize(shard_len) {
+                    return Err(e.at_shard(i));
+                }
+            }
+        }
 
-        Ok(Self {
-            data_shard_count: data_shards,
-            parity_shard_count: parity_shards,
-            total_shard_count: total_shards,
-            matrix,
-            tree: InversionTree::new(data_shards, parity_shards),
+        if !scratch.is_decoded() {
+            scratch.decode(&self.matrix, data_shard_count);
+        }
+
+        // Each missing shard is split off from the others, so it can be
+        // written while the others are read.
+        for i in 0..data_shard_count {
+            if scratch.present[i] {
+                continue;
+            }
+
+            let (before, rest) = shards.split_at_mut(i);
+            let (output, after) = rest.split_first_mut().expect("i < total shard count; qed");
+            let output = output.get().expect("missing shards initialized; qed");
+            let row = &scratch.work.get_row(i)[data_shard_count..];
+
+            self.record(
+                shard_len * data_shard_count,
+                shard_len,
+                shard_len * data_shard_count,
+            );
+
+            for (j, &valid_index) in scratch.valid_indices.iter().enumerate() {
+                let input = if valid_index < i {
+                    before[valid_index].get()
+                } else {
+                    after[valid_index - i - 1].get()
+                }
+                .expect("valid shards present; qed");
+
+                if j == 0 {
+                    self.mul_slice(row[j], input, output);
+                } else {
+                    self.mul_slice_add(row[j], input, output);
+                }
+            }
+        }
+
+        if data_only {
+            return Ok(());
+        }
+
+        for i in data_shard_count..self.total_shard_count {
+            if scratch.present[i] {
+                continue;
+            }
+
+            let (before, rest) = shards.split_at_mut(i);
+            let output = rest[0].get().expect("missing shards initialized; qed");
+            let row = self.matrix.get_row(i);
+
+            self.record(
+                shard_len * data_shard_count,
+                shard_len,
+                shard_len * data_shard_count,
+            );
+
+            for (j, data) in before[..data_shard_count].iter_mut().enumerate() {
+                let input = data.get().expect("data shards reconstructed; qed");
+
+                if j == 0 {
+                    self.mul_slice(row[j], input, output);
+                } else {
+                    self.mul_slice_add(row[j], input, output);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Computes the coefficients reconstructing the missing shards of
+    /// stripes whose present shards are flagged by `present`, to apply to
+    /// many such stripes, see `DecodePlan`.
+    ///
+    /// Returns `Error::InvalidShardFlags` if the number of flags does not
+    /// match the total shard count, and `Error::TooFewShardsPresent` if
+    /// fewer than `data_shard_count` shards are present.
+    pub fn decode_plan(&self, present: &[bool]) -> Result<DecodePlan<F>, Error> {
+        if present.len() != self.total_shard_count {
+            return Err(Error::InvalidShardFlags);
+        }
+
+        let valid_indices: Vec<usize> = (0..self.total_shard_count)
+            .filter(|&i| present[i])
+            .take(self.data_shard_count)
+            .collect();
+        if valid_indices.len() < self.data_shard_count {
+            return Err(Error::TooFewShardsPresent);
+        }
+        let invalid_indices: Vec<usize> = (0..self.total_shard_count)
+            .filter(|&i| !present[i])
+            .collect();
+
+        let rows = if invalid_indices.is_empty() {
+            Vec::new()
+        } else {
+            self.get_rebuild_rows(&valid_indices, &invalid_indices, &invalid_indices)?
+        };
+
+        Ok(DecodePlan {
+            data_shard_count: self.data_shard_count,
+            total_shard_count: self.total_shard_count,
+            present: present.to_vec(),
+            valid_indices,
+            rebuilt: invalid_indices,
+            rows,
+            platform: self.platform,
         })
     }
 
-    pub fn data_shard_count(&self) -> usize {
-        self.data_shard_count
+    /// Reconstructs all shards like `reconstruct`, splitting the work into
+    /// byte ranges handled by `threads` threads.
+    ///
+    /// This shortens the time to recover wide stripes with long shards,
+    /// and thus the window in which further losses are fatal. A `threads`
+    /// of zero is treated as one.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_parallel<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+        threads: usize,
+    ) -> Result<(), Error>
+    where
+        Self: Sync,
+        F::Elem: Send,
+    {
+        self.reconstruct_parallel_internal(slices, threads, &ScopedThreads, false)
     }
 
-    pub fn parity_shard_count(&self) -> usize {
-        self.parity_shard_count
+    /// Reconstructs all shards like `reconstruct_parallel`, running the
+    /// `threads` byte ranges as jobs of `executor`, e.g. a thread pool
+    /// reserved for repairs, rather than on threads of their own.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_parallel_in<T: ReconstructShard<F>, E: Executor + ?Sized>(
+        &self,
+        slices: &mut [T],
+        threads: usize,
+        executor: &E,
+    ) -> Result<(), Error>
+    where
+        Self: Sync,
+        F::Elem: Send,
+    {
+        self.reconstruct_parallel_internal(slices, threads, executor, false)
     }
 
-    pub fn total_shard_count(&self) -> usize {
-        self.total_shard_count
+    /// Reconstructs only the data shards like `reconstruct_data`, splitting
+    /// the work into byte ranges handled by `threads` threads, see
+    /// `reconstruct_parallel`.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_data_parallel<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+        threads: usize,
+    ) -> Result<(), Error>
+    where
+        Self: Sync,
+        F::Elem: Send,
+    {
+        self.reconstruct_parallel_internal(slices, threads, &ScopedThreads, true)
     }
 
-    fn code_some_slices<T: AsRef<[F::Elem]>, U: AsMut<[F::Elem]>>(
+    /// Reconstructs only the data shards like `reconstruct_data_parallel`,
+    /// running the byte ranges as jobs of `executor`, see
+    /// `reconstruct_parallel_in`.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_data_parallel_in<T: ReconstructShard<F>, E: Executor + ?Sized>(
         &self,
-        matrix_rows: &[&[F::Elem]],
-        inputs: &[T],
-        outputs: &mut [U],
-    ) {
-        for i_input in 0..self.data_shard_count {
-            Self::code_single_slice(matrix_rows, i_input, inputs[i_input].as_ref(), outputs);
-        }
+        slices: &mut [T],
+        threads: usize,
+        executor: &E,
+    ) -> Result<(), Error>
+    where
+        Self: Sync,
+        F::Elem: Send,
+    {
+        self.reconstruct_parallel_internal(slices, threads, executor, true)
     }
 
-    fn code_single_slice<U: AsMut<[F::Elem]>>(
-        matrix_rows: &[&[F::Elem]],
-        i_input: usize,
-        input: &[F::Elem],
-        outputs: &mut [U],
-    ) {
-        outputs.iter_mut().enumerate().for_each(|(i_row, output)| {
-            let matrix_row_to_use = matrix_rows[i_row][i_input];
-            let output = output.as_mut();
+    fn reconstruct_parallel_internal<T: ReconstructShard<F>, E: Executor + ?Sized>(
+        &self,
+        shards: &mut [T],
+        threads: usize,
+        executor: &E,
+        data_only: bool,
+    ) -> Result<(), Error>
+    where
+        Self: Sync,
+        F::Elem: Send,
+    {
+        check_piece_count!(all => self, shards);
+        check_present_elems::<F, _>(shards)?;
 
-            if i_input == 0 {
-                F::mul_slice(matrix_row_to_use, input, output);
-            } else {
-                F::mul_slice_add(matrix_row_to_use, input, output);
+        let mut shard_len = None;
+        let mut present: SmallVec<[bool; 32]> = SmallVec::with_capacity(self.total_shard_count);
+
+        for (i, shard) in shards.iter().enumerate() {
+            if let Some(len) = shard.len() {
+                if len == 0 {
+                    return Err(Error::EmptyShard);
+                }
+                if let Some(old_len) = shard_len.filter(|&old_len| len != old_len) {
+                    return Err(Error::IncorrectShardSize {
+                        shard: i,
+                        expected: old_len,
+                        actual: len,
+                    });
+                }
+                shard_len = Some(len);
             }
-        });
+            present.push(shard.len().is_some());
+        }
+
+        let number_present = present.iter().filter(|&&x| x).count();
+        if number_present == self.total_shard_count {
+            return Ok(());
+        }
+        if number_present < self.data_shard_count {
+            return Err(Error::TooFewShardsPresent);
+        }
+
+        let shard_len = shard_len.expect("at least one shard present; qed");
+
+        // Compute the data decode matrix once up front rather than
+        // in several threads at once.
+        let valid_indices: SmallVec<[usize; 32]> = (0..self.total_shard_count)
+            .filter(|&i| present[i])
+            .take(self.data_shard_count)
+            .collect();
+        let invalid_indices: SmallVec<[usize; 32]> = (0..self.total_shard_count)
+            .filter(|&i| !present[i])
+            .collect();
+        self.get_data_decode_matrix(&valid_indices, &invalid_indices)?;
+
+        let chunk_len = shard_len.div_ceil(threads.max(1));
+        let chunk_count = shard_len.div_ceil(chunk_len);
+
+        let mut sub_stripes: Vec<SmallVec<[_; 32]>> = (0..chunk_count)
+            .map(|_| SmallVec::with_capacity(self.total_shard_count))
+            .collect();
+
+        for (i, shard) in shards.iter_mut().enumerate() {
+            if data_only && i >= self.data_shard_count && !present[i] {
+                for sub_stripe in &mut sub_stripes {
+                    sub_stripe.push((&mut [][..], false));
+                }
+                continue;
+            }
+
+            let slice = match shard.get_or_initialize(shard_len) {
+                Ok(x) | Err(Ok(x)) => x,
+                Err(Err(e)) => return Err(e.at_shard(i)),
+            };
+
+            for (sub_stripe, chunk) in sub_stripes.iter_mut().zip(slice.chunks_mut(chunk_len)) {
+                sub_stripe.push((chunk, present[i]));
+            }
+        }
+
+        let mut results: Vec<Result<(), Error>> = vec![Ok(()); sub_stripes.len()];
+        let jobs = sub_stripes
+            .into_iter()
+            .zip(&mut results)
+            .map(|(mut sub_stripe, result)| {
+                Box::new(move || {
+                    *result = self.reconstruct_internal(&mut sub_stripe, data_only, None);
+                }) as Box<dyn FnOnce() + Send + '_>
+            })
+            .collect();
+        executor.execute(jobs);
+
+        results.into_iter().collect()
     }
 
-    fn check_some_slices_with_buffer<T, U>(
+    /// Reconstructs only the elements `range` of the missing shards, e.g. to
+    /// serve a small range read from a degraded stripe without decoding
+    /// whole shards.
+    ///
+    /// `present` flags the shards which are present. Only the elements
+    /// `range` of the present shards are read, and only the ones of the
+    /// missing shards are written. When only the ranges of the shards are
+    /// at hand, passing them to `reconstruct` does the same.
+    ///
+    /// Returns `Error::InvalidShardFlags` if the number of flags does not
+    /// match the total shard count.
+    ///
+    /// Returns `Error::InvalidRange` if `range` does not lie within the
+    /// shards, and `Error::EmptyShard` if `range` is empty.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_range<T: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
         &self,
-        matrix_rows: &[&[F::Elem]],
-        inputs: &[T],
-        to_check: &[T],
-        buffer: &mut [U],
-    ) -> bool
-    where
-        T: AsRef<[F::Elem]>,
-        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
-    {
-        self.code_some_slices(matrix_rows, inputs, buffer);
+        shards: &mut [T],
+        present: &[bool],
+        range: Range<usize>,
+    ) -> Result<(), Error> {
+        self.reconstruct_range_internal(shards, present, range, false)
+    }
 
-        let at_least_one_mismatch_present = buffer
+    /// Reconstructs only the elements `range` of the missing data shards,
+    /// see `reconstruct_range`.
+    ///
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_data_range<T: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        shards: &mut [T],
+        present: &[bool],
+        range: Range<usize>,
+    ) -> Result<(), Error> {
+        self.reconstruct_range_internal(shards, present, range, true)
+    }
+
+    fn reconstruct_range_internal<T: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        shards: &mut [T],
+        present: &[bool],
+        range: Range<usize>,
+        data_only: bool,
+    ) -> Result<(), Error> {
+        check_piece_count!(all => self, shards);
+        check_slices!(multi => shards);
+
+        if present.len() != self.total_shard_count {
+            return Err(Error::InvalidShardFlags);
+        }
+        if range.start > range.end || range.end > shards[0].as_ref().len() {
+            return Err(Error::InvalidRange);
+        }
+        if range.is_empty() {
+            return Err(Error::EmptyShard);
+        }
+
+        let mut sub_stripe: SmallVec<[_; 32]> = shards
             .iter_mut()
-            .enumerate()
-            .map(|(i, expected_parity_shard)| {
-                expected_parity_shard.as_ref() == to_check[i].as_ref()
-            })
-            .any(|x| !x); // find the first false (some slice is different from the expected one)
-        !at_least_one_mismatch_present
+            .zip(present)
+            .map(|(shard, &present)| (&mut shard.as_mut()[range.clone()], present))
+            .collect();
+
+        self.reconstruct_internal(&mut sub_stripe, data_only, None)
     }
 
-    /// Constructs the parity shards partially using only the data shard
-    /// indexed by `i_data`.
+    /// Reconstructs the parts of the shards hit by bursts of corruption,
+    /// treating the shards as `depth` interleaved sub-stripes.
     ///
-    /// The slots where the parity shards sit at will be overwritten.
+    /// Each shard is split into `depth` segments of equal length (the last
+    /// one possibly shorter), and segment `i` of every shard makes up
+    /// sub-stripe `i`. The sub-stripes are reconstructed independently, so a
+    /// long burst in one shard only erases that shard in the sub-stripes it
+    /// overlaps, instead of the whole shard. Bursts are detected externally,
+    /// e.g. via per-segment checksums, and given in `erasures` as the shard
+    /// index and the range of corrupted elements.
     ///
-    /// # Warning
+    /// No special encoding is needed: every element position is coded
+    /// independently, so the shards from `encode` already consist of
+    /// valid sub-stripes for any depth.
     ///
-    /// You must apply this method on the data shards in strict sequential order (0..data shard count),
-    /// otherwise the parity shards will be incorrect.
+    /// Returns `Error::InvalidInterleaving` if `depth` is zero or an erasure
+    /// lies outside of the shards, and `Error::TooFewShardsPresent` if some
+    /// sub-stripe has more erased segments than the parity shard count.
     ///
-    /// It is recommended to use the `ShardByShard` bookkeeping struct instead of this method directly.
-    pub fn encode_single<T, U>(&self, i_data: usize, mut shards: T) -> Result<(), Error>
-    where
-        T: AsRef<[U]> + AsMut<[U]>,
-        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
-    {
-        let slices = shards.as_mut();
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_interleaved<T: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+        &self,
+        shards: &mut [T],
+        depth: usize,
+        erasures: &[(usize, Range<usize>)],
+    ) -> Result<(), Error> {
+        check_piece_count!(all => self, shards);
+        check_slices!(multi => shards);
 
-        check_slice_index!(data => self, i_data);
-        check_piece_count!(all=> self, slices);
-        check_slices!(multi => slices);
+        if depth == 0 {
+            return Err(Error::InvalidInterleaving);
+        }
+
+        let shard_len = shards[0].as_ref().len();
+        let segment_len = shard_len.div_ceil(depth);
+
+        // erased[i * depth + j] marks segment j of shard i
+        let mut erased = vec![false; self.total_shard_count * depth];
+
+        for &(shard, ref range) in erasures {
+            if shard >= self.total_shard_count || range.start > range.end || range.end > shard_len {
+                return Err(Error::InvalidInterleaving);
+            }
+            if range.is_empty() {
+                continue;
+            }
+
+            for segment in range.start / segment_len..range.end.div_ceil(segment_len) {
+                erased[shard * depth + segment] = true;
+            }
+        }
+
+        let erased_count = |segment: usize| {
+            (0..self.total_shard_count)
+                .filter(|&i| erased[i * depth + segment])
+                .count()
+        };
+
+        if (0..depth).any(|segment| erased_count(segment) > self.parity_shard_count) {
+            return Err(Error::TooFewShardsPresent);
+        }
 
-        // Get the slice of output buffers.
-        let (mut_input, output) = slices.split_at_mut(self.data_shard_count);
+        for segment in 0..depth {
+            let start = segment * segment_len;
+            let end = shard_len.min(start + segment_len);
+            if start >= end || erased_count(segment) == 0 {
+                continue;
+            }
 
-        let input = mut_input[i_data].as_ref();
+            let mut sub_stripe: SmallVec<[_; 32]> = shards
+                .iter_mut()
+                .enumerate()
+                .map(|(i, shard)| {
+                    (
+                        &mut shard.as_mut()[start..end],
+                        !erased[i * depth + segment],
+                    )
+                })
+                .collect();
 
-        self.encode_single_sep(i_data, input, output)
+            self.reconstruct(&mut sub_stripe)?;
+        }
+
+        Ok(())
     }
 
-    /// Constructs the parity shards partially using only the data shard provided.
-    ///
-    /// The data shard must match the index `i_data`.
+    /// Reconstructs all shards, where the last data shard may be shorter
+    /// than the others, see `encode_sep_padded`.
     ///
-    /// The slots where the parity shards sit at will be overwritten.
-    ///
-    /// # Warning
+    /// `last_len` is the true length of the last data shard. If present,
+    /// the last data shard must be of that length, and it is reconstructed
+    /// to that length if missing. The full shard length is taken from
+    /// the other present shards.
     ///
-    /// You must apply this method on the data shards in strict sequential order (0..data shard count),
-    /// otherwise the parity shards will be incorrect.
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_padded(
+        &self,
+        shards: &mut [Option<Vec<F::Elem>>],
+        last_len: usize,
+    ) -> Result<(), Error> {
+        self.reconstruct_padded_internal(shards, last_len, false)
+    }
+
+    /// Reconstructs only the data shards, where the last data shard may be
+    /// shorter than the others, see `reconstruct_padded`.
     ///
-    /// It is recommended to use the `ShardByShard` bookkeeping struct instead of this method directly.
-    pub fn encode_single_sep<U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+    /// This means if the method returns an `Error`, then nothing is touched.
+    pub fn reconstruct_data_padded(
         &self,
-        i_data: usize,
-        single_data: &[F::Elem],
-        parity: &mut [U],
+        shards: &mut [Option<Vec<F::Elem>>],
+        last_len: usize,
     ) -> Result<(), Error> {
-        check_slice_index!(data => self, i_data);
-        check_piece_count!(parity => self, parity);
-        check_slices!(multi => parity, single => single_data);
+        self.reconstruct_padded_internal(shards, last_len, true)
+    }
 
-        let parity_rows = self.get_parity_rows();
+    fn reconstruct_padded_internal(
+        &self,
+        shards: &mut [Option<Vec<F::Elem>>],
+        last_len: usize,
+        data_only: bool,
+    ) -> Result<(), Error> {
+        check_piece_count!(all => self, shards);
 
-        // Do the coding.
-        Self::code_single_slice(&parity_rows, i_data, single_data, parity);
+        let i_last = self.data_shard_count - 1;
 
-        Ok(())
-    }
+        let shard_len = shards
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != i_last)
+            .find_map(|(_, shard)| shard.as_ref().map(Vec::len))
+            .ok_or(Error::TooFewShardsPresent)?;
 
-    /// Constructs the parity shards.
-    ///
-    /// The slots where the parity shards sit at will be overwritten.
-    pub fn encode<T, U>(&self, mut shards: T) -> Result<(), Error>
-    where
-        T: AsRef<[U]> + AsMut<[U]>,
-        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
-    {
-        let slices: &mut [U] = shards.as_mut();
+        if last_len > shard_len {
+            return Err(Error::IncorrectShardSize {
+                shard: i_last,
+                expected: shard_len,
+                actual: last_len,
+            });
+        }
 
-        check_piece_count!(all => self, slices);
-        check_slices!(multi => slices);
+        let padded = match shards[i_last] {
+            Some(ref mut last) => {
+                if last.len() != last_len {
+                    return Err(Error::IncorrectShardSize {
+                        shard: i_last,
+                        expected: last_len,
+                        actual: last.len(),
+                    });
+                }
+                last.resize(shard_len, F::zero());
+                true
+            }
+            None => false,
+        };
 
-        // Get the slice of output buffers.
-        let (input, output) = slices.split_at_mut(self.data_shard_count);
+        let result = self.reconstruct_internal(shards, data_only, None);
 
-        self.encode_sep(&*input, output)
+        match (result, shards[i_last].as_mut()) {
+            // restore the last data shard as it was given
+            (Err(_), Some(last)) if padded => last.truncate(last_len),
+            // the other shards are left untouched on error
+            (Err(_), Some(_)) => shards[i_last] = None,
+            (Ok(()), Some(last)) => last.truncate(last_len),
+            (_, None) => {}
+        }
+
+        result
     }
 
-    /// Constructs the parity shards using a read-only view into the
-    /// data shards.
+    /// Reads and decodes the range `offset..offset + len` of every data shard.
     ///
-    /// The slots where the parity shards sit at will be overwritten.
-    pub fn encode_sep<T: AsRef<[F::Elem]>, U: AsRef<[F::Elem]> + AsMut<[F::Elem]>>(
+    /// As every element of a shard only depends on the elements at the same
+    /// position in the other shards, only this range is read, from exactly
+    /// `data_shard_count` shards. Data shards are read in preference to parity
+    /// shards, so no decoding is needed while they are all available.
+    ///
+    /// `available` flags the shards which are worth trying. Shards which
+    /// `fetcher` fails to read are replaced by the next available one.
+    ///
+    /// Returns `Error::InvalidShardFlags` if the number of flags does not
+    /// match the total shard count.
+    ///
+    /// Returns `Error::EmptyShard` if `len == 0`.
+    ///
+    /// Returns `Error::IncorrectShardSize` if `fetcher` returns a range
+    /// that is not of length `len`.
+    ///
+    /// Returns `Error::TooFewShardsPresent` if fewer than `data_shard_count`
+    /// shards could be read.
+    pub fn fetch_data_range<R: RangeFetch<F>>(
         &self,
-        data: &[T],
-        parity: &mut [U],
-    ) -> Result<(), Error> {
-        check_piece_count!(data => self, data);
-        check_piece_count!(parity => self, parity);
-        check_slices!(multi => data, multi => parity);
+        fetcher: &mut R,
+        available: &[bool],
+        offset: usize,
+        len: usize,
+    ) -> Result<Vec<Vec<F::Elem>>, Error> {
+        if available.len() != self.total_shard_count {
+            return Err(Error::InvalidShardFlags);
+        }
+        if len == 0 {
+            return Err(Error::EmptyShard);
+        }
 
-        let parity_rows = self.get_parity_rows();
+        let mut shards: Vec<Option<Vec<F::Elem>>> = vec![None; self.total_shard_count];
+        let mut number_present = 0;
 
-        // Do the coding.
-        self.code_some_slices(&parity_rows, data, parity);
+        for i in (0..self.total_shard_count).filter(|&i| available[i]) {
+            if number_present == self.data_shard_count {
+                break;
+            }
 
-        Ok(())
+            if let Some(range) = fetcher.fetch(i, offset, len) {
+                if range.len() != len {
+                    return Err(Error::IncorrectShardSize {
+                        shard: i,
+                        expected: len,
+                        actual: range.len(),
+                    });
+                }
+                shards[i] = Some(range);
+                number_present += 1;
+            }
+        }
+
+        if number_present < self.data_shard_count {
+            return Err(Error::TooFewShardsPresent);
+        }
+
+        let mut data = vec![vec![F::zero(); len]; self.data_shard_count];
+        self.decode_data(&mut shards, &mut data)?;
+
+        Ok(data)
     }
 
-    /// Checks if the parity shards are correct.
+    /// Returns the indices of the shards to read to obtain the shards
+    /// `wanted`, where `present` marks the shards which are present, e.g. to
+    /// plan network reads before pulling any bytes.
     ///
-    /// This is a wrapper of `verify_with_buffer`.
-    pub fn verify<T: AsRef<[F::Elem]>>(&self, slices: &[T]) -> Result<bool, Error> {
-        check_piece_count!(all => self, slices);
-        check_slices!(multi => slices);
+    /// Present wanted shards are read as is. If any wanted shard is missing,
+    /// exactly `data_shard_count` shards are needed to reconstruct it, so
+    /// the present wanted shards are completed with other present shards,
+    /// preferring data shards to parity shards. The indices are returned
+    /// in ascending order.
+    ///
+    /// Returns `Error::InvalidShardFlags` if the number of flags does not
+    /// match the total shard count.
+    ///
+    /// Returns `Error::InvalidIndex` if a wanted index is not a shard index.
+    ///
+    /// Returns `Error::TooFewShardsPresent` if a wanted shard is missing
+    /// and fewer than `data_shard_count` shards are present.
+    pub fn repair_plan(&self, present: &[bool], wanted: &[usize]) -> Result<Vec<usize>, Error> {
+        if present.len() != self.total_shard_count {
+            return Err(Error::InvalidShardFlags);
+        }
+        if wanted.iter().any(|&i| i >= self.total_shard_count) {
+            return Err(Error::InvalidIndex);
+        }
 
-        let slice_len = slices[0].as_ref().len();
+        let mut selected = vec![false; self.total_shard_count];
+        for &i in wanted {
+            selected[i] = present[i];
+        }
 
-        let mut buffer: SmallVec<[Vec<F::Elem>; 32]> =
-            SmallVec::with_capacity(self.parity_shard_count);
+        if wanted.iter().any(|&i| !present[i]) {
+            if present.iter().filter(|&&x| x).count() < self.data_shard_count {
+                return Err(Error::TooFewShardsPresent);
+            }
 
-        for _ in 0..self.parity_shard_count {
-            buffer.push(vec![F::zero(); slice_len]);
+            let mut number_selected = selected.iter().filter(|&&x| x).count();
+            for i in 0..self.total_shard_count {
+                if number_selected >= self.data_shard_count {
+                    break;
+                }
+                if present[i] && !selected[i] {
+                    selected[i] = true;
+                    number_selected += 1;
+                }
+            }
         }
 
-        self.verify_with_buffer(slices, &mut buffer)
+        Ok((0..self.total_shard_count)
+            .filter(|&i| selected[i])
+            .collect())
     }
 
-    /// Checks if the parity shards are correct.
-    pub fn verify_with_buffer<T, U>(&self, slices: &[T], buffer: &mut [U]) -> Result<bool, Error>
-    where
-        T: AsRef<[F::Elem]>,
-        U: AsRef<[F::Elem]> + AsMut<[F::Elem]>,
-    {
-        check_piece_count!(all => self, slices);
-        check_piece_count!(parity_buf => self, buffer);
-        check_slices!(multi => slices, multi => buffer);
-
-        let data = &slices[0..self.data_shard_count];
-        let to_check = &slices[self.data_shard_count..];
+    // The coefficients computing each shard of `rebuilt` directly from the
+    // shards of `valid_indices`, for backends which rebuild data and parity
+    // shards in one pass instead of re-encoding the parity, see `gpu`, and
+    // for `DecodePlan`.
+    pub(crate) fn get_rebuild_rows(
+        &self,
+        valid_indices: &[usize],
+        invalid_indices: &[usize],
+        rebuilt: &[usize],
+    ) -> Result<Vec<Vec<F::Elem>>, Error> {
+        let data_decode_matrix = self.get_data_decode_matrix(valid_indices, invalid_indices)?;
 
-        let parity_rows = self.get_parity_rows();
+        Ok(rebuilt
+            .iter()
+            .map(|&i| {
+                (0..self.data_shard_count)
+                    .map(|c| {
+                        (0..self.data_shard_count).fold(F::zero(), |acc, j| {
+                            F::add(
+                                acc,
+                                F::mul(self.matrix.get(i, j), data_decode_matrix.get(j, c)),
+                            )
+                        })
+                    })
+                    .collect()
+            })
+            .collect())
+    }
 
-        Ok(self.check_some_slices_with_buffer(&parity_rows, data, to_check, buffer))
+    // Multiplies `inputs` by the matrix of `rows` into `outputs` on the CPU,
+    // for backends whose device failed, see `offload`.
+    #[cfg(any(feature = "gpu", feature = "cuda"))]
+    pub(crate) fn code_rows<T: AsRef<[F::Elem]>, U: AsMut<[F::Elem]>>(
+        &self,
+        rows: &[Vec<F::Elem>],
+        inputs: &[T],
+        outputs: &mut [U],
+    ) {
+        let rows: SmallVec<[&[F::Elem]; 32]> = rows.iter().map(Vec::as_slice).collect();
+        self.code_some_slices(&rows, inputs, outputs);
     }
 
-    /// Reconstructs all shards.
-    ///
-    /// The shards marked not present are only overwritten when no error
-    /// is detected. All provided shards must have the same length.
-    ///
-    /// This means if the method returns an `Error`, then nothing is touched.
-    ///
-    /// `reconstruct`, `reconstruct_data`, `reconstruct_shards`,
-    /// `reconstruct_data_shards` share the same core code base.
-    pub fn reconstruct<T: ReconstructShard<F>>(&self, slices: &mut [T]) -> Result<(), Error> {
-        self.reconstruct_internal(slices, false)
+    // Caches the decode matrices of all patterns of up to `max_erasures`
+    // missing shards.
+    fn precompute_inversions(&self, max_erasures: usize) {
+        if self.small_decoder.is_some() || self.tree.policy() == InversionCachePolicy::Disabled {
+            return;
+        }
+
+        let max_erasures = max_erasures.min(self.parity_shard_count);
+        self.precompute_inversions_from(0, max_erasures, &mut SmallVec::new());
     }
 
-    /// Reconstructs only the data shards.
-    ///
-    /// The shards marked not present are only overwritten when no error
-    /// is detected. All provided shards must have the same length.
-    ///
-    /// This means if the method returns an `Error`, then nothing is touched.
-    ///
-    /// `reconstruct`, `reconstruct_data`, `reconstruct_shards`,
-    /// `reconstruct_data_shards` share the same core code base.
-    pub fn reconstruct_data<T: ReconstructShard<F>>(&self, slices: &mut [T]) -> Result<(), Error> {
-        self.reconstruct_internal(slices, true)
+    fn precompute_inversions_from(
+        &self,
+        first: usize,
+        max_erasures: usize,
+        invalid_indices: &mut SmallVec<[usize; 32]>,
+    ) {
+        if invalid_indices.len() == max_erasures {
+            return;
+        }
+
+        for i in first..self.total_shard_count {
+            invalid_indices.push(i);
+            let valid_indices: SmallVec<[usize; 32]> = (0..self.total_shard_count)
+                .filter(|j| !invalid_indices.contains(j))
+                .take(self.data_shard_count)
+                .collect();
+            // patterns which cannot be reconstructed from are not cached
+            let _ = self.get_data_decode_matrix(&valid_indices, invalid_indices);
+            self.precompute_inversions_from(i + 1, max_erasures, invalid_indices);
+            invalid_indices.pop();
+        }
     }
 
+    // Returns `Error::InvalidMatrix` if the shards of `valid_indices` cannot
+    // be reconstructed from, which only happens for encoding matrices passed
+    // to `new_with_matrix_unchecked`.
     fn get_data_decode_matrix(
         &self,
         valid_indices: &[usize],
         invalid_indices: &[usize],
-    ) -> Arc<Matrix<F>> {
+    ) -> Result<Arc<Matrix<F>>, Error> {
+        if let Some(m) = self
+            .small_decoder
+            .as_ref()
+            .and_then(|decoder| decoder.get(valid_indices))
+        {
+            return Ok(m);
+        }
+
         // Attempt to get the cached inverted matrix out of the tree
         // based on the indices of the invalid rows.
         match self.tree.get_inverted_matrix(invalid_indices) {
@@ -709,7 +5624,8 @@ impl<F: Field> ReedSolomon<F> {
                 // generates the shard that we want to decode.  Note that
                 // since this matrix maps back to the original data, it can
                 // be used to create a data shard, but not a parity shard.
-                let data_decode_matrix = Arc::new(sub_matrix.invert().unwrap());
+                let data_decode_matrix =
+                    Arc::new(sub_matrix.invert().map_err(|_| Error::InvalidMatrix)?);
 
                 // Cache the inverted matrix in the tree for future use keyed on the
                 // indices of the invalid rows.
@@ -717,9 +5633,9 @@ impl<F: Field> ReedSolomon<F> {
                     .insert_inverted_matrix(invalid_indices, &data_decode_matrix)
                     .unwrap();
 
-                data_decode_matrix
+                Ok(data_decode_matrix)
             }
-            Some(m) => m,
+            Some(m) => Ok(m),
         }
     }
 
@@ -727,8 +5643,10 @@ impl<F: Field> ReedSolomon<F> {
         &self,
         shards: &mut [T],
         data_only: bool,
+        report: Option<&mut ReconstructReport>,
     ) -> Result<(), Error> {
         check_piece_count!(all => self, shards);
+        check_present_elems::<F, _>(shards)?;
 
         let data_shard_count = self.data_shard_count;
 
@@ -737,7 +5655,7 @@ impl<F: Field> ReedSolomon<F> {
         let mut number_present = 0;
         let mut shard_len = None;
 
-        for shard in shards.iter_mut() {
+        for (i, shard) in shards.iter_mut().enumerate() {
             if let Some(len) = shard.len() {
                 if len == 0 {
                     return Err(Error::EmptyShard);
@@ -746,7 +5664,11 @@ impl<F: Field> ReedSolomon<F> {
                 if let Some(old_len) = shard_len {
                     if len != old_len {
                         // mismatch between shards.
-                        return Err(Error::IncorrectShardSize);
+                        return Err(Error::IncorrectShardSize {
+                            shard: i,
+                            expected: old_len,
+                            actual: len,
+                        });
                     }
                 }
                 shard_len = Some(len);
@@ -766,6 +5688,22 @@ impl<F: Field> ReedSolomon<F> {
 
         let shard_len = shard_len.expect("at least one shard present; qed");
 
+        // The decode matrix is found before any missing shard is initialized,
+        // as it may not exist for matrices passed to `new_with_matrix_unchecked`.
+        // Those are never decoded with the additive FFT.
+        let data_decode_matrix = if self.fft.is_none() {
+            let valid_indices: SmallVec<[usize; 32]> = (0..self.total_shard_count)
+                .filter(|&i| shards[i].len().is_some())
+                .take(data_shard_count)
+                .collect();
+            let invalid_indices: SmallVec<[usize; 32]> = (0..self.total_shard_count)
+                .filter(|&i| shards[i].len().is_none())
+                .collect();
+            Some(self.get_data_decode_matrix(&valid_indices, &invalid_indices)?)
+        } else {
+            None
+        };
+
         // Pull out an array holding just the shards that
         // correspond to the rows of the submatrix.  These shards
         // will be the input to the decoding process that re-creates
@@ -821,7 +5759,7 @@ impl<F: Field> ReedSolomon<F> {
                 }
                 Err(Some(x)) => {
                     // initialized missing shard data.
-                    let shard = x?;
+                    let shard = x.map_err(|e| e.at_shard(matrix_row))?;
                     if matrix_row < data_shard_count {
                         missing_data_slices.push(shard);
                     } else {
@@ -833,85 +5771,283 @@ impl<F: Field> ReedSolomon<F> {
             }
         }
 
-        let data_decode_matrix = self.get_data_decode_matrix(&valid_indices, &invalid_indices);
-
-        // Re-create any data shards that were missing.
-        //
-        // The input to the coding is all of the shards we actually
-        // have, and the output is the missing data shards. The computation
-        // is done using the special decode matrix we just built.
-        let mut matrix_rows: SmallVec<[&[F::Elem]; 32]> =
-            SmallVec::with_capacity(self.parity_shard_count);
+        // the shards the secondary implementation reconstructs from
+        #[cfg(feature = "shadow")]
+        let shadow_shards = self.shadow.is_set().then(|| {
+            let mut shadow_shards = vec![None; self.total_shard_count];
+            for (&i, shard) in valid_indices.iter().zip(&sub_shards) {
+                shadow_shards[i] = Some(shard.to_vec());
+            }
+            shadow_shards
+        });
 
-        for i_slice in invalid_indices
-            .iter()
-            .copied()
-            .take_while(|i| i < &data_shard_count)
-        {
-            matrix_rows.push(data_decode_matrix.get_row(i_slice));
+        if let Some(report) = report {
+            report.rebuilt = invalid_indices
+                .iter()
+                .copied()
+                .filter(|&i| !data_only || i < data_shard_count)
+                .collect();
+            report.used = if report.rebuilt.is_empty() {
+                Vec::new()
+            } else {
+                valid_indices.to_vec()
+            };
         }
 
-        self.code_some_slices(&matrix_rows, &sub_shards, &mut missing_data_slices);
-
-        if data_only {
-            Ok(())
+        // Decode with the additive FFT where it takes fewer multiplications
+        // than the decode matrix, which then need not be inverted either.
+        let missing = missing_data_slices.len() + missing_parity_slices.len();
+        if let Some(fft) = self
+            .fft
+            .as_ref()
+            .filter(|fft| fft.reconstruct_cost() < missing * data_shard_count)
+        {
+            let rebuilt = invalid_indices
+                .iter()
+                .copied()
+                .filter(|&i| !data_only || i < data_shard_count);
+            let outputs = missing_data_slices
+                .iter_mut()
+                .chain(missing_parity_slices.iter_mut())
+                .map(|shard| &mut **shard);
+            let mul_ops = fft.reconstruct(
+                self.platform,
+                &valid_indices,
+                &sub_shards,
+                rebuilt.zip(outputs),
+            );
+            self.record(data_shard_count * shard_len, missing * shard_len, mul_ops);
         } else {
-            // Now that we have all of the data shards intact, we can
-            // compute any of the parity that is missing.
+            let data_decode_matrix = match data_decode_matrix {
+                Some(m) => m,
+                None => self.get_data_decode_matrix(&valid_indices, &invalid_indices)?,
+            };
+
+            // Re-create any data shards that were missing.
             //
-            // The input to the coding is ALL of the data shards, including
-            // any that we just calculated.  The output is whichever of the
-            // parity shards were missing.
+            // The input to the coding is all of the shards we actually
+            // have, and the output is the missing data shards. The computation
+            // is done using the special decode matrix we just built.
             let mut matrix_rows: SmallVec<[&[F::Elem]; 32]> =
                 SmallVec::with_capacity(self.parity_shard_count);
-            let parity_rows = self.get_parity_rows();
 
             for i_slice in invalid_indices
                 .iter()
                 .copied()
-                .skip_while(|i| i < &data_shard_count)
+                .take_while(|i| i < &data_shard_count)
             {
-                matrix_rows.push(parity_rows[i_slice - data_shard_count]);
+                matrix_rows.push(data_decode_matrix.get_row(i_slice));
             }
-            {
-                // Gather up all the data shards.
-                // old data shards are in `sub_shards`,
-                // new ones are in `missing_data_slices`.
-                let mut i_old_data_slice = 0;
-                let mut i_new_data_slice = 0;
-
-                let mut all_data_slices: SmallVec<[&[F::Elem]; 32]> =
-                    SmallVec::with_capacity(data_shard_count);
-
-                let mut next_maybe_good = 0;
-                let mut push_good_up_to = move |data_slices: &mut SmallVec<_>, up_to| {
-                    // if next_maybe_good == up_to, this loop is a no-op.
-                    for _ in next_maybe_good..up_to {
-                        // push all good indices we just skipped.
-                        data_slices.push(sub_shards[i_old_data_slice]);
-                        i_old_data_slice += 1;
-                    }
 
-                    next_maybe_good = up_to + 1;
-                };
+            self.code_some_slices(&matrix_rows, &sub_shards, &mut missing_data_slices);
+
+            if !data_only {
+                // Now that we have all of the data shards intact, we can
+                // compute any of the parity that is missing.
+                //
+                // The input to the coding is ALL of the data shards, including
+                // any that we just calculated.  The output is whichever of the
+                // parity shards were missing.
+                let mut matrix_rows: SmallVec<[&[F::Elem]; 32]> =
+                    SmallVec::with_capacity(self.parity_shard_count);
+                let parity_rows = self.get_parity_rows();
 
                 for i_slice in invalid_indices
                     .iter()
                     .copied()
-                    .take_while(|i| i < &data_shard_count)
+                    .skip_while(|i| i < &data_shard_count)
                 {
-                    push_good_up_to(&mut all_data_slices, i_slice);
-                    all_data_slices.push(missing_data_slices[i_new_data_slice]);
-                    i_new_data_slice += 1;
+                    matrix_rows.push(parity_rows[i_slice - data_shard_count]);
                 }
-                push_good_up_to(&mut all_data_slices, data_shard_count);
+                {
+                    // Gather up all the data shards.
+                    // old data shards are in `sub_shards`,
+                    // new ones are in `missing_data_slices`.
+                    let mut i_old_data_slice = 0;
+                    let mut i_new_data_slice = 0;
+
+                    let mut all_data_slices: SmallVec<[&[F::Elem]; 32]> =
+                        SmallVec::with_capacity(data_shard_count);
+
+                    let mut next_maybe_good = 0;
+                    let mut push_good_up_to = move |data_slices: &mut SmallVec<_>, up_to| {
+                        // if next_maybe_good == up_to, this loop is a no-op.
+                        for _ in next_maybe_good..up_to {
+                            // push all good indices we just skipped.
+                            data_slices.push(sub_shards[i_old_data_slice]);
+                            i_old_data_slice += 1;
+                        }
 
-                // Now do the actual computation for the missing
-                // parity shards
-                self.code_some_slices(&matrix_rows, &all_data_slices, &mut missing_parity_slices);
+                        next_maybe_good = up_to + 1;
+                    };
+
+                    for i_slice in invalid_indices
+                        .iter()
+                        .copied()
+                        .take_while(|i| i < &data_shard_count)
+                    {
+                        push_good_up_to(&mut all_data_slices, i_slice);
+                        all_data_slices.push(missing_data_slices[i_new_data_slice]);
+                        i_new_data_slice += 1;
+                    }
+                    push_good_up_to(&mut all_data_slices, data_shard_count);
+
+                    // Now do the actual computation for the missing
+                    // parity shards
+                    self.code_some_slices(
+                        &matrix_rows,
+                        &all_data_slices,
+                        &mut missing_parity_slices,
+                    );
+                }
             }
+        }
 
-            Ok(())
+        #[cfg(feature = "shadow")]
+        if let Some(shadow_shards) = shadow_shards {
+            let rebuilt = invalid_indices
+                .iter()
+                .copied()
+                .filter(|&i| !data_only || i < data_shard_count)
+                .zip(missing_data_slices.iter().chain(&missing_parity_slices))
+                .map(|(i, shard)| (i, &**shard));
+            self.shadow
+                .check_reconstruct(shadow_shards, data_only, rebuilt);
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: Field<Elem = u8>> ReedSolomon<F> {
+    /// Constructs the parity shards like `encode`, returning the CRC-32C
+    /// checksum of each shard, data and parity, in the order of the shards.
+    ///
+    /// The stripe is encoded one block at a time, small enough to stay in
+    /// the CPU cache, and each block of the data and parity shards is
+    /// checksummed right after it is encoded, rather than in a second pass
+    /// over memory. The checksums can be passed to
+    /// `reconstruct_with_checksums`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[macro_use] extern crate reed_solomon_erasure;
+    /// # fn main () {
+    /// use reed_solomon_erasure::galois_8::ReedSolomon;
+    ///
+    /// let r = ReedSolomon::new(3, 2).unwrap();
+    /// let mut shards = shards!([0, 1, 2], [3, 4, 5], [6, 7, 8], [0, 0, 0], [0, 0, 0]);
+    ///
+    /// let checksums = r.encode_with_checksums(&mut shards).unwrap();
+    /// assert_eq!(5, checksums.len());
+    ///
+    /// let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+    /// shards[0] = None;
+    /// shards[1].as_mut().unwrap()[0] ^= 1;
+    ///
+    /// assert_eq!(vec![1], r.reconstruct_with_checksums(&mut shards, &checksums).unwrap());
+    /// assert_eq!(Some(vec![0, 1, 2]), shards[0]);
+    /// assert_eq!(Some(vec![3, 4, 5]), shards[1]);
+    /// # }
+    /// ```
+    pub fn encode_with_checksums<T, U>(&self, mut shards: T) -> Result<Vec<u32>, Error>
+    where
+        T: AsRef<[U]> + AsMut<[U]>,
+        U: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        let slices: &mut [U] = shards.as_mut();
+
+        check_piece_count!(all => self, slices);
+        check_slices!(multi => slices);
+
+        let shard_len = slices[0].as_ref().len();
+        let block_len = (CACHE_BLOCK_BYTES / self.total_shard_count).max(1);
+        let mut crcs = vec![Crc32c::new(); self.total_shard_count];
+
+        let mut offset = 0;
+        while offset < shard_len {
+            let end = shard_len.min(offset + block_len);
+
+            let mut sub_stripe: SmallVec<[&mut [u8]; 32]> = slices
+                .iter_mut()
+                .map(|slice| &mut slice.as_mut()[offset..end])
+                .collect();
+            self.encode(&mut sub_stripe[..])?;
+
+            for (crc, block) in crcs.iter_mut().zip(&sub_stripe) {
+                crc.update(block);
+            }
+
+            offset = end;
+        }
+
+        Ok(crcs.into_iter().map(Crc32c::value).collect())
+    }
+
+    /// Reconstructs all shards like `reconstruct`, rejecting the present
+    /// shards which do not match their CRC-32C checksum in `checksums`,
+    /// e.g. as returned by `encode_with_checksums`.
+    ///
+    /// The rejected shards are treated as missing, so they are overwritten
+    /// with their reconstruction. Returns their indices in ascending order.
+    ///
+    /// Returns `Error::TooFewShards` or `Error::TooManyShards` if there is
+    /// not one checksum per shard, and `Error::TooFewShardsPresent` if
+    /// fewer than `data_shard_count` shards are present and match their
+    /// checksum. This means if the method returns an `Error`, then nothing
+    /// is touched.
+    pub fn reconstruct_with_checksums<T: ReconstructShard<F>>(
+        &self,
+        slices: &mut [T],
+        checksums: &[u32],
+    ) -> Result<Vec<usize>, Error> {
+        check_piece_count!(all => self, slices);
+        check_piece_count!(all => self, checksums);
+
+        let mut shard_len = None;
+        let mut valid = vec![false; self.total_shard_count];
+        let mut rejected = Vec::new();
+        for (i, shard) in slices.iter_mut().enumerate() {
+            if let Some(slice) = shard.get() {
+                if slice.is_empty() {
+                    return Err(Error::EmptyShard);
+                }
+                if let Some(old_len) = shard_len {
+                    if slice.len() != old_len {
+                        return Err(Error::IncorrectShardSize {
+                            shard: i,
+                            expected: old_len,
+                            actual: slice.len(),
+                        });
+                    }
+                }
+                shard_len = Some(slice.len());
+
+                let mut crc = Crc32c::new();
+                crc.update(slice);
+                if crc.value() == checksums[i] {
+                    valid[i] = true;
+                } else {
+                    rejected.push(i);
+                }
+            }
         }
+
+        if valid.iter().filter(|&&x| x).count() < self.data_shard_count {
+            return Err(Error::TooFewShardsPresent);
+        }
+        let shard_len = shard_len.expect("at least one shard present; qed");
+
+        let mut view: SmallVec<[(&mut [u8], bool); 32]> = SmallVec::with_capacity(slices.len());
+        for (i, (shard, &valid)) in slices.iter_mut().zip(&valid).enumerate() {
+            match shard.get_or_initialize(shard_len) {
+                Ok(x) | Err(Ok(x)) => view.push((x, valid)),
+                Err(Err(e)) => return Err(e.at_shard(i)),
+            }
+        }
+        self.reconstruct(&mut view)?;
+
+        Ok(rejected)
     }
 }
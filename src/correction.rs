@@ -0,0 +1,443 @@
+//! Correction of corrupted shards whose locations are unknown, for when
+//! there are no checksums to tell the corrupted shards apart.
+//!
+//! A `Corrector` decodes each element position of a stripe on its own:
+//! the syndromes of the position are computed with the parity-check matrix
+//! of the codec, the Berlekamp-Massey algorithm finds the error locator,
+//! a Chien search over the shard indices finds the corrupted shards and the
+//! Forney algorithm computes the errors. Missing shards are decoded as
+//! erasures alongside, so that `e` corrupted and `f` missing shards are
+//! corrected as long as `2 * e + f` is at most the parity shard count.
+//!
+//! This works for the codecs which are Reed-Solomon codes at the points
+//! `F::nth(i)` of the shards, or `F::nth(i + 1)` for non-systematic codecs,
+//! i.e. those built with `MatrixKind::Vandermonde` or `MatrixKind::Cauchy`,
+//! and with `MatrixKind::AdditiveFft` for a power of 2 of data shards.
+//!
+//! # Example
+//!
+//! ```
+//! # #[macro_use] extern crate reed_solomon_erasure;
+//! # fn main () {
+//! use reed_solomon_erasure::correction::Corrector;
+//! use reed_solomon_erasure::galois_8::ReedSolomon;
+//!
+//! let r = ReedSolomon::new(3, 4).unwrap();
+//! let corrector = Corrector::new(&r).unwrap();
+//!
+//! let mut shards = shards!(
+//!     [0, 1, 2],
+//!     [3, 4, 5],
+//!     [6, 7, 8],
+//!     [0, 0, 0],
+//!     [0, 0, 0],
+//!     [0, 0, 0],
+//!     [0, 0, 0]
+//! );
+//! r.encode(&mut shards).unwrap();
+//! let expect = shards.clone();
+//!
+//! let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+//! shards[0].as_mut().unwrap()[1] ^= 0x55;
+//! shards[5].as_mut().unwrap()[2] ^= 0x0f;
+//!
+//! assert_eq!(vec![0, 5], corrector.correct(&mut shards).unwrap());
+//! assert_eq!(expect, shards.into_iter().flatten().collect::<Vec<_>>());
+//! # }
+//! ```
+
+use crate::core::{mul_slice_add, ReedSolomon};
+use crate::{Error, Field, Platform, ReconstructShard};
+
+/// A decoder of errors and erasures for the shards of a codec.
+///
+/// The shards are assigned the points `X_i = F::nth(i) + F::nth(n)`, with
+/// `n` the total shard count, or `F::nth(i + 1) + F::nth(0)` for
+/// non-systematic codecs, which are all nonzero.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Corrector<F: Field> {
+    data_shard_count: usize,
+    total_shard_count: usize,
+    platform: Option<Platform>,
+    points: Vec<F::Elem>,
+    inv_points: Vec<F::Elem>,
+    // the column multipliers of the parity-check matrix
+    multipliers: Vec<F::Elem>,
+    // row `j` holds `multipliers[i] * points[i]^j` for each shard `i`
+    parity_check: Vec<Vec<F::Elem>>,
+}
+
+impl<F: Field> Corrector<F> {
+    /// Creates a corrector for the shards of `codec`.
+    ///
+    /// Returns `Error::InvalidMatrix` if the code of `codec` is not a
+    /// Reed-Solomon code at the points of the shards, e.g. with
+    /// `MatrixKind::AdditiveFft` and a data shard count which is not a power
+    /// of 2, or a matrix passed to `new_with_matrix`. Returns
+    /// `Error::TooManyShards` if the total shard count is the order of the
+    /// field, which leaves no point to shift the points of the shards by.
+    pub fn new(codec: &ReedSolomon<F>) -> Result<Self, Error> {
+        let data_shard_count = codec.data_shard_count();
+        let total_shard_count = codec.total_shard_count();
+
+        // the shift is the one point of the field not used by the shards
+        let (first, shift) = if codec.is_systematic() {
+            let shift = F::checked_nth(total_shard_count)
+                .map_err(|_| Error::TooManyShards { max: F::ORDER - 1 })?;
+            (0, shift)
+        } else {
+            (1, F::nth(0))
+        };
+        let points: Vec<_> = (first..first + total_shard_count)
+            .map(|i| F::add(F::nth(i), shift))
+            .collect();
+        let inv_points = points.iter().map(|&x| F::div(F::one(), x)).collect();
+
+        let encoding = codec.encoding_matrix();
+        let multipliers = parity_check_multipliers::<F>(&encoding, &points, data_shard_count)?;
+
+        let parity_check = (0..total_shard_count - data_shard_count)
+            .map(|j| {
+                multipliers
+                    .iter()
+                    .zip(&points)
+                    .map(|(&w, &x)| F::mul(w, F::exp(x, j)))
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            data_shard_count,
+            total_shard_count,
+            platform: codec.platform(),
+            points,
+            inv_points,
+            multipliers,
+            parity_check,
+        })
+    }
+
+    /// Returns the number of corrupted shards which can be corrected
+    /// alongside `missing` missing shards.
+    pub const fn max_errors(&self, missing: usize) -> usize {
+        (self.total_shard_count - self.data_shard_count).saturating_sub(missing) / 2
+    }
+
+    /// Corrects the corrupted shards of `shards` and reconstructs the
+    /// missing ones.
+    ///
+    /// Returns the indices of the shards which were corrupted, in ascending
+    /// order, not including the missing ones.
+    ///
+    /// Returns `Error::TooFewShardsPresent` if more shards are missing than
+    /// there are parity shards, and `Error::InconsistentShards` if the
+    /// errors of an element position could not be corrected, i.e. there are
+    /// more than `max_errors` of them. The shards are left untouched on
+    /// error.
+    ///
+    /// Errors beyond `max_errors` may also be miscorrected into another
+    /// consistent stripe rather than detected, as with any decoder.
+    pub fn correct<T: ReconstructShard<F>>(&self, shards: &mut [T]) -> Result<Vec<usize>, Error> {
+        check_piece_count!(all => self, shards);
+
+        let mut shard_len = None;
+        for (i, shard) in shards.iter().enumerate() {
+            if let Some(len) = shard.len() {
+                if len == 0 {
+                    return Err(Error::EmptyShard);
+                }
+                if let Some(old_len) = shard_len {
+                    if len != old_len {
+                        return Err(Error::IncorrectShardSize {
+                            shard: i,
+                            expected: old_len,
+                            actual: len,
+                        });
+                    }
+                }
+                shard_len = Some(len);
+            }
+        }
+
+        let erasures: Vec<usize> = (0..self.total_shard_count)
+            .filter(|&i| shards[i].is_empty())
+            .collect();
+        let parity_shard_count = self.total_shard_count - self.data_shard_count;
+        if erasures.len() > parity_shard_count {
+            return Err(Error::TooFewShardsPresent);
+        }
+        let shard_len = shard_len.expect("at least one shard present; qed");
+
+        // (shard, position, error) of each corrected element
+        let mut corrections = Vec::new();
+        {
+            let slices: Vec<Option<&mut [F::Elem]>> =
+                shards.iter_mut().map(ReconstructShard::get).collect();
+
+            // the syndromes of all positions at once, shard by shard
+            let mut syndromes = vec![vec![F::zero(); shard_len]; parity_shard_count];
+            for (i, slice) in slices.iter().enumerate() {
+                if let Some(slice) = slice {
+                    for (row, syndrome) in self.parity_check.iter().zip(&mut syndromes) {
+                        mul_slice_add::<F>(self.platform, row[i], slice, syndrome);
+                    }
+                }
+            }
+
+            let erasure_locator = self.erasure_locator(&erasures);
+            let mut column = vec![F::zero(); parity_shard_count];
+            for pos in 0..shard_len {
+                for (s, syndrome) in column.iter_mut().zip(&syndromes) {
+                    *s = syndrome[pos];
+                }
+                if erasures.is_empty() && column.iter().all(|&s| s == F::zero()) {
+                    continue;
+                }
+
+                let errata = self
+                    .decode(&column, &erasure_locator, erasures.len())
+                    .ok_or(Error::InconsistentShards)?;
+                corrections.extend(errata.into_iter().map(|(i, e)| (i, pos, e)));
+            }
+        }
+
+        for &i in &erasures {
+            if let Err(Err(e)) = shards[i].get_or_initialize(shard_len) {
+                return Err(e.at_shard(i));
+            }
+        }
+
+        let mut corrupted = Vec::new();
+        for (i, pos, e) in corrections {
+            let slice = shards[i].get().expect("shard initialized above; qed");
+            slice[pos] = F::add(slice[pos], e);
+
+            if !erasures.contains(&i) {
+                corrupted.push(i);
+            }
+        }
+        corrupted.sort_unstable();
+        corrupted.dedup();
+
+        Ok(corrupted)
+    }
+
+    // Returns `prod(1 + X_i z)` over the missing shards `i`, lowest degree first.
+    fn erasure_locator(&self, erasures: &[usize]) -> Vec<F::Elem> {
+        let mut locator = vec![F::one()];
+        for &i in erasures {
+            locator = poly_mul_linear::<F>(&locator, self.points[i]);
+        }
+        locator
+    }
+
+    // Decodes the errata of one position from its syndromes, returning the
+    // error of each errata location, or `None` if there are too many errors.
+    fn decode(
+        &self,
+        syndromes: &[F::Elem],
+        erasure_locator: &[F::Elem],
+        erasure_count: usize,
+    ) -> Option<Vec<(usize, F::Elem)>> {
+        let parity_shard_count = syndromes.len();
+
+        // Berlekamp-Massey, starting from the erasure locator
+        let mut locator = erasure_locator.to_vec();
+        let mut prev = erasure_locator.to_vec();
+        let mut len = erasure_count;
+        let mut prev_discrepancy = F::one();
+        let mut shift = 1;
+
+        for step in erasure_count..parity_shard_count {
+            let discrepancy = locator
+                .iter()
+                .enumerate()
+                .take(step + 1)
+                .fold(F::zero(), |acc, (i, &c)| {
+                    F::add(acc, F::mul(c, syndromes[step - i]))
+                });
+
+            if discrepancy == F::zero() {
+                shift += 1;
+                continue;
+            }
+
+            let scale = F::div(discrepancy, prev_discrepancy);
+            let mut next = locator.clone();
+            if next.len() < prev.len() + shift {
+                next.resize(prev.len() + shift, F::zero());
+            }
+            for (i, &c) in prev.iter().enumerate() {
+                next[i + shift] = F::add(next[i + shift], F::mul(scale, c));
+            }
+
+            if 2 * len <= step + erasure_count {
+                len = step + 1 + erasure_count - len;
+                prev = std::mem::replace(&mut locator, next);
+                prev_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                locator = next;
+                shift += 1;
+            }
+        }
+
+        while locator.len() > 1 && locator.last() == Some(&F::zero()) {
+            locator.pop();
+        }
+        if locator.len() - 1 != len
+            || 2 * (len - erasure_count) + erasure_count > parity_shard_count
+        {
+            return None;
+        }
+
+        // Chien search over the points of the shards
+        let roots: Vec<usize> = (0..self.total_shard_count)
+            .filter(|&i| poly_eval::<F>(&locator, self.inv_points[i]) == F::zero())
+            .collect();
+        if roots.len() != len {
+            return None;
+        }
+
+        // Forney, with the evaluator `S(z) * locator(z) mod z^(n - k)`
+        let mut evaluator = vec![F::zero(); parity_shard_count];
+        for (i, &s) in syndromes.iter().enumerate() {
+            for (j, &c) in locator.iter().enumerate().take(parity_shard_count - i) {
+                evaluator[i + j] = F::add(evaluator[i + j], F::mul(s, c));
+            }
+        }
+        // the formal derivative, which only keeps the odd terms in GF(2^m)
+        let derivative: Vec<_> = locator
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| if i % 2 == 1 { c } else { F::zero() })
+            .collect();
+
+        let mut errata = Vec::with_capacity(roots.len());
+        for i in roots {
+            let inv_x = self.inv_points[i];
+            let denominator = poly_eval::<F>(&derivative, inv_x);
+            if denominator == F::zero() {
+                return None;
+            }
+
+            let value = F::div(
+                F::mul(self.points[i], poly_eval::<F>(&evaluator, inv_x)),
+                denominator,
+            );
+            errata.push((i, F::div(value, self.multipliers[i])));
+        }
+
+        Some(errata)
+    }
+}
+
+// Finds the column multipliers `w` of the parity-check matrix with rows
+// `w_i * X_i^j`, `j < n - k`, of the code spanned by the columns of
+// `encoding`, i.e. the null space of the equations
+// `sum_i w_i * X_i^j * encoding[i][c] = 0`, which is of dimension 1 for a
+// Reed-Solomon code at the points `X_i`.
+fn parity_check_multipliers<F: Field>(
+    encoding: &[Vec<F::Elem>],
+    points: &[F::Elem],
+    data_shard_count: usize,
+) -> Result<Vec<F::Elem>, Error> {
+    let total_shard_count = points.len();
+    let parity_shard_count = total_shard_count - data_shard_count;
+
+    // the rows in reduced row echelon form, with the column of their pivot
+    let mut rows: Vec<(usize, Vec<F::Elem>)> = Vec::new();
+    let mut powers = vec![F::one(); total_shard_count];
+
+    'equations: for _ in 0..parity_shard_count {
+        for c in 0..data_shard_count {
+            if rows.len() + 1 == total_shard_count {
+                break 'equations;
+            }
+
+            let mut eq: Vec<_> = (0..total_shard_count)
+                .map(|i| F::mul(powers[i], encoding[i][c]))
+                .collect();
+            for (pivot, row) in &rows {
+                let scale = eq[*pivot];
+                if scale != F::zero() {
+                    for (e, &r) in eq.iter_mut().zip(row) {
+                        *e = F::add(*e, F::mul(scale, r));
+                    }
+                }
+            }
+
+            let Some(pivot) = eq.iter().position(|&e| e != F::zero()) else {
+                continue;
+            };
+            let inv = F::div(F::one(), eq[pivot]);
+            for e in &mut eq {
+                *e = F::mul(*e, inv);
+            }
+            for (_, row) in &mut rows {
+                let scale = row[pivot];
+                if scale != F::zero() {
+                    for (r, &e) in row.iter_mut().zip(&eq) {
+                        *r = F::add(*r, F::mul(scale, e));
+                    }
+                }
+            }
+            rows.push((pivot, eq));
+        }
+
+        for (power, &x) in powers.iter_mut().zip(points) {
+            *power = F::mul(*power, x);
+        }
+    }
+
+    if rows.len() + 1 != total_shard_count {
+        return Err(Error::InvalidMatrix);
+    }
+
+    // each pivot is the sum of its row at the free column, which is set to 1
+    let free = (0..total_shard_count)
+        .find(|&i| rows.iter().all(|(pivot, _)| *pivot != i))
+        .expect("one column without a pivot; qed");
+    let mut multipliers = vec![F::one(); total_shard_count];
+    for (pivot, row) in &rows {
+        multipliers[*pivot] = row[free];
+    }
+    if multipliers.iter().any(|&w| w == F::zero()) {
+        return Err(Error::InvalidMatrix);
+    }
+
+    // only the equations needed to reach the rank were used, check them all
+    for c in 0..data_shard_count {
+        let mut terms: Vec<_> = (0..total_shard_count)
+            .map(|i| F::mul(multipliers[i], encoding[i][c]))
+            .collect();
+        for _ in 0..parity_shard_count {
+            if terms.iter().fold(F::zero(), |acc, &t| F::add(acc, t)) != F::zero() {
+                return Err(Error::InvalidMatrix);
+            }
+            for (t, &x) in terms.iter_mut().zip(points) {
+                *t = F::mul(*t, x);
+            }
+        }
+    }
+
+    Ok(multipliers)
+}
+
+// Multiplies `poly` by `1 + x z`.
+fn poly_mul_linear<F: Field>(poly: &[F::Elem], x: F::Elem) -> Vec<F::Elem> {
+    let mut out = poly.to_vec();
+    out.push(F::zero());
+    for (i, &c) in poly.iter().enumerate() {
+        out[i + 1] = F::add(out[i + 1], F::mul(c, x));
+    }
+    out
+}
+
+// Evaluates `poly`, lowest degree first, at `z` with Horner's method.
+fn poly_eval<F: Field>(poly: &[F::Elem], z: F::Elem) -> F::Elem {
+    poly.iter()
+        .rev()
+        .fold(F::zero(), |acc, &c| F::add(F::mul(acc, z), c))
+}
@@ -0,0 +1,48 @@
+// Multiplies the inputs by a matrix over GF(2^8), compiled to PTX by
+// build.rs for the `cuda` module.
+//
+// Each thread computes the word `word` of the output `blockIdx.y`, four
+// bytes at a time, from the logarithms of the coefficients, where
+// 0xffffffff marks a zero one. The inputs and outputs follow each other
+// at a stride of `words`.
+extern "C" __global__ void gal_mul_matrix(
+    const unsigned char *log_table,
+    const unsigned char *exp_table,
+    const unsigned int *coefficients,
+    const unsigned int *input,
+    unsigned int *output,
+    unsigned int inputs,
+    unsigned long long words)
+{
+    __shared__ unsigned char log_shared[256];
+    __shared__ unsigned char exp_shared[510];
+    for (unsigned int i = threadIdx.x; i < 510; i += blockDim.x) {
+        if (i < 256) {
+            log_shared[i] = log_table[i];
+        }
+        exp_shared[i] = exp_table[i];
+    }
+    __syncthreads();
+
+    unsigned long long word = (unsigned long long)blockIdx.x * blockDim.x + threadIdx.x;
+    unsigned int row = blockIdx.y;
+    if (word >= words) {
+        return;
+    }
+
+    unsigned int result = 0;
+    for (unsigned int i = 0; i < inputs; i++) {
+        unsigned int log_c = coefficients[row * inputs + i];
+        if (log_c == 0xffffffffu) {
+            continue;
+        }
+        unsigned int x = input[i * words + word];
+        for (unsigned int shift = 0; shift < 32; shift += 8) {
+            unsigned int byte = (x >> shift) & 0xff;
+            if (byte != 0) {
+                result ^= (unsigned int)exp_shared[log_c + log_shared[byte]] << shift;
+            }
+        }
+    }
+    output[row * words + word] = result;
+}
@@ -0,0 +1,222 @@
+//! Encoding and reconstruction on NVIDIA GPUs with CUDA, enabled by the
+//! `cuda` feature, for repair nodes which need the most reconstruction
+//! throughput they can get.
+//!
+//! Unlike the `gpu` module, this needs the CUDA toolkit: `nvcc` compiles
+//! the kernel at build time, and the driver library is linked. Stripes
+//! smaller than `CudaCodec::min_stripe_bytes` are coded on the CPU, as
+//! with `GpuCodec`.
+//!
+//! The shards are staged in page-locked host memory, which the GPU copies
+//! from and to at the full speed of the bus.
+
+use cust::context::{Context, CurrentContext};
+use cust::launch;
+use cust::memory::{AsyncCopyDestination, DeviceBuffer, LockedBuffer};
+use cust::module::Module;
+use cust::stream::{Stream, StreamFlags};
+
+use crate::galois_8::{Field, ReedSolomon, EXP_TABLE, LOG_TABLE};
+use crate::offload::{self, Offload};
+use crate::{Error, ReconstructShard};
+
+/// The default of `CudaCodec::min_stripe_bytes`.
+pub const DEFAULT_MIN_STRIPE_BYTES: usize = 16 * 1024 * 1024;
+
+// The bytes of each shard copied to the GPU per pass.
+const PASS_BYTES: usize = 16 * 1024 * 1024;
+
+// The threads of a block, each computing one word of an output.
+const BLOCK_SIZE: usize = 256;
+
+static PTX: &str = include_str!(concat!(env!("OUT_DIR"), "/cuda.ptx"));
+
+/// A codec over GF(2^8) coding large stripes on an NVIDIA GPU and all
+/// others on the CPU.
+///
+/// # Example
+///
+/// ```no_run
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// # fn main () {
+/// use reed_solomon_erasure::cuda::CudaCodec;
+/// use reed_solomon_erasure::galois_8::ReedSolomon;
+///
+/// let codec = CudaCodec::new(ReedSolomon::new(3, 2).unwrap())
+///     .unwrap()
+///     .min_stripe_bytes(0);
+///
+/// let mut shards = shards!([0, 1, 2], [3, 4, 5], [6, 7, 8], [0, 0, 0], [0, 0, 0]);
+/// codec.encode(&mut shards).unwrap();
+/// # }
+/// ```
+pub struct CudaCodec {
+    codec: ReedSolomon,
+    min_stripe_bytes: usize,
+    // the buffers and the module are dropped first, within the context
+    log_table: DeviceBuffer<u8>,
+    exp_table: DeviceBuffer<u8>,
+    module: Module,
+    context: Context,
+}
+
+impl CudaCodec {
+    /// Wraps `codec`, coding on the first CUDA device.
+    ///
+    /// Returns `None` if there is no CUDA device, or the driver cannot
+    /// load the kernel.
+    pub fn new(codec: ReedSolomon) -> Option<Self> {
+        let context = cust::quick_init().ok()?;
+        let module = Module::from_ptx(PTX, &[]).ok()?;
+        let log_table = DeviceBuffer::from_slice(&LOG_TABLE).ok()?;
+        let exp_table = DeviceBuffer::from_slice(&EXP_TABLE).ok()?;
+
+        Some(Self {
+            codec,
+            min_stripe_bytes: DEFAULT_MIN_STRIPE_BYTES,
+            log_table,
+            exp_table,
+            module,
+            context,
+        })
+    }
+
+    /// Sets the size of the smallest stripe, i.e. of all its shards, coded
+    /// on the GPU, `DEFAULT_MIN_STRIPE_BYTES` by default.
+    ///
+    /// 0 codes every stripe on the GPU, and `usize::MAX` none of them.
+    #[must_use]
+    pub const fn min_stripe_bytes(mut self, bytes: usize) -> Self {
+        self.min_stripe_bytes = bytes;
+        self
+    }
+
+    /// Returns the codec coding on the CPU.
+    pub const fn codec(&self) -> &ReedSolomon {
+        &self.codec
+    }
+
+    /// Constructs the parity shards like `ReedSolomon::encode`.
+    ///
+    /// If CUDA fails, e.g. runs out of memory, the stripe is coded on the
+    /// CPU instead.
+    pub fn encode<T, U>(&self, mut shards: T) -> Result<(), Error>
+    where
+        T: AsRef<[U]> + AsMut<[U]>,
+        U: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        offload::encode(&self.codec, self.min_stripe_bytes, self, shards.as_mut())
+    }
+
+    /// Constructs the parity shards like `ReedSolomon::encode_sep`.
+    ///
+    /// If CUDA fails, e.g. runs out of memory, the stripe is coded on the
+    /// CPU instead.
+    pub fn encode_sep<T: AsRef<[u8]>, U: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        data: &[T],
+        parity: &mut [U],
+    ) -> Result<(), Error> {
+        offload::encode_sep(&self.codec, self.min_stripe_bytes, self, data, parity)
+    }
+
+    /// Reconstructs all shards like `ReedSolomon::reconstruct`.
+    ///
+    /// On the GPU, the missing data and parity shards are computed in one
+    /// pass over the present ones.
+    ///
+    /// If CUDA fails, e.g. runs out of memory, the stripe is coded on the
+    /// CPU instead.
+    pub fn reconstruct<T: ReconstructShard<Field>>(&self, shards: &mut [T]) -> Result<(), Error> {
+        offload::reconstruct(&self.codec, self.min_stripe_bytes, self, shards, false)
+    }
+
+    /// Reconstructs only the data shards like
+    /// `ReedSolomon::reconstruct_data`.
+    ///
+    /// If CUDA fails, e.g. runs out of memory, the stripe is coded on the
+    /// CPU instead.
+    pub fn reconstruct_data<T: ReconstructShard<Field>>(
+        &self,
+        shards: &mut [T],
+    ) -> Result<(), Error> {
+        offload::reconstruct(&self.codec, self.min_stripe_bytes, self, shards, true)
+    }
+
+    fn try_code(
+        &self,
+        rows: &[Vec<u8>],
+        inputs: &[&[u8]],
+        outputs: &mut [&mut [u8]],
+    ) -> cust::error::CudaResult<()> {
+        // the calling thread may not have the context yet
+        CurrentContext::set_current(&self.context)?;
+        let stream = Stream::new(StreamFlags::NON_BLOCKING, None)?;
+        let function = self.module.get_function("gal_mul_matrix")?;
+
+        let shard_len = inputs[0].len();
+        let max_words = shard_len.div_ceil(4).min(PASS_BYTES / 4);
+        let coefficients = DeviceBuffer::from_slice(&offload::log_coefficients(rows))?;
+        let mut input_host = LockedBuffer::new(&0_u8, inputs.len() * max_words * 4)?;
+        let mut output_host = LockedBuffer::new(&0_u8, outputs.len() * max_words * 4)?;
+        let input = DeviceBuffer::zeroed(inputs.len() * max_words * 4)?;
+        let output = DeviceBuffer::zeroed(outputs.len() * max_words * 4)?;
+
+        let mut offset = 0;
+        while offset < shard_len {
+            let len = (shard_len - offset).min(max_words * 4);
+            let words = len.div_ceil(4);
+
+            // the inputs and outputs are packed at the stride of this pass
+            for (i, shard) in inputs.iter().enumerate() {
+                let at = i * words * 4;
+                input_host[at..at + len].copy_from_slice(&shard[offset..offset + len]);
+                input_host[at + len..at + words * 4].fill(0);
+            }
+            let in_bytes = inputs.len() * words * 4;
+            let out_bytes = outputs.len() * words * 4;
+
+            // Safe because the host buffers are page-locked and outlive
+            // the copies, which the stream is synchronized with below.
+            unsafe {
+                input
+                    .index(..in_bytes)
+                    .async_copy_from(&input_host[..in_bytes], &stream)?;
+                launch!(function<<<
+                    (words.div_ceil(BLOCK_SIZE) as u32, outputs.len() as u32, 1),
+                    BLOCK_SIZE as u32,
+                    0,
+                    stream
+                >>>(
+                    self.log_table.as_device_ptr(),
+                    self.exp_table.as_device_ptr(),
+                    coefficients.as_device_ptr(),
+                    input.as_device_ptr(),
+                    output.as_device_ptr(),
+                    inputs.len() as u32,
+                    words as u64
+                ))?;
+                output
+                    .index(..out_bytes)
+                    .async_copy_to(&mut output_host[..out_bytes], &stream)?;
+            }
+            stream.synchronize()?;
+
+            for (j, shard) in outputs.iter_mut().enumerate() {
+                let at = j * words * 4;
+                shard[offset..offset + len].copy_from_slice(&output_host[at..at + len]);
+            }
+
+            offset += len;
+        }
+
+        Ok(())
+    }
+}
+
+impl Offload for CudaCodec {
+    // Codes in passes of `PASS_BYTES` of each shard.
+    fn code(&self, rows: &[Vec<u8>], inputs: &[&[u8]], outputs: &mut [&mut [u8]]) -> bool {
+        self.try_code(rows, inputs, outputs).is_ok()
+    }
+}
@@ -0,0 +1,93 @@
+//! Comparison of stripes, e.g. for tracking down which replica of a stripe
+//! suffered from bit rot.
+
+/// The differences between two versions of a single shard.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ShardDiff {
+    /// The index of the shard in the stripe.
+    pub index: usize,
+    /// The offset of the first mismatching byte.
+    pub first: usize,
+    /// The offset of the last mismatching byte.
+    pub last: usize,
+    /// The number of mismatching bytes.
+    pub count: usize,
+}
+
+/// The differences between two stripes, as returned by `compare_stripes`.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct StripeDiff {
+    /// The shards which differ, in ascending order of index.
+    pub shards: Vec<ShardDiff>,
+}
+
+impl StripeDiff {
+    /// Returns `true` if the stripes are identical.
+    pub const fn is_empty(&self) -> bool {
+        self.shards.is_empty()
+    }
+
+    /// Returns the indices of the shards which differ.
+    pub fn indices(&self) -> Vec<usize> {
+        self.shards.iter().map(|x| x.index).collect()
+    }
+
+    /// Returns the total number of mismatching bytes.
+    pub fn count(&self) -> usize {
+        self.shards.iter().map(|x| x.count).sum()
+    }
+}
+
+/// Compares stripes `a` and `b` shard by shard.
+///
+/// Shards of different lengths are compared up to the length of the shorter
+/// one, with the remaining bytes of the longer one counted as mismatching.
+/// Likewise, shards present in only one of the stripes are compared against
+/// empty shards.
+///
+/// # Example
+///
+/// ```
+/// use reed_solomon_erasure::diff::{compare_stripes, ShardDiff};
+///
+/// let a = vec![vec![0u8, 1, 2, 3], vec![4, 5, 6, 7]];
+/// let mut b = a.clone();
+/// b[1][0] = 0;
+/// b[1][2] = 0;
+///
+/// let diff = compare_stripes(&a, &b);
+///
+/// assert_eq!(
+///     diff.shards,
+///     vec![ShardDiff { index: 1, first: 0, last: 2, count: 2 }]
+/// );
+/// ```
+pub fn compare_stripes<T: AsRef<[u8]>, U: AsRef<[u8]>>(a: &[T], b: &[U]) -> StripeDiff {
+    let mut shards = Vec::new();
+
+    for index in 0..a.len().max(b.len()) {
+        let x = a.get(index).map_or(&[][..], AsRef::as_ref);
+        let y = b.get(index).map_or(&[][..], AsRef::as_ref);
+
+        let mut mismatches = x
+            .iter()
+            .zip(y)
+            .enumerate()
+            .filter(|(_, (x, y))| x != y)
+            .map(|(i, _)| i)
+            .chain(x.len().min(y.len())..x.len().max(y.len()));
+
+        if let Some(first) = mismatches.next() {
+            let (last, count) = mismatches.fold((first, 1), |(_, count), i| (i, count + 1));
+
+            shards.push(ShardDiff {
+                index,
+                first,
+                last,
+                count,
+            });
+        }
+    }
+
+    StripeDiff { shards }
+}
@@ -1,45 +1,120 @@
 use std::fmt::Formatter;
+use std::io;
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[non_exhaustive]
 pub enum Error {
     TooFewShards,
-    TooManyShards,
+    TooManyShards {
+        /// The maximum number of shards, e.g. the total shard count of the
+        /// codec, or the order of the field when creating a codec.
+        max: usize,
+    },
     TooFewDataShards,
     TooManyDataShards,
     TooFewParityShards,
     TooManyParityShards,
     TooFewBufferShards,
     TooManyBufferShards,
-    IncorrectShardSize,
+    IncorrectShardSize {
+        /// The index of the offending shard in the list of shards it was
+        /// passed in, or the index of the data shard for methods taking
+        /// a single data shard.
+        shard: usize,
+        /// The length of the other shards.
+        expected: usize,
+        /// The length of the offending shard.
+        actual: usize,
+    },
     TooFewShardsPresent,
     EmptyShard,
     InvalidShardFlags,
     InvalidIndex,
+    InvalidInversionCache,
+    InvalidSamplePosition,
+    InvalidMatrix,
+    InvalidPuncturing,
+    InvalidInterleaving,
+    IncompatibleField,
+    InconsistentShards,
+    InvalidRange,
+    Cancelled,
+    DivisionByZero,
+    TooManyCalls,
+    LeftoverShards,
+    OutOfOrder,
+    UnsupportedPlatform,
+    NoDigest,
+    InvalidElement,
 }
 
 impl Error {
-    const fn as_str(&self) -> &str {
+    pub(crate) const fn as_str(&self) -> &'static str {
         match *self {
             Error::TooFewShards=> "The number of provided shards is smaller than the one in codec",
-            Error::TooManyShards => "The number of provided shards is greater than the one in codec",
+            Error::TooManyShards { .. } => "The number of provided shards is greater than the one in codec",
             Error::TooFewDataShards => "The number of provided data shards is smaller than the one in codec",
             Error::TooManyDataShards => "The number of provided data shards is greater than the one in codec",
             Error::TooFewParityShards => "The number of provided parity shards is smaller than the one in codec",
             Error::TooManyParityShards => "The number of provided parity shards is greater than the one in codec",
             Error::TooFewBufferShards => "The number of provided buffer shards is smaller than the number of parity shards in codec",
             Error::TooManyBufferShards => "The number of provided buffer shards is greater than the number of parity shards in codec",
-            Error::IncorrectShardSize => "At least one of the provided shards is not of the correct size",
+            Error::IncorrectShardSize { .. } => "At least one of the provided shards is not of the correct size",
             Error::TooFewShardsPresent => "The number of shards present is smaller than number of parity shards, cannot reconstruct missing shards",
             Error::EmptyShard => "The first shard provided is of zero length",
             Error::InvalidShardFlags => "The number of flags does not match the total number of shards",
             Error::InvalidIndex => "The data shard index provided is greater or equal to the number of data shards in codec",
+            Error::InvalidInversionCache => "The exported inversion cache is malformed or was exported from a codec with a different configuration",
+            Error::InvalidSamplePosition => "At least one of the provided sample positions is greater or equal to the shard size",
+            Error::InvalidMatrix => "The provided encoding matrix rows are of the wrong size or do not allow reconstruction from every set of data shard count shards",
+            Error::InvalidPuncturing => "At least one data shard is marked as punctured, only parity shards may be punctured",
+            Error::InvalidInterleaving => "The interleaving depth is zero or at least one of the provided erasures lies outside of the shards",
+            Error::IncompatibleField => "The field or polynomial of the provided configuration does not match the field of the codec",
+            Error::InconsistentShards => "The present shards do not match each other, at least one of them is corrupted",
+            Error::InvalidRange => "The provided range does not lie within the shards",
+            Error::Cancelled => "The operation was cancelled before it completed",
+            Error::DivisionByZero => "The divisor provided is zero",
+            Error::TooManyCalls => "All data shards of the stripe were already encoded shard by shard",
+            Error::LeftoverShards => "The stripe was reset before all of its data shards were encoded shard by shard",
+            Error::OutOfOrder => "The data shard provided is not the next one to be encoded shard by shard",
+            Error::UnsupportedPlatform => "The CPU does not support the SIMD platform the codec was configured with",
+            Error::NoDigest => "The stripe encoded shard by shard does not compute a checksum of its data shards",
+            Error::InvalidElement => "At least one of the provided shards holds a value which is not an element of the field",
+        }
+    }
+
+    // Sets the shard index of `Error::IncorrectShardSize`, for errors of
+    // callees which do not know it.
+    pub(crate) const fn at_shard(self, i: usize) -> Self {
+        match self {
+            Error::IncorrectShardSize {
+                expected, actual, ..
+            } => Error::IncorrectShardSize {
+                shard: i,
+                expected,
+                actual,
+            },
+            e => e,
         }
     }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
-        write!(f, "{}", self.as_str())
+        write!(f, "{}", self.as_str())?;
+
+        match *self {
+            Error::TooManyShards { max } => write!(f, " (at most {max})"),
+            Error::IncorrectShardSize {
+                shard,
+                expected,
+                actual,
+            } => write!(
+                f,
+                " (shard {shard} has length {actual}, expected {expected})"
+            ),
+            _ => Ok(()),
+        }
     }
 }
 
@@ -49,10 +124,34 @@ impl std::error::Error for Error {
     }
 }
 
+/// Wraps the error in an `io::Error` of kind `io::ErrorKind::InvalidInput`,
+/// e.g. to return it from functions which also do I/O.
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        Self::new(io::ErrorKind::InvalidInput, e)
+    }
+}
+
+/// `SBSError::RSError` is unwrapped, and the other errors become the
+/// `Error`s of the same name.
+impl From<SBSError> for Error {
+    fn from(e: SBSError) -> Self {
+        match e {
+            SBSError::TooManyCalls => Error::TooManyCalls,
+            SBSError::LeftoverShards => Error::LeftoverShards,
+            SBSError::OutOfOrder => Error::OutOfOrder,
+            SBSError::NoDigest => Error::NoDigest,
+            SBSError::RSError(e) => e,
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum SBSError {
     TooManyCalls,
     LeftoverShards,
+    OutOfOrder,
+    NoDigest,
     RSError(Error),
 }
 
@@ -61,6 +160,8 @@ impl SBSError {
         match *self {
             SBSError::TooManyCalls => "Too many calls",
             SBSError::LeftoverShards => "Leftover shards",
+            SBSError::OutOfOrder => "Data shard out of order",
+            SBSError::NoDigest => "No digest",
             SBSError::RSError(ref e) => e.as_str(),
         }
     }
@@ -68,7 +169,10 @@ impl SBSError {
 
 impl std::fmt::Display for SBSError {
     fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
-        write!(f, "{}", self.as_str())
+        match *self {
+            SBSError::RSError(ref e) => write!(f, "{e}"),
+            _ => write!(f, "{}", self.as_str()),
+        }
     }
 }
 
@@ -76,6 +180,34 @@ impl std::error::Error for SBSError {
     fn description(&self) -> &str {
         self.as_str()
     }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SBSError::RSError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The inverse of `From<SBSError> for Error`, so errors of the codec can be
+/// propagated with `?` from shard by shard encoding and vice versa.
+impl From<Error> for SBSError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::TooManyCalls => SBSError::TooManyCalls,
+            Error::LeftoverShards => SBSError::LeftoverShards,
+            Error::OutOfOrder => SBSError::OutOfOrder,
+            Error::NoDigest => SBSError::NoDigest,
+            e => SBSError::RSError(e),
+        }
+    }
+}
+
+/// Wraps the error in an `io::Error` of kind `io::ErrorKind::InvalidInput`.
+impl From<SBSError> for io::Error {
+    fn from(e: SBSError) -> Self {
+        Self::new(io::ErrorKind::InvalidInput, e)
+    }
 }
 
 #[cfg(test)]
@@ -90,8 +222,8 @@ mod tests {
             "The number of provided shards is smaller than the one in codec"
         );
         assert_eq!(
-            Error::TooManyShards.to_string(),
-            "The number of provided shards is greater than the one in codec"
+            Error::TooManyShards { max: 256 }.to_string(),
+            "The number of provided shards is greater than the one in codec (at most 256)"
         );
         assert_eq!(
             Error::TooFewDataShards.to_string(),
@@ -118,8 +250,13 @@ mod tests {
             "The number of provided buffer shards is greater than the number of parity shards in codec"
         );
         assert_eq!(
-            Error::IncorrectShardSize.to_string(),
-            "At least one of the provided shards is not of the correct size"
+            Error::IncorrectShardSize {
+                shard: 2,
+                expected: 10,
+                actual: 9
+            }
+            .to_string(),
+            "At least one of the provided shards is not of the correct size (shard 2 has length 9, expected 10)"
         );
         assert_eq!(Error::TooFewShardsPresent.to_string(), "The number of shards present is smaller than number of parity shards, cannot reconstruct missing shards");
         assert_eq!(
@@ -134,12 +271,91 @@ mod tests {
             Error::InvalidIndex.to_string(),
             "The data shard index provided is greater or equal to the number of data shards in codec"
         );
+        assert_eq!(
+            Error::DivisionByZero.to_string(),
+            "The divisor provided is zero"
+        );
+        assert_eq!(
+            Error::TooManyCalls.to_string(),
+            "All data shards of the stripe were already encoded shard by shard"
+        );
+        assert_eq!(
+            Error::LeftoverShards.to_string(),
+            "The stripe was reset before all of its data shards were encoded shard by shard"
+        );
+        assert_eq!(
+            Error::OutOfOrder.to_string(),
+            "The data shard provided is not the next one to be encoded shard by shard"
+        );
+        assert_eq!(
+            Error::UnsupportedPlatform.to_string(),
+            "The CPU does not support the SIMD platform the codec was configured with"
+        );
+        assert_eq!(
+            Error::NoDigest.to_string(),
+            "The stripe encoded shard by shard does not compute a checksum of its data shards"
+        );
+        assert_eq!(
+            Error::InvalidElement.to_string(),
+            "At least one of the provided shards holds a value which is not an element of the field"
+        );
     }
 
     #[test]
     fn test_sbserror_to_string_is_okay() {
         assert_eq!(SBSError::TooManyCalls.to_string(), "Too many calls");
         assert_eq!(SBSError::LeftoverShards.to_string(), "Leftover shards");
+        assert_eq!(SBSError::OutOfOrder.to_string(), "Data shard out of order");
+        assert_eq!(SBSError::NoDigest.to_string(), "No digest");
+    }
+
+    #[test]
+    fn test_sbserror_source() {
+        use std::error::Error as _;
+
+        let e = SBSError::RSError(Error::EmptyShard);
+        assert_eq!(
+            Some(Error::EmptyShard.to_string()),
+            e.source().map(ToString::to_string)
+        );
+        assert!(SBSError::TooManyCalls.source().is_none());
+    }
+
+    #[test]
+    fn test_sbserror_conversions() {
+        for &e in &[
+            SBSError::TooManyCalls,
+            SBSError::LeftoverShards,
+            SBSError::OutOfOrder,
+            SBSError::NoDigest,
+            SBSError::RSError(Error::EmptyShard),
+        ] {
+            assert_eq!(e, SBSError::from(Error::from(e)));
+        }
+        assert_eq!(
+            Error::EmptyShard,
+            SBSError::RSError(Error::EmptyShard).into()
+        );
+        assert_eq!(Error::OutOfOrder, SBSError::OutOfOrder.into());
+    }
+
+    #[test]
+    fn test_io_error_conversions() {
+        use std::io;
+
+        let e = io::Error::from(Error::EmptyShard);
+        assert_eq!(io::ErrorKind::InvalidInput, e.kind());
+        assert_eq!(
+            Some(&Error::EmptyShard),
+            e.get_ref().and_then(|e| e.downcast_ref::<Error>())
+        );
+
+        let e = io::Error::from(SBSError::TooManyCalls);
+        assert_eq!(io::ErrorKind::InvalidInput, e.kind());
+        assert_eq!(
+            Some(&SBSError::TooManyCalls),
+            e.get_ref().and_then(|e| e.downcast_ref::<SBSError>())
+        );
     }
 
     #[test]
@@ -0,0 +1,66 @@
+/// Runs the jobs of the `_parallel` methods of `ReedSolomon`, letting the
+/// caller choose the threads the work runs on.
+///
+/// `ScopedThreads`, which spawns a thread per job, is used by default. With
+/// the `rayon` feature, a `rayon::ThreadPool` can be used as well.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// # use reed_solomon_erasure::*;
+/// # use reed_solomon_erasure::galois_8::ReedSolomon;
+/// # fn main () {
+/// // runs the jobs one after the other on the calling thread
+/// struct Inline;
+///
+/// impl Executor for Inline {
+///     fn execute<'a>(&self, jobs: Vec<Box<dyn FnOnce() + Send + 'a>>) {
+///         for job in jobs {
+///             job();
+///         }
+///     }
+/// }
+///
+/// let r = ReedSolomon::new(3, 2).unwrap();
+/// let mut shards = shards!([0, 1, 2], [3, 4, 5], [6, 7, 8], [0, 0, 0], [0, 0, 0]);
+/// r.encode(&mut shards).unwrap();
+///
+/// let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+/// shards[1] = None;
+/// r.reconstruct_parallel_in(&mut shards, 2, &Inline).unwrap();
+/// assert_eq!(shards[1], Some(vec![3, 4, 5]));
+/// # }
+/// ```
+pub trait Executor {
+    /// Runs all `jobs`, in any order and on any threads, returning once
+    /// they have all finished.
+    ///
+    /// A panic of a job should be propagated to the caller.
+    fn execute<'a>(&self, jobs: Vec<Box<dyn FnOnce() + Send + 'a>>);
+}
+
+/// An `Executor` spawning a scoped thread per job.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScopedThreads;
+
+impl Executor for ScopedThreads {
+    fn execute<'a>(&self, jobs: Vec<Box<dyn FnOnce() + Send + 'a>>) {
+        std::thread::scope(|scope| {
+            for job in jobs {
+                scope.spawn(job);
+            }
+        });
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Executor for rayon::ThreadPool {
+    fn execute<'a>(&self, jobs: Vec<Box<dyn FnOnce() + Send + 'a>>) {
+        self.scope(|scope| {
+            for job in jobs {
+                scope.spawn(move |_| job());
+            }
+        });
+    }
+}
@@ -0,0 +1,406 @@
+//! C interface over GF(2^8), enabled by the `ffi` feature, so that C and
+//! C++ programs can use the codecs of this crate.
+//!
+//! All types are `#[repr(C)]` or opaque, so a header can be generated
+//! with cbindgen. A C library is built by compiling the crate with the
+//! `ffi` feature as a `cdylib` or `staticlib`, e.g. with
+//! `cargo rustc --release --features ffi --crate-type cdylib`.
+//!
+//! The interface is stable: functions are only ever added, and the values
+//! of `RseStatus` are never reused. Panics never unwind into C, they are
+//! reported as `RseStatus::Panicked` instead.
+//!
+//! Shards are passed as an array of `shard_count` pointers to buffers of
+//! `shard_len` bytes each, which must not overlap.
+
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::galois_8::ReedSolomon;
+use crate::Error;
+
+/// A codec, created with `rse_codec_new` and destroyed with `rse_codec_free`.
+#[derive(Debug)]
+pub struct RseCodec(ReedSolomon);
+
+/// The outcome of a call.
+///
+/// Every `Error` has a status of the same name.
+#[repr(C)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RseStatus {
+    Ok = 0,
+    TooFewShards = 1,
+    TooManyShards = 2,
+    TooFewDataShards = 3,
+    TooManyDataShards = 4,
+    TooFewParityShards = 5,
+    TooManyParityShards = 6,
+    TooFewBufferShards = 7,
+    TooManyBufferShards = 8,
+    IncorrectShardSize = 9,
+    TooFewShardsPresent = 10,
+    EmptyShard = 11,
+    InvalidShardFlags = 12,
+    InvalidIndex = 13,
+    InvalidInversionCache = 14,
+    InvalidSamplePosition = 15,
+    InvalidMatrix = 16,
+    InvalidPuncturing = 17,
+    InvalidInterleaving = 18,
+    IncompatibleField = 19,
+    InconsistentShards = 20,
+    InvalidRange = 21,
+    Cancelled = 22,
+    DivisionByZero = 23,
+    TooManyCalls = 24,
+    LeftoverShards = 25,
+    OutOfOrder = 26,
+    UnsupportedPlatform = 27,
+    NoDigest = 28,
+    InvalidElement = 29,
+    /// A required pointer was null.
+    NullPointer = 100,
+    /// The call panicked, which is a bug of this crate.
+    Panicked = 101,
+}
+
+impl From<Error> for RseStatus {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::TooFewShards => Self::TooFewShards,
+            Error::TooManyShards { .. } => Self::TooManyShards,
+            Error::TooFewDataShards => Self::TooFewDataShards,
+            Error::TooManyDataShards => Self::TooManyDataShards,
+            Error::TooFewParityShards => Self::TooFewParityShards,
+            Error::TooManyParityShards => Self::TooManyParityShards,
+            Error::TooFewBufferShards => Self::TooFewBufferShards,
+            Error::TooManyBufferShards => Self::TooManyBufferShards,
+            Error::IncorrectShardSize { .. } => Self::IncorrectShardSize,
+            Error::TooFewShardsPresent => Self::TooFewShardsPresent,
+            Error::EmptyShard => Self::EmptyShard,
+            Error::InvalidShardFlags => Self::InvalidShardFlags,
+            Error::InvalidIndex => Self::InvalidIndex,
+            Error::InvalidInversionCache => Self::InvalidInversionCache,
+            Error::InvalidSamplePosition => Self::InvalidSamplePosition,
+            Error::InvalidMatrix => Self::InvalidMatrix,
+            Error::InvalidPuncturing => Self::InvalidPuncturing,
+            Error::InvalidInterleaving => Self::InvalidInterleaving,
+            Error::IncompatibleField => Self::IncompatibleField,
+            Error::InconsistentShards => Self::InconsistentShards,
+            Error::InvalidRange => Self::InvalidRange,
+            Error::Cancelled => Self::Cancelled,
+            Error::DivisionByZero => Self::DivisionByZero,
+            Error::TooManyCalls => Self::TooManyCalls,
+            Error::LeftoverShards => Self::LeftoverShards,
+            Error::OutOfOrder => Self::OutOfOrder,
+            Error::UnsupportedPlatform => Self::UnsupportedPlatform,
+            Error::NoDigest => Self::NoDigest,
+            Error::InvalidElement => Self::InvalidElement,
+        }
+    }
+}
+
+impl RseStatus {
+    // The status of the value `status`, if it is known to this version.
+    const fn from_u32(status: u32) -> Option<Self> {
+        Some(match status {
+            0 => Self::Ok,
+            1 => Self::TooFewShards,
+            2 => Self::TooManyShards,
+            3 => Self::TooFewDataShards,
+            4 => Self::TooManyDataShards,
+            5 => Self::TooFewParityShards,
+            6 => Self::TooManyParityShards,
+            7 => Self::TooFewBufferShards,
+            8 => Self::TooManyBufferShards,
+            9 => Self::IncorrectShardSize,
+            10 => Self::TooFewShardsPresent,
+            11 => Self::EmptyShard,
+            12 => Self::InvalidShardFlags,
+            13 => Self::InvalidIndex,
+            14 => Self::InvalidInversionCache,
+            15 => Self::InvalidSamplePosition,
+            16 => Self::InvalidMatrix,
+            17 => Self::InvalidPuncturing,
+            18 => Self::InvalidInterleaving,
+            19 => Self::IncompatibleField,
+            20 => Self::InconsistentShards,
+            21 => Self::InvalidRange,
+            22 => Self::Cancelled,
+            23 => Self::DivisionByZero,
+            24 => Self::TooManyCalls,
+            25 => Self::LeftoverShards,
+            26 => Self::OutOfOrder,
+            27 => Self::UnsupportedPlatform,
+            28 => Self::NoDigest,
+            29 => Self::InvalidElement,
+            100 => Self::NullPointer,
+            101 => Self::Panicked,
+            _ => return None,
+        })
+    }
+
+    // The error of the status, without its details, or the description of
+    // statuses without one.
+    const fn error(self) -> Result<Error, &'static str> {
+        Ok(match self {
+            Self::Ok => return Err("Success"),
+            Self::NullPointer => return Err("A required pointer was null"),
+            Self::Panicked => return Err("The call panicked"),
+            Self::TooFewShards => Error::TooFewShards,
+            Self::TooManyShards => Error::TooManyShards { max: 0 },
+            Self::TooFewDataShards => Error::TooFewDataShards,
+            Self::TooManyDataShards => Error::TooManyDataShards,
+            Self::TooFewParityShards => Error::TooFewParityShards,
+            Self::TooManyParityShards => Error::TooManyParityShards,
+            Self::TooFewBufferShards => Error::TooFewBufferShards,
+            Self::TooManyBufferShards => Error::TooManyBufferShards,
+            Self::IncorrectShardSize => Error::IncorrectShardSize {
+                shard: 0,
+                expected: 0,
+                actual: 0,
+            },
+            Self::TooFewShardsPresent => Error::TooFewShardsPresent,
+            Self::EmptyShard => Error::EmptyShard,
+            Self::InvalidShardFlags => Error::InvalidShardFlags,
+            Self::InvalidIndex => Error::InvalidIndex,
+            Self::InvalidInversionCache => Error::InvalidInversionCache,
+            Self::InvalidSamplePosition => Error::InvalidSamplePosition,
+            Self::InvalidMatrix => Error::InvalidMatrix,
+            Self::InvalidPuncturing => Error::InvalidPuncturing,
+            Self::InvalidInterleaving => Error::InvalidInterleaving,
+            Self::IncompatibleField => Error::IncompatibleField,
+            Self::InconsistentShards => Error::InconsistentShards,
+            Self::InvalidRange => Error::InvalidRange,
+            Self::Cancelled => Error::Cancelled,
+            Self::DivisionByZero => Error::DivisionByZero,
+            Self::TooManyCalls => Error::TooManyCalls,
+            Self::LeftoverShards => Error::LeftoverShards,
+            Self::OutOfOrder => Error::OutOfOrder,
+            Self::UnsupportedPlatform => Error::UnsupportedPlatform,
+            Self::NoDigest => Error::NoDigest,
+            Self::InvalidElement => Error::InvalidElement,
+        })
+    }
+}
+
+fn status(f: impl FnOnce() -> Result<(), RseStatus>) -> RseStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => RseStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => RseStatus::Panicked,
+    }
+}
+
+// The shards behind `shards`, see the safety requirements of the callers.
+unsafe fn shards_mut<'a>(
+    shards: *const *mut u8,
+    shard_count: usize,
+    shard_len: usize,
+) -> Result<Vec<&'a mut [u8]>, RseStatus> {
+    if shards.is_null() {
+        return Err(RseStatus::NullPointer);
+    }
+
+    slice::from_raw_parts(shards, shard_count)
+        .iter()
+        .map(|&shard| {
+            if shard.is_null() {
+                Err(RseStatus::NullPointer)
+            } else {
+                Ok(slice::from_raw_parts_mut(shard, shard_len))
+            }
+        })
+        .collect()
+}
+
+// The read-only shards behind `shards`, see `shards_mut`.
+unsafe fn shards_ref<'a>(
+    shards: *const *const u8,
+    shard_count: usize,
+    shard_len: usize,
+) -> Result<Vec<&'a [u8]>, RseStatus> {
+    if shards.is_null() {
+        return Err(RseStatus::NullPointer);
+    }
+
+    slice::from_raw_parts(shards, shard_count)
+        .iter()
+        .map(|&shard| {
+            if shard.is_null() {
+                Err(RseStatus::NullPointer)
+            } else {
+                Ok(slice::from_raw_parts(shard, shard_len))
+            }
+        })
+        .collect()
+}
+
+/// Creates a codec with `data_shards` data shards and `parity_shards`
+/// parity shards, see `ReedSolomon::new`.
+///
+/// Returns null if the shard counts are not supported.
+#[no_mangle]
+pub extern "C" fn rse_codec_new(data_shards: usize, parity_shards: usize) -> *mut RseCodec {
+    panic::catch_unwind(|| ReedSolomon::new(data_shards, parity_shards))
+        .ok()
+        .and_then(Result::ok)
+        .map_or(ptr::null_mut(), |codec| {
+            Box::into_raw(Box::new(RseCodec(codec)))
+        })
+}
+
+/// Destroys `codec`. Does nothing if `codec` is null.
+///
+/// # Safety
+///
+/// `codec` must be null or have been returned by `rse_codec_new`, and
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rse_codec_free(codec: *mut RseCodec) {
+    if !codec.is_null() {
+        drop(Box::from_raw(codec));
+    }
+}
+
+/// Constructs the parity shards, see `ReedSolomon::encode`.
+///
+/// # Safety
+///
+/// `codec` must be a live codec, and `shards` an array of `shard_count`
+/// pointers to distinct buffers of `shard_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rse_encode(
+    codec: *const RseCodec,
+    shards: *const *mut u8,
+    shard_count: usize,
+    shard_len: usize,
+) -> RseStatus {
+    status(|| {
+        let codec = codec.as_ref().ok_or(RseStatus::NullPointer)?;
+        let mut shards = shards_mut(shards, shard_count, shard_len)?;
+        Ok(codec.0.encode(&mut shards)?)
+    })
+}
+
+/// Checks whether the parity shards are correct, see `ReedSolomon::verify`,
+/// storing the outcome in `*correct`.
+///
+/// # Safety
+///
+/// `codec` must be a live codec, `shards` an array of `shard_count`
+/// pointers to buffers of `shard_len` bytes, and `correct` must point
+/// to a `bool`.
+#[no_mangle]
+pub unsafe extern "C" fn rse_verify(
+    codec: *const RseCodec,
+    shards: *const *const u8,
+    shard_count: usize,
+    shard_len: usize,
+    correct: *mut bool,
+) -> RseStatus {
+    status(|| {
+        let codec = codec.as_ref().ok_or(RseStatus::NullPointer)?;
+        let correct = correct.as_mut().ok_or(RseStatus::NullPointer)?;
+        let shards = shards_ref(shards, shard_count, shard_len)?;
+        *correct = codec.0.verify(&shards)?;
+        Ok(())
+    })
+}
+
+/// Reconstructs the shards which are not `present`, see
+/// `ReedSolomon::reconstruct`. The buffers of the missing shards are
+/// overwritten.
+///
+/// # Safety
+///
+/// `codec` must be a live codec, `shards` an array of `shard_count`
+/// pointers to distinct buffers of `shard_len` bytes, and `present`
+/// an array of `shard_count` flags.
+#[no_mangle]
+pub unsafe extern "C" fn rse_reconstruct(
+    codec: *const RseCodec,
+    shards: *const *mut u8,
+    present: *const bool,
+    shard_count: usize,
+    shard_len: usize,
+) -> RseStatus {
+    reconstruct(codec, shards, present, shard_count, shard_len, false)
+}
+
+/// Reconstructs the data shards which are not `present`, see
+/// `ReedSolomon::reconstruct_data`. The buffers of the missing parity
+/// shards are left untouched.
+///
+/// # Safety
+///
+/// See `rse_reconstruct`.
+#[no_mangle]
+pub unsafe extern "C" fn rse_reconstruct_data(
+    codec: *const RseCodec,
+    shards: *const *mut u8,
+    present: *const bool,
+    shard_count: usize,
+    shard_len: usize,
+) -> RseStatus {
+    reconstruct(codec, shards, present, shard_count, shard_len, true)
+}
+
+unsafe fn reconstruct(
+    codec: *const RseCodec,
+    shards: *const *mut u8,
+    present: *const bool,
+    shard_count: usize,
+    shard_len: usize,
+    data_only: bool,
+) -> RseStatus {
+    status(|| {
+        let codec = codec.as_ref().ok_or(RseStatus::NullPointer)?;
+        if present.is_null() {
+            return Err(RseStatus::NullPointer);
+        }
+        let present = slice::from_raw_parts(present, shard_count);
+
+        let mut shards: Vec<(&mut [u8], bool)> = shards_mut(shards, shard_count, shard_len)?
+            .into_iter()
+            .zip(present.iter().copied())
+            .collect();
+        if data_only {
+            Ok(codec.0.reconstruct_data(&mut shards)?)
+        } else {
+            Ok(codec.0.reconstruct(&mut shards)?)
+        }
+    })
+}
+
+/// Writes a description of the `RseStatus` value `status` into `buf` as
+/// a NUL-terminated string, truncated to `len` bytes including the
+/// terminator, like `snprintf`.
+///
+/// The status is taken as an integer, since statuses added by later
+/// versions are not known to this one. Those are described as unknown.
+///
+/// Returns the length of the full description, excluding the terminator.
+///
+/// # Safety
+///
+/// `buf` must be null, with `len == 0`, or point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rse_status_message(status: u32, buf: *mut c_char, len: usize) -> usize {
+    let message = RseStatus::from_u32(status).map_or("Unknown status", |status| {
+        status
+            .error()
+            .map_or_else(|message| message, |e| e.as_str())
+    });
+
+    if !buf.is_null() && len > 0 {
+        let copied = message.len().min(len - 1);
+        ptr::copy_nonoverlapping(message.as_ptr().cast::<c_char>(), buf, copied);
+        *buf.add(copied) = 0;
+    }
+
+    message.len()
+}
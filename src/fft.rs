@@ -0,0 +1,338 @@
+//! Additive FFT over the novel polynomial basis of Lin, Chung and Han,
+//! used by codecs built with `MatrixKind::AdditiveFft`.
+//!
+//! With `K`, the data shard count rounded up to a power of 2, the shards
+//! are the values of a polynomial of degree below `K` at the points
+//! `nth(i)`: data shard `i` at `nth(i)`, parity shard `j` at `nth(K + j)`,
+//! and the polynomial is zero at the points `nth(data_shards..K)`, which
+//! stand for data shards that are always zero.
+//!
+//! The points `nth(0..2^j)` must form a subspace of the field for every `j`,
+//! i.e. `nth(a ^ b) == add(nth(a), nth(b))`, which holds for all fields
+//! of this crate.
+//!
+//! Encoding then takes `O(n log n)` multiplications per element rather than
+//! the `data_shards * parity_shards` of the encoding matrix, and decoding,
+//! following the formal derivative approach of the same authors, takes
+//! `O(n log n)` per element plus `O(n^2)` scalar multiplications.
+
+use crate::core::{mul_slice, mul_slice_add};
+use crate::platform::Platform;
+use crate::Field;
+
+/// The maximum total shard count of a codec with `data_shards` data shards.
+pub const fn max_shards<F: Field>(data_shards: usize) -> usize {
+    // the largest power of 2 not above the order, as the points
+    // must fit into a subspace
+    let domain = (F::ORDER / 2 + 1).next_power_of_two();
+    match data_shards.checked_next_power_of_two() {
+        Some(padded) if padded <= domain => domain - (padded - data_shards),
+        _ => 0,
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct AdditiveFft<F: Field> {
+    data_shards: usize,
+    parity_shards: usize,
+    /// The data shard count rounded up to a power of 2.
+    padded: usize,
+    /// The number of points decoding works on, the shard positions
+    /// rounded up to a power of 2.
+    domain: usize,
+    /// `skew[j][b]` is the normalized subspace polynomial of degree
+    /// `2^j` at `nth(2^b)`, for `b > j`.
+    skew: Vec<Vec<F::Elem>>,
+    /// The formal derivatives of the normalized subspace polynomials,
+    /// which are constant.
+    derivative: Vec<F::Elem>,
+}
+
+impl<F: Field> AdditiveFft<F> {
+    /// The shard counts must be within `max_shards`.
+    pub fn new(data_shards: usize, parity_shards: usize) -> Self {
+        let padded = data_shards.next_power_of_two();
+        let domain = (padded + parity_shards).next_power_of_two();
+        let bits = domain.trailing_zeros() as usize;
+
+        // `w[b]` is the subspace polynomial of degree `2^j` at `nth(2^b)`,
+        // using W_{j+1}(x) = W_j(x) * (W_j(x) + W_j(nth(2^j)))
+        let mut w: Vec<F::Elem> = (0..bits).map(|b| F::nth(1 << b)).collect();
+        // the product of the nonzero roots of W_j, its derivative
+        let mut roots = F::one();
+
+        let mut skew = Vec::with_capacity(bits);
+        let mut derivative = Vec::with_capacity(bits);
+        for j in 0..bits {
+            let w_j = w[j];
+            skew.push(
+                (0..bits)
+                    .map(|b| if b > j { F::div(w[b], w_j) } else { F::zero() })
+                    .collect(),
+            );
+            derivative.push(F::div(roots, w_j));
+
+            for b in j + 1..bits {
+                w[b] = F::mul(w[b], F::add(w[b], w_j));
+            }
+            for u in 1 << j..2 << j {
+                roots = F::mul(roots, F::nth(u));
+            }
+        }
+
+        Self {
+            data_shards,
+            parity_shards,
+            padded,
+            domain,
+            skew,
+            derivative,
+        }
+    }
+
+    /// The approximate number of elements multiplied or added per element
+    /// of the shards to encode all parity shards.
+    pub const fn encode_cost(&self) -> usize {
+        let blocks = 1 + self.parity_shards.div_ceil(self.padded);
+        blocks * self.padded * self.padded.trailing_zeros() as usize
+    }
+
+    /// The approximate number of elements multiplied or added per element
+    /// of the shards to reconstruct any shards.
+    pub const fn reconstruct_cost(&self) -> usize {
+        3 * self.domain * self.domain.trailing_zeros() as usize
+    }
+
+    // The point of shard `i`.
+    const fn position(&self, i: usize) -> usize {
+        if i < self.data_shards {
+            i
+        } else {
+            self.padded + i - self.data_shards
+        }
+    }
+
+    // The normalized subspace polynomial of degree `2^j` at `nth(r)`,
+    // which is additive and zero on `nth(0..2^j)`.
+    fn skew(&self, j: usize, r: usize) -> F::Elem {
+        let mut skew = F::zero();
+        let mut r = r >> (j + 1);
+        let mut b = j + 1;
+        while r != 0 {
+            if r & 1 == 1 {
+                skew = F::add(skew, self.skew[j][b]);
+            }
+            r >>= 1;
+            b += 1;
+        }
+
+        skew
+    }
+
+    /// Computes the parity shards with the kernel of `platform`, returning
+    /// the number of elements multiplied.
+    pub fn encode<T: AsRef<[F::Elem]>, U: AsMut<[F::Elem]>>(
+        &self,
+        platform: Option<Platform>,
+        data: &[T],
+        parity: &mut [U],
+    ) -> usize {
+        let len = data[0].as_ref().len();
+        if len == 0 {
+            return 0;
+        }
+
+        let mut coefficients = vec![F::zero(); self.padded * len];
+        for (points, data) in coefficients.chunks_mut(len).zip(data) {
+            points.copy_from_slice(data.as_ref());
+        }
+        let mut mul_ops = self.ifft(platform, &mut coefficients, len, 0);
+
+        // each block of `padded` parity shards is a coset of the points
+        // of the data shards
+        let mut values = vec![F::zero(); self.padded * len];
+        for (block, parity) in parity.chunks_mut(self.padded).enumerate() {
+            values.copy_from_slice(&coefficients);
+            mul_ops += self.fft(platform, &mut values, len, (block + 1) * self.padded);
+            for (parity, values) in parity.iter_mut().zip(values.chunks(len)) {
+                parity.as_mut().copy_from_slice(values);
+            }
+        }
+
+        mul_ops
+    }
+
+    /// Computes the shards `outputs` are paired with from the shards
+    /// `present` at the indices `valid`, of which there must be
+    /// `data_shards`, with the kernel of `platform`, returning the number
+    /// of elements multiplied.
+    pub fn reconstruct<'a, T: AsRef<[F::Elem]>>(
+        &self,
+        platform: Option<Platform>,
+        valid: &[usize],
+        present: &[T],
+        outputs: impl IntoIterator<Item = (usize, &'a mut [F::Elem])>,
+    ) -> usize
+    where
+        F::Elem: 'a,
+    {
+        let len = present[0].as_ref().len();
+
+        // the zero data shards are known as well
+        let mut known = vec![false; self.domain];
+        for &i in valid {
+            known[self.position(i)] = true;
+        }
+        for known in &mut known[self.data_shards..self.padded] {
+            *known = true;
+        }
+        let erased: Vec<usize> = (0..self.domain).filter(|&i| !known[i]).collect();
+
+        // the erasure locator polynomial at `nth(i)`, leaving out `nth(i)`
+        // itself for the erased points, for which it is the derivative
+        let locator = |i: usize| {
+            erased
+                .iter()
+                .filter(|&&e| e != i)
+                .fold(F::one(), |product, &e| F::mul(product, F::nth(i ^ e)))
+        };
+
+        // the values of the polynomial times the erasure locator, which
+        // is zero at the erased points
+        let mut values = vec![F::zero(); self.domain * len];
+        for (&i, present) in valid.iter().zip(present) {
+            let i = self.position(i);
+            mul_slice::<F>(
+                platform,
+                locator(i),
+                present.as_ref(),
+                &mut values[i * len..(i + 1) * len],
+            );
+        }
+        let mut mul_ops = valid.len() * len;
+
+        mul_ops += self.ifft(platform, &mut values, len, 0);
+        mul_ops += self.formal_derivative(platform, &mut values, len);
+        mul_ops += self.fft(platform, &mut values, len, 0);
+
+        // at an erased point the derivative of the product is the value
+        // of the polynomial times the derivative of the erasure locator
+        for (i, output) in outputs {
+            let i = self.position(i);
+            let scale = F::div(F::one(), locator(i));
+            mul_slice::<F>(platform, scale, &values[i * len..(i + 1) * len], output);
+            mul_ops += len;
+        }
+
+        mul_ops
+    }
+
+    // Evaluates the polynomial with the coefficients in `values` at the
+    // points `nth(offset..offset + points)`, `offset` being a multiple of
+    // the number of points, which are `len` elements each.
+    fn fft(
+        &self,
+        platform: Option<Platform>,
+        values: &mut [F::Elem],
+        len: usize,
+        offset: usize,
+    ) -> usize {
+        let points = values.len() / len;
+        let mut mul_ops = 0;
+
+        let mut half = points / 2;
+        while half > 0 {
+            let j = half.trailing_zeros() as usize;
+            for (block, values) in values.chunks_mut(2 * half * len).enumerate() {
+                let skew = self.skew(j, offset + block * 2 * half);
+                let (low, high) = values.split_at_mut(half * len);
+                if skew != F::zero() {
+                    mul_slice_add::<F>(platform, skew, high, low);
+                    mul_ops += low.len();
+                }
+                add_slice::<F>(low, high);
+            }
+            half /= 2;
+        }
+
+        mul_ops
+    }
+
+    // The inverse of `fft`.
+    fn ifft(
+        &self,
+        platform: Option<Platform>,
+        values: &mut [F::Elem],
+        len: usize,
+        offset: usize,
+    ) -> usize {
+        let points = values.len() / len;
+        let mut mul_ops = 0;
+
+        let mut half = 1;
+        while half < points {
+            let j = half.trailing_zeros() as usize;
+            for (block, values) in values.chunks_mut(2 * half * len).enumerate() {
+                let skew = self.skew(j, offset + block * 2 * half);
+                let (low, high) = values.split_at_mut(half * len);
+                add_slice::<F>(low, high);
+                if skew != F::zero() {
+                    mul_slice_add::<F>(platform, skew, high, low);
+                    mul_ops += low.len();
+                }
+            }
+            half *= 2;
+        }
+
+        mul_ops
+    }
+
+    // Replaces the coefficients in `values` by the ones of the formal
+    // derivative. The derivative of the basis polynomial `i` is the sum
+    // of the ones `i ^ 2^j` for the bits `j` of `i`, times `derivative[j]`.
+    fn formal_derivative(
+        &self,
+        platform: Option<Platform>,
+        values: &mut [F::Elem],
+        len: usize,
+    ) -> usize {
+        let points = values.len() / len;
+        let mut mul_ops = 0;
+
+        // coefficient `t` only depends on higher ones, which are
+        // replaced later
+        for t in 0..points {
+            let (low, high) = values.split_at_mut((t + 1) * len);
+            let out = &mut low[t * len..];
+
+            let mut first = true;
+            for (j, &derivative) in self.derivative.iter().enumerate() {
+                let s = t | 1 << j;
+                if s == t || s >= points {
+                    continue;
+                }
+
+                let input = &high[(s - t - 1) * len..(s - t) * len];
+                if first {
+                    mul_slice::<F>(platform, derivative, input, out);
+                    first = false;
+                } else {
+                    mul_slice_add::<F>(platform, derivative, input, out);
+                }
+                mul_ops += len;
+            }
+            if first {
+                out.fill(F::zero());
+            }
+        }
+
+        mul_ops
+    }
+}
+
+// Adds each element of `input` to the corresponding one of `out`.
+fn add_slice<F: Field>(input: &[F::Elem], out: &mut [F::Elem]) {
+    for (i, o) in input.iter().zip(out) {
+        *o = F::add(*o, *i);
+    }
+}
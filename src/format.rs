@@ -0,0 +1,137 @@
+//! Versions of the formats of data produced by this crate which outlive
+//! a process, so archival users can check programmatically that what they
+//! stored stays decodable.
+//!
+//! # Deprecation policy
+//!
+//! A format is never changed in place: any change to it comes with a new
+//! version. The version written by the current release is `Support::Current`.
+//! Older versions stay readable as `Support::Deprecated` for at least the
+//! remainder of the major version of the crate in which they were superseded,
+//! and may only become `Support::Unsupported` in a later major version.
+//!
+//! # Example
+//!
+//! ```
+//! use reed_solomon_erasure::format::{self, Format, Header, Support};
+//! use reed_solomon_erasure::galois_8::ReedSolomon;
+//!
+//! let r = ReedSolomon::new(3, 2).unwrap();
+//! let cache = r.export_inversion_cache();
+//!
+//! let header = Header::read(Format::InversionCache, &cache).unwrap();
+//! assert_eq!(format::INVERSION_CACHE_VERSION, header.version);
+//! assert_eq!(Support::Current, format::support(header));
+//! assert!(format::is_compatible(header));
+//! ```
+
+/// The version of the shards produced by `encode` for a given
+/// `ReedSolomonConfig`, i.e. of the fields and the matrix constructions.
+pub const SHARDS_VERSION: u8 = 1;
+
+/// The version of the bytes produced by `export_inversion_cache`.
+pub const INVERSION_CACHE_VERSION: u8 = 1;
+
+/// The version of the bytes produced by `content::geometry_fingerprint`.
+pub const GEOMETRY_FINGERPRINT_VERSION: u8 = 1;
+
+/// The version of the serialized form of `ReedSolomonConfig`.
+pub const CONFIG_VERSION: u8 = 1;
+
+/// A format of data produced by this crate.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Format {
+    /// The shards produced by `encode`, see `SHARDS_VERSION`.
+    Shards,
+    /// The bytes produced by `export_inversion_cache`,
+    /// see `INVERSION_CACHE_VERSION`.
+    InversionCache,
+    /// The bytes produced by `content::geometry_fingerprint`,
+    /// see `GEOMETRY_FINGERPRINT_VERSION`.
+    GeometryFingerprint,
+    /// The serialized form of `ReedSolomonConfig`, see `CONFIG_VERSION`.
+    Config,
+}
+
+impl Format {
+    /// Returns the version of the format written by this release.
+    pub const fn version(self) -> u8 {
+        match self {
+            Self::Shards => SHARDS_VERSION,
+            Self::InversionCache => INVERSION_CACHE_VERSION,
+            Self::GeometryFingerprint => GEOMETRY_FINGERPRINT_VERSION,
+            Self::Config => CONFIG_VERSION,
+        }
+    }
+
+    /// Returns the oldest version of the format readable by this release.
+    pub const fn min_supported_version(self) -> u8 {
+        match self {
+            Self::Shards | Self::InversionCache | Self::GeometryFingerprint | Self::Config => 1,
+        }
+    }
+}
+
+/// The format and version of some stored data, e.g. as recorded in the
+/// metadata of an archive.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Header {
+    /// The format of the data.
+    pub format: Format,
+    /// The version of the format.
+    pub version: u8,
+}
+
+impl Header {
+    /// Returns the header of data of `format` written by this release.
+    pub const fn current(format: Format) -> Self {
+        Self {
+            format,
+            version: format.version(),
+        }
+    }
+
+    /// Reads the header of data of `format` from its leading version byte.
+    ///
+    /// Returns `None` if `bytes` is empty, or if the format does not start
+    /// with a version byte, i.e. for `Format::Shards` and `Format::Config`,
+    /// whose version is to be recorded alongside the data.
+    pub fn read(format: Format, bytes: &[u8]) -> Option<Self> {
+        match format {
+            Format::InversionCache | Format::GeometryFingerprint => {
+                bytes.first().map(|&version| Self { format, version })
+            }
+            Format::Shards | Format::Config => None,
+        }
+    }
+}
+
+/// The support of this release for a version of a format.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Support {
+    /// The version written by this release.
+    Current,
+    /// An older version which is still readable,
+    /// see the deprecation policy of the module.
+    Deprecated,
+    /// A version which is not readable, either removed or newer than this release.
+    Unsupported,
+}
+
+/// Returns the support of this release for the data described by `header`.
+pub const fn support(header: Header) -> Support {
+    if header.version == header.format.version() {
+        Support::Current
+    } else if header.version >= header.format.min_supported_version()
+        && header.version < header.format.version()
+    {
+        Support::Deprecated
+    } else {
+        Support::Unsupported
+    }
+}
+
+/// Returns `true` if the data described by `header` is readable by this release.
+pub const fn is_compatible(header: Header) -> bool {
+    !matches!(support(header), Support::Unsupported)
+}
@@ -0,0 +1,96 @@
+//! Field-agnostic arithmetic over the fields of this crate, e.g. for
+//! custom coding schemes which should work over any of them.
+//!
+//! Each function forwards to the `Field` implementation of `F`, so slice
+//! operations use the SIMD support detected at runtime where the field has
+//! any, GF(2^16) by way of its GF(2^8) byte planes and GF(2^64) by way of
+//! carryless multiplication.
+//!
+//! # Example
+//!
+//! ```
+//! use reed_solomon_erasure::{galois, galois_16, galois_8, Field};
+//!
+//! fn scale<F: Field>(c: F::Elem, input: &[F::Elem]) -> Vec<F::Elem> {
+//!     let mut out = vec![F::zero(); input.len()];
+//!     galois::mul_slice::<F>(c, input, &mut out);
+//!     out
+//! }
+//!
+//! assert_eq!(vec![2, 4, 6], scale::<galois_8::Field>(2, &[1, 2, 3]));
+//! assert_eq!(vec![[0, 2]], scale::<galois_16::Field>([0, 2], &[[0, 1]]));
+//! assert_eq!(1, galois::mul::<galois_8::Field>(galois::div::<galois_8::Field>(1, 7), 7));
+//! ```
+
+use crate::{Error, Field};
+
+/// Adds `a` and `b`.
+pub fn add<F: Field>(a: F::Elem, b: F::Elem) -> F::Elem {
+    F::add(a, b)
+}
+
+/// Multiplies `a` by `b`.
+pub fn mul<F: Field>(a: F::Elem, b: F::Elem) -> F::Elem {
+    F::mul(a, b)
+}
+
+/// Divides `a` by `b`.
+///
+/// # Panics
+///
+/// Panics if `b` is zero, see `checked_div`.
+pub fn div<F: Field>(a: F::Elem, b: F::Elem) -> F::Elem {
+    F::div(a, b)
+}
+
+/// Divides `a` by `b`.
+///
+/// Returns `Error::DivisionByZero` if `b` is zero.
+pub fn checked_div<F: Field>(a: F::Elem, b: F::Elem) -> Result<F::Elem, Error> {
+    F::checked_div(a, b)
+}
+
+/// Raises `a` to the `n`th power.
+pub fn exp<F: Field>(a: F::Elem, n: usize) -> F::Elem {
+    F::exp(a, n)
+}
+
+/// Multiplies each element of `input` by `c`, writing the products into `out`.
+///
+/// # Panics
+///
+/// Panics if `out` is not as long as `input`, see `checked_mul_slice`.
+pub fn mul_slice<F: Field>(c: F::Elem, input: &[F::Elem], out: &mut [F::Elem]) {
+    F::mul_slice(c, input, out);
+}
+
+/// Multiplies each element of `input` by `c`, adding the products onto `out`.
+///
+/// # Panics
+///
+/// Panics if `out` is not as long as `input`, see `checked_mul_slice_add`.
+pub fn mul_slice_add<F: Field>(c: F::Elem, input: &[F::Elem], out: &mut [F::Elem]) {
+    F::mul_slice_add(c, input, out);
+}
+
+/// Multiplies each element of `input` by `c`, writing the products into `out`.
+///
+/// Returns `Error::IncorrectShardSize` if `out` is not as long as `input`.
+pub fn checked_mul_slice<F: Field>(
+    c: F::Elem,
+    input: &[F::Elem],
+    out: &mut [F::Elem],
+) -> Result<(), Error> {
+    F::checked_mul_slice(c, input, out)
+}
+
+/// Multiplies each element of `input` by `c`, adding the products onto `out`.
+///
+/// Returns `Error::IncorrectShardSize` if `out` is not as long as `input`.
+pub fn checked_mul_slice_add<F: Field>(
+    c: F::Elem,
+    input: &[F::Elem],
+    out: &mut [F::Elem],
+) -> Result<(), Error> {
+    F::checked_mul_slice_add(c, input, out)
+}
@@ -4,6 +4,10 @@
 //! field of `GF(2^8)`, as defined in the `galois_8` module.
 
 use crate::galois_8;
+use crate::platform::Platform;
+use crate::{Error, ReconstructShard};
+use smallvec::SmallVec;
+use std::iter;
 use std::ops::{Add, Div, Mul, Sub};
 
 // the irreducible polynomial used as a modulus for the field.
@@ -49,14 +53,743 @@ impl crate::Field for Field {
     fn nth_internal(n: usize) -> [u8; 2] {
         [(n >> 8) as u8, n as u8]
     }
+
+    fn mul_slice(elem: [u8; 2], input: &[[u8; 2]], out: &mut [[u8; 2]]) {
+        assert_eq!(input.len(), out.len());
+
+        mul_slice_planes(Platform::detect(), elem, input, out, false);
+    }
+
+    fn mul_slice_add(elem: [u8; 2], input: &[[u8; 2]], out: &mut [[u8; 2]]) {
+        assert_eq!(input.len(), out.len());
+
+        mul_slice_planes(Platform::detect(), elem, input, out, true);
+    }
+
+    fn mul_slice_on(platform: Platform, elem: [u8; 2], input: &[[u8; 2]], out: &mut [[u8; 2]]) {
+        assert_eq!(input.len(), out.len());
+
+        mul_slice_planes(platform, elem, input, out, false);
+    }
+
+    fn mul_slice_add_on(platform: Platform, elem: [u8; 2], input: &[[u8; 2]], out: &mut [[u8; 2]]) {
+        assert_eq!(input.len(), out.len());
+
+        mul_slice_planes(platform, elem, input, out, true);
+    }
+}
+
+// number of elements processed at a time by `mul_slice_planes`
+const PLANE_LEN: usize = 1024;
+
+// Multiplying by a constant is linear over `GF(2^8)`, so the two bytes of
+// the products are sums of `GF(2^8)` multiples of the two bytes of the
+// input. The bytes are split into separate planes so that the vectorized
+// `GF(2^8)` slice multiplication can be used on them.
+//
+// With `c = c0*x + c1`, `a = a0*x + a1` and `x^2 = EXT_POLY[1]*x + EXT_POLY[2]`:
+//   c * a = (c0*a1 + c1*a0 + EXT_POLY[1]*c0*a0)*x + (c1*a1 + EXT_POLY[2]*c0*a0)
+fn mul_slice_planes(
+    platform: Platform,
+    c: [u8; 2],
+    input: &[[u8; 2]],
+    out: &mut [[u8; 2]],
+    add: bool,
+) {
+    let k00 = galois_8::add(c[1], galois_8::mul(EXT_POLY[1], c[0]));
+    let k01 = c[0];
+    let k10 = galois_8::mul(EXT_POLY[2], c[0]);
+    let k11 = c[1];
+
+    let mut a0 = [0u8; PLANE_LEN];
+    let mut a1 = [0u8; PLANE_LEN];
+    let mut r0 = [0u8; PLANE_LEN];
+    let mut r1 = [0u8; PLANE_LEN];
+
+    for (input, out) in input.chunks(PLANE_LEN).zip(out.chunks_mut(PLANE_LEN)) {
+        let len = input.len();
+        let (a0, a1) = (&mut a0[..len], &mut a1[..len]);
+        let (r0, r1) = (&mut r0[..len], &mut r1[..len]);
+
+        split_planes(input, a0, a1);
+
+        galois_8::mul_slice_rows_platform(platform, &[k00, k10], a0, &mut [r0, r1], false);
+        galois_8::mul_slice_rows_platform(platform, &[k01, k11], a1, &mut [r0, r1], true);
+
+        merge_planes(r0, r1, out, add);
+    }
 }
 
+// Moves the first and second bytes of the elements into `a0` and `a1`.
+fn split_planes(input: &[[u8; 2]], a0: &mut [u8], a1: &mut [u8]) {
+    for ((a, x0), x1) in input.iter().zip(a0.iter_mut()).zip(a1.iter_mut()) {
+        let v = u16::from_le_bytes(*a);
+        *x0 = v as u8;
+        *x1 = (v >> 8) as u8;
+    }
+}
+
+// Inverse of `split_planes`, adding to the elements in `out` instead of
+// overwriting them if `add` is set.
+fn merge_planes(r0: &[u8], r1: &[u8], out: &mut [[u8; 2]], add: bool) {
+    if add {
+        for ((o, &x0), &x1) in out.iter_mut().zip(r0).zip(r1) {
+            let v = u16::from_le_bytes(*o) ^ (u16::from(x0) | (u16::from(x1) << 8));
+            *o = v.to_le_bytes();
+        }
+    } else {
+        for ((o, &x0), &x1) in out.iter_mut().zip(r0).zip(r1) {
+            *o = (u16::from(x0) | (u16::from(x1) << 8)).to_le_bytes();
+        }
+    }
+}
+
+/// The maximum number of shards, data and parity combined, of a codec over GF(2^16).
+pub const MAX_SHARDS: usize = <Field as crate::Field>::ORDER;
+
+/// The irreducible polynomial GF(2^16) is constructed with over GF(2^8),
+/// x^2 + a*x + a^7, with its coefficients 1, 2 and 128 packed one per byte.
+pub const POLYNOMIAL: u32 = 0x01_02_80;
+
 /// Type alias of ReedSolomon over GF(2^8).
 pub type ReedSolomon = crate::ReedSolomon<Field>;
 
 /// Type alias of ShardByShard over GF(2^8).
 pub type ShardByShard<'a> = crate::ShardByShard<'a, Field>;
 
+/// Type alias of SbsNotStarted over GF(2^16).
+pub type SbsNotStarted<'a> = crate::SbsNotStarted<'a, Field>;
+
+/// Type alias of SbsFeeding over GF(2^16).
+pub type SbsFeeding<'a> = crate::SbsFeeding<'a, Field>;
+
+/// Type alias of SbsDone over GF(2^16).
+pub type SbsDone<'a> = crate::SbsDone<'a, Field>;
+
+/// Type alias of SbsFed over GF(2^16).
+pub type SbsFed<'a> = crate::SbsFed<'a, Field>;
+
+/// Type alias of StreamDecoder over GF(2^16).
+pub type StreamDecoder<'a> = crate::StreamDecoder<'a, Field>;
+
+/// Type alias of IncrementalDecoder over GF(2^16).
+pub type IncrementalDecoder<'a> = crate::IncrementalDecoder<'a, Field>;
+
+/// Type alias of FrozenCodec over GF(2^16).
+pub type FrozenCodec = crate::FrozenCodec<Field>;
+
+/// Type alias of ReconstructScratch over GF(2^16).
+pub type ReconstructScratch = crate::ReconstructScratch<Field>;
+
+/// Wrapper of `ReedSolomon` over GF(2^16) operating on byte shards.
+///
+/// Each pair of bytes of a shard is taken as one element of the field,
+/// in the same order as the `[u8; 2]` elements used by `ReedSolomon`,
+/// so shards produced by either can be used by the other. No copies
+/// of the shards are made.
+///
+/// Shards of odd length are reported as `Error::IncorrectShardSize`,
+/// other errors are the ones of the wrapped methods. Data shards of any
+/// length are supported by the methods suffixed with `_unaligned`.
+///
+/// # Example
+///
+/// ```
+/// use reed_solomon_erasure::galois_16::ByteReedSolomon;
+///
+/// let r = ByteReedSolomon::new(3, 2).unwrap();
+///
+/// let mut shards = vec![vec![1, 2], vec![3, 4], vec![5, 6], vec![0, 0], vec![0, 0]];
+/// r.encode(&mut shards).unwrap();
+/// assert!(r.verify(&shards).unwrap());
+///
+/// let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+/// shards[0] = None;
+/// shards[4] = None;
+/// r.reconstruct(&mut shards).unwrap();
+/// assert_eq!(Some(vec![1, 2]), shards[0]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteReedSolomon {
+    codec: ReedSolomon,
+}
+
+impl ByteReedSolomon {
+    /// Creates a new instance, see `ReedSolomon::new`.
+    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self, Error> {
+        Ok(Self::from_codec(ReedSolomon::new(
+            data_shards,
+            parity_shards,
+        )?))
+    }
+
+    /// Wraps a codec, e.g. one made with `ReedSolomon::builder`.
+    pub const fn from_codec(codec: ReedSolomon) -> Self {
+        Self { codec }
+    }
+
+    /// Returns the wrapped codec.
+    pub const fn codec(&self) -> &ReedSolomon {
+        &self.codec
+    }
+
+    pub fn data_shard_count(&self) -> usize {
+        self.codec.data_shard_count()
+    }
+
+    pub fn parity_shard_count(&self) -> usize {
+        self.codec.parity_shard_count()
+    }
+
+    pub fn total_shard_count(&self) -> usize {
+        self.codec.total_shard_count()
+    }
+
+    /// Constructs the parity shards, see `ReedSolomon::encode`.
+    pub fn encode<T: AsRef<[u8]> + AsMut<[u8]>>(&self, shards: &mut [T]) -> Result<(), Error> {
+        let mut shards = shards
+            .iter_mut()
+            .enumerate()
+            .map(|(i, shard)| as_elems_mut(i, shard.as_mut()))
+            .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+
+        self.codec.encode(&mut shards[..]).map_err(in_bytes)
+    }
+
+    /// Constructs the parity shards from a read-only view into the
+    /// data shards, see `ReedSolomon::encode_sep`.
+    pub fn encode_sep<T, U>(&self, data: &[T], parity: &mut [U]) -> Result<(), Error>
+    where
+        T: AsRef<[u8]>,
+        U: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        let data = data
+            .iter()
+            .enumerate()
+            .map(|(i, shard)| as_elems(i, shard.as_ref()))
+            .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+        let mut parity = parity
+            .iter_mut()
+            .enumerate()
+            .map(|(i, shard)| as_elems_mut(i, shard.as_mut()))
+            .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+
+        self.codec.encode_sep(&data, &mut parity).map_err(in_bytes)
+    }
+
+    /// Checks if the parity shards are correct, see `ReedSolomon::verify`.
+    pub fn verify<T: AsRef<[u8]>>(&self, shards: &[T]) -> Result<bool, Error> {
+        let shards = shards
+            .iter()
+            .enumerate()
+            .map(|(i, shard)| as_elems(i, shard.as_ref()))
+            .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+
+        self.codec.verify(&shards).map_err(in_bytes)
+    }
+
+    /// Reconstructs all shards, see `ReedSolomon::reconstruct`.
+    ///
+    /// As with the wrapped method, nothing is touched if an error is returned.
+    pub fn reconstruct<T: ReconstructShard<galois_8::Field>>(
+        &self,
+        shards: &mut [T],
+    ) -> Result<(), Error> {
+        self.reconstruct_internal(shards, false)
+    }
+
+    /// Reconstructs only the data shards, see `ReedSolomon::reconstruct_data`.
+    ///
+    /// As with the wrapped method, nothing is touched if an error is returned.
+    pub fn reconstruct_data<T: ReconstructShard<galois_8::Field>>(
+        &self,
+        shards: &mut [T],
+    ) -> Result<(), Error> {
+        self.reconstruct_internal(shards, true)
+    }
+
+    // `ReconstructShard<galois_8::Field>` is used for byte shards, as its
+    // elements are bytes. Missing shards are only initialized once the
+    // checks of the wrapped method are known to pass.
+    fn reconstruct_internal<T: ReconstructShard<galois_8::Field>>(
+        &self,
+        shards: &mut [T],
+        data_only: bool,
+    ) -> Result<(), Error> {
+        if shards.len() < self.total_shard_count() {
+            return Err(Error::TooFewShards);
+        }
+        if shards.len() > self.total_shard_count() {
+            return Err(Error::TooManyShards {
+                max: self.total_shard_count(),
+            });
+        }
+
+        let mut number_present = 0;
+        let mut shard_len = None;
+
+        for (i, shard) in shards.iter().enumerate() {
+            if let Some(len) = shard.len() {
+                if len == 0 {
+                    return Err(Error::EmptyShard);
+                }
+                if let Some(old_len) = shard_len.filter(|&old_len| len != old_len) {
+                    return Err(Error::IncorrectShardSize {
+                        shard: i,
+                        expected: old_len,
+                        actual: len,
+                    });
+                }
+                if len & 1 == 1 {
+                    return Err(odd_len(i, len));
+                }
+                number_present += 1;
+                shard_len = Some(len);
+            }
+        }
+
+        if number_present < self.data_shard_count() {
+            return Err(Error::TooFewShardsPresent);
+        }
+
+        let shard_len = shard_len.expect("at least one shard present; qed");
+        let data_shard_count = self.data_shard_count();
+
+        let mut elem_shards = shards
+            .iter_mut()
+            .enumerate()
+            .map(|(i, shard)| {
+                if data_only && i >= data_shard_count {
+                    return shard.get().map_or(Ok((&mut [][..], false)), |x| {
+                        as_elems_mut(i, x).map(|x| (x, true))
+                    });
+                }
+
+                match shard.get_or_initialize(shard_len) {
+                    Ok(x) => as_elems_mut(i, x).map(|x| (x, true)),
+                    Err(Ok(x)) => as_elems_mut(i, x).map(|x| (x, false)),
+                    Err(Err(e)) => Err(e.at_shard(i)),
+                }
+            })
+            .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+
+        if data_only {
+            self.codec.reconstruct_data(&mut elem_shards)
+        } else {
+            self.codec.reconstruct(&mut elem_shards)
+        }
+    }
+
+    /// Returns the length of the parity shards of a stripe whose data
+    /// shards are `data_len` bytes long, see `encode_unaligned`.
+    pub const fn parity_len(data_len: usize) -> usize {
+        data_len + (data_len & 1)
+    }
+
+    /// Constructs the parity shards of data shards of any length.
+    ///
+    /// Data shards of odd length are taken as padded with a zero byte,
+    /// which is not stored, so the parity shards are one byte longer than
+    /// the data shards, see `parity_len`. The data shards round-trip as
+    /// they are through `reconstruct_unaligned`, given their length.
+    ///
+    /// Returns `Error::IncorrectShardSize` if the data shards differ
+    /// in length or the parity shards are not `parity_len` long.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reed_solomon_erasure::galois_16::ByteReedSolomon;
+    ///
+    /// let r = ByteReedSolomon::new(2, 1).unwrap();
+    ///
+    /// let mut shards = vec![vec![1, 2, 3], vec![4, 5, 6], vec![0; 4]];
+    /// r.encode_unaligned(&mut shards).unwrap();
+    /// assert!(r.verify_unaligned(&shards).unwrap());
+    ///
+    /// let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+    /// shards[0] = None;
+    /// r.reconstruct_unaligned(&mut shards, 3).unwrap();
+    /// assert_eq!(Some(vec![1, 2, 3]), shards[0]);
+    /// ```
+    pub fn encode_unaligned<T: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        shards: &mut [T],
+    ) -> Result<(), Error> {
+        let data_len = self.check_unaligned(shards)?;
+        let even_len = data_len & !1;
+
+        let (data, parity) = shards.split_at_mut(self.data_shard_count());
+
+        if even_len > 0 {
+            let data = data
+                .iter()
+                .enumerate()
+                .map(|(i, shard)| as_elems(i, &shard.as_ref()[..even_len]))
+                .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+            let mut parity = parity
+                .iter_mut()
+                .enumerate()
+                .map(|(i, shard)| as_elems_mut(i, &mut shard.as_mut()[..even_len]))
+                .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+
+            self.codec.encode_sep(&data, &mut parity)?;
+        }
+
+        if data_len != even_len {
+            let data: SmallVec<[_; 32]> = data
+                .iter()
+                .map(|shard| [[shard.as_ref()[even_len], 0]])
+                .collect();
+            let mut parity = parity
+                .iter_mut()
+                .enumerate()
+                .map(|(i, shard)| as_elems_mut(i, &mut shard.as_mut()[even_len..]))
+                .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+
+            self.codec.encode_sep(&data, &mut parity)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the parity shards of data shards of any length are
+    /// correct, see `encode_unaligned`.
+    pub fn verify_unaligned<T: AsRef<[u8]>>(&self, shards: &[T]) -> Result<bool, Error> {
+        let data_len = self.check_unaligned(shards)?;
+        let even_len = data_len & !1;
+
+        if even_len > 0 {
+            let shards = shards
+                .iter()
+                .enumerate()
+                .map(|(i, shard)| as_elems(i, &shard.as_ref()[..even_len]))
+                .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+
+            if !self.codec.verify(&shards)? {
+                return Ok(false);
+            }
+        }
+
+        if data_len != even_len {
+            let data_shard_count = self.data_shard_count();
+            let tails: SmallVec<[_; 32]> = shards
+                .iter()
+                .enumerate()
+                .map(|(i, shard)| {
+                    let shard = shard.as_ref();
+                    if i < data_shard_count {
+                        [[shard[even_len], 0]]
+                    } else {
+                        [[shard[even_len], shard[even_len + 1]]]
+                    }
+                })
+                .collect();
+
+            return self.codec.verify(&tails);
+        }
+
+        Ok(true)
+    }
+
+    /// Reconstructs all shards of data shards of any length,
+    /// see `encode_unaligned`.
+    ///
+    /// `data_len` is the length of the data shards, which cannot be told
+    /// from the parity shards alone. Missing shards are initialized to
+    /// `data_len` bytes, or `parity_len(data_len)` for parity shards.
+    ///
+    /// As with `reconstruct`, nothing is touched if an error is returned.
+    pub fn reconstruct_unaligned<T: ReconstructShard<galois_8::Field>>(
+        &self,
+        shards: &mut [T],
+        data_len: usize,
+    ) -> Result<(), Error> {
+        self.reconstruct_unaligned_internal(shards, data_len, false)
+    }
+
+    /// Reconstructs only the data shards of data shards of any length,
+    /// see `reconstruct_unaligned`.
+    pub fn reconstruct_data_unaligned<T: ReconstructShard<galois_8::Field>>(
+        &self,
+        shards: &mut [T],
+        data_len: usize,
+    ) -> Result<(), Error> {
+        self.reconstruct_unaligned_internal(shards, data_len, true)
+    }
+
+    // Checks the shards of a stripe with data shards of any length,
+    // returns the length of the data shards.
+    fn check_unaligned<T: AsRef<[u8]>>(&self, shards: &[T]) -> Result<usize, Error> {
+        if shards.len() < self.total_shard_count() {
+            return Err(Error::TooFewShards);
+        }
+        if shards.len() > self.total_shard_count() {
+            return Err(Error::TooManyShards {
+                max: self.total_shard_count(),
+            });
+        }
+
+        let data_len = shards[0].as_ref().len();
+        if data_len == 0 {
+            return Err(Error::EmptyShard);
+        }
+
+        for (i, shard) in shards.iter().enumerate() {
+            let expected = if i < self.data_shard_count() {
+                data_len
+            } else {
+                Self::parity_len(data_len)
+            };
+            if shard.as_ref().len() != expected {
+                return Err(Error::IncorrectShardSize {
+                    shard: i,
+                    expected,
+                    actual: shard.as_ref().len(),
+                });
+            }
+        }
+
+        Ok(data_len)
+    }
+
+    fn reconstruct_unaligned_internal<T: ReconstructShard<galois_8::Field>>(
+        &self,
+        shards: &mut [T],
+        data_len: usize,
+        data_only: bool,
+    ) -> Result<(), Error> {
+        if shards.len() < self.total_shard_count() {
+            return Err(Error::TooFewShards);
+        }
+        if shards.len() > self.total_shard_count() {
+            return Err(Error::TooManyShards {
+                max: self.total_shard_count(),
+            });
+        }
+        if data_len == 0 {
+            return Err(Error::EmptyShard);
+        }
+
+        let data_shard_count = self.data_shard_count();
+        let shard_len = |i: usize| {
+            if i < data_shard_count {
+                data_len
+            } else {
+                Self::parity_len(data_len)
+            }
+        };
+
+        let mut number_present = 0;
+        for (i, shard) in shards.iter().enumerate() {
+            if let Some(len) = shard.len() {
+                if len != shard_len(i) {
+                    return Err(Error::IncorrectShardSize {
+                        shard: i,
+                        expected: shard_len(i),
+                        actual: len,
+                    });
+                }
+                number_present += 1;
+            }
+        }
+
+        if number_present < data_shard_count {
+            return Err(Error::TooFewShardsPresent);
+        }
+
+        let mut byte_shards = shards
+            .iter_mut()
+            .enumerate()
+            .map(|(i, shard)| {
+                if data_only && i >= data_shard_count {
+                    return Ok(shard.get().map_or((&mut [][..], false), |x| (x, true)));
+                }
+
+                match shard.get_or_initialize(shard_len(i)) {
+                    Ok(x) => Ok((x, true)),
+                    Err(Ok(x)) => Ok((x, false)),
+                    Err(Err(e)) => Err(e.at_shard(i)),
+                }
+            })
+            .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+
+        let even_len = data_len & !1;
+
+        if even_len > 0 {
+            let mut elem_shards = byte_shards
+                .iter_mut()
+                .enumerate()
+                .map(|(i, (x, present))| {
+                    let len = x.len().min(even_len);
+                    as_elems_mut(i, &mut x[..len]).map(|x| (x, *present))
+                })
+                .collect::<Result<SmallVec<[_; 32]>, _>>()?;
+
+            if data_only {
+                self.codec.reconstruct_data(&mut elem_shards)?;
+            } else {
+                self.codec.reconstruct(&mut elem_shards)?;
+            }
+        }
+
+        if data_len != even_len {
+            let mut tails: SmallVec<[_; 32]> = byte_shards
+                .iter()
+                .enumerate()
+                .map(|(i, (x, present))| match x.len() {
+                    0 => ([[0; 2]], false),
+                    _ if i < data_shard_count => ([[x[even_len], 0]], *present),
+                    _ => ([[x[even_len], x[even_len + 1]]], *present),
+                })
+                .collect();
+
+            if data_only {
+                self.codec.reconstruct_data(&mut tails)?;
+            } else {
+                self.codec.reconstruct(&mut tails)?;
+            }
+
+            for (i, ((x, present), ([tail], _))) in byte_shards.iter_mut().zip(&tails).enumerate() {
+                if *present || x.is_empty() {
+                    continue;
+                }
+                if i < data_shard_count {
+                    x[even_len] = tail[0];
+                } else {
+                    x[even_len..].copy_from_slice(tail);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts a stripe of a codec over GF(2^8) into a stripe of `to`,
+/// e.g. when migrating archives to wide stripes one stripe at a time.
+///
+/// Missing shards of the source stripe are reconstructed first, like in
+/// `reconstruct`, so `shards` is modified in place. The source stripe is then
+/// verified, so corruption is caught before it is carried over into the new
+/// stripe, which can only happen when more than `from.data_shard_count()`
+/// shards are present.
+///
+/// The data, i.e. the data shards of `from` one after another, is split
+/// into the data shards of `to`, taking each pair of bytes as one element.
+/// The last data shard is padded with zeros, so the length of the data
+/// has to be kept to strip the padding when decoding.
+///
+/// Returns the `data shard count + parity shard count` shards of `to`.
+///
+/// Returns `Error::InconsistentShards` if the present shards of the source
+/// stripe do not match, and the errors of `reconstruct` otherwise.
+///
+/// # Example
+///
+/// ```
+/// use reed_solomon_erasure::{galois_16, galois_8};
+///
+/// let from = galois_8::ReedSolomon::new(2, 1).unwrap();
+/// let to = galois_16::ReedSolomon::new(2, 2).unwrap();
+///
+/// let mut shards = vec![vec![1, 2, 3], vec![4, 5, 6], vec![0; 3]];
+/// from.encode(&mut shards).unwrap();
+///
+/// let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+/// shards[0] = None;
+///
+/// let new_shards = galois_16::transcode(&from, &to, &mut shards).unwrap();
+/// assert_eq!(vec![[1, 2], [3, 4]], new_shards[0]);
+/// assert_eq!(vec![[5, 6], [0, 0]], new_shards[1]);
+/// assert!(to.verify(&new_shards).unwrap());
+/// ```
+pub fn transcode<T: ReconstructShard<galois_8::Field>>(
+    from: &galois_8::ReedSolomon,
+    to: &ReedSolomon,
+    shards: &mut [T],
+) -> Result<Vec<Vec<[u8; 2]>>, Error> {
+    from.reconstruct(shards)?;
+
+    let shard_len = shards[0].len().expect("shards reconstructed; qed");
+
+    {
+        let present: SmallVec<[&[u8]; 32]> = shards
+            .iter_mut()
+            .map(|shard| &*shard.get().expect("shards reconstructed; qed"))
+            .collect();
+        if !from.verify(&present)? {
+            return Err(Error::InconsistentShards);
+        }
+    }
+
+    let mut data = vec![vec![0u8; shard_len]; from.data_shard_count()];
+    from.decode_data(shards, &mut data)?;
+
+    let data_len = data.len() * shard_len;
+    let new_len = data_len.div_ceil(2).div_ceil(to.data_shard_count());
+
+    let mut bytes = data.iter().flatten().copied().chain(iter::repeat(0));
+    let new_data: Vec<Vec<[u8; 2]>> = (0..to.data_shard_count())
+        .map(|_| {
+            (0..new_len)
+                .map(|_| [bytes.next().unwrap(), bytes.next().unwrap()])
+                .collect()
+        })
+        .collect();
+
+    let mut new_shards = vec![vec![[0; 2]; new_len]; to.total_shard_count()];
+    to.encode_data(&new_data, &mut new_shards)?;
+
+    Ok(new_shards)
+}
+
+// Converts the lengths of `Error::IncorrectShardSize` from elements to bytes.
+const fn in_bytes(e: Error) -> Error {
+    match e {
+        Error::IncorrectShardSize {
+            shard,
+            expected,
+            actual,
+        } => Error::IncorrectShardSize {
+            shard,
+            expected: expected * 2,
+            actual: actual * 2,
+        },
+        e => e,
+    }
+}
+
+// Odd lengths are reported as one byte short.
+const fn odd_len(i: usize, len: usize) -> Error {
+    Error::IncorrectShardSize {
+        shard: i,
+        expected: len + 1,
+        actual: len,
+    }
+}
+
+const fn as_elems(i: usize, shard: &[u8]) -> Result<&[[u8; 2]], Error> {
+    if shard.len() & 1 == 1 {
+        return Err(odd_len(i, shard.len()));
+    }
+
+    // Safe because `[u8; 2]` has the alignment of `u8`, and the length
+    // covers exactly the bytes of `shard`.
+    Ok(unsafe { std::slice::from_raw_parts(shard.as_ptr().cast::<[u8; 2]>(), shard.len() / 2) })
+}
+
+const fn as_elems_mut(i: usize, shard: &mut [u8]) -> Result<&mut [[u8; 2]], Error> {
+    if shard.len() & 1 == 1 {
+        return Err(odd_len(i, shard.len()));
+    }
+
+    // Safe for the same reasons as in `as_elems`.
+    Ok(unsafe {
+        std::slice::from_raw_parts_mut(shard.as_mut_ptr().cast::<[u8; 2]>(), shard.len() / 2)
+    })
+}
+
 /// An element of `GF(2^16)`.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 struct Element(pub [u8; 2]);
@@ -399,12 +1132,83 @@ mod tests {
         }
     }
 
+    quickcheck! {
+        fn qc_mul_slice(c: Element, input: Vec<Element>) -> bool {
+            let input: Vec<[u8; 2]> = input.into_iter().map(|a| a.0).collect();
+            let mut out = vec![[0; 2]; input.len()];
+
+            <Field as crate::Field>::mul_slice(c.0, &input, &mut out);
+
+            input.iter().zip(&out).all(|(&a, &o)| (c * Element(a)).0 == o)
+        }
+
+        fn qc_mul_slice_add(c: Element, input: Vec<Element>, start: Vec<Element>) -> bool {
+            let input: Vec<[u8; 2]> = input.into_iter().map(|a| a.0).collect();
+            let start: Vec<[u8; 2]> = start
+                .into_iter()
+                .map(|a| a.0)
+                .chain(std::iter::repeat([0; 2]))
+                .take(input.len())
+                .collect();
+            let mut out = start.clone();
+
+            <Field as crate::Field>::mul_slice_add(c.0, &input, &mut out);
+
+            input
+                .iter()
+                .zip(&start)
+                .zip(&out)
+                .all(|((&a, &s), &o)| (Element(s) + c * Element(a)).0 == o)
+        }
+    }
+
+    #[test]
+    fn test_mul_slice_multiple_planes() {
+        let input: Vec<[u8; 2]> = (0..3 * PLANE_LEN + 7)
+            .map(|i| [(i >> 8) as u8, i as u8])
+            .collect();
+
+        for &c in &[[0, 0], [0, 1], [1, 0], [0x53, 0xca], [0xff, 0xff]] {
+            let mut out = vec![[0; 2]; input.len()];
+            <Field as crate::Field>::mul_slice(c, &input, &mut out);
+            for (&a, &o) in input.iter().zip(&out) {
+                assert_eq!((Element(c) * Element(a)).0, o);
+            }
+
+            let mut out_add = out.clone();
+            <Field as crate::Field>::mul_slice_add(c, &input, &mut out_add);
+            assert!(out_add.iter().all(|&o| o == [0, 0]));
+        }
+    }
+
     #[test]
     #[should_panic]
     fn test_div_b_is_0() {
         let _ = Element([1, 0]) / Element::zero();
     }
 
+    #[test]
+    fn test_checked() {
+        use crate::{Error, Field as _};
+
+        assert_eq!(
+            Err(Error::DivisionByZero),
+            Field::checked_div([1, 0], [0, 0])
+        );
+        assert_eq!(Ok([0, 1]), Field::checked_div([1, 0], [1, 0]));
+        assert_eq!(Err(Error::InvalidIndex), Field::checked_nth(65536));
+
+        let mut out = [[0; 2]; 2];
+        assert_eq!(
+            Err(Error::IncorrectShardSize {
+                shard: 0,
+                expected: 1,
+                actual: 2
+            }),
+            Field::checked_mul_slice([0, 2], &[[0, 1]], &mut out)
+        );
+    }
+
     #[test]
     fn zero_to_zero_is_one() {
         assert_eq!(Element::zero().exp(0), Element::constant(1));
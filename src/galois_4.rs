@@ -0,0 +1,438 @@
+//! Implementation of GF(2^4): the finite field with 2^4 elements.
+//!
+//! The field allows at most 16 shards, but only needs 31 bytes of tables,
+//! instead of the 64 KiB of the multiplication table of GF(2^8), which
+//! makes it suitable for microcontrollers.
+//!
+//! Elements of `Field` are the values 0 to 15 of a `u8`, so shards take up
+//! one byte per nibble. The codecs over `Field` return
+//! `Error::InvalidElement` for shards holding larger values. `PackedField` operates on shards with two elements
+//! per byte instead, the low and the high nibble, which are coded
+//! independently of each other.
+//!
+//! # Example
+//!
+//! ```
+//! use reed_solomon_erasure::galois_4::PackedReedSolomon;
+//!
+//! let r = PackedReedSolomon::new(3, 2).unwrap();
+//!
+//! let mut shards = vec![vec![0x12], vec![0x34], vec![0x56], vec![0], vec![0]];
+//! r.encode(&mut shards).unwrap();
+//!
+//! let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+//! shards[0] = None;
+//! shards[4] = None;
+//! r.reconstruct(&mut shards).unwrap();
+//! assert_eq!(Some(vec![0x12]), shards[0]);
+//! ```
+
+use crate::Error;
+
+/// The field GF(2^4).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Field;
+
+impl crate::Field for Field {
+    const ORDER: usize = 16;
+
+    type Elem = u8;
+
+    fn add(a: u8, b: u8) -> u8 {
+        add(a, b)
+    }
+
+    fn mul(a: u8, b: u8) -> u8 {
+        mul(a, b)
+    }
+
+    fn div(a: u8, b: u8) -> u8 {
+        div(a, b)
+    }
+
+    fn exp(elem: u8, n: usize) -> u8 {
+        exp(elem, n)
+    }
+
+    fn zero() -> u8 {
+        0
+    }
+
+    fn one() -> u8 {
+        1
+    }
+
+    fn nth_internal(n: usize) -> u8 {
+        n as u8
+    }
+
+    fn is_elem(elem: u8) -> bool {
+        elem < 16
+    }
+
+    fn mul_slice(c: u8, input: &[u8], out: &mut [u8]) {
+        mul_slice(c, input, out);
+    }
+
+    fn mul_slice_add(c: u8, input: &[u8], out: &mut [u8]) {
+        mul_slice_xor(c, input, out);
+    }
+}
+
+/// GF(2^4) with two elements packed into each byte.
+///
+/// Each operation is performed on the low and the high nibble separately.
+/// The coefficients used by the codec hold the same element in both
+/// nibbles, e.g. `one` is `0x11`, so that they form a field, while the
+/// nibbles of the shards are arbitrary.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PackedField;
+
+impl crate::Field for PackedField {
+    const ORDER: usize = 16;
+
+    type Elem = u8;
+
+    fn add(a: u8, b: u8) -> u8 {
+        add(a, b)
+    }
+
+    fn mul(a: u8, b: u8) -> u8 {
+        pack(mul(a & 0xf, b & 0xf), mul(a >> 4, b >> 4))
+    }
+
+    /// Divides the nibbles of `a` by the ones of `b`.
+    /// Panics if a nibble of `b` is 0 while the one of `a` is not.
+    fn div(a: u8, b: u8) -> u8 {
+        pack(div(a & 0xf, b & 0xf), div(a >> 4, b >> 4))
+    }
+
+    fn exp(elem: u8, n: usize) -> u8 {
+        pack(exp(elem & 0xf, n), exp(elem >> 4, n))
+    }
+
+    fn zero() -> u8 {
+        0
+    }
+
+    fn one() -> u8 {
+        0x11
+    }
+
+    fn nth_internal(n: usize) -> u8 {
+        pack(n as u8, n as u8)
+    }
+
+    fn mul_slice(c: u8, input: &[u8], out: &mut [u8]) {
+        mul_slice_packed(c, input, out, false);
+    }
+
+    fn mul_slice_add(c: u8, input: &[u8], out: &mut [u8]) {
+        mul_slice_packed(c, input, out, true);
+    }
+}
+
+const fn pack(low: u8, high: u8) -> u8 {
+    low | (high << 4)
+}
+
+/// The maximum number of shards, data and parity combined, of a codec over GF(2^4).
+pub const MAX_SHARDS: usize = <Field as crate::Field>::ORDER;
+
+/// The irreducible polynomial GF(2^4) is constructed with, x^4 + x + 1.
+pub const POLYNOMIAL: u32 = 0x13;
+
+/// Type alias of ReedSolomon over GF(2^4).
+///
+/// The shards may only hold the values 0 to 15, the codec returns
+/// `Error::InvalidElement` otherwise.
+pub type ReedSolomon = crate::ReedSolomon<Field>;
+
+/// Type alias of ReedSolomon over GF(2^4) with two elements per byte.
+pub type PackedReedSolomon = crate::ReedSolomon<PackedField>;
+
+/// Type alias of ShardByShard over GF(2^4).
+pub type ShardByShard<'a> = crate::ShardByShard<'a, Field>;
+
+/// Type alias of SbsNotStarted over GF(2^4).
+pub type SbsNotStarted<'a> = crate::SbsNotStarted<'a, Field>;
+
+/// Type alias of SbsFeeding over GF(2^4).
+pub type SbsFeeding<'a> = crate::SbsFeeding<'a, Field>;
+
+/// Type alias of SbsDone over GF(2^4).
+pub type SbsDone<'a> = crate::SbsDone<'a, Field>;
+
+/// Type alias of SbsFed over GF(2^4).
+pub type SbsFed<'a> = crate::SbsFed<'a, Field>;
+
+/// Type alias of StreamDecoder over GF(2^4).
+pub type StreamDecoder<'a> = crate::StreamDecoder<'a, Field>;
+
+/// Type alias of IncrementalDecoder over GF(2^4).
+pub type IncrementalDecoder<'a> = crate::IncrementalDecoder<'a, Field>;
+
+/// Type alias of FrozenCodec over GF(2^4).
+pub type FrozenCodec = crate::FrozenCodec<Field>;
+
+/// Type alias of ReconstructScratch over GF(2^4).
+pub type ReconstructScratch = crate::ReconstructScratch<Field>;
+
+// the powers of the generator x, as there are only 15 of them
+const EXP_TABLE: [u8; 15] = exp_table();
+
+// the inverse of `EXP_TABLE`, the entry of 0 is unused
+const LOG_TABLE: [u8; 16] = log_table();
+
+const fn exp_table() -> [u8; 15] {
+    let mut table = [0; 15];
+    let mut x = 1;
+    let mut i = 0;
+    while i < 15 {
+        table[i] = x as u8;
+        x <<= 1;
+        if x & 0x10 != 0 {
+            x ^= POLYNOMIAL;
+        }
+        i += 1;
+    }
+
+    table
+}
+
+const fn log_table() -> [u8; 16] {
+    let mut table = [0; 16];
+    let mut i = 0;
+    while i < 15 {
+        table[EXP_TABLE[i] as usize] = i as u8;
+        i += 1;
+    }
+
+    table
+}
+
+/// Add two elements.
+pub const fn add(a: u8, b: u8) -> u8 {
+    a ^ b
+}
+
+/// Multiply two elements.
+///
+/// # Panics
+///
+/// Panics if `a` or `b` is not below 16.
+pub const fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        let log_result = LOG_TABLE[a as usize] as usize + LOG_TABLE[b as usize] as usize;
+        EXP_TABLE[log_result % 15]
+    }
+}
+
+/// Divide one element by another. `b`, the divisor, may not be 0.
+///
+/// # Panics
+///
+/// Panics if `b` is 0, or if `a` or `b` is not below 16.
+pub fn div(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        0
+    } else if b == 0 {
+        panic!("Divisor is 0")
+    } else {
+        let log_result = 15 + LOG_TABLE[a as usize] as usize - LOG_TABLE[b as usize] as usize;
+        EXP_TABLE[log_result % 15]
+    }
+}
+
+/// Divide one element by another.
+///
+/// Returns `Error::DivisionByZero` if `b`, the divisor, is 0.
+pub fn checked_div(a: u8, b: u8) -> Result<u8, Error> {
+    if b == 0 {
+        return Err(Error::DivisionByZero);
+    }
+
+    Ok(div(a, b))
+}
+
+/// Compute a^n.
+///
+/// # Panics
+///
+/// Panics if `a` is not below 16.
+pub const fn exp(a: u8, n: usize) -> u8 {
+    if n == 0 {
+        1
+    } else if a == 0 {
+        0
+    } else {
+        EXP_TABLE[LOG_TABLE[a as usize] as usize * (n % 15) % 15]
+    }
+}
+
+// The products of `c` and each of the 16 elements.
+fn mul_table(c: u8) -> [u8; 16] {
+    let mut table = [0; 16];
+    for (x, product) in table.iter_mut().enumerate() {
+        *product = mul(c, x as u8);
+    }
+
+    table
+}
+
+/// Multiply each element of `input` by `c`, writing the products to `out`.
+///
+/// # Panics
+///
+/// Panics if `input` and `out` are not of the same length, or if `c` or
+/// an element of `input` is not below 16.
+pub fn mul_slice(c: u8, input: &[u8], out: &mut [u8]) {
+    assert_eq!(input.len(), out.len());
+
+    let table = mul_table(c);
+    for (i, o) in input.iter().zip(out) {
+        *o = table[*i as usize];
+    }
+}
+
+/// Multiply each element of `input` by `c`, adding the products to the
+/// corresponding elements of `out`.
+///
+/// # Panics
+///
+/// Panics if `input` and `out` are not of the same length, or if `c` or
+/// an element of `input` is not below 16.
+pub fn mul_slice_xor(c: u8, input: &[u8], out: &mut [u8]) {
+    assert_eq!(input.len(), out.len());
+
+    let table = mul_table(c);
+    for (i, o) in input.iter().zip(out) {
+        *o ^= table[*i as usize];
+    }
+}
+
+fn mul_slice_packed(c: u8, input: &[u8], out: &mut [u8], add: bool) {
+    assert_eq!(input.len(), out.len());
+
+    let low = mul_table(c & 0xf);
+    let high = mul_table(c >> 4);
+    for (i, o) in input.iter().zip(out) {
+        let product = pack(low[(*i & 0xf) as usize], high[(*i >> 4) as usize]);
+        *o = if add { *o ^ product } else { product };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Field as _;
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(
+            [1, 2, 4, 8, 3, 6, 12, 11, 5, 10, 7, 14, 15, 13, 9],
+            EXP_TABLE
+        );
+        for x in 1..16 {
+            assert_eq!(x, EXP_TABLE[LOG_TABLE[x as usize] as usize]);
+        }
+    }
+
+    #[test]
+    fn test_field_axioms() {
+        for a in 0..16 {
+            assert_eq!(a, mul(a, 1));
+            assert_eq!(0, mul(a, 0));
+            assert_eq!(1, exp(a, 0));
+            if a != 0 {
+                assert_eq!(1, mul(a, div(1, a)));
+                assert_eq!(1, exp(a, 15));
+            }
+
+            for b in 0..16 {
+                assert_eq!(mul(a, b), mul(b, a));
+                if b != 0 {
+                    assert_eq!(a, mul(div(a, b), b));
+                }
+
+                for c in 0..16 {
+                    assert_eq!(mul(a, mul(b, c)), mul(mul(a, b), c));
+                    assert_eq!(mul(a, add(b, c)), add(mul(a, b), mul(a, c)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mul_slice() {
+        let input: Vec<u8> = (0..16).collect();
+        for c in 0..16 {
+            let mut out = vec![0; 16];
+            mul_slice(c, &input, &mut out);
+            for (&a, &o) in input.iter().zip(&out) {
+                assert_eq!(mul(c, a), o);
+            }
+
+            mul_slice_xor(c, &input, &mut out);
+            assert!(out.iter().all(|&o| o == 0));
+        }
+    }
+
+    #[test]
+    fn test_packed() {
+        let input: Vec<u8> = (0..=255).collect();
+        for c in 0..16 {
+            let c = PackedField::nth(c);
+            let mut out = vec![0; 256];
+            PackedField::mul_slice(c, &input, &mut out);
+            for (&a, &o) in input.iter().zip(&out) {
+                assert_eq!(PackedField::mul(c, a), o);
+                assert_eq!(mul(c & 0xf, a & 0xf), o & 0xf);
+                assert_eq!(mul(c & 0xf, a >> 4), o >> 4);
+            }
+
+            PackedField::mul_slice_add(c, &input, &mut out);
+            assert!(out.iter().all(|&o| o == 0));
+        }
+
+        assert_eq!(0x11, PackedField::one());
+        assert_eq!(0x5a, PackedField::div(0x5a, 0x11));
+        assert_eq!(0x00, PackedField::div(0x00, 0x30));
+    }
+
+    #[test]
+    #[should_panic(expected = "Divisor is 0")]
+    fn test_div_b_is_0() {
+        div(1, 0);
+    }
+
+    #[test]
+    fn test_checked() {
+        assert_eq!(Err(Error::DivisionByZero), checked_div(1, 0));
+        assert_eq!(Ok(1), checked_div(7, 7));
+        assert_eq!(Err(Error::InvalidIndex), Field::checked_nth(16));
+        assert_eq!(Ok(0xff), PackedField::checked_nth(15));
+    }
+
+    #[test]
+    fn test_invalid_element() {
+        assert!(Field::is_elem(15));
+        assert!(!Field::is_elem(16));
+        assert!(PackedField::is_elem(0xff));
+
+        let r = ReedSolomon::new(3, 2).unwrap();
+        let mut shards = vec![vec![1], vec![0x10], vec![3], vec![0], vec![0]];
+        assert_eq!(Err(Error::InvalidElement), r.encode(&mut shards));
+        assert_eq!(Err(Error::InvalidElement), r.verify(&shards));
+
+        shards[1][0] = 2;
+        r.encode(&mut shards).unwrap();
+        let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+        shards[0] = None;
+        shards[3] = Some(vec![0xf0]);
+        assert_eq!(Err(Error::InvalidElement), r.reconstruct(&mut shards));
+        assert_eq!(None, shards[0]);
+    }
+}
@@ -0,0 +1,364 @@
+//! Implementation of GF(2^64): the finite field with 2^64 elements.
+//!
+//! Elements are the polynomials over GF(2) of degree below 64, with the
+//! coefficient of x^i in bit i of a `u64`. They are multiplied with
+//! carryless multiplication, using the PCLMULQDQ instruction where the CPU
+//! supports it, and reduced modulo x^64 + x^4 + x^3 + x + 1.
+//!
+//! There are no multiplication tables, so coding over GF(2^64) is slower
+//! than over GF(2^8). The field is meant for applications which need a
+//! very large number of distinct elements, e.g. fingerprinting.
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::platform::pclmulqdq_detected;
+use crate::Error;
+
+/// The field GF(2^64).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Field;
+
+impl crate::Field for Field {
+    // the order of the field, 2^64, does not fit, but neither
+    // does any number of shards it would restrict
+    const ORDER: usize = usize::MAX;
+
+    type Elem = u64;
+
+    fn add(a: u64, b: u64) -> u64 {
+        add(a, b)
+    }
+
+    fn mul(a: u64, b: u64) -> u64 {
+        mul(a, b)
+    }
+
+    fn div(a: u64, b: u64) -> u64 {
+        div(a, b)
+    }
+
+    fn exp(elem: u64, n: usize) -> u64 {
+        exp(elem, n)
+    }
+
+    fn zero() -> u64 {
+        0
+    }
+
+    fn one() -> u64 {
+        1
+    }
+
+    fn nth_internal(n: usize) -> u64 {
+        n as u64
+    }
+
+    fn mul_slice(c: u64, input: &[u64], out: &mut [u64]) {
+        mul_slice(c, input, out);
+    }
+
+    fn mul_slice_add(c: u64, input: &[u64], out: &mut [u64]) {
+        mul_slice_xor(c, input, out);
+    }
+}
+
+/// The maximum number of shards, data and parity combined, of a codec over
+/// GF(2^64), which is only limited by `usize`.
+pub const MAX_SHARDS: usize = <Field as crate::Field>::ORDER;
+
+/// The irreducible polynomial GF(2^64) is constructed with,
+/// x^64 + x^4 + x^3 + x + 1, without the x^64 term.
+pub const POLYNOMIAL: u64 = 0x1b;
+
+/// Type alias of ReedSolomon over GF(2^64).
+pub type ReedSolomon = crate::ReedSolomon<Field>;
+
+/// Type alias of ShardByShard over GF(2^64).
+pub type ShardByShard<'a> = crate::ShardByShard<'a, Field>;
+
+/// Type alias of SbsNotStarted over GF(2^64).
+pub type SbsNotStarted<'a> = crate::SbsNotStarted<'a, Field>;
+
+/// Type alias of SbsFeeding over GF(2^64).
+pub type SbsFeeding<'a> = crate::SbsFeeding<'a, Field>;
+
+/// Type alias of SbsDone over GF(2^64).
+pub type SbsDone<'a> = crate::SbsDone<'a, Field>;
+
+/// Type alias of SbsFed over GF(2^64).
+pub type SbsFed<'a> = crate::SbsFed<'a, Field>;
+
+/// Type alias of StreamDecoder over GF(2^64).
+pub type StreamDecoder<'a> = crate::StreamDecoder<'a, Field>;
+
+/// Type alias of IncrementalDecoder over GF(2^64).
+pub type IncrementalDecoder<'a> = crate::IncrementalDecoder<'a, Field>;
+
+/// Type alias of FrozenCodec over GF(2^64).
+pub type FrozenCodec = crate::FrozenCodec<Field>;
+
+/// Type alias of ReconstructScratch over GF(2^64).
+pub type ReconstructScratch = crate::ReconstructScratch<Field>;
+
+/// Add two elements.
+pub const fn add(a: u64, b: u64) -> u64 {
+    a ^ b
+}
+
+/// Multiply two elements.
+pub fn mul(a: u64, b: u64) -> u64 {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if pclmulqdq_detected() {
+            // Safe because pclmulqdq_detected() checked for support.
+            return unsafe { mul_pclmulqdq(a, b) };
+        }
+    }
+
+    mul_pure(a, b)
+}
+
+/// Divide one element by another. `b`, the divisor, may not be 0.
+pub fn div(a: u64, b: u64) -> u64 {
+    if a == 0 {
+        0
+    } else if b == 0 {
+        panic!("Divisor is 0")
+    } else {
+        // the multiplicative group has order 2^64 - 1, so b^(2^64 - 2) = 1/b
+        mul(a, pow(b, u64::MAX - 1))
+    }
+}
+
+/// Divide one element by another.
+///
+/// Returns `Error::DivisionByZero` if `b`, the divisor, is 0.
+pub fn checked_div(a: u64, b: u64) -> Result<u64, Error> {
+    if b == 0 {
+        return Err(Error::DivisionByZero);
+    }
+
+    Ok(div(a, b))
+}
+
+/// Compute a^n.
+pub fn exp(a: u64, n: usize) -> u64 {
+    pow(a, n as u64)
+}
+
+fn pow(mut a: u64, mut n: u64) -> u64 {
+    let mut result = 1;
+    while n > 0 {
+        if n & 1 == 1 {
+            result = mul(result, a);
+        }
+        a = mul(a, a);
+        n >>= 1;
+    }
+
+    result
+}
+
+/// Multiply each element of `input` by `c`, writing the products to `out`.
+///
+/// # Panics
+///
+/// Panics if `input` and `out` are not of the same length.
+pub fn mul_slice(c: u64, input: &[u64], out: &mut [u64]) {
+    assert_eq!(input.len(), out.len());
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if pclmulqdq_detected() {
+            // Safe because pclmulqdq_detected() checked for support.
+            unsafe { mul_slice_pclmulqdq(c, input, out, false) };
+            return;
+        }
+    }
+
+    for (i, o) in input.iter().zip(out) {
+        *o = mul_pure(c, *i);
+    }
+}
+
+/// Multiply each element of `input` by `c`, adding the products to the
+/// corresponding elements of `out`.
+///
+/// # Panics
+///
+/// Panics if `input` and `out` are not of the same length.
+pub fn mul_slice_xor(c: u64, input: &[u64], out: &mut [u64]) {
+    assert_eq!(input.len(), out.len());
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if pclmulqdq_detected() {
+            // Safe because pclmulqdq_detected() checked for support.
+            unsafe { mul_slice_pclmulqdq(c, input, out, true) };
+            return;
+        }
+    }
+
+    for (i, o) in input.iter().zip(out) {
+        *o ^= mul_pure(c, *i);
+    }
+}
+
+// Reduces the 128-bit product `x` modulo the polynomial. With
+// x^64 = x^4 + x^3 + x + 1, the high half is multiplied by POLYNOMIAL
+// and added to the low half. The at most 4 bits which overflow are
+// multiplied by POLYNOMIAL once more, which fits into 8 bits.
+#[inline(always)]
+const fn reduce(x: u128) -> u64 {
+    let lo = x as u64;
+    let hi = (x >> 64) as u64;
+
+    let overflow = (hi >> 63) ^ (hi >> 61) ^ (hi >> 60);
+    let hi = hi ^ overflow;
+
+    lo ^ hi ^ (hi << 1) ^ (hi << 3) ^ (hi << 4)
+}
+
+fn mul_pure(a: u64, b: u64) -> u64 {
+    let mut product = 0u128;
+    for i in 0..64 {
+        if (b >> i) & 1 == 1 {
+            product ^= u128::from(a) << i;
+        }
+    }
+
+    reduce(product)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "pclmulqdq")]
+unsafe fn mul_pclmulqdq(a: u64, b: u64) -> u64 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{__m128i, _mm_clmulepi64_si128, _mm_set_epi64x};
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{__m128i, _mm_clmulepi64_si128, _mm_set_epi64x};
+
+    let product = _mm_clmulepi64_si128(_mm_set_epi64x(0, a as i64), _mm_set_epi64x(0, b as i64), 0);
+
+    reduce(std::mem::transmute::<__m128i, u128>(product))
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "pclmulqdq")]
+unsafe fn mul_slice_pclmulqdq(c: u64, input: &[u64], out: &mut [u64], add: bool) {
+    for (i, o) in input.iter().zip(out) {
+        let product = mul_pclmulqdq(c, *i);
+        *o = if add { *o ^ product } else { product };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    quickcheck! {
+        fn qc_add_associativity(a: u64, b: u64, c: u64) -> bool {
+            add(a, add(b, c)) == add(add(a, b), c)
+        }
+
+        fn qc_mul_associativity(a: u64, b: u64, c: u64) -> bool {
+            mul(a, mul(b, c)) == mul(mul(a, b), c)
+        }
+
+        fn qc_mul_commutativity(a: u64, b: u64) -> bool {
+            mul(a, b) == mul(b, a)
+        }
+
+        fn qc_add_distributivity(a: u64, b: u64, c: u64) -> bool {
+            mul(a, add(b, c)) == add(mul(a, b), mul(a, c))
+        }
+
+        fn qc_inverse(a: u64) -> bool {
+            a == 0 || mul(a, div(1, a)) == 1
+        }
+
+        fn qc_div(a: u64, b: u64) -> bool {
+            b == 0 || mul(div(a, b), b) == a
+        }
+
+        fn qc_mul_pure(a: u64, b: u64) -> bool {
+            mul(a, b) == mul_pure(a, b)
+        }
+
+        fn qc_exponent(a: u64, n: u8) -> bool {
+            let mut b = 1;
+            for _ in 0..n {
+                b = mul(b, a);
+            }
+
+            b == exp(a, n as usize)
+        }
+
+        fn qc_mul_slice(c: u64, input: Vec<u64>, start: Vec<u64>) -> bool {
+            let start: Vec<u64> = start
+                .into_iter()
+                .chain(std::iter::repeat(0))
+                .take(input.len())
+                .collect();
+
+            let mut out = start.clone();
+            mul_slice(c, &input, &mut out);
+            let mut out_add = start.clone();
+            mul_slice_xor(c, &input, &mut out_add);
+
+            input
+                .iter()
+                .zip(&start)
+                .zip(out.iter().zip(&out_add))
+                .all(|((&a, &s), (&o, &o_add))| o == mul(c, a) && o_add == s ^ mul(c, a))
+        }
+    }
+
+    #[test]
+    fn test_reduction() {
+        // x^63 * x = x^64 = x^4 + x^3 + x + 1
+        assert_eq!(POLYNOMIAL, mul(1 << 63, 2));
+        assert_eq!(POLYNOMIAL, mul_pure(1 << 63, 2));
+        // x^63 * x^63 = x^126 = x^66 + x^65 + x^63 + x^62
+        //             = x^63 + x^62 + x^6 + x^4 + x^3 + x
+        assert_eq!(0xc000_0000_0000_005a, mul(1 << 63, 1 << 63));
+        assert_eq!(u64::MAX, mul(u64::MAX, 1));
+        assert_eq!(0, mul(u64::MAX, 0));
+    }
+
+    #[test]
+    fn test_exp() {
+        assert_eq!(1, exp(0, 0));
+        assert_eq!(0, exp(0, 3));
+        assert_eq!(1 << 63, exp(2, 63));
+        assert_eq!(POLYNOMIAL, exp(2, 64));
+        // the multiplicative group has order 2^64 - 1
+        assert_eq!(1, pow(2, u64::MAX));
+        assert_eq!(1, pow(0x1234_5678_9abc_def0, u64::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "Divisor is 0")]
+    fn test_div_b_is_0() {
+        div(1, 0);
+    }
+
+    #[test]
+    fn test_checked() {
+        use crate::Field as _;
+
+        assert_eq!(Err(Error::DivisionByZero), checked_div(1, 0));
+        assert_eq!(Ok(1), checked_div(7, 7));
+        assert_eq!(Ok(42), Field::checked_nth(42));
+        assert_eq!(Err(Error::InvalidIndex), Field::checked_nth(usize::MAX));
+
+        let mut out = [0; 2];
+        assert_eq!(
+            Err(Error::IncorrectShardSize {
+                shard: 0,
+                expected: 1,
+                actual: 2
+            }),
+            Field::checked_mul_slice(2, &[1], &mut out)
+        );
+    }
+}
@@ -2,13 +2,8 @@
 
 include!(concat!(env!("OUT_DIR"), "/table.rs"));
 
-#[cfg(any(
-    target_arch = "x86",
-    target_arch = "x86_64",
-    target_arch = "arm",
-    target_arch = "aarch64"
-))]
 use crate::platform::Platform;
+use crate::Error;
 
 /// The field GF(2^8).
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
@@ -53,14 +48,60 @@ impl crate::Field for Field {
     fn mul_slice_add(c: u8, input: &[u8], out: &mut [u8]) {
         mul_slice_xor(c, input, out);
     }
+
+    fn mul_slice_on(platform: Platform, c: u8, input: &[u8], out: &mut [u8]) {
+        mul_slice_platform(platform, c, input, out, false);
+    }
+
+    fn mul_slice_add_on(platform: Platform, c: u8, input: &[u8], out: &mut [u8]) {
+        mul_slice_platform(platform, c, input, out, true);
+    }
+
+    fn mul_slice_rows_on(platform: Platform, cs: &[u8], input: &[u8], outs: &mut [&mut [u8]]) {
+        mul_slice_rows_platform(platform, cs, input, outs, false);
+    }
+
+    fn mul_slice_add_rows_on(platform: Platform, cs: &[u8], input: &[u8], outs: &mut [&mut [u8]]) {
+        mul_slice_rows_platform(platform, cs, input, outs, true);
+    }
 }
 
+/// The maximum number of shards, data and parity combined, of a codec over GF(2^8).
+pub const MAX_SHARDS: usize = <Field as crate::Field>::ORDER;
+
+/// The irreducible polynomial GF(2^8) is constructed with, x^8 + x^4 + x^3 + x^2 + 1.
+pub const POLYNOMIAL: u32 = 0x11d;
+
 /// Type alias of ReedSolomon over GF(2^8).
 pub type ReedSolomon = crate::ReedSolomon<Field>;
 
 /// Type alias of ShardByShard over GF(2^8).
 pub type ShardByShard<'a> = crate::ShardByShard<'a, Field>;
 
+/// Type alias of SbsNotStarted over GF(2^8).
+pub type SbsNotStarted<'a> = crate::SbsNotStarted<'a, Field>;
+
+/// Type alias of SbsFeeding over GF(2^8).
+pub type SbsFeeding<'a> = crate::SbsFeeding<'a, Field>;
+
+/// Type alias of SbsDone over GF(2^8).
+pub type SbsDone<'a> = crate::SbsDone<'a, Field>;
+
+/// Type alias of SbsFed over GF(2^8).
+pub type SbsFed<'a> = crate::SbsFed<'a, Field>;
+
+/// Type alias of StreamDecoder over GF(2^8).
+pub type StreamDecoder<'a> = crate::StreamDecoder<'a, Field>;
+
+/// Type alias of IncrementalDecoder over GF(2^8).
+pub type IncrementalDecoder<'a> = crate::IncrementalDecoder<'a, Field>;
+
+/// Type alias of FrozenCodec over GF(2^8).
+pub type FrozenCodec = crate::FrozenCodec<Field>;
+
+/// Type alias of ReconstructScratch over GF(2^8).
+pub type ReconstructScratch = crate::ReconstructScratch<Field>;
+
 /// Add two elements.
 pub const fn add(a: u8, b: u8) -> u8 {
     a ^ b
@@ -94,6 +135,17 @@ pub fn div(a: u8, b: u8) -> u8 {
     }
 }
 
+/// Divide one element by another.
+///
+/// Returns `Error::DivisionByZero` if `b`, the divisor, is 0.
+pub fn checked_div(a: u8, b: u8) -> Result<u8, Error> {
+    if b == 0 {
+        return Err(Error::DivisionByZero);
+    }
+
+    Ok(div(a, b))
+}
+
 /// Compute a^n.
 pub fn exp(a: u8, n: usize) -> u8 {
     if n == 0 {
@@ -198,31 +250,65 @@ fn mul_slice_xor_pure(c: u8, input: &[u8], out: &mut [u8]) {
     }
 }
 
+/// Multiply each byte of `input` by `c`, writing the products to `out`.
+///
+/// Uses the SIMD implementation the CPU supports, if any, detected at runtime.
+///
+/// # Panics
+///
+/// Panics if `input` and `out` are not of the same length.
 #[cfg(not(any(
     target_arch = "x86",
     target_arch = "x86_64",
     target_arch = "arm",
-    target_arch = "aarch64"
+    target_arch = "aarch64",
+    target_arch = "powerpc64",
+    target_arch = "s390x",
+    target_arch = "loongarch64",
+    all(target_arch = "wasm32", target_feature = "simd128")
 )))]
 pub fn mul_slice(c: u8, input: &[u8], out: &mut [u8]) {
     mul_slice_pure(c, input, out);
 }
 
+/// Multiply each byte of `input` by `c`, adding the products to the
+/// corresponding bytes of `out`.
+///
+/// Uses the SIMD implementation the CPU supports, if any, detected at runtime.
+///
+/// # Panics
+///
+/// Panics if `input` and `out` are not of the same length.
 #[cfg(not(any(
     target_arch = "x86",
     target_arch = "x86_64",
     target_arch = "arm",
-    target_arch = "aarch64"
+    target_arch = "aarch64",
+    target_arch = "powerpc64",
+    target_arch = "s390x",
+    target_arch = "loongarch64",
+    all(target_arch = "wasm32", target_feature = "simd128")
 )))]
 pub fn mul_slice_xor(c: u8, input: &[u8], out: &mut [u8]) {
     mul_slice_xor_pure(c, input, out);
 }
 
+/// Multiply each byte of `input` by `c`, writing the products to `out`.
+///
+/// Uses the SIMD implementation the CPU supports, if any, detected at runtime.
+///
+/// # Panics
+///
+/// Panics if `input` and `out` are not of the same length.
 #[cfg(any(
     target_arch = "x86",
     target_arch = "x86_64",
     target_arch = "arm",
-    target_arch = "aarch64"
+    target_arch = "aarch64",
+    target_arch = "powerpc64",
+    target_arch = "s390x",
+    target_arch = "loongarch64",
+    all(target_arch = "wasm32", target_feature = "simd128")
 ))]
 pub fn mul_slice(c: u8, input: &[u8], out: &mut [u8]) {
     let platform = Platform::detect();
@@ -233,11 +319,23 @@ pub fn mul_slice(c: u8, input: &[u8], out: &mut [u8]) {
     }
 }
 
+/// Multiply each byte of `input` by `c`, adding the products to the
+/// corresponding bytes of `out`.
+///
+/// Uses the SIMD implementation the CPU supports, if any, detected at runtime.
+///
+/// # Panics
+///
+/// Panics if `input` and `out` are not of the same length.
 #[cfg(any(
     target_arch = "x86",
     target_arch = "x86_64",
     target_arch = "arm",
-    target_arch = "aarch64"
+    target_arch = "aarch64",
+    target_arch = "powerpc64",
+    target_arch = "s390x",
+    target_arch = "loongarch64",
+    all(target_arch = "wasm32", target_feature = "simd128")
 ))]
 pub fn mul_slice_xor(c: u8, input: &[u8], out: &mut [u8]) {
     let platform = Platform::detect();
@@ -248,19 +346,166 @@ pub fn mul_slice_xor(c: u8, input: &[u8], out: &mut [u8]) {
     }
 }
 
+// Multiplies with the kernel of `platform`, adding the products to `out`
+// if `add` is set, or writing them otherwise.
+//
+// Panics if the CPU does not support `platform`.
+pub(crate) fn mul_slice_platform(
+    platform: Platform,
+    c: u8,
+    input: &[u8],
+    out: &mut [u8],
+    add: bool,
+) {
+    assert!(platform.is_supported(), "{:?} is not supported", platform);
+
+    match (platform, add) {
+        (Platform::Portable, false) => mul_slice_pure(c, input, out),
+        (Platform::Portable, true) => mul_slice_xor_pure(c, input, out),
+        #[cfg(any(
+            target_arch = "x86",
+            target_arch = "x86_64",
+            target_arch = "arm",
+            target_arch = "aarch64",
+            target_arch = "powerpc64",
+            target_arch = "s390x",
+            target_arch = "loongarch64",
+            all(target_arch = "wasm32", target_feature = "simd128")
+        ))]
+        (platform, false) => mul_slice_simd(c, input, out, platform),
+        #[cfg(any(
+            target_arch = "x86",
+            target_arch = "x86_64",
+            target_arch = "arm",
+            target_arch = "aarch64",
+            target_arch = "powerpc64",
+            target_arch = "s390x",
+            target_arch = "loongarch64",
+            all(target_arch = "wasm32", target_feature = "simd128")
+        ))]
+        (platform, true) => mul_slice_xor_simd(c, input, out, platform),
+    }
+}
+
+// The number of rows the fused kernels multiply per pass over the input.
+const FUSED_ROWS: usize = 4;
+
+// Multiplies `input` by each of `cs`, writing the products to the
+// corresponding `outs` or adding them if `add` is set. The shuffle
+// kernels do up to `FUSED_ROWS` rows per pass over `input`.
+//
+// Panics if the CPU does not support `platform`.
+pub(crate) fn mul_slice_rows_platform(
+    platform: Platform,
+    cs: &[u8],
+    input: &[u8],
+    outs: &mut [&mut [u8]],
+    add: bool,
+) {
+    assert!(platform.is_supported(), "{:?} is not supported", platform);
+    assert_eq!(cs.len(), outs.len());
+    for out in outs.iter() {
+        assert_eq!(input.len(), out.len());
+    }
+
+    for (cs, outs) in cs.chunks(FUSED_ROWS).zip(outs.chunks_mut(FUSED_ROWS)) {
+        let done = match cs.len() {
+            1 => mul_slice_rows_fused::<1>(platform, cs, input, outs, add),
+            2 => mul_slice_rows_fused::<2>(platform, cs, input, outs, add),
+            3 => mul_slice_rows_fused::<3>(platform, cs, input, outs, add),
+            _ => mul_slice_rows_fused::<4>(platform, cs, input, outs, add),
+        };
+
+        for (&c, out) in cs.iter().zip(outs.iter_mut()) {
+            mul_slice_platform(platform, c, &input[done..], &mut out[done..], add);
+        }
+    }
+}
+
+// Runs the fused kernel of `platform` on `N` rows, returning the number
+// of bytes done, which is 0 for the platforms without one.
+#[allow(unused_variables)]
+#[allow(unreachable_code)]
+fn mul_slice_rows_fused<const N: usize>(
+    platform: Platform,
+    cs: &[u8],
+    input: &[u8],
+    outs: &mut [&mut [u8]],
+    add: bool,
+) -> usize {
+    type Kernel<const N: usize> =
+        unsafe fn([*const u8; N], [*const u8; N], *const u8, [*mut u8; N], usize) -> usize;
+
+    if input.is_empty() {
+        return 0;
+    }
+
+    let kernel: Kernel<N> = match (platform, add) {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        (Platform::SSE3, false) => crate::galois_8_sse3::gal_mul_rows::<N>,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        (Platform::SSE3, true) => crate::galois_8_sse3::gal_mul_xor_rows::<N>,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        (Platform::AVX2, false) => crate::galois_8_avx2::gal_mul_rows::<N>,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        (Platform::AVX2, true) => crate::galois_8_avx2::gal_mul_xor_rows::<N>,
+        // AVX512 implementation not enabled by default.
+        #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "unstable"))]
+        (Platform::AVX512, false) => crate::galois_8_avx512::gal_mul_rows::<N>,
+        #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "unstable"))]
+        (Platform::AVX512, true) => crate::galois_8_avx512::gal_mul_xor_rows::<N>,
+        #[cfg(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            not(feature = "unstable")
+        ))]
+        (Platform::AVX512, false) => crate::galois_8_avx2::gal_mul_rows::<N>,
+        #[cfg(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            not(feature = "unstable")
+        ))]
+        (Platform::AVX512, true) => crate::galois_8_avx2::gal_mul_xor_rows::<N>,
+        // NEON on arm implementation not enabled by default.
+        #[cfg(any(
+            target_arch = "aarch64",
+            all(target_arch = "arm", feature = "unstable")
+        ))]
+        (Platform::NEON, false) => crate::galois_8_neon::gal_mul_rows::<N>,
+        #[cfg(any(
+            target_arch = "aarch64",
+            all(target_arch = "arm", feature = "unstable")
+        ))]
+        (Platform::NEON, true) => crate::galois_8_neon::gal_mul_xor_rows::<N>,
+        _ => return 0,
+    };
+
+    let low = std::array::from_fn(|row| MUL_TABLE_LOW[cs[row] as usize].as_ptr());
+    let high = std::array::from_fn(|row| MUL_TABLE_HIGH[cs[row] as usize].as_ptr());
+    let out = std::array::from_fn(|row| outs[row].as_mut_ptr());
+
+    // Safe because the caller checked for platform support, and all
+    // of the `N` outputs are as long as the input.
+    unsafe { kernel(low, high, input.as_ptr(), out, input.len()) }
+}
+
 #[cfg(any(
     target_arch = "x86",
     target_arch = "x86_64",
     target_arch = "arm",
-    target_arch = "aarch64"
+    target_arch = "aarch64",
+    target_arch = "powerpc64",
+    target_arch = "s390x",
+    target_arch = "loongarch64",
+    all(target_arch = "wasm32", target_feature = "simd128")
 ))]
 #[allow(unused_variables)]
 #[allow(unreachable_code)]
-pub fn mul_slice_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platform) {
+pub(crate) fn mul_slice_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platform) {
     let low: *const u8 = &MUL_TABLE_LOW[c as usize][0];
     let high: *const u8 = &MUL_TABLE_HIGH[c as usize][0];
+    let table: *const u8 = &MUL_TABLE[c as usize][0];
 
     assert_eq!(input.len(), out.len());
+    return_if_empty!(input.len());
 
     let input_ptr: *const u8 = &input[0];
     let out_ptr: *mut u8 = &mut out[0];
@@ -279,6 +524,16 @@ pub fn mul_slice_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platform) {
         },
         // Safe because detect() checked for platform support.
         #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Platform::AVX512VBMI => unsafe {
+            crate::galois_8_avx512vbmi::gal_mul(table, input_ptr, out_ptr, size)
+        },
+        // Safe because detect() checked for platform support.
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Platform::GFNI => unsafe {
+            crate::galois_8_gfni::gal_mul(gfni_matrix(c), input_ptr, out_ptr, size)
+        },
+        // Safe because detect() checked for platform support.
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
         Platform::AVX512 => {
             // AVX512 implementation not enabled by default.
             // Safe because detect() checked for platform support.
@@ -296,6 +551,11 @@ pub fn mul_slice_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platform) {
         Platform::NEON => unsafe {
             crate::galois_8_neon::gal_mul(low, high, input_ptr, out_ptr, size)
         },
+        // Safe because detect() checked for platform support.
+        #[cfg(target_arch = "aarch64")]
+        Platform::SVE => unsafe {
+            crate::galois_8_sve::gal_mul(low, high, input_ptr, out_ptr, size)
+        },
         #[cfg(target_arch = "arm")]
         Platform::NEON => {
             // NEON on arm implementation not enabled by default.
@@ -309,6 +569,63 @@ pub fn mul_slice_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platform) {
                 return mul_slice_pure(c, input, out);
             }
         }
+        #[cfg(target_arch = "powerpc64")]
+        Platform::VSX => {
+            // VSX implementation not enabled by default.
+            // Safe because detect() checked for platform support.
+            #[cfg(feature = "unstable")]
+            {
+                unsafe { crate::galois_8_vsx::gal_mul(low, high, input_ptr, out_ptr, size) }
+            }
+            #[cfg(not(feature = "unstable"))]
+            {
+                return mul_slice_pure(c, input, out);
+            }
+        }
+        #[cfg(target_arch = "s390x")]
+        Platform::ZVECTOR => {
+            // z/Architecture vector implementation not enabled by default.
+            // Safe because detect() checked for platform support.
+            #[cfg(feature = "unstable")]
+            {
+                unsafe { crate::galois_8_zvector::gal_mul(low, high, input_ptr, out_ptr, size) }
+            }
+            #[cfg(not(feature = "unstable"))]
+            {
+                return mul_slice_pure(c, input, out);
+            }
+        }
+        #[cfg(target_arch = "loongarch64")]
+        Platform::LSX => {
+            // LSX implementation not enabled by default.
+            // Safe because detect() checked for platform support.
+            #[cfg(feature = "unstable")]
+            {
+                unsafe { crate::galois_8_lsx::gal_mul(low, high, input_ptr, out_ptr, size) }
+            }
+            #[cfg(not(feature = "unstable"))]
+            {
+                return mul_slice_pure(c, input, out);
+            }
+        }
+        #[cfg(target_arch = "loongarch64")]
+        Platform::LASX => {
+            // LASX implementation not enabled by default.
+            // Safe because detect() checked for platform support.
+            #[cfg(feature = "unstable")]
+            {
+                unsafe { crate::galois_8_lasx::gal_mul(low, high, input_ptr, out_ptr, size) }
+            }
+            #[cfg(not(feature = "unstable"))]
+            {
+                return mul_slice_pure(c, input, out);
+            }
+        }
+        // Safe because detect() checked for platform support.
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        Platform::SIMD128 => unsafe {
+            crate::galois_8_wasm::gal_mul(low, high, input_ptr, out_ptr, size)
+        },
         Platform::Portable => unreachable!(),
     };
 
@@ -319,15 +636,21 @@ pub fn mul_slice_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platform) {
     target_arch = "x86",
     target_arch = "x86_64",
     target_arch = "arm",
-    target_arch = "aarch64"
+    target_arch = "aarch64",
+    target_arch = "powerpc64",
+    target_arch = "s390x",
+    target_arch = "loongarch64",
+    all(target_arch = "wasm32", target_feature = "simd128")
 ))]
 #[allow(unused_variables)]
 #[allow(unreachable_code)]
-pub fn mul_slice_xor_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platform) {
+pub(crate) fn mul_slice_xor_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platform) {
     let low: *const u8 = &MUL_TABLE_LOW[c as usize][0];
     let high: *const u8 = &MUL_TABLE_HIGH[c as usize][0];
+    let table: *const u8 = &MUL_TABLE[c as usize][0];
 
     assert_eq!(input.len(), out.len());
+    return_if_empty!(input.len());
 
     let input_ptr: *const u8 = &input[0];
     let out_ptr: *mut u8 = &mut out[0];
@@ -346,6 +669,16 @@ pub fn mul_slice_xor_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platfor
         },
         // Safe because detect() checked for platform support.
         #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Platform::AVX512VBMI => unsafe {
+            crate::galois_8_avx512vbmi::gal_mul_xor(table, input_ptr, out_ptr, size)
+        },
+        // Safe because detect() checked for platform support.
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Platform::GFNI => unsafe {
+            crate::galois_8_gfni::gal_mul_xor(gfni_matrix(c), input_ptr, out_ptr, size)
+        },
+        // Safe because detect() checked for platform support.
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
         Platform::AVX512 => {
             // AVX512 implementation not enabled by default.
             // Safe because detect() checked for platform support.
@@ -363,6 +696,11 @@ pub fn mul_slice_xor_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platfor
         Platform::NEON => unsafe {
             crate::galois_8_neon::gal_mul_xor(low, high, input_ptr, out_ptr, size)
         },
+        // Safe because detect() checked for platform support.
+        #[cfg(target_arch = "aarch64")]
+        Platform::SVE => unsafe {
+            crate::galois_8_sve::gal_mul_xor(low, high, input_ptr, out_ptr, size)
+        },
         #[cfg(target_arch = "arm")]
         Platform::NEON => {
             // NEON for arm implementation not enabled by default.
@@ -376,14 +714,93 @@ pub fn mul_slice_xor_simd(c: u8, input: &[u8], out: &mut [u8], platform: Platfor
                 return mul_slice_xor_pure(c, input, out);
             }
         }
+        #[cfg(target_arch = "powerpc64")]
+        Platform::VSX => {
+            // VSX implementation not enabled by default.
+            // Safe because detect() checked for platform support.
+            #[cfg(feature = "unstable")]
+            {
+                unsafe { crate::galois_8_vsx::gal_mul_xor(low, high, input_ptr, out_ptr, size) }
+            }
+            #[cfg(not(feature = "unstable"))]
+            {
+                return mul_slice_xor_pure(c, input, out);
+            }
+        }
+        #[cfg(target_arch = "s390x")]
+        Platform::ZVECTOR => {
+            // z/Architecture vector implementation not enabled by default.
+            // Safe because detect() checked for platform support.
+            #[cfg(feature = "unstable")]
+            {
+                unsafe { crate::galois_8_zvector::gal_mul_xor(low, high, input_ptr, out_ptr, size) }
+            }
+            #[cfg(not(feature = "unstable"))]
+            {
+                return mul_slice_xor_pure(c, input, out);
+            }
+        }
+        #[cfg(target_arch = "loongarch64")]
+        Platform::LSX => {
+            // LSX implementation not enabled by default.
+            // Safe because detect() checked for platform support.
+            #[cfg(feature = "unstable")]
+            {
+                unsafe { crate::galois_8_lsx::gal_mul_xor(low, high, input_ptr, out_ptr, size) }
+            }
+            #[cfg(not(feature = "unstable"))]
+            {
+                return mul_slice_xor_pure(c, input, out);
+            }
+        }
+        #[cfg(target_arch = "loongarch64")]
+        Platform::LASX => {
+            // LASX implementation not enabled by default.
+            // Safe because detect() checked for platform support.
+            #[cfg(feature = "unstable")]
+            {
+                unsafe { crate::galois_8_lasx::gal_mul_xor(low, high, input_ptr, out_ptr, size) }
+            }
+            #[cfg(not(feature = "unstable"))]
+            {
+                return mul_slice_xor_pure(c, input, out);
+            }
+        }
+        // Safe because detect() checked for platform support.
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        Platform::SIMD128 => unsafe {
+            crate::galois_8_wasm::gal_mul_xor(low, high, input_ptr, out_ptr, size)
+        },
         Platform::Portable => unreachable!(),
     };
 
     mul_slice_xor_pure(c, &input[bytes_done..], &mut out[bytes_done..]);
 }
 
-#[cfg(test)]
-fn slice_xor(input: &[u8], out: &mut [u8]) {
+// The bit matrix of multiplying a byte by `c` for `vgf2p8affineqb`,
+// where byte `7 - i` selects the bits of the input making up bit `i`
+// of the product.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn gfni_matrix(c: u8) -> u64 {
+    let mut matrix = 0;
+    for j in 0..8 {
+        let column = mul(c, 1 << j);
+        for i in 0..8 {
+            if column & (1 << i) != 0 {
+                matrix |= 1 << (8 * (7 - i) + j);
+            }
+        }
+    }
+
+    matrix
+}
+
+/// Add each byte of `input` to the corresponding byte of `out`.
+///
+/// # Panics
+///
+/// Panics if `input` and `out` are not of the same length.
+pub fn add_slice(input: &[u8], out: &mut [u8]) {
     assert_eq!(input.len(), out.len());
 
     let len: isize = input.len() as isize;
@@ -416,10 +833,6 @@ fn slice_xor(input: &[u8], out: &mut [u8]) {
             n += 1;
         }
     }
-
-    /*for n in 0..input.len() {
-        out[n] ^= input[n]
-    }*/
 }
 
 #[cfg(test)]
@@ -655,7 +1068,7 @@ mod tests {
             for i in 0..expect.len() {
                 expect[i] = input[i] ^ output[i];
             }
-            slice_xor(&input, &mut output);
+            add_slice(&input, &mut output);
             for i in 0..expect.len() {
                 assert_eq!(expect[i], output[i]);
             }
@@ -663,13 +1076,156 @@ mod tests {
             for i in 0..expect.len() {
                 expect[i] = input[i] ^ output[i];
             }
-            slice_xor(&input, &mut output);
+            add_slice(&input, &mut output);
             for i in 0..expect.len() {
                 assert_eq!(expect[i], output[i]);
             }
         }
     }
 
+    // Compares the kernel of `platform` against the portable implementation.
+    #[cfg(any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "powerpc64",
+        target_arch = "s390x",
+        target_arch = "loongarch64"
+    ))]
+    fn check_platform(platform: Platform) {
+        let mut input = vec![0; 200];
+        fill_random(&mut input);
+        for c in 0..=255 {
+            // every length of tail, including the masked ones
+            for len in (0..=130).chain(Some(200)) {
+                let mut expect = vec![0; len];
+                mul_slice_pure(c, &input[..len], &mut expect);
+                let mut output = vec![0; len];
+                mul_slice_simd(c, &input[..len], &mut output, platform);
+                assert_eq!(expect, output);
+
+                mul_slice_xor_pure(c, &input[..len], &mut expect);
+                mul_slice_xor_simd(c, &input[..len], &mut output, platform);
+                assert_eq!(expect, output);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn test_avx2() {
+        if crate::platform::avx2_detected() {
+            check_platform(Platform::AVX2);
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "aarch64")]
+    fn test_sve() {
+        if crate::platform::sve_detected() {
+            check_platform(Platform::SVE);
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "powerpc64")]
+    fn test_vsx() {
+        if crate::platform::vsx_detected() {
+            check_platform(Platform::VSX);
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "s390x")]
+    fn test_zvector() {
+        if crate::platform::zvector_detected() {
+            check_platform(Platform::ZVECTOR);
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "loongarch64")]
+    fn test_lsx() {
+        if crate::platform::lsx_detected() {
+            check_platform(Platform::LSX);
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "loongarch64")]
+    fn test_lasx() {
+        if crate::platform::lasx_detected() {
+            check_platform(Platform::LASX);
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn test_gfni() {
+        if crate::platform::gfni_detected() {
+            check_platform(Platform::GFNI);
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn test_avx512vbmi() {
+        if crate::platform::avx512vbmi_detected() {
+            check_platform(Platform::AVX512VBMI);
+        }
+    }
+
+    #[test]
+    fn test_mul_slice_rows() {
+        let mut input = vec![0; 200];
+        fill_random(&mut input);
+        let mut cs = vec![0; 9];
+        fill_random(&mut cs);
+
+        for platform in crate::Platform::supported() {
+            // up to two groups of fused rows and a partial one
+            for rows in 0..=cs.len() {
+                for &len in &[0, 1, 31, 32, 33, 63, 64, 65, 200] {
+                    let input = &input[..len];
+                    let mut start = vec![vec![0; len]; rows];
+                    for start in &mut start {
+                        fill_random(start);
+                    }
+
+                    for &add in &[false, true] {
+                        let mut expect = start.clone();
+                        for (&c, expect) in cs.iter().zip(&mut expect) {
+                            if add {
+                                mul_slice_xor_pure(c, input, expect);
+                            } else {
+                                mul_slice_pure(c, input, expect);
+                            }
+                        }
+
+                        let mut output = start.clone();
+                        let mut outs: Vec<&mut [u8]> =
+                            output.iter_mut().map(|x| &mut x[..]).collect();
+                        mul_slice_rows_platform(platform, &cs[..rows], input, &mut outs, add);
+                        assert_eq!(expect, output);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_slice_ops_empty() {
+        mul_slice(7, &[], &mut []);
+        mul_slice_xor(7, &[], &mut []);
+        add_slice(&[], &mut []);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_add_slice_length_mismatch() {
+        add_slice(&[1, 2, 3], &mut [0; 2]);
+    }
+
     #[test]
     fn test_div_a_is_0() {
         assert_eq!(0, div(0, 100));
@@ -681,6 +1237,43 @@ mod tests {
         div(1, 0);
     }
 
+    #[test]
+    fn test_checked() {
+        use crate::Field as _;
+
+        assert_eq!(Ok(div(7, 3)), checked_div(7, 3));
+        assert_eq!(Err(Error::DivisionByZero), checked_div(1, 0));
+        assert_eq!(Err(Error::DivisionByZero), checked_div(0, 0));
+        assert_eq!(Err(Error::DivisionByZero), Field::checked_div(1, 0));
+
+        assert_eq!(Ok(Field::nth(255)), Field::checked_nth(255));
+        assert_eq!(Err(Error::InvalidIndex), Field::checked_nth(256));
+
+        let input = [1, 2, 3];
+        let mut out = [9; 4];
+        assert_eq!(
+            Err(Error::IncorrectShardSize {
+                shard: 0,
+                expected: 3,
+                actual: 4
+            }),
+            Field::checked_mul_slice(2, &input, &mut out)
+        );
+        assert_eq!(
+            Err(Error::IncorrectShardSize {
+                shard: 0,
+                expected: 3,
+                actual: 4
+            }),
+            Field::checked_mul_slice_add(2, &input, &mut out)
+        );
+        assert_eq!([9; 4], out);
+
+        Field::checked_mul_slice(2, &input, &mut out[..3]).unwrap();
+        Field::checked_mul_slice_add(2, &input, &mut out[..3]).unwrap();
+        assert_eq!([0, 0, 0, 9], out);
+    }
+
     #[test]
     fn test_same_as_maybe_ffi() {
         let len = 10_003;
@@ -1,15 +1,17 @@
 #[cfg(target_arch = "x86")]
 use std::arch::x86::{
-    __m128i, __m256i, _mm256_and_si256, _mm256_broadcastsi128_si256, _mm256_loadu_si256,
-    _mm256_set1_epi8, _mm256_shuffle_epi8, _mm256_srli_epi64, _mm256_storeu_si256,
-    _mm256_xor_si256, _mm_loadu_si128,
+    __m128i, __m256i, _mm256_and_si256, _mm256_broadcastsi128_si256, _mm256_cmpgt_epi32,
+    _mm256_loadu_si256, _mm256_maskload_epi32, _mm256_maskstore_epi32, _mm256_set1_epi32,
+    _mm256_set1_epi8, _mm256_setr_epi32, _mm256_shuffle_epi8, _mm256_srli_epi64,
+    _mm256_storeu_si256, _mm256_xor_si256, _mm_loadu_si128,
 };
 
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::{
-    __m128i, __m256i, _mm256_and_si256, _mm256_broadcastsi128_si256, _mm256_loadu_si256,
-    _mm256_set1_epi8, _mm256_shuffle_epi8, _mm256_srli_epi64, _mm256_storeu_si256,
-    _mm256_xor_si256, _mm_loadu_si128,
+    __m128i, __m256i, _mm256_and_si256, _mm256_broadcastsi128_si256, _mm256_cmpgt_epi32,
+    _mm256_loadu_si256, _mm256_maskload_epi32, _mm256_maskstore_epi32, _mm256_set1_epi32,
+    _mm256_set1_epi8, _mm256_setr_epi32, _mm256_shuffle_epi8, _mm256_srli_epi64,
+    _mm256_storeu_si256, _mm256_xor_si256, _mm_loadu_si128,
 };
 
 type Vec128 = __m128i;
@@ -59,6 +61,27 @@ unsafe fn storeu_v(out: *mut u8, vec: Vec) {
     _mm256_storeu_si256(out.cast::<__m256i>(), vec);
 }
 
+// The mask of the first `lanes` 32-bit lanes.
+#[inline(always)]
+unsafe fn lane_mask_v(lanes: usize) -> Vec {
+    _mm256_cmpgt_epi32(
+        _mm256_set1_epi32(lanes as i32),
+        _mm256_setr_epi32(0, 1, 2, 3, 4, 5, 6, 7),
+    )
+}
+
+#[inline(always)]
+#[allow(clippy::cast_ptr_alignment)]
+unsafe fn maskload_v(in_0: *const u8, mask: Vec) -> Vec {
+    _mm256_maskload_epi32(in_0.cast::<i32>(), mask)
+}
+
+#[inline(always)]
+#[allow(clippy::cast_ptr_alignment)]
+unsafe fn maskstore_v(out: *mut u8, mask: Vec, vec: Vec) {
+    _mm256_maskstore_epi32(out.cast::<i32>(), mask, vec);
+}
+
 #[inline(always)]
 unsafe fn replicate_v128_v(vec: Vec128) -> Vec {
     _mm256_broadcastsi128_si256(vec)
@@ -117,6 +140,66 @@ unsafe fn gal_mul_impl(
         x = x.wrapping_add(1);
     }
 
+    // The whole 32-bit lanes of the tail are done with masked loads and
+    // stores, `vpmaskmovd` has no byte granularity.
+    let lanes = (len - done) / 4;
+    if lanes > 0 {
+        let mask = lane_mask_v(lanes);
+        let in_x = maskload_v(in_0.add(done), mask);
+        let old = maskload_v(out.add(done), mask);
+        let result = gal_mul_v(
+            low_mask_unpacked,
+            low_vector,
+            high_vector,
+            modifier,
+            in_x,
+            old,
+        );
+        maskstore_v(out.add(done), mask, result);
+        done += lanes * 4;
+    }
+
+    done
+}
+
+// Multiplies each vector of the input by the coefficients of all `N`
+// rows, so that the input is loaded and split into nibbles only once.
+#[inline(always)]
+unsafe fn gal_mul_rows_impl<const N: usize>(
+    low: [*const u8; N],
+    high: [*const u8; N],
+    in_0: *const u8,
+    out: [*mut u8; N],
+    len: usize,
+    modifier: Option<unsafe fn(_: Vec, _: Vec) -> Vec>,
+) -> usize {
+    let low_mask_unpacked = set1_epi8_v(0xf_i8);
+    let mut low_vectors = [low_mask_unpacked; N];
+    let mut high_vectors = [low_mask_unpacked; N];
+    for row in 0..N {
+        low_vectors[row] = replicate_v128_v(loadu_v128(low[row]));
+        high_vectors[row] = replicate_v128_v(loadu_v128(high[row]));
+    }
+    let modifier = modifier.expect("non-null function pointer");
+    let mut done = 0;
+
+    let s_v = std::mem::size_of::<Vec>();
+    while done + s_v <= len {
+        let in_x = loadu_v(in_0.add(done));
+        let low_input = and_v(in_x, low_mask_unpacked);
+        let high_input = and_v(srli_epi64_v::<4>(in_x), low_mask_unpacked);
+
+        for row in 0..N {
+            let mul_low_part = shuffle_epi8_v(low_vectors[row], low_input);
+            let mul_high_part = shuffle_epi8_v(high_vectors[row], high_input);
+            let new = xor_v(mul_low_part, mul_high_part);
+
+            let old = loadu_v(out[row].add(done));
+            storeu_v(out[row].add(done), modifier(new, old));
+        }
+        done += s_v;
+    }
+
     done
 }
 
@@ -166,3 +249,55 @@ pub unsafe fn gal_mul_xor(
         Some(xor_v as unsafe fn(_: Vec, _: Vec) -> Vec),
     )
 }
+
+/// Multiplies the input by the coefficients of `N` rows at once, see `gal_mul`.
+///
+/// # Safety
+///
+/// The CPU must support AVX2. `low[i]` and `high[i]` must each point
+/// to 16 readable bytes, `in_0` to `len` readable bytes, and `out[i]`
+/// to `len` readable and writable bytes not overlapping `in_0` or
+/// any other row.
+#[target_feature(enable = "avx2")]
+pub unsafe fn gal_mul_rows<const N: usize>(
+    low: [*const u8; N],
+    high: [*const u8; N],
+    in_0: *const u8,
+    out: [*mut u8; N],
+    len: usize,
+) -> usize {
+    gal_mul_rows_impl(
+        low,
+        high,
+        in_0,
+        out,
+        len,
+        Some(noop as unsafe fn(_: Vec, _: Vec) -> Vec),
+    )
+}
+
+/// Multiplies the input by the coefficients of `N` rows at once, see `gal_mul_xor`.
+///
+/// # Safety
+///
+/// The CPU must support AVX2. `low[i]` and `high[i]` must each point
+/// to 16 readable bytes, `in_0` to `len` readable bytes, and `out[i]`
+/// to `len` readable and writable bytes not overlapping `in_0` or
+/// any other row.
+#[target_feature(enable = "avx2")]
+pub unsafe fn gal_mul_xor_rows<const N: usize>(
+    low: [*const u8; N],
+    high: [*const u8; N],
+    in_0: *const u8,
+    out: [*mut u8; N],
+    len: usize,
+) -> usize {
+    gal_mul_rows_impl(
+        low,
+        high,
+        in_0,
+        out,
+        len,
+        Some(xor_v as unsafe fn(_: Vec, _: Vec) -> Vec),
+    )
+}
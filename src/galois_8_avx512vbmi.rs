@@ -0,0 +1,95 @@
+#[cfg(target_arch = "x86")]
+use std::arch::x86::{
+    __m512i, __mmask64, _mm512_loadu_si512, _mm512_mask_storeu_epi8, _mm512_maskz_loadu_epi8,
+    _mm512_maskz_mov_epi8, _mm512_movepi8_mask, _mm512_permutex2var_epi8, _mm512_set1_epi8,
+    _mm512_storeu_si512, _mm512_xor_si512,
+};
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::{
+    __m512i, __mmask64, _mm512_loadu_si512, _mm512_mask_storeu_epi8, _mm512_maskz_loadu_epi8,
+    _mm512_maskz_mov_epi8, _mm512_movepi8_mask, _mm512_permutex2var_epi8, _mm512_set1_epi8,
+    _mm512_storeu_si512, _mm512_xor_si512,
+};
+
+type Vec = __m512i;
+
+#[inline(always)]
+unsafe fn gal_mul_v(table_low: Vec, table_high: Vec, top_bit: Vec, in_x: Vec) -> Vec {
+    let low = _mm512_permutex2var_epi8(table_low, in_x, table_high);
+    let high = _mm512_maskz_mov_epi8(_mm512_movepi8_mask(in_x), top_bit);
+    _mm512_xor_si512(low, high)
+}
+
+// `vpermi2b` looks up each byte in two 64-byte tables at once, i.e. in
+// the products of the 128 bytes with the top bit clear, so a single
+// shuffle covers 7 bits of the input instead of the 4 of `vpshufb`.
+// The product of the top bit is added to the bytes which have it set,
+// as selected by `vpmovb2m`.
+//
+// `table` holds the products of `c` and each of the 256 bytes,
+// see `galois_8::MUL_TABLE`.
+//
+// The tail of fewer than 64 bytes is done with masked loads and stores,
+// so all of `len` is done.
+#[inline(always)]
+#[allow(clippy::cast_ptr_alignment)]
+unsafe fn gal_mul_impl(
+    table: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+    add: bool,
+) -> usize {
+    let table_low = _mm512_loadu_si512(table.cast::<__m512i>());
+    let table_high = _mm512_loadu_si512(table.add(64).cast::<__m512i>());
+    let top_bit = _mm512_set1_epi8(*table.add(128) as i8);
+    let s_v = std::mem::size_of::<Vec>();
+
+    let mut done = 0;
+    while done + s_v <= len {
+        let in_x = _mm512_loadu_si512(in_0.add(done).cast::<__m512i>());
+        let mut result = gal_mul_v(table_low, table_high, top_bit, in_x);
+        if add {
+            let old = _mm512_loadu_si512(out.add(done).cast::<__m512i>());
+            result = _mm512_xor_si512(result, old);
+        }
+        _mm512_storeu_si512(out.add(done).cast::<__m512i>(), result);
+        done += s_v;
+    }
+
+    if done < len {
+        // the bytes past the end are neither read nor written
+        let mask: __mmask64 = (1 << (len - done)) - 1;
+        let in_x = _mm512_maskz_loadu_epi8(mask, in_0.add(done).cast::<i8>());
+        let mut result = gal_mul_v(table_low, table_high, top_bit, in_x);
+        if add {
+            let old = _mm512_maskz_loadu_epi8(mask, out.add(done).cast::<i8>());
+            result = _mm512_xor_si512(result, old);
+        }
+        _mm512_mask_storeu_epi8(out.add(done).cast::<i8>(), mask, result);
+        done = len;
+    }
+
+    done
+}
+
+/// # Safety
+///
+/// The CPU must support AVX-512F, AVX-512BW and AVX-512VBMI. `table`
+/// must point to the 256 readable bytes of the products, `in_0` to `len`
+/// readable bytes, and `out` to `len` writable bytes.
+#[target_feature(enable = "avx512f,avx512bw,avx512vbmi")]
+pub unsafe fn gal_mul(table: *const u8, in_0: *const u8, out: *mut u8, len: usize) -> usize {
+    gal_mul_impl(table, in_0, out, len, false)
+}
+
+/// # Safety
+///
+/// The CPU must support AVX-512F, AVX-512BW and AVX-512VBMI. `table`
+/// must point to the 256 readable bytes of the products, `in_0` to `len`
+/// readable bytes, and `out` to `len` readable and writable bytes.
+#[target_feature(enable = "avx512f,avx512bw,avx512vbmi")]
+pub unsafe fn gal_mul_xor(table: *const u8, in_0: *const u8, out: *mut u8, len: usize) -> usize {
+    gal_mul_impl(table, in_0, out, len, true)
+}
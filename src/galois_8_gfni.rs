@@ -0,0 +1,77 @@
+#[cfg(target_arch = "x86")]
+use std::arch::x86::{
+    __m256i, _mm256_cmpgt_epi32, _mm256_gf2p8affine_epi64_epi8, _mm256_loadu_si256,
+    _mm256_maskload_epi32, _mm256_maskstore_epi32, _mm256_set1_epi32, _mm256_set1_epi64x,
+    _mm256_setr_epi32, _mm256_storeu_si256, _mm256_xor_si256,
+};
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::{
+    __m256i, _mm256_cmpgt_epi32, _mm256_gf2p8affine_epi64_epi8, _mm256_loadu_si256,
+    _mm256_maskload_epi32, _mm256_maskstore_epi32, _mm256_set1_epi32, _mm256_set1_epi64x,
+    _mm256_setr_epi32, _mm256_storeu_si256, _mm256_xor_si256,
+};
+
+type Vec = __m256i;
+
+// `vgf2p8mulb` multiplies modulo the AES polynomial, 0x11b, rather than
+// the one of `galois_8`, so each byte is multiplied by the constant as
+// an affine transformation instead. Multiplying by a constant is linear
+// over GF(2), i.e. an 8x8 bit matrix, see `galois_8::gfni_matrix`.
+//
+// The whole 32-bit lanes of the tail are done with masked loads and
+// stores, `vpmaskmovd` has no byte granularity.
+#[inline(always)]
+#[allow(clippy::cast_ptr_alignment)]
+unsafe fn gal_mul_impl(matrix: u64, in_0: *const u8, out: *mut u8, len: usize, add: bool) -> usize {
+    let matrix = _mm256_set1_epi64x(matrix as i64);
+    let s_v = std::mem::size_of::<Vec>();
+
+    let mut done = 0;
+    while done + s_v <= len {
+        let in_x = _mm256_loadu_si256(in_0.add(done).cast::<__m256i>());
+        let mut result = _mm256_gf2p8affine_epi64_epi8::<0>(in_x, matrix);
+        if add {
+            let old = _mm256_loadu_si256(out.add(done).cast::<__m256i>());
+            result = _mm256_xor_si256(result, old);
+        }
+        _mm256_storeu_si256(out.add(done).cast::<__m256i>(), result);
+        done += s_v;
+    }
+
+    let lanes = (len - done) / 4;
+    if lanes > 0 {
+        let mask = _mm256_cmpgt_epi32(
+            _mm256_set1_epi32(lanes as i32),
+            _mm256_setr_epi32(0, 1, 2, 3, 4, 5, 6, 7),
+        );
+        let in_x = _mm256_maskload_epi32(in_0.add(done).cast::<i32>(), mask);
+        let mut result = _mm256_gf2p8affine_epi64_epi8::<0>(in_x, matrix);
+        if add {
+            let old = _mm256_maskload_epi32(out.add(done).cast::<i32>(), mask);
+            result = _mm256_xor_si256(result, old);
+        }
+        _mm256_maskstore_epi32(out.add(done).cast::<i32>(), mask, result);
+        done += lanes * 4;
+    }
+
+    done
+}
+
+/// # Safety
+///
+/// The CPU must support GFNI and AVX2. `in_0` must point to `len`
+/// readable bytes, and `out` to `len` writable bytes.
+#[target_feature(enable = "gfni,avx2")]
+pub unsafe fn gal_mul(matrix: u64, in_0: *const u8, out: *mut u8, len: usize) -> usize {
+    gal_mul_impl(matrix, in_0, out, len, false)
+}
+
+/// # Safety
+///
+/// The CPU must support GFNI and AVX2. `in_0` must point to `len`
+/// readable bytes, and `out` to `len` readable and writable bytes.
+#[target_feature(enable = "gfni,avx2")]
+pub unsafe fn gal_mul_xor(matrix: u64, in_0: *const u8, out: *mut u8, len: usize) -> usize {
+    gal_mul_impl(matrix, in_0, out, len, true)
+}
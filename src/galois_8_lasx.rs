@@ -0,0 +1,87 @@
+use std::arch::loongarch64::{
+    lasx_xvandi_b, lasx_xvld, lasx_xvshuf_b, lasx_xvsrli_b, lasx_xvst, lasx_xvxor_v, lsx_vld,
+    m128i, m256i,
+};
+use std::mem::transmute;
+
+type Vec128 = m128i;
+type Vec = m256i;
+
+#[inline(always)]
+unsafe fn loadu_v128(in_0: *const u8) -> Vec128 {
+    lsx_vld::<0>(in_0.cast::<i8>())
+}
+
+#[inline(always)]
+unsafe fn loadu_v(in_0: *const u8) -> Vec {
+    lasx_xvld::<0>(in_0.cast::<i8>())
+}
+
+#[inline(always)]
+unsafe fn storeu_v(out: *mut u8, vec: Vec) {
+    lasx_xvst::<0>(vec, out.cast::<i8>());
+}
+
+#[inline(always)]
+unsafe fn broadcast_v(vec: Vec128) -> Vec {
+    transmute::<[Vec128; 2], Vec>([vec, vec])
+}
+
+// `xvshuf.b` shuffles within each 128-bit lane, so the 16-byte tables
+// are repeated in both lanes, as with `vpshufb` on AVX2.
+#[inline(always)]
+unsafe fn gal_mul_impl(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+    add: bool,
+) -> usize {
+    let low_vector = broadcast_v(loadu_v128(low));
+    let high_vector = broadcast_v(loadu_v128(high));
+    let s_v = std::mem::size_of::<Vec>();
+
+    let mut done = 0;
+    while done + s_v <= len {
+        let in_x = loadu_v(in_0.add(done));
+        let mul_low_part = lasx_xvshuf_b(low_vector, low_vector, lasx_xvandi_b::<0x0f>(in_x));
+        let mul_high_part = lasx_xvshuf_b(high_vector, high_vector, lasx_xvsrli_b::<4>(in_x));
+        let mut result = lasx_xvxor_v(mul_low_part, mul_high_part);
+        if add {
+            result = lasx_xvxor_v(result, loadu_v(out.add(done)));
+        }
+        storeu_v(out.add(done), result);
+        done += s_v;
+    }
+
+    done
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "lasx")]
+pub unsafe fn gal_mul(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(low, high, in_0, out, len, false)
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "lasx")]
+pub unsafe fn gal_mul_xor(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(low, high, in_0, out, len, true)
+}
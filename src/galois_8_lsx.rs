@@ -0,0 +1,75 @@
+use std::arch::loongarch64::{
+    lsx_vandi_b, lsx_vld, lsx_vshuf_b, lsx_vsrli_b, lsx_vst, lsx_vxor_v, m128i,
+};
+
+type Vec = m128i;
+
+#[inline(always)]
+unsafe fn loadu_v(in_0: *const u8) -> Vec {
+    lsx_vld::<0>(in_0.cast::<i8>())
+}
+
+#[inline(always)]
+unsafe fn storeu_v(out: *mut u8, vec: Vec) {
+    lsx_vst::<0>(vec, out.cast::<i8>());
+}
+
+// `vshuf.b` looks up each byte in the 32 bytes of its two table operands,
+// and zeroes bytes whose index has either of the top two bits set. Both
+// nibbles are brought into 0..16 before the lookup.
+#[inline(always)]
+unsafe fn gal_mul_impl(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+    add: bool,
+) -> usize {
+    let low_vector = loadu_v(low);
+    let high_vector = loadu_v(high);
+    let s_v = std::mem::size_of::<Vec>();
+
+    let mut done = 0;
+    while done + s_v <= len {
+        let in_x = loadu_v(in_0.add(done));
+        let mul_low_part = lsx_vshuf_b(low_vector, low_vector, lsx_vandi_b::<0x0f>(in_x));
+        let mul_high_part = lsx_vshuf_b(high_vector, high_vector, lsx_vsrli_b::<4>(in_x));
+        let mut result = lsx_vxor_v(mul_low_part, mul_high_part);
+        if add {
+            result = lsx_vxor_v(result, loadu_v(out.add(done)));
+        }
+        storeu_v(out.add(done), result);
+        done += s_v;
+    }
+
+    done
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "lsx")]
+pub unsafe fn gal_mul(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(low, high, in_0, out, len, false)
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "lsx")]
+pub unsafe fn gal_mul_xor(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(low, high, in_0, out, len, true)
+}
@@ -164,6 +164,47 @@ unsafe fn gal_mul_impl(
     done
 }
 
+// Multiplies each vector of the input by the coefficients of all `N`
+// rows, so that the input is loaded and split into nibbles only once.
+#[inline(always)]
+unsafe fn gal_mul_rows_impl<const N: usize>(
+    low: [*const u8; N],
+    high: [*const u8; N],
+    in_0: *const u8,
+    out: [*mut u8; N],
+    len: usize,
+    modifier: Option<unsafe fn(_: Vec, _: Vec) -> Vec>,
+) -> usize {
+    let low_mask_unpacked = set1_epi8_v(0xf_u8);
+    let mut low_vectors = [low_mask_unpacked; N];
+    let mut high_vectors = [low_mask_unpacked; N];
+    for row in 0..N {
+        low_vectors[row] = replicate_v128_v(loadu_v128(low[row]));
+        high_vectors[row] = replicate_v128_v(loadu_v128(high[row]));
+    }
+    let modifier = modifier.expect("non-null function pointer");
+    let mut done = 0;
+
+    let s_v = size_of::<Vec>();
+    while done + s_v <= len {
+        let in_x = loadu_v(in_0.add(done));
+        let low_input = and_v(in_x, low_mask_unpacked);
+        let high_input = and_v(srli_epi64_v::<4>(in_x), low_mask_unpacked);
+
+        for row in 0..N {
+            let mul_low_part = shuffle_epi8_v(low_vectors[row], low_input);
+            let mul_high_part = shuffle_epi8_v(high_vectors[row], high_input);
+            let new = xor_v(mul_low_part, mul_high_part);
+
+            let old = loadu_v(out[row].add(done));
+            storeu_v(out[row].add(done), modifier(new, old));
+        }
+        done += s_v;
+    }
+
+    done
+}
+
 #[inline(always)]
 const fn noop(new: Vec, _old: Vec) -> Vec {
     new
@@ -212,3 +253,51 @@ pub unsafe fn gal_mul_xor(
         Some(xor_v as unsafe fn(_: Vec, _: Vec) -> Vec),
     )
 }
+
+/// Multiplies the input by the coefficients of `N` rows at once, see `gal_mul`.
+///
+/// # Safety
+///
+///
+#[target_feature(enable = "neon")]
+#[cfg_attr(target_arch = "arm", target_feature(enable = "v7"))]
+pub unsafe fn gal_mul_rows<const N: usize>(
+    low: [*const u8; N],
+    high: [*const u8; N],
+    in_0: *const u8,
+    out: [*mut u8; N],
+    len: usize,
+) -> usize {
+    gal_mul_rows_impl(
+        low,
+        high,
+        in_0,
+        out,
+        len,
+        Some(noop as unsafe fn(_: Vec, _: Vec) -> Vec),
+    )
+}
+
+/// Multiplies the input by the coefficients of `N` rows at once, see `gal_mul_xor`.
+///
+/// # Safety
+///
+///
+#[target_feature(enable = "neon")]
+#[cfg_attr(target_arch = "arm", target_feature(enable = "v7"))]
+pub unsafe fn gal_mul_xor_rows<const N: usize>(
+    low: [*const u8; N],
+    high: [*const u8; N],
+    in_0: *const u8,
+    out: [*mut u8; N],
+    len: usize,
+) -> usize {
+    gal_mul_rows_impl(
+        low,
+        high,
+        in_0,
+        out,
+        len,
+        Some(xor_v as unsafe fn(_: Vec, _: Vec) -> Vec),
+    )
+}
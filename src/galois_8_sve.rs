@@ -0,0 +1,102 @@
+use std::arch::asm;
+
+// The SVE intrinsics are not in `std::arch` yet, so the kernel is written
+// in inline assembly, which is stable.
+//
+// `ld1rqb` repeats the 16-byte tables in every 128-bit quadword, so `tbl`,
+// which indexes the whole vector, finds the nibbles in the first one
+// whatever the vector length. `whilelo` masks the lanes past `len`, so
+// the last, partial vector needs no scalar tail.
+#[inline(always)]
+unsafe fn gal_mul_impl(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+    add: bool,
+) -> usize {
+    asm!(
+        "ptrue p0.b",
+        "ld1rqb {{z0.b}}, p0/z, [{low}]",
+        "ld1rqb {{z1.b}}, p0/z, [{high}]",
+        "mov {done}, #0",
+        "whilelo p1.b, {done}, {len}",
+        "b.none 3f",
+        "2:",
+        "ld1b {{z2.b}}, p1/z, [{in_0}, {done}]",
+        "mov z3.d, z2.d",
+        "and z3.b, z3.b, #0x0f",
+        "lsr z4.b, z2.b, #4",
+        "tbl z3.b, {{z0.b}}, z3.b",
+        "tbl z4.b, {{z1.b}}, z4.b",
+        "eor z3.d, z3.d, z4.d",
+        "cbz {add:w}, 4f",
+        "ld1b {{z5.b}}, p1/z, [{out}, {done}]",
+        "eor z3.d, z3.d, z5.d",
+        "4:",
+        "st1b {{z3.b}}, p1, [{out}, {done}]",
+        "incb {done}",
+        "whilelo p1.b, {done}, {len}",
+        "b.first 2b",
+        "3:",
+        low = in(reg) low,
+        high = in(reg) high,
+        in_0 = in(reg) in_0,
+        out = in(reg) out,
+        len = in(reg) len,
+        add = in(reg) u32::from(add),
+        done = out(reg) _,
+        out("v0") _,
+        out("v1") _,
+        out("v2") _,
+        out("v3") _,
+        out("v4") _,
+        out("v5") _,
+        out("p0") _,
+        out("p1") _,
+        options(nostack),
+    );
+
+    len
+}
+
+/// Returns the vector length in bytes.
+///
+/// # Safety
+///
+/// The CPU must support SVE.
+#[target_feature(enable = "sve")]
+pub unsafe fn vector_len() -> usize {
+    let len: usize;
+    asm!("cntb {len}", len = out(reg) len, options(pure, nomem, nostack));
+    len
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "sve")]
+pub unsafe fn gal_mul(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(low, high, in_0, out, len, false)
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "sve")]
+pub unsafe fn gal_mul_xor(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(low, high, in_0, out, len, true)
+}
@@ -0,0 +1,145 @@
+use std::arch::wasm32::{
+    u8x16_shr, u8x16_splat, u8x16_swizzle, v128, v128_and, v128_load, v128_store, v128_xor,
+};
+
+use std::mem::size_of;
+
+type Vec = v128;
+
+// wasm loads and stores have no alignment requirement
+#[inline(always)]
+#[allow(clippy::cast_ptr_alignment)]
+unsafe fn loadu_v(in_0: *const u8) -> Vec {
+    v128_load(in_0.cast::<v128>())
+}
+
+#[inline(always)]
+fn set1_epi8_v(c: u8) -> Vec {
+    u8x16_splat(c)
+}
+
+#[inline(always)]
+fn srli_epi8_v<const N: u32>(in_0: Vec) -> Vec {
+    u8x16_shr(in_0, N)
+}
+
+#[inline(always)]
+fn and_v(a: Vec, b: Vec) -> Vec {
+    v128_and(a, b)
+}
+
+#[inline(always)]
+unsafe fn xor_v(a: Vec, b: Vec) -> Vec {
+    v128_xor(a, b)
+}
+
+#[inline(always)]
+fn shuffle_epi8_v(vec: Vec, mask: Vec) -> Vec {
+    // the indices are masked to 0..16, where swizzle matches pshufb
+    u8x16_swizzle(vec, mask)
+}
+
+#[inline(always)]
+#[allow(clippy::cast_ptr_alignment)]
+unsafe fn storeu_v(out: *mut u8, vec: Vec) {
+    v128_store(out.cast::<v128>(), vec);
+}
+
+#[inline(always)]
+unsafe fn gal_mul_v(
+    low_mask_unpacked: Vec,
+    low_vector: Vec,
+    high_vector: Vec,
+    modifier: Option<unsafe fn(_: Vec, _: Vec) -> Vec>,
+    in_x: Vec,
+    old: Vec,
+) -> Vec {
+    let low_input = and_v(in_x, low_mask_unpacked);
+    let in_x_shifted = srli_epi8_v::<4>(in_x);
+    let high_input = and_v(in_x_shifted, low_mask_unpacked);
+    let mul_low_part = shuffle_epi8_v(low_vector, low_input);
+    let mul_high_part = shuffle_epi8_v(high_vector, high_input);
+    let new = v128_xor(mul_low_part, mul_high_part);
+
+    modifier.expect("non-null function pointer")(new, old)
+}
+
+#[inline(always)]
+unsafe fn gal_mul_impl(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+    modifier: Option<unsafe fn(_: Vec, _: Vec) -> Vec>,
+) -> usize {
+    let low_mask_unpacked = set1_epi8_v(0xf_u8);
+    let low_vector = loadu_v(low);
+    let high_vector = loadu_v(high);
+    let mut done = 0;
+
+    let s_v = size_of::<Vec>();
+    for _ in 0..len / s_v {
+        let in_x = loadu_v(in_0.add(done));
+        let old = loadu_v(out.add(done));
+        let result = gal_mul_v(
+            low_mask_unpacked,
+            low_vector,
+            high_vector,
+            modifier,
+            in_x,
+            old,
+        );
+        storeu_v(out.add(done), result);
+        done += s_v;
+    }
+
+    done
+}
+
+#[inline(always)]
+const unsafe fn noop(new: Vec, _old: Vec) -> Vec {
+    new
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "simd128")]
+pub unsafe fn gal_mul(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(
+        low,
+        high,
+        in_0,
+        out,
+        len,
+        Some(noop as unsafe fn(_: Vec, _: Vec) -> Vec),
+    )
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "simd128")]
+pub unsafe fn gal_mul_xor(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(
+        low,
+        high,
+        in_0,
+        out,
+        len,
+        Some(xor_v as unsafe fn(_: Vec, _: Vec) -> Vec),
+    )
+}
@@ -0,0 +1,77 @@
+use std::arch::s390x::{vec_perm, vec_splats, vec_sr, vec_xor, vector_unsigned_char};
+use std::mem::transmute;
+use std::ptr::{read_unaligned, write_unaligned};
+
+type Vec = vector_unsigned_char;
+
+#[inline(always)]
+unsafe fn loadu_v(in_0: *const u8) -> Vec {
+    transmute(read_unaligned(in_0.cast::<[u8; 16]>()))
+}
+
+#[inline(always)]
+unsafe fn storeu_v(out: *mut u8, vec: Vec) {
+    write_unaligned(out.cast::<[u8; 16]>(), transmute::<Vec, [u8; 16]>(vec));
+}
+
+// `vperm` looks up each byte in the 32 bytes of its two table operands
+// by the low 5 bits. With the same 16-byte table in both, the fifth bit
+// does not matter, so the low nibble needs no masking, and the high one
+// is shifted down.
+#[inline(always)]
+unsafe fn gal_mul_impl(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+    add: bool,
+) -> usize {
+    let low_vector = loadu_v(low);
+    let high_vector = loadu_v(high);
+    let shift = vec_splats(4_u8);
+    let s_v = std::mem::size_of::<Vec>();
+
+    let mut done = 0;
+    while done + s_v <= len {
+        let in_x = loadu_v(in_0.add(done));
+        let mul_low_part = vec_perm(low_vector, low_vector, in_x);
+        let mul_high_part = vec_perm(high_vector, high_vector, vec_sr(in_x, shift));
+        let mut result = vec_xor(mul_low_part, mul_high_part);
+        if add {
+            result = vec_xor(result, loadu_v(out.add(done)));
+        }
+        storeu_v(out.add(done), result);
+        done += s_v;
+    }
+
+    done
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "vector")]
+pub unsafe fn gal_mul(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(low, high, in_0, out, len, false)
+}
+
+/// # Safety
+///
+///
+#[target_feature(enable = "vector")]
+pub unsafe fn gal_mul_xor(
+    low: *const u8,
+    high: *const u8,
+    in_0: *const u8,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    gal_mul_impl(low, high, in_0, out, len, true)
+}
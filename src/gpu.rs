@@ -0,0 +1,385 @@
+//! Encoding and reconstruction as compute shaders on the GPU, enabled by
+//! the `gpu` feature, for archival coding of large objects, where the CPU
+//! is held back by memory bandwidth.
+//!
+//! Moving the shards to the GPU and back takes time of its own, which only
+//! pays off for large stripes, so `GpuCodec` codes stripes smaller than
+//! `GpuCodec::min_stripe_bytes` on the CPU with the codec it wraps.
+//!
+//! The GPU is driven with `wgpu`, which picks Vulkan, Metal, DirectX 12 or
+//! OpenGL, whichever the platform has. The calls block until the GPU is
+//! done, so this module is not meant for the web.
+
+use std::sync::mpsc;
+
+use wgpu::util::DeviceExt;
+
+use crate::galois_8::{Field, ReedSolomon, EXP_TABLE, LOG_TABLE};
+use crate::offload::{self, Offload};
+use crate::{Error, ReconstructShard};
+
+/// The default of `GpuCodec::min_stripe_bytes`.
+pub const DEFAULT_MIN_STRIPE_BYTES: usize = 64 * 1024 * 1024;
+
+// The invocations of a workgroup, each computing one word of an output.
+const WORKGROUP_SIZE: usize = 64;
+
+// Each invocation computes the word `id.x` of the output `id.y`, four
+// bytes at a time, multiplying by the logarithm and exponent tables.
+const SHADER: &str = r"
+struct Params {
+    inputs: u32,
+    outputs: u32,
+    words: u32,
+    padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> log_table: array<u32>;
+@group(0) @binding(2) var<storage, read> exp_table: array<u32>;
+@group(0) @binding(3) var<storage, read> coefficients: array<u32>;
+@group(0) @binding(4) var<storage, read> input: array<u32>;
+@group(0) @binding(5) var<storage, read_write> output: array<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let word = id.x;
+    let row = id.y;
+    if (word >= params.words || row >= params.outputs) {
+        return;
+    }
+
+    var result = 0u;
+    for (var i = 0u; i < params.inputs; i++) {
+        let log_c = coefficients[row * params.inputs + i];
+        if (log_c == 0xffffffffu) {
+            continue;
+        }
+        let x = input[i * params.words + word];
+        for (var shift = 0u; shift < 32u; shift += 8u) {
+            let byte = (x >> shift) & 0xffu;
+            if (byte != 0u) {
+                result ^= exp_table[log_c + log_table[byte]] << shift;
+            }
+        }
+    }
+    output[row * params.words + word] = result;
+}
+";
+
+// Writes `bytes` to `buffer` at `offset`, padding them with zeros to whole
+// words, as the GPU only copies those.
+fn write_padded(queue: &wgpu::Queue, buffer: &wgpu::Buffer, offset: u64, bytes: &[u8]) {
+    let aligned = bytes.len() / 4 * 4;
+    if aligned > 0 {
+        queue.write_buffer(buffer, offset, &bytes[..aligned]);
+    }
+    if aligned < bytes.len() {
+        let mut tail = [0; 4];
+        tail[..bytes.len() - aligned].copy_from_slice(&bytes[aligned..]);
+        queue.write_buffer(buffer, offset + aligned as u64, &tail);
+    }
+}
+
+fn words_as_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+/// A codec over GF(2^8) coding large stripes on the GPU and all others on
+/// the CPU.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// # fn main () {
+/// use reed_solomon_erasure::galois_8::ReedSolomon;
+/// use reed_solomon_erasure::gpu::GpuCodec;
+///
+/// let codec = ReedSolomon::new(3, 2).unwrap();
+/// // fall back to the CPU if there is no GPU
+/// let codec = match GpuCodec::new(codec.clone()) {
+///     Some(gpu) => gpu.min_stripe_bytes(0),
+///     None => return,
+/// };
+///
+/// let mut shards = shards!([0, 1, 2], [3, 4, 5], [6, 7, 8], [0, 0, 0], [0, 0, 0]);
+/// codec.encode(&mut shards).unwrap();
+///
+/// let mut expect = shards!([0, 1, 2], [3, 4, 5], [6, 7, 8], [0, 0, 0], [0, 0, 0]);
+/// codec.codec().encode(&mut expect).unwrap();
+/// assert_eq!(expect, shards);
+/// # }
+/// ```
+pub struct GpuCodec {
+    codec: ReedSolomon,
+    min_stripe_bytes: usize,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    log_table: wgpu::Buffer,
+    exp_table: wgpu::Buffer,
+}
+
+impl GpuCodec {
+    /// Wraps `codec`, coding on the fastest GPU there is.
+    ///
+    /// Returns `None` if there is no GPU, or it cannot be opened.
+    pub fn new(codec: ReedSolomon) -> Option<Self> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .ok()?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("reed-solomon-erasure"),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }))
+        .ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        let log_table = LOG_TABLE.iter().map(|&x| u32::from(x)).collect::<Vec<_>>();
+        let exp_table = EXP_TABLE.iter().map(|&x| u32::from(x)).collect::<Vec<_>>();
+        let log_table = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: &words_as_bytes(&log_table),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let exp_table = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: &words_as_bytes(&exp_table),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        Some(Self {
+            codec,
+            min_stripe_bytes: DEFAULT_MIN_STRIPE_BYTES,
+            device,
+            queue,
+            pipeline,
+            log_table,
+            exp_table,
+        })
+    }
+
+    /// Sets the size of the smallest stripe, i.e. of all its shards, coded
+    /// on the GPU, `DEFAULT_MIN_STRIPE_BYTES` by default.
+    ///
+    /// 0 codes every stripe on the GPU, and `usize::MAX` none of them.
+    #[must_use]
+    pub const fn min_stripe_bytes(mut self, bytes: usize) -> Self {
+        self.min_stripe_bytes = bytes;
+        self
+    }
+
+    /// Returns the codec coding on the CPU.
+    pub const fn codec(&self) -> &ReedSolomon {
+        &self.codec
+    }
+
+    /// Returns the name of the GPU.
+    pub fn gpu_name(&self) -> String {
+        self.device.adapter_info().name
+    }
+
+    /// Constructs the parity shards like `ReedSolomon::encode`.
+    ///
+    /// If the GPU fails, e.g. is lost or runs out of memory, the stripe is
+    /// coded on the CPU instead.
+    pub fn encode<T, U>(&self, mut shards: T) -> Result<(), Error>
+    where
+        T: AsRef<[U]> + AsMut<[U]>,
+        U: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        offload::encode(&self.codec, self.min_stripe_bytes, self, shards.as_mut())
+    }
+
+    /// Constructs the parity shards like `ReedSolomon::encode_sep`.
+    ///
+    /// If the GPU fails, e.g. is lost or runs out of memory, the stripe is
+    /// coded on the CPU instead.
+    pub fn encode_sep<T: AsRef<[u8]>, U: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        data: &[T],
+        parity: &mut [U],
+    ) -> Result<(), Error> {
+        offload::encode_sep(&self.codec, self.min_stripe_bytes, self, data, parity)
+    }
+
+    /// Reconstructs all shards like `ReedSolomon::reconstruct`.
+    ///
+    /// On the GPU, the missing data and parity shards are computed in one
+    /// pass over the present ones.
+    ///
+    /// If the GPU fails, e.g. is lost or runs out of memory, the stripe is
+    /// coded on the CPU instead.
+    pub fn reconstruct<T: ReconstructShard<Field>>(&self, shards: &mut [T]) -> Result<(), Error> {
+        offload::reconstruct(&self.codec, self.min_stripe_bytes, self, shards, false)
+    }
+
+    /// Reconstructs only the data shards like
+    /// `ReedSolomon::reconstruct_data`.
+    ///
+    /// If the GPU fails, e.g. is lost or runs out of memory, the stripe is
+    /// coded on the CPU instead.
+    pub fn reconstruct_data<T: ReconstructShard<Field>>(
+        &self,
+        shards: &mut [T],
+    ) -> Result<(), Error> {
+        offload::reconstruct(&self.codec, self.min_stripe_bytes, self, shards, true)
+    }
+
+    // Codes in as many passes as the buffer limits of the GPU call for.
+    //
+    // Returns `None` if the GPU failed to run a pass or to map its output.
+    fn try_code(
+        &self,
+        rows: &[Vec<u8>],
+        inputs: &[&[u8]],
+        outputs: &mut [&mut [u8]],
+    ) -> Option<()> {
+        let shard_len = inputs[0].len();
+        let limits = self.device.limits();
+        let widest = inputs.len().max(outputs.len());
+        let max_words = shard_len
+            .div_ceil(4)
+            .min(limits.max_compute_workgroups_per_dimension as usize * WORKGROUP_SIZE)
+            .min(limits.max_storage_buffer_binding_size as usize / 4 / widest)
+            .min(limits.max_buffer_size as usize / 4 / widest);
+
+        let coefficients = offload::log_coefficients(rows);
+        let coefficients = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: &words_as_bytes(&coefficients),
+                usage: wgpu::BufferUsages::STORAGE,
+            });
+        let buffer = |words: usize, usage| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: (words * 4) as u64,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let params = buffer(
+            4,
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        );
+        let input = buffer(
+            inputs.len() * max_words,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        let output = buffer(
+            outputs.len() * max_words,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        );
+        let read = buffer(
+            outputs.len() * max_words,
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        );
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                &params,
+                &self.log_table,
+                &self.exp_table,
+                &coefficients,
+                &input,
+                &output,
+            ]
+            .iter()
+            .enumerate()
+            .map(|(i, buffer)| wgpu::BindGroupEntry {
+                binding: i as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>(),
+        });
+
+        let mut offset = 0;
+        while offset < shard_len {
+            let len = (shard_len - offset).min(max_words * 4);
+            let words = len.div_ceil(4);
+
+            // the inputs and outputs are packed at the stride of this pass
+            for (i, shard) in inputs.iter().enumerate() {
+                let at = (i * words * 4) as u64;
+                write_padded(&self.queue, &input, at, &shard[offset..offset + len]);
+            }
+            let counts = [inputs.len(), outputs.len(), words, 0].map(|x| x as u32);
+            self.queue
+                .write_buffer(&params, 0, &words_as_bytes(&counts));
+
+            let out_bytes = (outputs.len() * words * 4) as u64;
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch_workgroups(
+                    words.div_ceil(WORKGROUP_SIZE) as u32,
+                    outputs.len() as u32,
+                    1,
+                );
+            }
+            encoder.copy_buffer_to_buffer(&output, 0, &read, 0, out_bytes);
+            self.queue.submit(Some(encoder.finish()));
+
+            let slice = read.slice(..out_bytes);
+            let (sender, receiver) = mpsc::channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            self.device.poll(wgpu::PollType::wait_indefinitely()).ok()?;
+            receiver.recv().ok()?.ok()?;
+            {
+                let view = slice.get_mapped_range().expect("buffer is mapped; qed");
+                for (j, shard) in outputs.iter_mut().enumerate() {
+                    let at = j * words * 4;
+                    shard[offset..offset + len].copy_from_slice(&view[at..at + len]);
+                }
+            }
+            read.unmap();
+
+            offset += len;
+        }
+
+        Some(())
+    }
+}
+
+impl Offload for GpuCodec {
+    fn code(&self, rows: &[Vec<u8>], inputs: &[&[u8]], outputs: &mut [&mut [u8]]) -> bool {
+        // The errors of the device would otherwise go to its uncaptured
+        // error handler, which panics.
+        let out_of_memory = self.device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        let validation = self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let internal = self.device.push_error_scope(wgpu::ErrorFilter::Internal);
+        let coded = self.try_code(rows, inputs, outputs).is_some();
+
+        // the scopes are popped in the reverse order of their creation
+        let errors = [internal.pop(), validation.pop(), out_of_memory.pop()]
+            .map(|error| pollster::block_on(error).is_some());
+        coded && !errors.contains(&true)
+    }
+}
@@ -1,11 +1,19 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::sync::Mutex;
+use std::sync::RwLock;
 
+use crate::core::InversionCachePolicy;
 use crate::matrix::Matrix;
 use crate::Field;
 
-const DEFAULT_INDICES_LIMIT: usize = 254;
+pub const DEFAULT_INDICES_LIMIT: usize = 254;
+
+// The largest total shard count whose entries are kept in a flat map
+// keyed by a bitmap of the invalid indices rather than in the tree.
+const FLAT_MAX_SHARDS: usize = 64;
 
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum Error {
@@ -15,44 +23,136 @@ pub enum Error {
 
 #[derive(Debug)]
 pub struct InversionTree<F: Field> {
-    pub root: Mutex<InversionNode<F>>,
+    // Lookups only take the lock for reading, so that concurrent
+    // reconstructions hitting the cache do not wait for each other.
+    pub root: RwLock<InversionNode<F>>,
+    // Replaces the tree below the root for codecs of up to
+    // `FLAT_MAX_SHARDS` shards, so a lookup is a single hash rather than
+    // a walk down the tree.
+    flat: Option<RwLock<HashMap<u64, FlatEntry<F>>>>,
+    data_shards: usize,
     total_shards: usize,
     total_indices: AtomicUsize,
     indices_limit: usize,
+    policy: InversionCachePolicy,
+    total_bytes: AtomicUsize,
+    total_entries: AtomicUsize,
+    clock: AtomicU64,
 }
 
 #[derive(Debug)]
 pub struct InversionNode<F: Field> {
     pub matrix: Option<Arc<Matrix<F>>>,
     pub children: Vec<Option<InversionNode<F>>>,
-    pub used: u64,
+    pub used: AtomicU64,
+    /// The tick of the clock of the tree at the last use of the matrix.
+    pub last_used: AtomicU64,
+}
+
+#[derive(Debug)]
+struct FlatEntry<F: Field> {
+    matrix: Arc<Matrix<F>>,
+    used: AtomicU64,
+    last_used: AtomicU64,
+}
+
+fn bitmap(invalid_indices: &[usize]) -> u64 {
+    invalid_indices.iter().fold(0, |bits, &i| bits | 1 << i)
 }
 
 impl<F: Field> InversionTree<F> {
+    // Always keeps the entries in the tree, see `new_flat`.
+    #[cfg(test)]
     pub fn new(data_shards: usize, parity_shards: usize) -> Self {
+        let mut tree = Self::new_flat(data_shards, parity_shards);
+        tree.flat = None;
+        tree
+    }
+
+    #[cfg(test)]
+    pub fn new_flat(data_shards: usize, parity_shards: usize) -> Self {
+        Self::with_policy(
+            data_shards,
+            parity_shards,
+            DEFAULT_INDICES_LIMIT,
+            InversionCachePolicy::LeastUsed,
+        )
+    }
+
+    pub fn with_policy(
+        data_shards: usize,
+        parity_shards: usize,
+        indices_limit: usize,
+        policy: InversionCachePolicy,
+    ) -> Self {
         Self {
-            root: Mutex::new(InversionNode::new(
+            root: RwLock::new(InversionNode::new(
                 Some(Arc::new(Matrix::identity(data_shards))),
                 data_shards + parity_shards,
             )),
+            flat: (data_shards + parity_shards <= FLAT_MAX_SHARDS)
+                .then(|| RwLock::new(HashMap::new())),
+            data_shards,
             total_shards: data_shards + parity_shards,
             total_indices: AtomicUsize::new(0),
-            indices_limit: DEFAULT_INDICES_LIMIT,
+            indices_limit,
+            policy,
+            total_bytes: AtomicUsize::new(0),
+            total_entries: AtomicUsize::new(0),
+            clock: AtomicU64::new(0),
         }
     }
 
+    pub const fn indices_limit(&self) -> usize {
+        self.indices_limit
+    }
+
+    pub const fn policy(&self) -> InversionCachePolicy {
+        self.policy
+    }
+
+    /// Approximate memory used by a matrix cached `depth` levels below
+    /// the root, counting the nodes on its path, which are shared
+    /// with other entries but freed along with it when it is evicted.
+    const fn entry_bytes(&self, depth: usize) -> usize {
+        let matrix = self.data_shards * self.data_shards * mem::size_of::<F::Elem>();
+        let node = mem::size_of::<InversionNode<F>>()
+            + self.total_shards * mem::size_of::<Option<InversionNode<F>>>();
+        matrix + depth * node
+    }
+
+    /// Approximate memory used by a matrix cached in the flat map.
+    const fn flat_entry_bytes(&self) -> usize {
+        let matrix = self.data_shards * self.data_shards * mem::size_of::<F::Elem>();
+        matrix + mem::size_of::<(u64, FlatEntry<F>)>() + mem::size_of::<Matrix<F>>()
+    }
+
     pub fn get_inverted_matrix(&self, invalid_indices: &[usize]) -> Option<Arc<Matrix<F>>> {
         if invalid_indices.is_empty() {
-            match self.root.lock().unwrap().matrix {
+            match self.root.read().unwrap().matrix {
                 None => panic!(),
                 Some(ref x) => return Some(Arc::clone(x)),
             }
         }
 
+        if self.policy == InversionCachePolicy::Disabled {
+            return None;
+        }
+
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        if let Some(flat) = &self.flat {
+            let key = bitmap(invalid_indices);
+            return flat.read().unwrap().get(&key).map(|entry| {
+                entry.used.fetch_add(1, Ordering::Relaxed);
+                entry.last_used.store(tick, Ordering::Relaxed);
+                Arc::clone(&entry.matrix)
+            });
+        }
+
         self.root
-            .lock()
+            .read()
             .unwrap()
-            .get_inverted_matrix(invalid_indices, self.total_shards, 0)
+            .get_inverted_matrix(invalid_indices, 0, tick)
     }
 
     pub fn insert_inverted_matrix(
@@ -70,6 +170,22 @@ impl<F: Field> InversionTree<F> {
             return Err(Error::NotSquare);
         }
 
+        if self.policy == InversionCachePolicy::Disabled {
+            return Ok(());
+        }
+        if let Some(flat) = &self.flat {
+            self.insert_flat(&mut flat.write().unwrap(), invalid_indices, matrix);
+            return Ok(());
+        }
+        if let InversionCachePolicy::Weighted { budget_bytes } = self.policy {
+            self.insert_weighted(invalid_indices, matrix, budget_bytes);
+            return Ok(());
+        }
+        if let InversionCachePolicy::LeastRecentlyUsed { max_entries } = self.policy {
+            self.insert_lru(invalid_indices, matrix, max_entries);
+            return Ok(());
+        }
+
         // https://github.com/darrenldl/reed-solomon-erasure/issues/74
         // partial solution from https://github.com/near/nearcore/pull/2317
         // suggested eviction policy: LRU
@@ -77,7 +193,7 @@ impl<F: Field> InversionTree<F> {
         total_indices += invalid_indices.len();
 
         if total_indices >= self.indices_limit {
-            self.root.lock().unwrap().evict(invalid_indices.len());
+            self.root.write().unwrap().evict(invalid_indices.len());
         } else {
             self.total_indices.store(total_indices, Ordering::Relaxed);
         }
@@ -86,15 +202,167 @@ impl<F: Field> InversionTree<F> {
         // Recursively create nodes for the inverted matrix in the tree until
         // we reach the node to insert the matrix to.  We start by passing in
         // 0 as the parent index as we start at the root of the tree.
-        self.root.lock().unwrap().insert_inverted_matrix(
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        self.root.write().unwrap().insert_inverted_matrix(
             matrix,
             invalid_indices,
             self.total_shards,
             0,
+            tick,
         );
 
         Ok(())
     }
+
+    // Evicts the entries with the fewest uses per byte until the matrix
+    // fits within the budget, so a single wide entry can displace several
+    // small ones but only if it is used at least as often per byte.
+    fn insert_weighted(&self, invalid_indices: &[usize], matrix: &Arc<Matrix<F>>, budget: usize) {
+        let size = self.entry_bytes(invalid_indices.len());
+        if size > budget {
+            return;
+        }
+
+        let mut root = self.root.write().unwrap();
+        let mut total_bytes = self.total_bytes.load(Ordering::Relaxed);
+        while total_bytes + size > budget {
+            if let Some(freed) = root.evict_lightest(&|depth| self.entry_bytes(depth)) {
+                total_bytes = total_bytes.saturating_sub(freed);
+            } else {
+                // nothing is left to account for
+                total_bytes = 0;
+                break;
+            }
+        }
+
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        self.total_bytes.store(total_bytes, Ordering::Relaxed);
+        // replacing an entry takes no more memory
+        if root.insert_inverted_matrix(matrix, invalid_indices, self.total_shards, 0, tick) {
+            self.total_bytes.fetch_add(size, Ordering::Relaxed);
+        }
+    }
+
+    // Evicts the least recently used entries, wherever they are in the
+    // tree, until at most `max_entries` are left.
+    fn insert_lru(&self, invalid_indices: &[usize], matrix: &Arc<Matrix<F>>, max_entries: usize) {
+        if max_entries == 0 {
+            return;
+        }
+
+        let mut root = self.root.write().unwrap();
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        let mut total_entries = self.total_entries.load(Ordering::Relaxed);
+        if root.insert_inverted_matrix(matrix, invalid_indices, self.total_shards, 0, tick) {
+            total_entries += 1;
+        }
+        while total_entries > max_entries && root.evict_oldest() {
+            total_entries -= 1;
+        }
+        self.total_entries.store(total_entries, Ordering::Relaxed);
+        drop(root);
+    }
+
+    // Applies the policy of the cache like the tree does, except that
+    // every entry of the flat map has the same size.
+    fn insert_flat(
+        &self,
+        flat: &mut HashMap<u64, FlatEntry<F>>,
+        invalid_indices: &[usize],
+        matrix: &Arc<Matrix<F>>,
+    ) {
+        let key = bitmap(invalid_indices);
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        if let Some(entry) = flat.get_mut(&key) {
+            entry.matrix = Arc::clone(matrix);
+            *entry.last_used.get_mut() = tick;
+            return;
+        }
+
+        let max_entries = match self.policy {
+            InversionCachePolicy::LeastUsed => {
+                // the limit is on the invalid indices keying the entries
+                let key_len = |key: &u64| key.count_ones() as usize;
+                let mut total_indices: usize = flat.keys().map(key_len).sum();
+                while total_indices + invalid_indices.len() > self.indices_limit {
+                    match evict_flat(flat, |entry| entry.used.load(Ordering::Relaxed)) {
+                        Some(key) => total_indices -= key_len(&key),
+                        None => break,
+                    }
+                }
+                usize::MAX
+            }
+            InversionCachePolicy::Weighted { budget_bytes } => {
+                budget_bytes / self.flat_entry_bytes()
+            }
+            InversionCachePolicy::LeastRecentlyUsed { max_entries } => max_entries,
+            InversionCachePolicy::Disabled => 0,
+        };
+        if max_entries == 0 {
+            return;
+        }
+        while flat.len() >= max_entries {
+            let score = |entry: &FlatEntry<F>| match self.policy {
+                InversionCachePolicy::LeastRecentlyUsed { .. } => {
+                    entry.last_used.load(Ordering::Relaxed)
+                }
+                _ => entry.used.load(Ordering::Relaxed),
+            };
+            if evict_flat(flat, score).is_none() {
+                break;
+            }
+        }
+
+        flat.insert(
+            key,
+            FlatEntry {
+                matrix: Arc::clone(matrix),
+                used: AtomicU64::new(0),
+                last_used: AtomicU64::new(tick),
+            },
+        );
+    }
+
+    /// Returns the invalid indices keying each cached matrix,
+    /// excluding the root which holds the identity matrix.
+    pub fn cached_indices(&self) -> Vec<Vec<usize>> {
+        if let Some(flat) = &self.flat {
+            // in the order of a walk down the tree
+            let mut keys: Vec<Vec<usize>> = flat
+                .read()
+                .unwrap()
+                .keys()
+                .map(|&key| {
+                    (0..self.total_shards)
+                        .filter(|&i| key >> i & 1 == 1)
+                        .collect()
+                })
+                .collect();
+            keys.sort_unstable();
+            return keys;
+        }
+
+        let mut keys = Vec::new();
+        let mut invalid_indices = Vec::new();
+
+        self.root
+            .read()
+            .unwrap()
+            .collect_indices(0, &mut invalid_indices, &mut keys);
+
+        keys
+    }
+}
+
+// Removes the entry with the lowest score, returning its key, or `None` if
+// there are no entries left.
+fn evict_flat<F: Field>(
+    flat: &mut HashMap<u64, FlatEntry<F>>,
+    score: impl Fn(&FlatEntry<F>) -> u64,
+) -> Option<u64> {
+    let (&key, _) = flat.iter().min_by_key(|(_, entry)| score(entry))?;
+    flat.remove(&key);
+    Some(key)
 }
 
 fn get_petals<F: Field>(node: &mut Option<InversionNode<F>>) -> Vec<&mut Option<InversionNode<F>>> {
@@ -114,6 +382,11 @@ fn get_petals<F: Field>(node: &mut Option<InversionNode<F>>) -> Vec<&mut Option<
     petals
 }
 
+// Compares uses per byte without rounding.
+fn cmp_score((a_used, a_bytes): (u64, usize), (b_used, b_bytes): (u64, usize)) -> CmpOrdering {
+    (u128::from(a_used) * b_bytes as u128).cmp(&(u128::from(b_used) * a_bytes as u128))
+}
+
 impl<F: Field> InversionNode<F> {
     pub fn new(matrix: Option<Arc<Matrix<F>>>, children_count: usize) -> Self {
         let mut children = Vec::with_capacity(children_count);
@@ -123,7 +396,8 @@ impl<F: Field> InversionNode<F> {
         Self {
             matrix,
             children,
-            used: 0,
+            used: AtomicU64::new(0),
+            last_used: AtomicU64::new(0),
         }
     }
 
@@ -143,7 +417,7 @@ impl<F: Field> InversionNode<F> {
                 Some(_) => match self.children.get_mut(node_index) {
                     None | Some(None) => panic!(),
                     Some(Some(ref mut x)) => {
-                        x.used += 1;
+                        *x.used.get_mut() += 1;
                     }
                 },
             }
@@ -154,31 +428,40 @@ impl<F: Field> InversionNode<F> {
         }
     }
 
+    // Unlike insertions, lookups do not create the nodes on their path,
+    // so they only need shared access to the tree.
     pub fn get_inverted_matrix(
-        &mut self,
+        &self,
         invalid_indices: &[usize],
-        total_shards: usize,
         offset: usize,
+        tick: u64,
     ) -> Option<Arc<Matrix<F>>> {
-        if invalid_indices.is_empty() {
-            self.matrix.as_ref().map(Arc::clone)
-        } else {
-            let requested_index = invalid_indices[0];
-            let remaining_indices = &invalid_indices[1..];
-            self.get_child(offset, requested_index, total_shards)
-                .get_inverted_matrix(remaining_indices, total_shards, requested_index + 1)
+        match invalid_indices.split_first() {
+            None => {
+                self.last_used.store(tick, Ordering::Relaxed);
+                self.matrix.as_ref().map(Arc::clone)
+            }
+            Some((&requested_index, remaining_indices)) => {
+                let child = self.children[requested_index - offset].as_ref()?;
+                child.used.fetch_add(1, Ordering::Relaxed);
+                child.get_inverted_matrix(remaining_indices, requested_index + 1, tick)
+            }
         }
     }
 
+    /// Returns whether the matrix is a new entry rather than replacing
+    /// one.
     pub fn insert_inverted_matrix(
         &mut self,
         matrix: &Arc<Matrix<F>>,
         invalid_indices: &[usize],
         total_shards: usize,
         offset: usize,
-    ) {
+        tick: u64,
+    ) -> bool {
         if invalid_indices.is_empty() {
-            self.matrix = Some(Arc::clone(matrix));
+            *self.last_used.get_mut() = tick;
+            self.matrix.replace(Arc::clone(matrix)).is_none()
         } else {
             let requested_index = invalid_indices[0];
             let remaining_indices = &invalid_indices[1..];
@@ -188,7 +471,133 @@ impl<F: Field> InversionNode<F> {
                     remaining_indices,
                     total_shards,
                     requested_index + 1,
-                );
+                    tick,
+                )
+        }
+    }
+
+    fn collect_indices(
+        &self,
+        offset: usize,
+        invalid_indices: &mut Vec<usize>,
+        keys: &mut Vec<Vec<usize>>,
+    ) {
+        for (i, child) in self.children.iter().enumerate() {
+            if let Some(child) = child {
+                invalid_indices.push(offset + i);
+                if child.matrix.is_some() {
+                    keys.push(invalid_indices.clone());
+                }
+                child.collect_indices(offset + i + 1, invalid_indices, keys);
+                invalid_indices.pop();
+            }
+        }
+    }
+
+    // Finds the path to the leaf with the fewest uses per byte, as
+    // `(uses, bytes)`. Leaves without a matrix, left behind by evictions
+    // of the entries below them, score zero and go first.
+    fn find_lightest(
+        &self,
+        depth: usize,
+        entry_bytes: &dyn Fn(usize) -> usize,
+        path: &mut Vec<usize>,
+        lightest: &mut Option<((u64, usize), Vec<usize>)>,
+    ) {
+        for (i, child) in self.children.iter().enumerate() {
+            if let Some(child) = child {
+                path.push(i);
+                if child.children.iter().all(Option::is_none) {
+                    let score = if child.matrix.is_some() {
+                        (
+                            child.used.load(Ordering::Relaxed) + 1,
+                            entry_bytes(depth + 1),
+                        )
+                    } else {
+                        (0, 1)
+                    };
+                    let lighter = lightest
+                        .as_ref()
+                        .is_none_or(|(x, _)| cmp_score(score, *x) == CmpOrdering::Less);
+                    if lighter {
+                        *lightest = Some((score, path.clone()));
+                    }
+                } else {
+                    child.find_lightest(depth + 1, entry_bytes, path, lightest);
+                }
+                path.pop();
+            }
+        }
+    }
+
+    /// Removes the leaf with the fewest uses per byte, returning
+    /// the bytes freed, or `None` if there are no leaves left.
+    pub fn evict_lightest(&mut self, entry_bytes: &dyn Fn(usize) -> usize) -> Option<usize> {
+        let mut lightest = None;
+        self.find_lightest(0, entry_bytes, &mut Vec::new(), &mut lightest);
+        let (_, path) = lightest?;
+
+        let mut node = self;
+        for &i in &path[..path.len() - 1] {
+            node = node.children[i].as_mut().unwrap();
+        }
+        let leaf = node.children[path[path.len() - 1]].take().unwrap();
+
+        Some(if leaf.matrix.is_some() {
+            entry_bytes(path.len())
+        } else {
+            0
+        })
+    }
+
+    // Finds the path to the node whose matrix was used least recently,
+    // as `(last_used, path)`.
+    fn find_oldest(&self, path: &mut Vec<usize>, oldest: &mut Option<(u64, Vec<usize>)>) {
+        for (i, child) in self.children.iter().enumerate() {
+            if let Some(child) = child {
+                path.push(i);
+                let child_last_used = child.last_used.load(Ordering::Relaxed);
+                if child.matrix.is_some()
+                    && oldest
+                        .as_ref()
+                        .is_none_or(|(last_used, _)| child_last_used < *last_used)
+                {
+                    *oldest = Some((child_last_used, path.clone()));
+                }
+                child.find_oldest(path, oldest);
+                path.pop();
+            }
+        }
+    }
+
+    // Removes the matrix at `path`, and the nodes left without a matrix
+    // or children on the way back up. Returns whether this node is left
+    // empty.
+    fn remove_entry(&mut self, path: &[usize]) -> bool {
+        match path.split_first() {
+            None => self.matrix = None,
+            Some((&i, rest)) => {
+                let child = self.children[i].as_mut().unwrap();
+                if child.remove_entry(rest) {
+                    self.children[i] = None;
+                }
+            }
+        }
+
+        self.matrix.is_none() && self.children.iter().all(Option::is_none)
+    }
+
+    /// Removes the least recently used matrix below this node, returning
+    /// `false` if there is none.
+    pub fn evict_oldest(&mut self) -> bool {
+        let mut oldest = None;
+        self.find_oldest(&mut Vec::new(), &mut oldest);
+        match oldest {
+            Some((_, path)) => {
+                self.remove_entry(&path);
+                true
+            }
+            None => false,
         }
     }
 
@@ -205,11 +614,11 @@ impl<F: Field> InversionNode<F> {
             petals.sort_by(|a, b| {
                 let a_used = match a {
                     None => 0,
-                    Some(aa) => aa.used,
+                    Some(aa) => aa.used.load(Ordering::Relaxed),
                 };
                 let b_used = match b {
                     None => 0,
-                    Some(bb) => bb.used,
+                    Some(bb) => bb.used.load(Ordering::Relaxed),
                 };
                 a_used.cmp(&b_used)
             });
@@ -251,7 +660,7 @@ mod tests {
     fn test_new_inversion_tree() {
         let tree: InversionTree<galois_8::Field> = InversionTree::new(3, 2);
 
-        let children = tree.root.lock().unwrap().children.len();
+        let children = tree.root.read().unwrap().children.len();
         assert_eq!(5, children);
 
         let expect = matrix!([1, 0, 0], [0, 1, 0], [0, 0, 1]);
@@ -274,6 +683,11 @@ mod tests {
         let matrix = tree.get_inverted_matrix(&[1, 2]);
         assert_eq!(None, matrix);
 
+        // lookups leave no nodes behind
+        let root = tree.root.read().unwrap();
+        assert!(root.children.iter().all(Option::is_none));
+        drop(root);
+
         let matrix = Matrix::new(3, 3);
         let matrix_copy = matrix.clone();
         tree.insert_inverted_matrix(&[1], &Arc::new(matrix))
@@ -372,6 +786,152 @@ mod tests {
         assert_eq!(matrix3_copy, *result);
     }
 
+    #[test]
+    fn test_weighted_eviction() {
+        let budget = |tree: &InversionTree<galois_8::Field>| 2 * tree.entry_bytes(1);
+        let mut tree: InversionTree<galois_8::Field> = InversionTree::new(3, 2);
+        tree.policy = InversionCachePolicy::Weighted {
+            budget_bytes: budget(&tree),
+        };
+
+        let matrix = Arc::new(Matrix::make_random(3));
+        tree.insert_inverted_matrix(&[0], &matrix).unwrap();
+        tree.insert_inverted_matrix(&[1], &matrix).unwrap();
+        for _ in 0..3 {
+            tree.get_inverted_matrix(&[0]).unwrap();
+        }
+
+        // the least used entry makes room
+        tree.insert_inverted_matrix(&[2], &matrix).unwrap();
+        assert_eq!(vec![vec![0], vec![2]], tree.cached_indices());
+
+        // deeper entries cost more, so with equal uses they go first
+        let mut tree: InversionTree<galois_8::Field> = InversionTree::new(3, 2);
+        tree.policy = InversionCachePolicy::Weighted {
+            budget_bytes: tree.entry_bytes(2) + tree.entry_bytes(1),
+        };
+        tree.insert_inverted_matrix(&[0, 1], &matrix).unwrap();
+        tree.insert_inverted_matrix(&[2], &matrix).unwrap();
+        tree.insert_inverted_matrix(&[3], &matrix).unwrap();
+        assert_eq!(vec![vec![2], vec![3]], tree.cached_indices());
+
+        // entries over the budget are never cached
+        let mut tree: InversionTree<galois_8::Field> = InversionTree::new(3, 2);
+        tree.policy = InversionCachePolicy::Weighted {
+            budget_bytes: tree.entry_bytes(1) - 1,
+        };
+        tree.insert_inverted_matrix(&[0], &matrix).unwrap();
+        assert!(tree.cached_indices().is_empty());
+    }
+
+    #[test]
+    fn test_weighted_replace() {
+        let mut tree: InversionTree<galois_8::Field> = InversionTree::new(3, 2);
+        let size = tree.entry_bytes(1);
+        tree.policy = InversionCachePolicy::Weighted {
+            budget_bytes: 2 * size,
+        };
+
+        let matrix = Arc::new(Matrix::make_random(3));
+        tree.insert_inverted_matrix(&[0], &matrix).unwrap();
+        assert_eq!(size, tree.total_bytes.load(Ordering::Relaxed));
+
+        // replacing an entry is only counted once
+        tree.insert_inverted_matrix(&[0], &matrix).unwrap();
+        assert_eq!(size, tree.total_bytes.load(Ordering::Relaxed));
+        tree.insert_inverted_matrix(&[1], &matrix).unwrap();
+        assert_eq!(2 * size, tree.total_bytes.load(Ordering::Relaxed));
+        assert_eq!(vec![vec![0], vec![1]], tree.cached_indices());
+
+        // a stale total is dropped once the tree is emptied
+        tree.total_bytes.store(10 * size, Ordering::Relaxed);
+        tree.insert_inverted_matrix(&[2], &matrix).unwrap();
+        assert_eq!(size, tree.total_bytes.load(Ordering::Relaxed));
+        assert_eq!(vec![vec![2]], tree.cached_indices());
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut tree: InversionTree<galois_8::Field> = InversionTree::new(3, 2);
+        tree.policy = InversionCachePolicy::LeastRecentlyUsed { max_entries: 2 };
+
+        let matrix = Arc::new(Matrix::make_random(3));
+        tree.insert_inverted_matrix(&[0], &matrix).unwrap();
+        tree.insert_inverted_matrix(&[1], &matrix).unwrap();
+        for _ in 0..3 {
+            tree.get_inverted_matrix(&[1]).unwrap();
+        }
+        tree.get_inverted_matrix(&[0]).unwrap();
+
+        // the least recently used entry goes, however often it was used
+        tree.insert_inverted_matrix(&[2], &matrix).unwrap();
+        assert_eq!(vec![vec![0], vec![2]], tree.cached_indices());
+
+        // replacing an entry does not evict another
+        tree.insert_inverted_matrix(&[0], &matrix).unwrap();
+        assert_eq!(vec![vec![0], vec![2]], tree.cached_indices());
+
+        // entries above others are evicted as well
+        tree.insert_inverted_matrix(&[0, 1], &matrix).unwrap();
+        assert_eq!(vec![vec![0], vec![0, 1]], tree.cached_indices());
+        tree.insert_inverted_matrix(&[3], &matrix).unwrap();
+        assert_eq!(vec![vec![0, 1], vec![3]], tree.cached_indices());
+        tree.get_inverted_matrix(&[0, 1]).unwrap();
+        tree.insert_inverted_matrix(&[4], &matrix).unwrap();
+        assert_eq!(vec![vec![0, 1], vec![4]], tree.cached_indices());
+
+        // a limit of zero caches nothing
+        let mut tree: InversionTree<galois_8::Field> = InversionTree::new(3, 2);
+        tree.policy = InversionCachePolicy::LeastRecentlyUsed { max_entries: 0 };
+        tree.insert_inverted_matrix(&[0], &matrix).unwrap();
+        assert!(tree.cached_indices().is_empty());
+    }
+
+    #[test]
+    fn test_flat_eviction() {
+        let matrix = Arc::new(Matrix::make_random(3));
+        let mut tree: InversionTree<galois_8::Field> = InversionTree::new_flat(3, 2);
+        assert!(tree.flat.is_some());
+        tree.policy = InversionCachePolicy::LeastRecentlyUsed { max_entries: 2 };
+
+        tree.insert_inverted_matrix(&[0], &matrix).unwrap();
+        tree.insert_inverted_matrix(&[1, 4], &matrix).unwrap();
+        tree.get_inverted_matrix(&[0]).unwrap();
+        tree.insert_inverted_matrix(&[0, 1], &matrix).unwrap();
+        assert_eq!(vec![vec![0], vec![0, 1]], tree.cached_indices());
+        assert_eq!(None, tree.get_inverted_matrix(&[1, 4]));
+
+        // the least used entries make room for the indices of the new one
+        let mut tree: InversionTree<galois_8::Field> = InversionTree::new_flat(3, 2);
+        tree.indices_limit = 4;
+        tree.insert_inverted_matrix(&[0, 1], &matrix).unwrap();
+        tree.insert_inverted_matrix(&[2], &matrix).unwrap();
+        tree.insert_inverted_matrix(&[3], &matrix).unwrap();
+        tree.get_inverted_matrix(&[0, 1]).unwrap();
+        tree.get_inverted_matrix(&[3]).unwrap();
+        tree.insert_inverted_matrix(&[4], &matrix).unwrap();
+        assert_eq!(vec![vec![0, 1], vec![3], vec![4]], tree.cached_indices());
+
+        let mut tree: InversionTree<galois_8::Field> = InversionTree::new_flat(3, 2);
+        tree.policy = InversionCachePolicy::Weighted {
+            budget_bytes: 2 * tree.flat_entry_bytes(),
+        };
+        tree.insert_inverted_matrix(&[0], &matrix).unwrap();
+        tree.insert_inverted_matrix(&[1], &matrix).unwrap();
+        tree.get_inverted_matrix(&[0]).unwrap();
+        tree.insert_inverted_matrix(&[2], &matrix).unwrap();
+        assert_eq!(vec![vec![0], vec![2]], tree.cached_indices());
+
+        // wider codecs keep the tree
+        let tree: InversionTree<galois_8::Field> = InversionTree::with_policy(
+            60,
+            5,
+            DEFAULT_INDICES_LIMIT,
+            InversionCachePolicy::LeastUsed,
+        );
+        assert!(tree.flat.is_none());
+    }
+
     fn make_random_invalid_indices(data_shards: usize, parity_shards: usize) -> Vec<usize> {
         let mut invalid_count = 0;
         let mut res = Vec::new();
@@ -429,6 +989,11 @@ mod tests {
     fn qc_tree_same_as_hash_map_prop(param: QCTreeTestParam) -> bool {
         let tree: InversionTree<galois_8::Field> =
             InversionTree::new(param.data_shards, param.parity_shards);
+        let flat = InversionTree::new_flat(param.data_shards, param.parity_shards);
+        qc_same_as_hash_map(&tree, &param) && qc_same_as_hash_map(&flat, &param)
+    }
+
+    fn qc_same_as_hash_map(tree: &InversionTree<galois_8::Field>, param: &QCTreeTestParam) -> bool {
         let mut map = HashMap::with_capacity(param.matrix_count);
 
         let mut invalid_indices_set = Vec::with_capacity(param.matrix_count);
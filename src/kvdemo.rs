@@ -0,0 +1,312 @@
+//! A small erasure-coded key-value store over GF(2^8), enabled by the
+//! `kvdemo` feature.
+//!
+//! It is meant as living documentation of how the pieces of this crate fit
+//! together rather than as a production store: each value is encoded as a
+//! stripe of its own, whose shards are written to a `ShardBackend`. A
+//! `StripeManifest` per key records the stripe of the value along with a
+//! CRC-32C checksum of each shard, so `get` can tell lost and corrupted
+//! shards apart from good ones and decode the value from the rest, and
+//! `repair` can rebuild and rewrite them.
+//!
+//! Errors of the codec are returned as errors of kind
+//! `io::ErrorKind::InvalidInput` wrapping the `Error`, see
+//! `From<Error> for io::Error`, e.g. `Error::TooFewShardsPresent` if too
+//! many shards of a value are lost.
+//!
+//! # Example
+//!
+//! ```
+//! use reed_solomon_erasure::galois_8::ReedSolomon;
+//! use reed_solomon_erasure::kvdemo::{KvStore, MemoryBackend, ShardBackend};
+//!
+//! let mut store = KvStore::new(ReedSolomon::new(3, 2).unwrap(), MemoryBackend::default());
+//! store.put(b"greeting", b"hello, world").unwrap();
+//!
+//! // lose two shards of the value
+//! let stripe = store.manifest(b"greeting").unwrap().stripe;
+//! store.backend_mut().delete(stripe, 0).unwrap();
+//! store.backend_mut().delete(stripe, 4).unwrap();
+//!
+//! assert_eq!(Some(b"hello, world".to_vec()), store.get(b"greeting").unwrap());
+//! assert_eq!(vec![0, 4], store.repair(b"greeting").unwrap());
+//! assert!(store.repair(b"greeting").unwrap().is_empty());
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+
+use crate::crc32c::Crc32c;
+use crate::galois_8::ReedSolomon;
+use crate::Error;
+
+/// Storage of whole shards keyed by stripe and shard index,
+/// e.g. a directory per stripe or a client of an object store.
+///
+/// This trait is used by `KvStore`.
+pub trait ShardBackend {
+    /// Stores `bytes` as shard `shard` of stripe `stripe`,
+    /// replacing what was stored there.
+    fn put(&mut self, stripe: u64, shard: usize, bytes: &[u8]) -> io::Result<()>;
+
+    /// Loads shard `shard` of stripe `stripe`.
+    ///
+    /// Returns `None` if the shard is not stored, e.g. because it was lost.
+    fn get(&mut self, stripe: u64, shard: usize) -> io::Result<Option<Vec<u8>>>;
+
+    /// Removes shard `shard` of stripe `stripe`, if it is stored.
+    fn delete(&mut self, stripe: u64, shard: usize) -> io::Result<()>;
+}
+
+/// A `ShardBackend` keeping the shards in memory, e.g. for tests.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct MemoryBackend {
+    shards: HashMap<(u64, usize), Vec<u8>>,
+}
+
+impl MemoryBackend {
+    /// Returns the number of shards stored.
+    pub fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns `true` if no shard is stored.
+    pub fn is_empty(&self) -> bool {
+        self.shards.is_empty()
+    }
+
+    /// Returns shard `shard` of stripe `stripe` for modification,
+    /// e.g. to simulate corruption, or `None` if it is not stored.
+    pub fn shard_mut(&mut self, stripe: u64, shard: usize) -> Option<&mut Vec<u8>> {
+        self.shards.get_mut(&(stripe, shard))
+    }
+}
+
+impl ShardBackend for MemoryBackend {
+    fn put(&mut self, stripe: u64, shard: usize, bytes: &[u8]) -> io::Result<()> {
+        self.shards.insert((stripe, shard), bytes.to_vec());
+        Ok(())
+    }
+
+    fn get(&mut self, stripe: u64, shard: usize) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.shards.get(&(stripe, shard)).cloned())
+    }
+
+    fn delete(&mut self, stripe: u64, shard: usize) -> io::Result<()> {
+        self.shards.remove(&(stripe, shard));
+        Ok(())
+    }
+}
+
+/// Where the value of a key is stored.
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StripeManifest {
+    /// The stripe holding the value.
+    pub stripe: u64,
+    /// The length of the value, which is padded with zeros to fill
+    /// the data shards.
+    pub value_len: usize,
+    /// The length of the shards of the stripe.
+    pub shard_len: usize,
+    /// The CRC-32C checksum of each shard of the stripe.
+    pub checksums: Vec<u32>,
+}
+
+/// An erasure-coded key-value store, see the module documentation.
+#[derive(Debug)]
+pub struct KvStore<B> {
+    codec: ReedSolomon,
+    backend: B,
+    manifests: BTreeMap<Vec<u8>, StripeManifest>,
+    next_stripe: u64,
+}
+
+fn checksum(shard: &[u8]) -> u32 {
+    let mut crc = Crc32c::new();
+    crc.update(shard);
+    crc.value()
+}
+
+impl<B: ShardBackend> KvStore<B> {
+    /// Creates an empty store encoding values with `codec`
+    /// into shards stored in `backend`.
+    pub const fn new(codec: ReedSolomon, backend: B) -> Self {
+        Self {
+            codec,
+            backend,
+            manifests: BTreeMap::new(),
+            next_stripe: 0,
+        }
+    }
+
+    pub const fn codec(&self) -> &ReedSolomon {
+        &self.codec
+    }
+
+    pub const fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Returns the backend, e.g. to simulate the loss of shards.
+    pub const fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Returns the manifest of the value of `key`, or `None` if there is none.
+    pub fn manifest(&self, key: &[u8]) -> Option<&StripeManifest> {
+        self.manifests.get(key)
+    }
+
+    /// Returns the keys holding values, in ascending order.
+    pub fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.manifests.keys().map(Vec::as_slice)
+    }
+
+    /// Stores `value` under `key`, replacing the previous value if any.
+    ///
+    /// The value is encoded into a new stripe, and the stripe of the
+    /// previous value is only deleted once all of the new shards are
+    /// stored, so a failed `put` leaves the previous value in place.
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> io::Result<()> {
+        let data_shards = self.codec.data_shard_count();
+        let shard_len = value.len().div_ceil(data_shards).max(1);
+
+        let mut shards = vec![vec![0; shard_len]; self.codec.total_shard_count()];
+        for (shard, bytes) in shards.iter_mut().zip(value.chunks(shard_len)) {
+            shard[..bytes.len()].copy_from_slice(bytes);
+        }
+        self.codec.encode(&mut shards)?;
+
+        let stripe = self.next_stripe;
+        self.next_stripe += 1;
+        for (i, shard) in shards.iter().enumerate() {
+            self.backend.put(stripe, i, shard)?;
+        }
+
+        let manifest = StripeManifest {
+            stripe,
+            value_len: value.len(),
+            shard_len,
+            checksums: shards.iter().map(|x| checksum(x)).collect(),
+        };
+        if let Some(old) = self.manifests.insert(key.to_vec(), manifest) {
+            self.delete_stripe(old.stripe)?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads the value of `key`, or `None` if there is none.
+    ///
+    /// Shards which are lost or do not match their checksum are decoded
+    /// from the others, without being rewritten, see `repair`.
+    pub fn get(&mut self, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        let manifest = match self.manifests.get(key) {
+            Some(manifest) => manifest.clone(),
+            None => return Ok(None),
+        };
+
+        let mut shards = self.load(&manifest)?;
+        self.codec.reconstruct_data(&mut shards)?;
+
+        let mut value: Vec<u8> = shards
+            .into_iter()
+            .take(self.codec.data_shard_count())
+            .flat_map(|x| x.expect("data shards are reconstructed; qed"))
+            .collect();
+        value.truncate(manifest.value_len);
+
+        Ok(Some(value))
+    }
+
+    /// Deletes the value of `key`, returning `false` if there was none.
+    pub fn delete(&mut self, key: &[u8]) -> io::Result<bool> {
+        match self.manifests.remove(key) {
+            Some(manifest) => {
+                self.delete_stripe(manifest.stripe)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Rebuilds and rewrites the shards of the value of `key` which are
+    /// lost or do not match their checksum, returning their indices in
+    /// ascending order.
+    ///
+    /// Returns `Error::InvalidIndex` if there is no value for `key`.
+    pub fn repair(&mut self, key: &[u8]) -> io::Result<Vec<usize>> {
+        let manifest = self.manifests.get(key).ok_or(Error::InvalidIndex)?.clone();
+
+        let mut shards = self.load(&manifest)?;
+        let damaged: Vec<usize> = (0..shards.len()).filter(|&i| shards[i].is_none()).collect();
+        if damaged.is_empty() {
+            return Ok(damaged);
+        }
+
+        self.codec.reconstruct(&mut shards)?;
+        for &i in &damaged {
+            let shard = shards[i]
+                .as_ref()
+                .expect("all shards are reconstructed; qed");
+            self.backend.put(manifest.stripe, i, shard)?;
+        }
+
+        Ok(damaged)
+    }
+
+    /// Repairs the values of all keys, see `repair`, returning the keys
+    /// which had damaged shards along with the indices of those shards.
+    ///
+    /// Values which cannot be repaired are skipped, so a single lost
+    /// value does not prevent repairing the others. Their keys are
+    /// returned along with the error.
+    pub fn repair_all(&mut self) -> RepairReport {
+        let keys: Vec<Vec<u8>> = self.manifests.keys().cloned().collect();
+
+        let mut report = RepairReport::default();
+        for key in keys {
+            match self.repair(&key) {
+                Ok(damaged) if damaged.is_empty() => {}
+                Ok(damaged) => report.repaired.push((key, damaged)),
+                Err(e) => report.failed.push((key, e)),
+            }
+        }
+
+        report
+    }
+
+    // Loads the shards of the stripe of `manifest`, as `None` for the ones
+    // which are lost or do not match their checksum.
+    fn load(&mut self, manifest: &StripeManifest) -> io::Result<Vec<Option<Vec<u8>>>> {
+        let mut shards = Vec::with_capacity(self.codec.total_shard_count());
+        for (i, &expected) in manifest.checksums.iter().enumerate() {
+            let shard = self
+                .backend
+                .get(manifest.stripe, i)?
+                .filter(|shard| shard.len() == manifest.shard_len && checksum(shard) == expected);
+            shards.push(shard);
+        }
+
+        Ok(shards)
+    }
+
+    fn delete_stripe(&mut self, stripe: u64) -> io::Result<()> {
+        for i in 0..self.codec.total_shard_count() {
+            self.backend.delete(stripe, i)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The outcome of `KvStore::repair_all`.
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    /// The keys whose values had damaged shards, along with the indices
+    /// of the shards which were rebuilt, in ascending key order.
+    pub repaired: Vec<(Vec<u8>, Vec<usize>)>,
+    /// The keys whose values could not be repaired, along with the error.
+    pub failed: Vec<(Vec<u8>, io::Error)>,
+}
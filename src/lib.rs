@@ -9,13 +9,25 @@
 //! the missing data.
 
 #![cfg_attr(
-    feature = "unstable",
+    all(feature = "unstable", target_arch = "arm"),
+    feature(arm_target_feature)
+)]
+#![cfg_attr(
+    all(feature = "unstable", target_arch = "powerpc64"),
     feature(
-        stdsimd,
-        avx512_target_feature,
-        arm_target_feature
+        powerpc_target_feature,
+        stdarch_powerpc,
+        stdarch_powerpc_feature_detection
     )
 )]
+#![cfg_attr(
+    all(feature = "unstable", target_arch = "s390x"),
+    feature(s390x_target_feature, stdarch_s390x)
+)]
+#![cfg_attr(
+    all(feature = "unstable", target_arch = "loongarch64"),
+    feature(stdarch_loongarch)
+)]
 #![warn(
     clippy::all,
     clippy::pedantic,
@@ -44,27 +56,65 @@
 #[macro_use]
 extern crate quickcheck;
 
+use std::io;
 use std::iter::{self, FromIterator};
 
 #[macro_use]
 mod macros;
 
+mod config;
 mod core;
+mod crc32c;
 mod errors;
+mod executor;
+mod fft;
 mod inversion_tree;
-mod matrix;
 mod platform;
+#[cfg(feature = "shadow")]
+mod shadow;
+#[cfg(feature = "metrics")]
+mod stats;
 
 #[cfg(test)]
 mod tests;
 
+pub mod content;
+pub mod correction;
+#[cfg(feature = "cuda")]
+pub mod cuda;
+pub mod diff;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod format;
+pub mod galois;
 pub mod galois_16;
+pub mod galois_4;
+pub mod galois_64;
 pub mod galois_8;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "kvdemo")]
+pub mod kvdemo;
+pub mod matrix;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod pack;
+pub mod testkit;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 mod galois_8_avx2;
 #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "unstable"))]
 mod galois_8_avx512;
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+mod galois_8_avx512vbmi;
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+mod galois_8_gfni;
+#[cfg(all(target_arch = "loongarch64", feature = "unstable"))]
+mod galois_8_lasx;
+#[cfg(all(target_arch = "loongarch64", feature = "unstable"))]
+mod galois_8_lsx;
 #[cfg(any(
     target_arch = "aarch64",
     all(target_arch = "arm", feature = "unstable")
@@ -72,12 +122,50 @@ mod galois_8_avx512;
 mod galois_8_neon;
 #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 mod galois_8_sse3;
+#[cfg(target_arch = "aarch64")]
+mod galois_8_sve;
+#[cfg(all(target_arch = "powerpc64", feature = "unstable"))]
+mod galois_8_vsx;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod galois_8_wasm;
+#[cfg(all(target_arch = "s390x", feature = "unstable"))]
+mod galois_8_zvector;
+#[cfg(any(feature = "gpu", feature = "cuda"))]
+mod offload;
 
 pub use crate::errors::Error;
 pub use crate::errors::SBSError;
-
+pub use crate::executor::{Executor, ScopedThreads};
+pub use crate::platform::Platform;
+
+pub use crate::config::{FieldKind, KnownField, ReedSolomonConfig};
+
+pub use crate::core::DecodePlan;
+pub use crate::core::FrozenCodec;
+pub use crate::core::IncrementalDecoder;
+pub use crate::core::InversionCachePolicy;
+pub use crate::core::MatrixKind;
+pub use crate::core::MismatchPolicy;
+pub use crate::core::Progress;
+pub use crate::core::ReconstructReport;
+pub use crate::core::ReconstructScratch;
 pub use crate::core::ReedSolomon;
+pub use crate::core::ReedSolomonBuilder;
+pub use crate::core::Resolution;
+pub use crate::core::SbsDone;
+pub use crate::core::SbsFed;
+pub use crate::core::SbsFeeding;
+pub use crate::core::SbsNotStarted;
 pub use crate::core::ShardByShard;
+pub use crate::core::StreamDecoder;
+pub use crate::core::VerifyReport;
+pub use crate::core::WorkPlan;
+pub use crate::core::MAX_CHECKED_SUBMATRICES;
+
+#[cfg(feature = "shadow")]
+pub use crate::shadow::{Divergence, Shadow, ShadowOperation};
+#[cfg(feature = "metrics")]
+pub use crate::stats::Stats;
 
 type Result<T> = std::result::Result<T, std::result::Result<T, Error>>;
 
@@ -99,6 +187,17 @@ pub trait Field: Sized {
     /// Divide a by b. Panics is b is zero.
     fn div(a: Self::Elem, b: Self::Elem) -> Self::Elem;
 
+    /// Divide a by b.
+    ///
+    /// Returns `Error::DivisionByZero` if b is zero.
+    fn checked_div(a: Self::Elem, b: Self::Elem) -> std::result::Result<Self::Elem, Error> {
+        if b == Self::zero() {
+            return Err(Error::DivisionByZero);
+        }
+
+        Ok(Self::div(a, b))
+    }
+
     /// Raise `a` to the n'th power.
     fn exp(a: Self::Elem, n: usize) -> Self::Elem;
 
@@ -110,6 +209,16 @@ pub trait Field: Sized {
 
     fn nth_internal(n: usize) -> Self::Elem;
 
+    /// Returns whether `elem` is an element of the field, for fields whose
+    /// `Elem` holds more values than the field has elements.
+    ///
+    /// The codecs return `Error::InvalidElement` for shards holding other
+    /// values, which the slice functions of such fields may panic on.
+    fn is_elem(elem: Self::Elem) -> bool {
+        let _ = elem;
+        true
+    }
+
     /// Yield the nth element of the field. Panics if n >= ORDER.
     /// Assignment is arbitrary but must be unique to `n`.
     fn nth(n: usize) -> Self::Elem {
@@ -121,6 +230,17 @@ pub trait Field: Sized {
         Self::nth_internal(n)
     }
 
+    /// Yield the nth element of the field, see `nth`.
+    ///
+    /// Returns `Error::InvalidIndex` if n >= ORDER.
+    fn checked_nth(n: usize) -> std::result::Result<Self::Elem, Error> {
+        if n >= Self::ORDER {
+            return Err(Error::InvalidIndex);
+        }
+
+        Ok(Self::nth_internal(n))
+    }
+
     /// Multiply a slice of elements by another. Writes into the output slice.
     ///
     /// # Panics
@@ -145,6 +265,167 @@ pub trait Field: Sized {
             *o = Self::add(*o, Self::mul(elem, *i));
         }
     }
+
+    /// Multiply a slice of elements by another like `mul_slice`, using the
+    /// SIMD kernel of `platform` if the field has any.
+    ///
+    /// # Panics
+    /// Panics if the output slice does not have equal length to the input,
+    /// or if the CPU does not support `platform`.
+    fn mul_slice_on(
+        platform: Platform,
+        elem: Self::Elem,
+        input: &[Self::Elem],
+        out: &mut [Self::Elem],
+    ) {
+        assert!(platform.is_supported(), "{:?} is not supported", platform);
+        Self::mul_slice(elem, input, out);
+    }
+
+    /// Multiply a slice of elements by another, adding each result to the
+    /// corresponding value in `out` like `mul_slice_add`, using the SIMD
+    /// kernel of `platform` if the field has any.
+    ///
+    /// # Panics
+    /// Panics if the output slice does not have equal length to the input,
+    /// or if the CPU does not support `platform`.
+    fn mul_slice_add_on(
+        platform: Platform,
+        elem: Self::Elem,
+        input: &[Self::Elem],
+        out: &mut [Self::Elem],
+    ) {
+        assert!(platform.is_supported(), "{:?} is not supported", platform);
+        Self::mul_slice_add(elem, input, out);
+    }
+
+    /// Multiply a slice of elements by each of `elems`, writing the results
+    /// into the corresponding slices of `outs`, using the SIMD kernel of
+    /// `platform` if the field has any.
+    ///
+    /// This is `mul_slice_on` for each of `elems`, which fields may speed up
+    /// by passing over `input` once for several of them.
+    ///
+    /// # Panics
+    /// Panics if `elems` and `outs` are not of the same length, if an output
+    /// slice does not have equal length to the input, or if the CPU does not
+    /// support `platform`.
+    fn mul_slice_rows_on(
+        platform: Platform,
+        elems: &[Self::Elem],
+        input: &[Self::Elem],
+        outs: &mut [&mut [Self::Elem]],
+    ) {
+        assert_eq!(elems.len(), outs.len());
+
+        for (&elem, out) in elems.iter().zip(outs) {
+            Self::mul_slice_on(platform, elem, input, out);
+        }
+    }
+
+    /// Multiply a slice of elements by each of `elems`, adding the results
+    /// to the corresponding slices of `outs`, see `mul_slice_rows_on`.
+    ///
+    /// # Panics
+    /// Panics if `elems` and `outs` are not of the same length, if an output
+    /// slice does not have equal length to the input, or if the CPU does not
+    /// support `platform`.
+    fn mul_slice_add_rows_on(
+        platform: Platform,
+        elems: &[Self::Elem],
+        input: &[Self::Elem],
+        outs: &mut [&mut [Self::Elem]],
+    ) {
+        assert_eq!(elems.len(), outs.len());
+
+        for (&elem, out) in elems.iter().zip(outs) {
+            Self::mul_slice_add_on(platform, elem, input, out);
+        }
+    }
+
+    /// Multiply a slice of elements by another, see `mul_slice`.
+    ///
+    /// Returns `Error::IncorrectShardSize` if the output slice does not
+    /// have equal length to the input, in which case it is left untouched.
+    fn checked_mul_slice(
+        elem: Self::Elem,
+        input: &[Self::Elem],
+        out: &mut [Self::Elem],
+    ) -> std::result::Result<(), Error> {
+        if input.len() != out.len() {
+            return Err(Error::IncorrectShardSize {
+                shard: 0,
+                expected: input.len(),
+                actual: out.len(),
+            });
+        }
+
+        Self::mul_slice(elem, input, out);
+        Ok(())
+    }
+
+    /// Multiply a slice of elements by another, adding each result to the
+    /// corresponding value in `out`, see `mul_slice_add`.
+    ///
+    /// Returns `Error::IncorrectShardSize` if the output slice does not
+    /// have equal length to the input, in which case it is left untouched.
+    fn checked_mul_slice_add(
+        elem: Self::Elem,
+        input: &[Self::Elem],
+        out: &mut [Self::Elem],
+    ) -> std::result::Result<(), Error> {
+        if input.len() != out.len() {
+            return Err(Error::IncorrectShardSize {
+                shard: 0,
+                expected: input.len(),
+                actual: out.len(),
+            });
+        }
+
+        Self::mul_slice_add(elem, input, out);
+        Ok(())
+    }
+}
+
+/// Something which can read byte ranges of stored shards,
+/// e.g. a client of an object store supporting range requests.
+///
+/// This trait is used in `ReedSolomon::fetch_data_range`.
+pub trait RangeFetch<F: Field> {
+    /// Reads `len` elements of shard `shard` starting at element `offset`.
+    ///
+    /// Returns `None` if the shard cannot be read, in which case another
+    /// shard is read instead if possible.
+    fn fetch(&mut self, shard: usize, offset: usize, len: usize) -> Option<Vec<F::Elem>>;
+}
+
+/// Storage of the shards of stripes supporting reads and writes of ranges,
+/// e.g. a set of block devices or files.
+///
+/// This trait is used in `ReedSolomon::update_range`.
+pub trait RangeStore<F: Field> {
+    /// Returns the length of the shards of stripe `stripe`.
+    fn shard_len(&mut self, stripe: usize) -> io::Result<usize>;
+
+    /// Reads `len` elements of shard `shard` of stripe `stripe`
+    /// starting at element `offset`.
+    fn read(
+        &mut self,
+        stripe: usize,
+        shard: usize,
+        offset: usize,
+        len: usize,
+    ) -> io::Result<Vec<F::Elem>>;
+
+    /// Writes `data` over shard `shard` of stripe `stripe`
+    /// starting at element `offset`.
+    fn write(
+        &mut self,
+        stripe: usize,
+        shard: usize,
+        offset: usize,
+        data: &[F::Elem],
+    ) -> io::Result<()>;
 }
 
 /// Something which might hold a shard.
@@ -165,6 +446,9 @@ pub trait ReconstructShard<F: Field> {
 
     /// Get a mutable reference to the shard data, initializing it to the
     /// given length if it was `None`. Returns an error if initialization fails.
+    ///
+    /// The shard index of an `Error::IncorrectShardSize` returned here is
+    /// filled in by the codec.
     fn get_or_initialize(&mut self, len: usize) -> Result<&mut [F::Elem]>;
 }
 
@@ -219,7 +503,11 @@ impl<F: Field, T: AsRef<[F::Elem]> + AsMut<[F::Elem]>> ReconstructShard<F> for (
                 Err(Ok(x))
             }
         } else {
-            Err(Err(Error::IncorrectShardSize))
+            Err(Err(Error::IncorrectShardSize {
+                shard: 0,
+                expected: len,
+                actual: x.len(),
+            }))
         }
     }
 }
@@ -139,6 +139,48 @@ macro_rules! convert_2D_slices {
     }}
 }
 
+/// Checks shard counts at compile time.
+///
+/// Fails to compile if `ReedSolomon::<$field>::new($data_shards, $parity_shards)`
+/// would return an error, so a geometry configured in constants is
+/// rejected at build time rather than at codec construction.
+///
+/// # Example
+/// ```rust
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// use reed_solomon_erasure::galois_8;
+///
+/// const DATA_SHARDS: usize = 10;
+/// const PARITY_SHARDS: usize = 4;
+///
+/// const_assert_geometry!(DATA_SHARDS, PARITY_SHARDS, galois_8::Field);
+/// # fn main () {}
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate reed_solomon_erasure;
+/// use reed_solomon_erasure::galois_8;
+///
+/// const_assert_geometry!(200, 100, galois_8::Field);
+/// # fn main () {}
+/// ```
+#[macro_export]
+macro_rules! const_assert_geometry {
+    (
+        $data_shards:expr, $parity_shards:expr, $field:ty
+    ) => {
+        const _: () = {
+            assert!($data_shards > 0, "too few data shards");
+            assert!($parity_shards > 0, "too few parity shards");
+            let total_shards: usize = $data_shards + $parity_shards;
+            assert!(
+                total_shards <= <$field as $crate::Field>::ORDER,
+                "too many shards"
+            );
+        };
+    };
+}
+
 macro_rules! check_slices {
     (
         multi => $slices:expr
@@ -147,25 +189,49 @@ macro_rules! check_slices {
         if size == 0 {
             return Err(Error::EmptyShard);
         }
-        for slice in $slices.iter() {
+        for (i, slice) in $slices.iter().enumerate() {
             if slice.as_ref().len() != size {
-                return Err(Error::IncorrectShardSize);
+                return Err(Error::IncorrectShardSize {
+                    shard: i,
+                    expected: size,
+                    actual: slice.as_ref().len(),
+                });
+            }
+            if !all_elems::<F>(slice.as_ref()) {
+                return Err(Error::InvalidElement);
             }
         }
     }};
     (
         single => $slice_left:expr, single => $slice_right:expr
+    ) => {{
+        check_slices!(single => $slice_left, single => $slice_right, index => 0);
+    }};
+    (
+        single => $slice_left:expr, single => $slice_right:expr, index => $index:expr
     ) => {{
         if $slice_left.as_ref().len() != $slice_right.as_ref().len() {
-            return Err(Error::IncorrectShardSize);
+            return Err(Error::IncorrectShardSize {
+                shard: $index,
+                expected: $slice_left.as_ref().len(),
+                actual: $slice_right.as_ref().len(),
+            });
         }
     }};
     (
         multi => $slices:expr, single => $single:expr
+    ) => {{
+        check_slices!(multi => $slices, single => $single, index => 0);
+    }};
+    (
+        multi => $slices:expr, single => $single:expr, index => $index:expr
     ) => {{
         check_slices!(multi => $slices);
 
-        check_slices!(single => $slices[0], single => $single);
+        check_slices!(single => $slices[0], single => $single, index => $index);
+        if !all_elems::<F>($single.as_ref()) {
+            return Err(Error::InvalidElement);
+        }
     }};
     (
         multi => $slices_left:expr, multi => $slices_right:expr
@@ -209,7 +275,9 @@ macro_rules! check_piece_count {
             return Err(Error::TooFewShards);
         }
         if $pieces.as_ref().len() > $codec.total_shard_count {
-            return Err(Error::TooManyShards);
+            return Err(Error::TooManyShards {
+                max: $codec.total_shard_count,
+            });
         }
     }};
     (
@@ -1,12 +1,49 @@
-#![allow(dead_code)]
+//! Dense matrices over a `Field`, as used to build the encoding and
+//! decode matrices of `ReedSolomon`, for experimenting with codes on top
+//! of the fields of this crate.
+//!
+//! # Example
+//!
+//! ```
+//! use reed_solomon_erasure::galois_8::Field;
+//! use reed_solomon_erasure::matrix::Matrix;
+//!
+//! // the systematic encoding matrix of a code with 3 data and 2 parity shards
+//! let vandermonde: Matrix<Field> = Matrix::vandermonde(5, 3);
+//! let top = vandermonde.sub_matrix(0, 0, 3, 3);
+//! let encoding = vandermonde.multiply(&top.invert().unwrap());
+//! assert_eq!(Matrix::identity(3), encoding.sub_matrix(0, 0, 3, 3));
+//!
+//! // any 3 of its rows can be inverted to decode
+//! let rows: Matrix<Field> = Matrix::new_with_data(vec![
+//!     encoding.get_row(1).to_vec(),
+//!     encoding.get_row(3).to_vec(),
+//!     encoding.get_row(4).to_vec(),
+//! ]);
+//! let decode = rows.invert().unwrap();
+//! assert_eq!(Matrix::identity(3), decode.multiply(&rows));
+//! ```
+
 use crate::Field;
 use smallvec::SmallVec;
 
-#[derive(Debug)]
+/// The error of `Matrix::invert` and `Matrix::gaussian_elim`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum Error {
+    /// The matrix is not invertible.
     SingularMatrix,
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::SingularMatrix => write!(f, "The matrix is singular"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 macro_rules! acc {
     (
         $m:ident, $r:expr, $c:expr
@@ -15,7 +52,7 @@ macro_rules! acc {
     };
 }
 
-pub fn flatten<T>(m: Vec<Vec<T>>) -> Vec<T> {
+fn flatten<T>(m: Vec<Vec<T>>) -> Vec<T> {
     let mut result: Vec<T> = Vec::with_capacity(m.len() * m[0].len());
     for row in m {
         for v in row {
@@ -25,6 +62,7 @@ pub fn flatten<T>(m: Vec<Vec<T>>) -> Vec<T> {
     result
 }
 
+/// A matrix of elements of `F`, stored row by row.
 #[derive(PartialEq, Debug, Clone)]
 pub struct Matrix<F: Field> {
     row_count: usize,
@@ -45,6 +83,7 @@ impl<F: Field> Matrix<F> {
         calc_matrix_row_start_end(self.col_count, row)
     }
 
+    /// Creates a `rows` by `cols` matrix of zeros.
     pub fn new(rows: usize, cols: usize) -> Self {
         let data = SmallVec::from_vec(vec![F::zero(); rows * cols]);
 
@@ -55,6 +94,11 @@ impl<F: Field> Matrix<F> {
         }
     }
 
+    /// Creates a matrix out of its rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no rows or the rows differ in length.
     pub fn new_with_data(init_data: Vec<Vec<F::Elem>>) -> Self {
         let rows = init_data.len();
         let cols = init_data[0].len();
@@ -85,6 +129,7 @@ impl<F: Field> Matrix<F> {
         Self::new_with_data(vec)
     }
 
+    /// Creates the `size` by `size` identity matrix.
     pub fn identity(size: usize) -> Self {
         let mut result = Self::new(size, size);
         for i in 0..size {
@@ -93,22 +138,33 @@ impl<F: Field> Matrix<F> {
         result
     }
 
+    /// Returns the number of columns.
     pub fn col_count(&self) -> usize {
         self.col_count
     }
 
+    /// Returns the number of rows.
     pub fn row_count(&self) -> usize {
         self.row_count
     }
 
+    /// Returns the element at row `r` and column `c`.
     pub fn get(&self, r: usize, c: usize) -> F::Elem {
         acc!(self, r, c)
     }
 
+    /// Sets the element at row `r` and column `c` to `val`.
     pub fn set(&mut self, r: usize, c: usize, val: F::Elem) {
         acc!(self, r, c) = val;
     }
 
+    /// Returns the product `self * rhs`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the column count of `self` differs from the row count
+    /// of `rhs`.
+    #[must_use]
     pub fn multiply(&self, rhs: &Self) -> Self {
         assert!(
             !(self.col_count != rhs.row_count),
@@ -131,6 +187,12 @@ impl<F: Field> Matrix<F> {
         result
     }
 
+    /// Returns `self` with the columns of `rhs` appended on the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the row counts differ.
+    #[must_use]
     pub fn augment(&self, rhs: &Self) -> Self {
         assert!(
             !(self.row_count != rhs.row_count),
@@ -152,6 +214,8 @@ impl<F: Field> Matrix<F> {
         result
     }
 
+    /// Returns the rows `rmin..rmax` of the columns `c_min..c_max`.
+    #[must_use]
     pub fn sub_matrix(&self, rmin: usize, c_min: usize, rmax: usize, c_max: usize) -> Self {
         let mut result = Self::new(rmax - rmin, c_max - c_min);
         for r in rmin..rmax {
@@ -162,12 +226,14 @@ impl<F: Field> Matrix<F> {
         result
     }
 
+    /// Returns the elements of row `row`.
     pub fn get_row(&self, row: usize) -> &[F::Elem] {
         let (start, end) = self.calc_row_start_end(row);
 
         &self.data[start..end]
     }
 
+    /// Swaps the rows `r1` and `r2`.
     pub fn swap_rows(&mut self, r1: usize, r2: usize) {
         let (r1_s, _) = self.calc_row_start_end(r1);
         let (r2_s, _) = self.calc_row_start_end(r2);
@@ -180,10 +246,16 @@ impl<F: Field> Matrix<F> {
         }
     }
 
+    /// Returns `true` if the matrix has as many rows as columns.
     pub fn is_square(&self) -> bool {
         self.row_count == self.col_count
     }
 
+    /// Reduces the matrix in place to reduced row echelon form by Gauss-Jordan
+    /// elimination, turning its leftmost square part into the identity.
+    ///
+    /// Returns `Error::SingularMatrix` if that part is singular, in which
+    /// case the matrix is left partially reduced.
     pub fn gaussian_elim(&mut self) -> Result<(), Error> {
         for r in 0..self.row_count {
             if acc!(self, r, r) == F::zero() {
@@ -201,9 +273,7 @@ impl<F: Field> Matrix<F> {
             // Scale to 1.
             if acc!(self, r, r) != F::one() {
                 let scale = F::div(F::one(), acc!(self, r, r));
-                for c in 0..self.col_count {
-                    acc!(self, r, c) = F::mul(scale, acc!(self, r, c));
-                }
+                self.scale_row(r, scale);
             }
             // Make everything below the 1 be a 0 by subtracting
             // a multiple of it.  (Subtraction and addition are
@@ -211,10 +281,7 @@ impl<F: Field> Matrix<F> {
             for r_below in r + 1..self.row_count {
                 if acc!(self, r_below, r) != F::zero() {
                     let scale = acc!(self, r_below, r);
-                    for c in 0..self.col_count {
-                        acc!(self, r_below, c) =
-                            F::add(acc!(self, r_below, c), F::mul(scale, acc!(self, r, c)));
-                    }
+                    self.add_scaled_row(r, r_below, scale);
                 }
             }
         }
@@ -224,16 +291,53 @@ impl<F: Field> Matrix<F> {
             for r_above in 0..d {
                 if acc!(self, r_above, d) != F::zero() {
                     let scale = acc!(self, r_above, d);
-                    for c in 0..self.col_count {
-                        acc!(self, r_above, c) =
-                            F::add(acc!(self, r_above, c), F::mul(scale, acc!(self, d, c)));
-                    }
+                    self.add_scaled_row(d, r_above, scale);
                 }
             }
         }
         Ok(())
     }
 
+    // Multiplies row `r` by `scale` with the kernels of the field, from
+    // column `r` on as the ones before are zero during elimination.
+    fn scale_row(&mut self, r: usize, scale: F::Elem) {
+        let (start, end) = self.calc_row_start_end(r);
+        let row = &mut self.data[start + r..end];
+        let input: SmallVec<[F::Elem; 256]> = SmallVec::from_slice(row);
+        F::mul_slice(scale, &input, row);
+    }
+
+    // Adds `scale` times row `src` to row `dst` with the kernels of the
+    // field, from column `src` on as the ones before are zero in `src`
+    // during elimination.
+    fn add_scaled_row(&mut self, src: usize, dst: usize, scale: F::Elem) {
+        let (src_start, src_end) = self.calc_row_start_end(src);
+        let (dst_start, dst_end) = self.calc_row_start_end(dst);
+        let skip = src;
+        if src < dst {
+            let (head, tail) = self.data.split_at_mut(dst_start);
+            F::mul_slice_add(
+                scale,
+                &head[src_start + skip..src_end],
+                &mut tail[skip..dst_end - dst_start],
+            );
+        } else {
+            let (head, tail) = self.data.split_at_mut(src_start);
+            F::mul_slice_add(
+                scale,
+                &tail[skip..src_end - src_start],
+                &mut head[dst_start + skip..dst_end],
+            );
+        }
+    }
+
+    /// Returns the inverse of the matrix.
+    ///
+    /// Returns `Error::SingularMatrix` if the matrix is singular.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the matrix is not square.
     pub fn invert(&self) -> Result<Self, Error> {
         assert!(self.is_square(), "Trying to invert a non-square matrix");
 
@@ -246,6 +350,9 @@ impl<F: Field> Matrix<F> {
         Ok(work.sub_matrix(0, row_count, col_count, col_count * 2))
     }
 
+    /// Creates a `rows` by `cols` Vandermonde matrix, whose row `r` holds the
+    /// powers `0..cols` of the `r`th element of the field, see `Field::nth`.
+    /// Any `cols` of its rows are linearly independent.
     pub fn vandermonde(rows: usize, cols: usize) -> Self {
         let mut result = Self::new(rows, cols);
 
@@ -260,12 +367,33 @@ impl<F: Field> Matrix<F> {
 
         result
     }
+
+    /// Identity matrix on top of a Cauchy matrix, with the rows below
+    /// the identity defined by `1 / (x_r + y_c)`.
+    pub fn cauchy(rows: usize, cols: usize) -> Self {
+        let mut result = Self::new(rows, cols);
+
+        for i in 0..cols {
+            acc!(result, i, i) = F::one();
+        }
+
+        for r in cols..rows {
+            // `x_r` and `y_c` are distinct since `r != c`,
+            // so every square submatrix is invertible.
+            let x_r = F::nth(r);
+            for c in 0..cols {
+                acc!(result, r, c) = F::div(F::one(), F::add(x_r, F::nth(c)));
+            }
+        }
+
+        result
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Matrix;
-    use crate::galois_8;
+    use crate::{galois_16, galois_8};
 
     macro_rules! matrix {
         (
@@ -332,7 +460,7 @@ mod tests {
         let m1 = matrix!([0, 1], [0, 1], [0, 1]);
         let m2 = matrix!([0, 1, 2]);
 
-        m1.multiply(&m2);
+        let _ = m1.multiply(&m2);
     }
 
     #[test]
@@ -341,7 +469,7 @@ mod tests {
         let m1 = matrix!([0, 1]);
         let m2 = matrix!([0, 1], [2, 3]);
 
-        m1.augment(&m2);
+        let _ = m1.augment(&m2);
     }
 
     #[test]
@@ -404,4 +532,14 @@ mod tests {
     fn test_matrix_inverse_singular() {
         matrix!([4, 2], [12, 6]).invert().unwrap();
     }
+
+    #[test]
+    fn test_matrix_inverse_wide() {
+        // long enough rows for the vector kernels, over several fields
+        let m: Matrix<galois_8::Field> = Matrix::vandermonde(200, 100).sub_matrix(50, 0, 150, 100);
+        assert_eq!(Matrix::identity(100), m.multiply(&m.invert().unwrap()));
+
+        let m: Matrix<galois_16::Field> = Matrix::cauchy(300, 100).sub_matrix(150, 0, 250, 100);
+        assert_eq!(Matrix::identity(100), m.invert().unwrap().multiply(&m));
+    }
 }
@@ -0,0 +1,198 @@
+//! Encoding and reconstruction over memory-mapped shard files, enabled by
+//! the `mmap` feature, so that large files need not be read into memory.
+//!
+//! The shard files are mapped for the duration of a call. The files must
+//! not be modified by other processes meanwhile, as the mappings would
+//! change under the codec.
+//!
+//! Errors of the codec are returned as errors of kind
+//! `io::ErrorKind::InvalidInput` wrapping the `Error`, see
+//! `From<Error> for io::Error`, and are always detected before any file
+//! is touched.
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+
+use memmap2::{Mmap, MmapMut, MmapOptions};
+
+use crate::galois_8::ReedSolomon;
+use crate::Error;
+
+// The common length of `files`, which must not be empty, along with their
+// shard indices. Lengths which do not fit into `usize` are reported as
+// `usize::MAX`.
+fn common_len<'a>(files: impl Iterator<Item = (usize, &'a File)>) -> io::Result<usize> {
+    let mut shard_len = None;
+    for (i, file) in files {
+        let len =
+            usize::try_from(file.metadata()?.len()).map_err(|_| Error::IncorrectShardSize {
+                shard: i,
+                expected: shard_len.unwrap_or(0),
+                actual: usize::MAX,
+            })?;
+        if len == 0 {
+            return Err(Error::EmptyShard.into());
+        }
+        if let Some(old_len) = shard_len.filter(|&old_len| len != old_len) {
+            return Err(Error::IncorrectShardSize {
+                shard: i,
+                expected: old_len,
+                actual: len,
+            }
+            .into());
+        }
+        shard_len = Some(len);
+    }
+
+    shard_len.ok_or_else(|| Error::TooFewShardsPresent.into())
+}
+
+/// Constructs the parity shard files `parity` from the data shard files
+/// `data`, like `ReedSolomon::encode_sep`.
+///
+/// The data files are only read. The parity files are resized to the
+/// length of the data files, so they may be empty, and flushed to disk
+/// before the call returns. They have to be opened for reading and writing.
+///
+/// Returns `Error::TooFewDataShards`, `Error::TooManyDataShards`,
+/// `Error::TooFewParityShards` or `Error::TooManyParityShards` if the
+/// number of files does not match the codec, `Error::EmptyShard` if the
+/// data files are empty, and `Error::IncorrectShardSize` if they are not
+/// all of the same length.
+pub fn encode_files(codec: &ReedSolomon, data: &[File], parity: &[File]) -> io::Result<()> {
+    if data.len() < codec.data_shard_count() {
+        return Err(Error::TooFewDataShards.into());
+    }
+    if data.len() > codec.data_shard_count() {
+        return Err(Error::TooManyDataShards.into());
+    }
+    if parity.len() < codec.parity_shard_count() {
+        return Err(Error::TooFewParityShards.into());
+    }
+    if parity.len() > codec.parity_shard_count() {
+        return Err(Error::TooManyParityShards.into());
+    }
+
+    let shard_len = common_len(data.iter().enumerate())?;
+
+    // the files are not modified elsewhere during the call, see above
+    let data_maps = data
+        .iter()
+        .map(|file| unsafe { Mmap::map(file) })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut parity_maps = Vec::with_capacity(parity.len());
+    for file in parity {
+        file.set_len(shard_len as u64)?;
+        parity_maps.push(unsafe { MmapMut::map_mut(file) }?);
+    }
+
+    codec.encode_sep(&data_maps, &mut parity_maps)?;
+
+    for map in &parity_maps {
+        map.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Reconstructs the shard files which are not `present`, like
+/// `ReedSolomon::reconstruct`.
+///
+/// The present files are only read. The missing files are resized to the
+/// length of the present files, so they may be empty, and flushed to disk
+/// before the call returns. They have to be opened for reading and writing.
+///
+/// Returns `Error::TooFewShards` or `Error::TooManyShards` if the number
+/// of files does not match the codec, `Error::InvalidShardFlags` if the
+/// number of flags does not match the number of files, and the errors of
+/// `ReedSolomon::reconstruct` for the present files otherwise.
+pub fn reconstruct_files(codec: &ReedSolomon, shards: &[File], present: &[bool]) -> io::Result<()> {
+    reconstruct_files_internal(codec, shards, present, false)
+}
+
+/// Reconstructs the data shard files which are not `present`, like
+/// `ReedSolomon::reconstruct_data`, see `reconstruct_files`.
+///
+/// The missing parity files are left untouched.
+pub fn reconstruct_data_files(
+    codec: &ReedSolomon,
+    shards: &[File],
+    present: &[bool],
+) -> io::Result<()> {
+    reconstruct_files_internal(codec, shards, present, true)
+}
+
+fn reconstruct_files_internal(
+    codec: &ReedSolomon,
+    shards: &[File],
+    present: &[bool],
+    data_only: bool,
+) -> io::Result<()> {
+    if shards.len() < codec.total_shard_count() {
+        return Err(Error::TooFewShards.into());
+    }
+    if shards.len() > codec.total_shard_count() {
+        return Err(Error::TooManyShards {
+            max: codec.total_shard_count(),
+        }
+        .into());
+    }
+    if present.len() != shards.len() {
+        return Err(Error::InvalidShardFlags.into());
+    }
+
+    let shard_len = common_len(
+        shards
+            .iter()
+            .enumerate()
+            .zip(present)
+            .filter(|(_, &present)| present)
+            .map(|(file, _)| file),
+    )?;
+    if present.iter().filter(|&&x| x).count() < codec.data_shard_count() {
+        return Err(Error::TooFewShardsPresent.into());
+    }
+
+    let to_rebuild = |i: usize| !present[i] && (!data_only || i < codec.data_shard_count());
+
+    // The present files are mapped copy-on-write, as the codec needs
+    // mutable shards, but never writes to the present ones.
+    let mut maps = Vec::with_capacity(shards.len());
+    for (i, file) in shards.iter().enumerate() {
+        let map = if present[i] {
+            Some(unsafe { MmapOptions::new().map_copy(file) }?)
+        } else if to_rebuild(i) {
+            file.set_len(shard_len as u64)?;
+            Some(unsafe { MmapMut::map_mut(file) }?)
+        } else {
+            None
+        };
+        maps.push(map);
+    }
+
+    let mut slices: Vec<(&mut [u8], bool)> = maps
+        .iter_mut()
+        .zip(present)
+        .map(|(map, &present)| {
+            map.as_mut()
+                .map_or_else(|| (&mut [][..], false), |map| (&mut map[..], present))
+        })
+        .collect();
+
+    let result = if data_only {
+        codec.reconstruct_data(&mut slices)
+    } else {
+        codec.reconstruct(&mut slices)
+    };
+    result?;
+
+    for (i, map) in maps.iter().enumerate() {
+        if let (true, Some(map)) = (to_rebuild(i), map) {
+            map.flush()?;
+        }
+    }
+
+    Ok(())
+}
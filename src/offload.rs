@@ -0,0 +1,190 @@
+// The checks and the fallback to the CPU shared by the backends coding on
+// other devices, see `gpu` and `cuda`, which only multiply matrices.
+
+use crate::galois_8::{Field, ReedSolomon, LOG_TABLE};
+use crate::{Error, ReconstructShard};
+
+// Marks a zero coefficient, which has no logarithm.
+pub const ZERO_LOG: u32 = u32::MAX;
+
+pub trait Offload {
+    // Multiplies `inputs` by the matrix of `rows` into `outputs`, one row
+    // per output, all of the same, non-zero length.
+    //
+    // Returns false if the device failed, e.g. was lost, leaving `outputs`
+    // to be coded on the CPU.
+    fn code(&self, rows: &[Vec<u8>], inputs: &[&[u8]], outputs: &mut [&mut [u8]]) -> bool;
+}
+
+// The logarithms of the coefficients of `rows`, one after the other, which
+// the kernels multiply by.
+pub fn log_coefficients(rows: &[Vec<u8>]) -> Vec<u32> {
+    rows.iter()
+        .flatten()
+        .map(|&c| {
+            if c == 0 {
+                ZERO_LOG
+            } else {
+                u32::from(LOG_TABLE[c as usize])
+            }
+        })
+        .collect()
+}
+
+// The common length of the shards, which must not be empty, along with
+// their shard indices.
+fn common_len(shards: impl Iterator<Item = (usize, usize)>) -> Result<usize, Error> {
+    let mut shard_len = None;
+    for (i, len) in shards {
+        if len == 0 {
+            return Err(Error::EmptyShard);
+        }
+        if let Some(old_len) = shard_len.filter(|&old_len| len != old_len) {
+            return Err(Error::IncorrectShardSize {
+                shard: i,
+                expected: old_len,
+                actual: len,
+            });
+        }
+        shard_len = Some(len);
+    }
+
+    shard_len.ok_or(Error::TooFewShardsPresent)
+}
+
+fn offloaded(codec: &ReedSolomon, min_stripe_bytes: usize, shard_len: usize) -> bool {
+    shard_len.saturating_mul(codec.total_shard_count()) >= min_stripe_bytes
+}
+
+pub fn encode<B: Offload, U: AsRef<[u8]> + AsMut<[u8]>>(
+    codec: &ReedSolomon,
+    min_stripe_bytes: usize,
+    backend: &B,
+    shards: &mut [U],
+) -> Result<(), Error> {
+    if shards.len() < codec.total_shard_count() {
+        return Err(Error::TooFewShards);
+    }
+    if shards.len() > codec.total_shard_count() {
+        return Err(Error::TooManyShards {
+            max: codec.total_shard_count(),
+        });
+    }
+
+    let (data, parity) = shards.split_at_mut(codec.data_shard_count());
+    encode_sep(codec, min_stripe_bytes, backend, data, parity)
+}
+
+pub fn encode_sep<B: Offload, T: AsRef<[u8]>, U: AsRef<[u8]> + AsMut<[u8]>>(
+    codec: &ReedSolomon,
+    min_stripe_bytes: usize,
+    backend: &B,
+    data: &[T],
+    parity: &mut [U],
+) -> Result<(), Error> {
+    if data.len() < codec.data_shard_count() {
+        return Err(Error::TooFewDataShards);
+    }
+    if data.len() > codec.data_shard_count() {
+        return Err(Error::TooManyDataShards);
+    }
+    if parity.len() < codec.parity_shard_count() {
+        return Err(Error::TooFewParityShards);
+    }
+    if parity.len() > codec.parity_shard_count() {
+        return Err(Error::TooManyParityShards);
+    }
+
+    let data_lens = data.iter().map(|shard| shard.as_ref().len());
+    let parity_lens = parity.iter().map(|shard| shard.as_ref().len());
+    let shard_len = common_len(data_lens.chain(parity_lens).enumerate())?;
+    if !offloaded(codec, min_stripe_bytes, shard_len) {
+        return codec.encode_sep(data, parity);
+    }
+
+    let rows = codec.parity_matrix();
+    let inputs: Vec<&[u8]> = data.iter().map(AsRef::as_ref).collect();
+    let mut outputs: Vec<&mut [u8]> = parity.iter_mut().map(AsMut::as_mut).collect();
+    if !backend.code(&rows, &inputs, &mut outputs) {
+        codec.code_rows(&rows, &inputs, &mut outputs);
+    }
+
+    Ok(())
+}
+
+// Reconstructs the missing data and parity shards in one pass over the
+// present ones.
+pub fn reconstruct<B: Offload, T: ReconstructShard<Field>>(
+    codec: &ReedSolomon,
+    min_stripe_bytes: usize,
+    backend: &B,
+    shards: &mut [T],
+    data_only: bool,
+) -> Result<(), Error> {
+    let data_shard_count = codec.data_shard_count();
+    if shards.len() < codec.total_shard_count() {
+        return Err(Error::TooFewShards);
+    }
+    if shards.len() > codec.total_shard_count() {
+        return Err(Error::TooManyShards {
+            max: codec.total_shard_count(),
+        });
+    }
+
+    let lens = shards.iter().map(ReconstructShard::len).enumerate();
+    let shard_len = common_len(lens.filter_map(|(i, len)| Some((i, len?))))?;
+    let number_present = shards.iter().filter(|shard| shard.len().is_some()).count();
+    if number_present == shards.len() {
+        return Ok(());
+    }
+    if number_present < data_shard_count {
+        return Err(Error::TooFewShardsPresent);
+    }
+    if !offloaded(codec, min_stripe_bytes, shard_len) {
+        return if data_only {
+            codec.reconstruct_data(shards)
+        } else {
+            codec.reconstruct(shards)
+        };
+    }
+
+    // the rows are found before any missing shard is initialized
+    let present: Vec<bool> = shards.iter().map(|shard| shard.len().is_some()).collect();
+    let valid_indices: Vec<usize> = (0..shards.len())
+        .filter(|&i| present[i])
+        .take(data_shard_count)
+        .collect();
+    let invalid_indices: Vec<usize> = (0..shards.len()).filter(|&i| !present[i]).collect();
+    let rebuilt: Vec<usize> = invalid_indices
+        .iter()
+        .copied()
+        .filter(|&i| !data_only || i < data_shard_count)
+        .collect();
+    let rows = codec.get_rebuild_rows(&valid_indices, &invalid_indices, &rebuilt)?;
+
+    let mut inputs = Vec::with_capacity(data_shard_count);
+    let mut outputs = Vec::with_capacity(rebuilt.len());
+    for (i, shard) in shards.iter_mut().enumerate() {
+        if present[i] {
+            if inputs.len() < data_shard_count {
+                inputs.push(&*shard.get().expect("shard is present; qed"));
+            }
+            continue;
+        }
+
+        if data_only && i >= data_shard_count {
+            continue;
+        }
+        let shard = match shard.get_or_initialize(shard_len) {
+            Ok(shard) | Err(Ok(shard)) => shard,
+            Err(Err(e)) => return Err(e.at_shard(i)),
+        };
+        outputs.push(shard);
+    }
+
+    if !backend.code(&rows, &inputs, &mut outputs) {
+        codec.code_rows(&rows, &inputs, &mut outputs);
+    }
+
+    Ok(())
+}
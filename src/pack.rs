@@ -0,0 +1,445 @@
+//! Packing of many small objects into shared stripes over GF(2^8).
+//!
+//! Encoding each small object as a stripe of its own wastes most of the
+//! space on padding and multiplies the number of shards to store. `Packer`
+//! instead lays the objects out back to back across the data shards of
+//! shared stripes, and records where each object landed in a `Manifest`.
+//! `read_object` then reads a single object back, reading only its own
+//! bytes while they are available, and decoding just the ranges of its
+//! stripes which are lost otherwise.
+//!
+//! `Tiering` moves stripes between a hot tier with more parity shards and
+//! a cold tier with fewer of them as they age, e.g. to keep recently
+//! written objects highly available while aged ones take less space.
+//!
+//! # Example
+//!
+//! ```
+//! use reed_solomon_erasure::galois_8::ReedSolomon;
+//! use reed_solomon_erasure::pack::{self, Packer, StripeFetch};
+//!
+//! struct Store(Vec<Vec<Option<Vec<u8>>>>);
+//!
+//! impl StripeFetch for Store {
+//!     fn fetch(&mut self, stripe: usize, shard: usize, offset: usize, len: usize) -> Option<Vec<u8>> {
+//!         let shard = self.0[stripe][shard].as_ref()?;
+//!         Some(shard[offset..offset + len].to_vec())
+//!     }
+//! }
+//!
+//! let r = ReedSolomon::new(2, 1).unwrap();
+//!
+//! let mut packer = Packer::new(&r, 4).unwrap();
+//! let a = packer.push(b"abc");
+//! let b = packer.push(b"defgh");
+//! let (stripes, manifest) = packer.finish().unwrap();
+//! assert_eq!(1, stripes.len());
+//!
+//! let mut store = Store(
+//!     stripes
+//!         .into_iter()
+//!         .map(|x| x.into_iter().map(Some).collect())
+//!         .collect(),
+//! );
+//! store.0[0][1] = None;
+//!
+//! assert_eq!(b"abc", &pack::read_object(&r, &manifest, a, &mut store).unwrap()[..]);
+//! assert_eq!(b"defgh", &pack::read_object(&r, &manifest, b, &mut store).unwrap()[..]);
+//! ```
+
+use std::time::Duration;
+
+use crate::galois_8::{self, ReedSolomon};
+use crate::{Error, RangeFetch};
+
+/// The shards of a stripe.
+pub type Stripe = Vec<Vec<u8>>;
+
+/// A contiguous part of an object stored in a single data shard.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Extent {
+    /// The index of the stripe.
+    pub stripe: usize,
+    /// The index of the data shard in the stripe.
+    pub shard: usize,
+    /// The offset of the part in the shard.
+    pub offset: usize,
+    /// The length of the part.
+    pub len: usize,
+}
+
+/// Where the objects packed by a `Packer` are stored.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Manifest {
+    /// The length of the shards of the stripes.
+    pub shard_len: usize,
+    /// The number of stripes.
+    pub stripe_count: usize,
+    /// The extents of each object, in the order of the object bytes.
+    /// Empty objects have no extents.
+    pub objects: Vec<Vec<Extent>>,
+    /// The number of parity shards stored for each stripe, which is lower
+    /// for stripes moved to the cold tier of a `Tiering`. Stripes without
+    /// an entry have all the parity shards of the codec.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parity: Vec<usize>,
+}
+
+impl Manifest {
+    /// Returns the length of object `object`, or `None` if there is
+    /// no such object.
+    pub fn object_len(&self, object: usize) -> Option<usize> {
+        self.objects
+            .get(object)
+            .map(|extents| extents.iter().map(|x| x.len).sum())
+    }
+}
+
+/// Packs objects back to back into the data shards of shared stripes.
+///
+/// The objects fill the data shards of a stripe in order, so an object
+/// may be split across shards and stripes, and the last stripe is padded
+/// with zeros.
+#[derive(Debug)]
+pub struct Packer<'a> {
+    codec: &'a ReedSolomon,
+    shard_len: usize,
+    data: Vec<u8>,
+    objects: Vec<Vec<Extent>>,
+}
+
+impl<'a> Packer<'a> {
+    /// Creates a packer of stripes of `codec` with shards of `shard_len` bytes.
+    ///
+    /// Returns `Error::EmptyShard` if `shard_len == 0`.
+    pub const fn new(codec: &'a ReedSolomon, shard_len: usize) -> Result<Self, Error> {
+        if shard_len == 0 {
+            return Err(Error::EmptyShard);
+        }
+
+        Ok(Self {
+            codec,
+            shard_len,
+            data: Vec::new(),
+            objects: Vec::new(),
+        })
+    }
+
+    /// Appends `object`, returning its index in the manifest.
+    pub fn push(&mut self, object: &[u8]) -> usize {
+        let stripe_len = self.codec.data_shard_count() * self.shard_len;
+
+        let mut extents = Vec::new();
+        let mut pos = self.data.len();
+        let end = pos + object.len();
+        while pos < end {
+            let within = pos % stripe_len;
+            let offset = within % self.shard_len;
+            let len = (self.shard_len - offset).min(end - pos);
+            extents.push(Extent {
+                stripe: pos / stripe_len,
+                shard: within / self.shard_len,
+                offset,
+                len,
+            });
+            pos += len;
+        }
+
+        self.data.extend_from_slice(object);
+        self.objects.push(extents);
+        self.objects.len() - 1
+    }
+
+    /// Encodes the stripes holding the objects pushed so far,
+    /// returning them along with their manifest.
+    pub fn finish(self) -> Result<(Vec<Stripe>, Manifest), Error> {
+        let data_shards = self.codec.data_shard_count();
+        let stripe_len = data_shards * self.shard_len;
+
+        let mut stripes = Vec::new();
+        for chunk in self.data.chunks(stripe_len) {
+            let mut shards = vec![vec![0; self.shard_len]; self.codec.total_shard_count()];
+            for (shard, bytes) in shards.iter_mut().zip(chunk.chunks(self.shard_len)) {
+                shard[..bytes.len()].copy_from_slice(bytes);
+            }
+            self.codec.encode(&mut shards)?;
+            stripes.push(shards);
+        }
+
+        let manifest = Manifest {
+            shard_len: self.shard_len,
+            stripe_count: stripes.len(),
+            objects: self.objects,
+            parity: vec![self.codec.parity_shard_count(); stripes.len()],
+        };
+
+        Ok((stripes, manifest))
+    }
+}
+
+/// A source of ranges of the shards of packed stripes,
+/// e.g. a client of an object store supporting range requests.
+///
+/// This trait is used in `read_object`.
+pub trait StripeFetch {
+    /// Reads `len` bytes of shard `shard` of stripe `stripe` starting at `offset`.
+    ///
+    /// Returns `None` if the shard cannot be read.
+    fn fetch(&mut self, stripe: usize, shard: usize, offset: usize, len: usize) -> Option<Vec<u8>>;
+}
+
+struct StripeRanges<'a, R> {
+    fetcher: &'a mut R,
+    stripe: usize,
+}
+
+impl<R: StripeFetch> RangeFetch<galois_8::Field> for StripeRanges<'_, R> {
+    fn fetch(&mut self, shard: usize, offset: usize, len: usize) -> Option<Vec<u8>> {
+        self.fetcher.fetch(self.stripe, shard, offset, len)
+    }
+}
+
+/// Reads object `object` of stripes of `codec` packed as described by
+/// `manifest`.
+///
+/// Each extent of the object is read from its data shard. If that shard
+/// cannot be read, the same range is read from `data_shard_count` other
+/// shards of the stripe and decoded, see `ReedSolomon::fetch_data_range`.
+///
+/// Returns `Error::InvalidIndex` if there is no such object, or if one of
+/// its extents does not lie within a data shard of `codec`.
+///
+/// Returns `Error::IncorrectShardSize` if `fetcher` returns a range that
+/// is not of the requested length.
+///
+/// Parity shards which are not stored for a stripe according to
+/// `manifest.parity` are never read, so stripes of both tiers of a
+/// `Tiering` are read with its hot codec.
+///
+/// Returns `Error::TooFewShardsPresent` if a lost extent cannot be decoded.
+pub fn read_object<R: StripeFetch>(
+    codec: &ReedSolomon,
+    manifest: &Manifest,
+    object: usize,
+    fetcher: &mut R,
+) -> Result<Vec<u8>, Error> {
+    let extents = manifest.objects.get(object).ok_or(Error::InvalidIndex)?;
+
+    let mut bytes = Vec::with_capacity(extents.iter().map(|x| x.len).sum());
+    for extent in extents {
+        if extent.shard >= codec.data_shard_count()
+            || extent.offset + extent.len > manifest.shard_len
+        {
+            return Err(Error::InvalidIndex);
+        }
+
+        match fetcher.fetch(extent.stripe, extent.shard, extent.offset, extent.len) {
+            Some(range) if range.len() == extent.len => bytes.extend_from_slice(&range),
+            Some(range) => {
+                return Err(Error::IncorrectShardSize {
+                    shard: extent.shard,
+                    expected: extent.len,
+                    actual: range.len(),
+                })
+            }
+            None => {
+                let stored = manifest.parity.get(extent.stripe).map_or_else(
+                    || codec.total_shard_count(),
+                    |&parity| codec.data_shard_count() + parity,
+                );
+                let mut available: Vec<bool> =
+                    (0..codec.total_shard_count()).map(|i| i < stored).collect();
+                available[extent.shard] = false;
+
+                let mut ranges = StripeRanges {
+                    fetcher: &mut *fetcher,
+                    stripe: extent.stripe,
+                };
+                let data =
+                    codec.fetch_data_range(&mut ranges, &available, extent.offset, extent.len)?;
+                bytes.extend_from_slice(&data[extent.shard]);
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// The redundancy of a stripe managed by a `Tiering`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Tier {
+    /// All the parity shards of the hot codec are stored.
+    Hot,
+    /// Only the parity shards of the cold codec are stored.
+    Cold,
+}
+
+/// The tiers of a `Tiering`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct TierPolicy {
+    /// The number of parity shards of hot stripes.
+    pub hot_parity: usize,
+    /// The number of parity shards of cold stripes.
+    pub cold_parity: usize,
+    /// The age from which stripes belong to the cold tier.
+    pub cold_after: Duration,
+}
+
+impl TierPolicy {
+    /// Returns the tier of stripes of age `age`.
+    pub fn tier(&self, age: Duration) -> Tier {
+        if age >= self.cold_after {
+            Tier::Cold
+        } else {
+            Tier::Hot
+        }
+    }
+}
+
+/// Moves packed stripes between a hot and a cold tier of parity.
+///
+/// The parity shards of the cold codec are the first parity shards of the
+/// hot codec, see `ReedSolomon::grow_parity`. Moving a stripe to the cold
+/// tier thus only drops its trailing parity shards, and moving it back only
+/// computes those from its data shards, which are never rewritten.
+///
+/// Stripes are packed and read with the hot codec, see `read_object`.
+#[derive(Debug)]
+pub struct Tiering {
+    hot: ReedSolomon,
+    cold: ReedSolomon,
+    policy: TierPolicy,
+}
+
+impl Tiering {
+    /// Creates the tiers of `policy` for stripes of `data_shards` data shards.
+    ///
+    /// Returns `Error::TooFewParityShards` if `policy.hot_parity` is lower
+    /// than `policy.cold_parity`, and the errors of `ReedSolomon::new` if the
+    /// shard counts of either tier are not supported.
+    pub fn new(data_shards: usize, policy: TierPolicy) -> Result<Self, Error> {
+        let cold = ReedSolomon::new(data_shards, policy.cold_parity)?;
+        let hot = cold.grow_parity(policy.hot_parity)?;
+
+        Ok(Self { hot, cold, policy })
+    }
+
+    /// Returns the codec of hot stripes, which packs and reads all stripes.
+    pub const fn hot(&self) -> &ReedSolomon {
+        &self.hot
+    }
+
+    /// Returns the codec of cold stripes.
+    pub const fn cold(&self) -> &ReedSolomon {
+        &self.cold
+    }
+
+    pub const fn policy(&self) -> &TierPolicy {
+        &self.policy
+    }
+
+    /// Returns the tier of stripe `stripe` described by `manifest`, or `None`
+    /// if its parity shard count matches neither tier.
+    pub fn tier_of(&self, manifest: &Manifest, stripe: usize) -> Option<Tier> {
+        let parity = manifest
+            .parity
+            .get(stripe)
+            .copied()
+            .unwrap_or(self.policy.hot_parity);
+
+        if parity == self.policy.hot_parity {
+            Some(Tier::Hot)
+        } else if parity == self.policy.cold_parity {
+            Some(Tier::Cold)
+        } else {
+            None
+        }
+    }
+
+    /// Moves stripe `index` described by `manifest` to tier `tier`, e.g. to
+    /// bring a cold stripe back up on demand, updating `manifest`.
+    ///
+    /// Returns whether the stripe changed tiers.
+    ///
+    /// Returns `Error::InvalidIndex` if `manifest` has no such stripe, and
+    /// `Error::TooFewShards` or `Error::TooManyShards` if the number of
+    /// shards of `stripe` does not match its tier in `manifest`.
+    pub fn set_tier(
+        &self,
+        manifest: &mut Manifest,
+        index: usize,
+        stripe: &mut Stripe,
+        tier: Tier,
+    ) -> Result<bool, Error> {
+        if index >= manifest.stripe_count {
+            return Err(Error::InvalidIndex);
+        }
+        let current = self.tier_of(manifest, index).ok_or(Error::InvalidIndex)?;
+        let expected = match current {
+            Tier::Hot => self.hot.total_shard_count(),
+            Tier::Cold => self.cold.total_shard_count(),
+        };
+        if stripe.len() < expected {
+            return Err(Error::TooFewShards);
+        }
+        if stripe.len() > expected {
+            return Err(Error::TooManyShards { max: expected });
+        }
+
+        match (current, tier) {
+            (Tier::Hot, Tier::Cold) => stripe.truncate(self.cold.total_shard_count()),
+            (Tier::Cold, Tier::Hot) => {
+                let shard_len = stripe[0].len();
+                let mut parity =
+                    vec![vec![0; shard_len]; self.policy.hot_parity - self.policy.cold_parity];
+                let data = &stripe[..self.hot.data_shard_count()];
+                self.hot
+                    .encode_parity_from(self.policy.cold_parity, data, &mut parity)?;
+                stripe.extend(parity);
+            }
+            _ => return Ok(false),
+        }
+
+        if manifest.parity.len() < manifest.stripe_count {
+            manifest
+                .parity
+                .resize(manifest.stripe_count, self.policy.hot_parity);
+        }
+        manifest.parity[index] = match tier {
+            Tier::Hot => self.policy.hot_parity,
+            Tier::Cold => self.policy.cold_parity,
+        };
+
+        Ok(true)
+    }
+
+    /// Moves each of `stripes` described by `manifest` to the tier of its
+    /// age in `ages` under the policy, updating `manifest`.
+    ///
+    /// Returns the indices of the stripes which changed tiers.
+    ///
+    /// Returns `Error::InvalidIndex` if the number of stripes or ages does
+    /// not match `manifest`, and the errors of `set_tier` otherwise. Stripes
+    /// before the failing one are left moved.
+    pub fn transcode(
+        &self,
+        manifest: &mut Manifest,
+        stripes: &mut [Stripe],
+        ages: &[Duration],
+    ) -> Result<Vec<usize>, Error> {
+        if stripes.len() != manifest.stripe_count || ages.len() != manifest.stripe_count {
+            return Err(Error::InvalidIndex);
+        }
+
+        let mut moved = Vec::new();
+        for (i, (stripe, &age)) in stripes.iter_mut().zip(ages).enumerate() {
+            if self.set_tier(manifest, i, stripe, self.policy.tier(age))? {
+                moved.push(i);
+            }
+        }
+
+        Ok(moved)
+    }
+}
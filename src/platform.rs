@@ -5,23 +5,189 @@
     clippy::upper_case_acronyms
 )]
 
-#[derive(Clone, Copy, Debug)]
+use std::time::Instant;
+
+use crate::Field;
+
+// The number of bytes multiplied per round by `Platform::calibrate`.
+const CALIBRATION_BYTES: usize = 16 * 1024;
+
+// The rounds timed per kernel by `Platform::calibrate`, of which the
+// fastest one counts.
+const CALIBRATION_ROUNDS: usize = 8;
+
+/// The kernels multiplying GF(2^8) slices, which GF(2^16) uses as well.
+///
+/// The fastest one the CPU supports is detected at runtime, unless a codec
+/// is built with `ReedSolomonBuilder::platform`. The variants available
+/// depend on the target architecture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Platform {
+    /// Plain Rust, available everywhere.
     Portable,
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     SSE3,
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     AVX2,
+    /// AVX512F, which uses the AVX2 kernel unless the crate is built
+    /// with the `unstable` feature.
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     AVX512,
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    AVX512VBMI,
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    GFNI,
+    /// NEON, which is portable on 32-bit ARM unless the crate is built
+    /// with the `unstable` feature.
     #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
     NEON,
+    /// SVE or SVE2, at any vector length.
+    #[cfg(target_arch = "aarch64")]
+    SVE,
+    /// VSX, which is portable unless the crate is built with the
+    /// `unstable` feature.
+    #[cfg(target_arch = "powerpc64")]
+    VSX,
+    /// The vector facility of z/Architecture, which is portable unless
+    /// the crate is built with the `unstable` feature.
+    #[cfg(target_arch = "s390x")]
+    ZVECTOR,
+    /// LSX, the 128-bit vectors of LoongArch, which is portable unless
+    /// the crate is built with the `unstable` feature.
+    #[cfg(target_arch = "loongarch64")]
+    LSX,
+    /// LASX, the 256-bit vectors of LoongArch, which is portable unless
+    /// the crate is built with the `unstable` feature.
+    #[cfg(target_arch = "loongarch64")]
+    LASX,
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    SIMD128,
 }
 
 impl Platform {
+    /// Returns whether the CPU supports the kernel, going by the same
+    /// detection as `detect`, so the `no_*` features turn kernels off.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Self::Portable => true,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::SSE3 => sse3_detected(),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::AVX2 => avx2_detected(),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::AVX512 => avx512_detected() && avx2_detected(),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::AVX512VBMI => avx512vbmi_detected(),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::GFNI => gfni_detected(),
+            #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+            Self::NEON => neon_detected(),
+            #[cfg(target_arch = "aarch64")]
+            Self::SVE => sve_detected(),
+            #[cfg(target_arch = "powerpc64")]
+            Self::VSX => vsx_detected(),
+            #[cfg(target_arch = "s390x")]
+            Self::ZVECTOR => zvector_detected(),
+            #[cfg(target_arch = "loongarch64")]
+            Self::LSX => lsx_detected(),
+            #[cfg(target_arch = "loongarch64")]
+            Self::LASX => lasx_detected(),
+            #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+            Self::SIMD128 => simd128_detected(),
+        }
+    }
+
+    /// Returns all kernels the CPU supports, `detect` first.
+    pub fn supported() -> Vec<Self> {
+        let mut supported = vec![Self::detect()];
+        for platform in [
+            Self::Portable,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::SSE3,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::AVX2,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::AVX512,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::AVX512VBMI,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::GFNI,
+            #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+            Self::NEON,
+            #[cfg(target_arch = "aarch64")]
+            Self::SVE,
+            #[cfg(target_arch = "powerpc64")]
+            Self::VSX,
+            #[cfg(target_arch = "s390x")]
+            Self::ZVECTOR,
+            #[cfg(target_arch = "loongarch64")]
+            Self::LSX,
+            #[cfg(target_arch = "loongarch64")]
+            Self::LASX,
+            #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+            Self::SIMD128,
+        ] {
+            if platform.is_supported() && !supported.contains(&platform) {
+                supported.push(platform);
+            }
+        }
+
+        supported
+    }
+
+    /// Returns the kernel which multiplies slices over `F` the fastest on
+    /// this CPU, measured with a microbenchmark of a few milliseconds.
+    ///
+    /// `detect` goes by the instruction set extensions instead, which gets
+    /// it wrong on CPUs where e.g. AVX-512 lowers the clock rate so much
+    /// that AVX2 is faster. Ties go to `detect`.
+    ///
+    /// Returns `detect` on targets without a clock, i.e.
+    /// `wasm32-unknown-unknown`.
+    pub fn calibrate<F: Field>() -> Self {
+        if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+            return Self::detect();
+        }
+
+        // the elements take up the same bytes whatever their size
+        let len = (CALIBRATION_BYTES / std::mem::size_of::<F::Elem>()).max(1);
+        let distinct = F::ORDER.min(256);
+        let input: Vec<F::Elem> = (0..len).map(|i| F::nth(i % distinct)).collect();
+        let mut out = vec![F::zero(); len];
+        let c = F::nth(distinct - 1);
+
+        let mut fastest = (Self::detect(), u128::MAX);
+        for platform in Self::supported() {
+            // the first round warms up the caches
+            F::mul_slice_add_on(platform, c, &input, &mut out);
+
+            let elapsed = (0..CALIBRATION_ROUNDS)
+                .map(|_| {
+                    let start = Instant::now();
+                    F::mul_slice_add_on(platform, c, &input, &mut out);
+                    start.elapsed().as_nanos()
+                })
+                .min()
+                .unwrap_or(u128::MAX);
+            if elapsed < fastest.1 {
+                fastest = (platform, elapsed);
+            }
+        }
+
+        fastest.0
+    }
+
+    /// Returns the kernel of the most recent instruction set extension
+    /// the CPU supports, which is usually the fastest one, see `calibrate`.
     pub fn detect() -> Self {
         #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
         {
+            if gfni_detected() {
+                return Self::GFNI;
+            }
+            if avx512vbmi_detected() {
+                return Self::AVX512VBMI;
+            }
             if avx512_detected() {
                 return Self::AVX512;
             }
@@ -33,6 +199,15 @@ impl Platform {
             }
         }
 
+        // At 128 bits, SVE does the same work as NEON with more setup.
+        #[cfg(target_arch = "aarch64")]
+        {
+            // Safe because SVE was detected.
+            if sve_detected() && unsafe { crate::galois_8_sve::vector_len() } > 16 {
+                return Self::SVE;
+            }
+        }
+
         #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
         {
             if neon_detected() {
@@ -40,10 +215,70 @@ impl Platform {
             }
         }
 
+        #[cfg(target_arch = "powerpc64")]
+        {
+            if vsx_detected() {
+                return Self::VSX;
+            }
+        }
+
+        #[cfg(target_arch = "s390x")]
+        {
+            if zvector_detected() {
+                return Self::ZVECTOR;
+            }
+        }
+
+        #[cfg(target_arch = "loongarch64")]
+        {
+            if lasx_detected() {
+                return Self::LASX;
+            }
+            if lsx_detected() {
+                return Self::LSX;
+            }
+        }
+
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            if simd128_detected() {
+                return Self::SIMD128;
+            }
+        }
+
         Self::Portable
     }
 }
 
+// The GFNI kernel uses 256-bit vectors, so it needs AVX2 as well.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline(always)]
+pub fn gfni_detected() -> bool {
+    if cfg!(feature = "no_gfni") {
+        return false;
+    }
+    #[cfg(target_feature = "gfni")]
+    {
+        return avx2_detected();
+    }
+    is_x86_feature_detected!("gfni") && avx2_detected()
+}
+
+// VBMI adds byte permutes to AVX512F, the masks used along with them
+// come with AVX512BW, which every CPU with VBMI has.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline(always)]
+pub fn avx512vbmi_detected() -> bool {
+    if cfg!(feature = "no_avx512vbmi") {
+        return false;
+    }
+    #[cfg(all(target_feature = "avx512vbmi", target_feature = "avx512bw"))]
+    {
+        return true;
+    }
+    is_x86_feature_detected!("avx512vbmi") && is_x86_feature_detected!("avx512bw")
+}
+
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 #[inline(always)]
 pub fn avx512_detected() -> bool {
@@ -83,6 +318,21 @@ pub fn sse3_detected() -> bool {
     is_x86_feature_detected!("sse3")
 }
 
+// Carryless multiplication is used by GF(2^64), see `galois_64`,
+// independently of the SIMD support used by GF(2^8).
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline(always)]
+pub fn pclmulqdq_detected() -> bool {
+    if cfg!(feature = "no_pclmulqdq") {
+        return false;
+    }
+    #[cfg(target_feature = "pclmulqdq")]
+    {
+        return true;
+    }
+    is_x86_feature_detected!("pclmulqdq")
+}
+
 #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
 #[inline(always)]
 pub fn neon_detected() -> bool {
@@ -109,3 +359,99 @@ pub fn neon_detected() -> bool {
     }
     false
 }
+
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+pub fn sve_detected() -> bool {
+    if cfg!(feature = "no_sve") {
+        return false;
+    }
+    #[cfg(target_feature = "sve")]
+    {
+        return true;
+    }
+    std::arch::is_aarch64_feature_detected!("sve")
+}
+
+// The POWER intrinsics and their runtime detection are not stable yet,
+// so VSX is only used with the `unstable` feature.
+#[cfg(target_arch = "powerpc64")]
+#[inline(always)]
+pub fn vsx_detected() -> bool {
+    if cfg!(feature = "no_vsx") {
+        return false;
+    }
+    #[cfg(feature = "unstable")]
+    {
+        #[cfg(target_feature = "vsx")]
+        {
+            return true;
+        }
+        return std::arch::is_powerpc64_feature_detected!("vsx");
+    }
+    false
+}
+
+// Like VSX, the z/Architecture intrinsics and their runtime detection
+// are not stable yet.
+#[cfg(target_arch = "s390x")]
+#[inline(always)]
+pub fn zvector_detected() -> bool {
+    if cfg!(feature = "no_zvector") {
+        return false;
+    }
+    #[cfg(feature = "unstable")]
+    {
+        #[cfg(target_feature = "vector")]
+        {
+            return true;
+        }
+        return std::arch::is_s390x_feature_detected!("vector");
+    }
+    false
+}
+
+// The LoongArch intrinsics are not stable yet either, although their
+// runtime detection is.
+#[cfg(target_arch = "loongarch64")]
+#[inline(always)]
+pub fn lsx_detected() -> bool {
+    if cfg!(feature = "no_lsx") {
+        return false;
+    }
+    #[cfg(feature = "unstable")]
+    {
+        #[cfg(target_feature = "lsx")]
+        {
+            return true;
+        }
+        return std::arch::is_loongarch_feature_detected!("lsx");
+    }
+    false
+}
+
+#[cfg(target_arch = "loongarch64")]
+#[inline(always)]
+pub fn lasx_detected() -> bool {
+    if cfg!(feature = "no_lasx") {
+        return false;
+    }
+    #[cfg(feature = "unstable")]
+    {
+        #[cfg(target_feature = "lasx")]
+        {
+            return true;
+        }
+        return std::arch::is_loongarch_feature_detected!("lasx");
+    }
+    false
+}
+
+// WebAssembly has no runtime feature detection, a module using SIMD
+// instructions fails to load on engines without support, so SIMD128
+// is only used when the crate is built with it enabled.
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+#[inline(always)]
+pub fn simd128_detected() -> bool {
+    !cfg!(feature = "no_simd128")
+}
@@ -0,0 +1,151 @@
+//! Comparison against a secondary implementation, enabled by the `shadow`
+//! feature, e.g. to check this crate against a C library during a migration.
+
+use std::fmt;
+use std::sync::Arc;
+
+use smallvec::SmallVec;
+
+use crate::Field;
+
+/// A secondary implementation of a codec, e.g. FFI bindings to ISA-L,
+/// run alongside it, see `ReedSolomon::set_shadow`.
+///
+/// It has to use the same encoding matrix as the codec, otherwise every
+/// output diverges.
+pub trait Shadow<F: Field>: Send + Sync {
+    /// Constructs the parity shards of `data` into `parity`, which are
+    /// zeroed and as long as the data shards.
+    fn encode(&self, data: &[&[F::Elem]], parity: &mut [Vec<F::Elem>]);
+
+    /// Reconstructs the shards which are `None`, or only the data shards
+    /// if `data_only` is set, leaving the others `None`.
+    fn reconstruct(&self, shards: &mut [Option<Vec<F::Elem>>], data_only: bool);
+
+    /// Reports outputs of the codec which do not match the outputs of
+    /// this implementation, e.g. to log them.
+    fn divergence(&self, divergence: &Divergence);
+}
+
+/// An operation compared against a `Shadow`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ShadowOperation {
+    /// `ReedSolomon::encode_sep`, which `encode` and most other encoding
+    /// methods use.
+    Encode,
+    /// A reconstruction of all shards.
+    Reconstruct,
+    /// A reconstruction of only the data shards.
+    ReconstructData,
+}
+
+/// Outputs of a codec which do not match the outputs of its `Shadow`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Divergence {
+    /// The operation which diverged.
+    pub operation: ShadowOperation,
+    /// The indices of the shards which differ, in ascending order.
+    pub shards: Vec<usize>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} diverged from the secondary implementation in shards {:?}",
+            self.operation, self.shards
+        )
+    }
+}
+
+// The secondary implementation registered with a codec, if any.
+pub struct Slot<F: Field>(pub Option<Arc<dyn Shadow<F>>>);
+
+impl<F: Field> Default for Slot<F> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<F: Field> Clone for Slot<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<F: Field> fmt::Debug for Slot<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Slot")
+            .field(&self.0.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl<F: Field> Slot<F> {
+    pub const fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn check_encode<T: AsRef<[F::Elem]>, U: AsRef<[F::Elem]>>(
+        &self,
+        data_shard_count: usize,
+        data: &[T],
+        parity: &[U],
+    ) {
+        let Some(ref shadow) = self.0 else {
+            return;
+        };
+
+        let data: SmallVec<[&[F::Elem]; 32]> = data.iter().map(AsRef::as_ref).collect();
+        let mut expected = vec![vec![F::zero(); data[0].len()]; parity.len()];
+        shadow.encode(&data, &mut expected);
+
+        let shards: Vec<usize> = expected
+            .iter()
+            .zip(parity)
+            .enumerate()
+            .filter(|(_, (expected, actual))| expected.as_slice() != actual.as_ref())
+            .map(|(i, _)| data_shard_count + i)
+            .collect();
+
+        if !shards.is_empty() {
+            shadow.divergence(&Divergence {
+                operation: ShadowOperation::Encode,
+                shards,
+            });
+        }
+    }
+
+    // `shards` holds the shards the codec reconstructed from, and `rebuilt`
+    // the shards it rebuilt along with their indices.
+    pub fn check_reconstruct<'a>(
+        &self,
+        mut shards: Vec<Option<Vec<F::Elem>>>,
+        data_only: bool,
+        rebuilt: impl Iterator<Item = (usize, &'a [F::Elem])>,
+    ) where
+        F::Elem: 'a,
+    {
+        let Some(ref shadow) = self.0 else {
+            return;
+        };
+
+        shadow.reconstruct(&mut shards, data_only);
+
+        let shards: Vec<usize> = rebuilt
+            .filter(|&(i, actual)| shards[i].as_deref() != Some(actual))
+            .map(|(i, _)| i)
+            .collect();
+
+        if !shards.is_empty() {
+            shadow.divergence(&Divergence {
+                operation: if data_only {
+                    ShadowOperation::ReconstructData
+                } else {
+                    ShadowOperation::Reconstruct
+                },
+                shards,
+            });
+        }
+    }
+}
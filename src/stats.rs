@@ -0,0 +1,49 @@
+//! Accounting of the work done by a codec, enabled by the `metrics` feature.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Totals of the work done by a codec, see `ReedSolomon::stats`.
+///
+/// Bytes are counted once per shard read or produced by a call, e.g.
+/// encoding a 10+4 stripe of 1 KiB shards reads 10 KiB, writes 4 KiB and
+/// performs 40 Ki multiplications over GF(2^8).
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Stats {
+    /// The number of bytes of shards read.
+    pub bytes_read: u64,
+    /// The number of bytes of shards written.
+    pub bytes_written: u64,
+    /// The number of field element multiplications.
+    pub mul_ops: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Counters {
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    mul_ops: AtomicU64,
+}
+
+impl Counters {
+    pub fn record(&self, bytes_read: usize, bytes_written: usize, mul_ops: usize) {
+        self.bytes_read
+            .fetch_add(bytes_read as u64, Ordering::Relaxed);
+        self.bytes_written
+            .fetch_add(bytes_written as u64, Ordering::Relaxed);
+        self.mul_ops.fetch_add(mul_ops as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> Stats {
+        Stats {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            mul_ops: self.mul_ops.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.bytes_read.store(0, Ordering::Relaxed);
+        self.bytes_written.store(0, Ordering::Relaxed);
+        self.mul_ops.store(0, Ordering::Relaxed);
+    }
+}
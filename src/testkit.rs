@@ -0,0 +1,79 @@
+//! Deterministic stripes for tests.
+//!
+//! The stripes produced here are meant to be shared as fixtures, e.g. by
+//! integration tests of downstream crates or by conformance suites of
+//! implementations in other languages, so their content is part of the API
+//! and will not change between versions.
+
+use crate::errors::Error;
+use crate::galois_8::{self, ReedSolomon};
+
+/// SplitMix64, chosen as it is trivial to port to other languages.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    const fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Generates a stripe of `data_shards` pseudo-random data shards followed by
+/// their `parity_shards` parity shards over GF(2^8), each of length `shard_len`.
+///
+/// The data is the output of SplitMix64 seeded with `seed`, each 64-bit output
+/// taken as 8 bytes in little-endian order, filling the data shards one after
+/// another. Bytes of the last output which are not needed are dropped.
+///
+/// The parity is computed element by element with the scalar field
+/// arithmetic, independently of the SIMD code paths used by `encode`,
+/// using the encoding matrix of `ReedSolomon::new(data_shards, parity_shards)`.
+///
+/// Returns the errors of `ReedSolomon::new`.
+///
+/// # Example
+///
+/// ```
+/// use reed_solomon_erasure::galois_8::ReedSolomon;
+/// use reed_solomon_erasure::testkit;
+///
+/// let shards = testkit::stripe(42, 4, 2, 100).unwrap();
+///
+/// assert_eq!(shards, testkit::stripe(42, 4, 2, 100).unwrap());
+/// assert!(ReedSolomon::new(4, 2).unwrap().verify(&shards).unwrap());
+/// ```
+pub fn stripe(
+    seed: u64,
+    data_shards: usize,
+    parity_shards: usize,
+    shard_len: usize,
+) -> Result<Vec<Vec<u8>>, Error> {
+    let codec = ReedSolomon::new(data_shards, parity_shards)?;
+
+    let mut rng = SplitMix64(seed);
+    let mut bytes =
+        std::iter::repeat_with(|| rng.next_u64().to_le_bytes()).flat_map(|x| x.to_vec());
+
+    let mut shards: Vec<Vec<u8>> = (0..data_shards)
+        .map(|_| bytes.by_ref().take(shard_len).collect())
+        .collect();
+
+    for parity_row in codec.get_parity_rows() {
+        let parity = (0..shard_len)
+            .map(|i| {
+                parity_row
+                    .iter()
+                    .zip(&shards[..data_shards])
+                    .fold(0, |acc, (&coef, shard)| {
+                        galois_8::add(acc, galois_8::mul(coef, shard[i]))
+                    })
+            })
+            .collect();
+        shards.push(parity);
+    }
+
+    Ok(shards)
+}
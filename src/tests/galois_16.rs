@@ -1,5 +1,7 @@
-use super::{fill_random, option_shards_into_shards, shards_into_option_shards};
-use crate::galois_16::ReedSolomon;
+use super::{all_platforms, fill_random, option_shards_into_shards, shards_into_option_shards};
+use crate::correction::Corrector;
+use crate::galois_16::{ByteReedSolomon, ReedSolomon};
+use crate::{Error, MatrixKind};
 
 macro_rules! make_random_shards {
     ($per_shard:expr, $size:expr) => {{
@@ -482,3 +484,327 @@ quickcheck! {
         expect == shards
     }
 }
+
+#[test]
+fn test_byte_codec() {
+    let r = ReedSolomon::new(6, 3).unwrap();
+    let byte_r = ByteReedSolomon::from_codec(r.clone());
+    assert_eq!(9, byte_r.total_shard_count());
+
+    let mut expect = make_random_shards!(100, 9);
+    r.encode(&mut expect).unwrap();
+
+    let mut byte_shards: Vec<Vec<u8>> = expect.iter().map(|s| s.concat()).collect();
+    let expect_bytes = byte_shards.clone();
+    for shard in &mut byte_shards[6..] {
+        *shard = vec![0; 200];
+    }
+    byte_r.encode(&mut byte_shards).unwrap();
+    assert_eq!(expect_bytes, byte_shards);
+    assert!(byte_r.verify(&byte_shards).unwrap());
+
+    let mut parity = vec![vec![0u8; 200]; 3];
+    byte_r.encode_sep(&byte_shards[..6], &mut parity).unwrap();
+    assert_eq!(&expect_bytes[6..], &parity[..]);
+
+    let mut shards: Vec<_> = byte_shards.iter().cloned().map(Some).collect();
+    shards[1] = None;
+    shards[4] = None;
+    shards[7] = None;
+    byte_r.reconstruct(&mut shards).unwrap();
+    assert_eq!(
+        expect_bytes,
+        shards.into_iter().map(Option::unwrap).collect::<Vec<_>>()
+    );
+
+    let mut shards: Vec<_> = byte_shards.iter().cloned().map(|s| (s, true)).collect();
+    shards[0].1 = false;
+    shards[8] = (vec![], false);
+    byte_r.reconstruct_data(&mut shards).unwrap();
+    assert_eq!(expect_bytes[0], shards[0].0);
+    assert!(shards[8].0.is_empty());
+
+    // odd lengths
+    let mut odd: Vec<Vec<u8>> = vec![vec![0; 3]; 9];
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 0,
+            expected: 4,
+            actual: 3
+        },
+        byte_r.encode(&mut odd).unwrap_err()
+    );
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 0,
+            expected: 4,
+            actual: 3
+        },
+        byte_r.verify(&odd).unwrap_err()
+    );
+
+    // nothing is touched on error
+    let mut shards: Vec<Option<Vec<u8>>> = odd.into_iter().map(Some).collect();
+    shards[0] = None;
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 1,
+            expected: 4,
+            actual: 3
+        },
+        byte_r.reconstruct(&mut shards).unwrap_err()
+    );
+    assert_eq!(None, shards[0]);
+
+    let mut shards: Vec<_> = byte_shards.into_iter().map(Some).collect();
+    for shard in &mut shards[..4] {
+        *shard = None;
+    }
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        byte_r.reconstruct(&mut shards).unwrap_err()
+    );
+    assert!(shards[..4].iter().all(Option::is_none));
+}
+
+#[test]
+fn test_byte_codec_unaligned() {
+    let r = ReedSolomon::new(4, 3).unwrap();
+    let byte_r = ByteReedSolomon::from_codec(r);
+
+    assert_eq!(4, ByteReedSolomon::parity_len(3));
+    assert_eq!(4, ByteReedSolomon::parity_len(4));
+
+    for &data_len in &[1, 2, 7, 100, 101] {
+        let parity_len = ByteReedSolomon::parity_len(data_len);
+
+        let mut expect: Vec<Vec<u8>> = (0..7)
+            .map(|i| vec![0; if i < 4 { data_len } else { parity_len }])
+            .collect();
+        for shard in &mut expect[..4] {
+            fill_random(shard);
+        }
+        byte_r.encode_unaligned(&mut expect).unwrap();
+        assert!(byte_r.verify_unaligned(&expect).unwrap());
+
+        // the aligned part matches the codec on the truncated shards
+        if data_len & 1 == 0 {
+            assert!(byte_r.verify(&expect).unwrap());
+        }
+
+        let mut shards = shards_into_option_shards(expect.clone());
+        shards[0] = None;
+        shards[2] = None;
+        shards[5] = None;
+        byte_r.reconstruct_unaligned(&mut shards, data_len).unwrap();
+        assert_eq!(expect, option_shards_into_shards(shards));
+
+        // all data shards missing
+        let mut shards = shards_into_option_shards(expect.clone());
+        for shard in &mut shards[..3] {
+            *shard = None;
+        }
+        byte_r.reconstruct_unaligned(&mut shards, data_len).unwrap();
+        assert_eq!(expect, option_shards_into_shards(shards));
+
+        let mut shards: Vec<_> = expect.iter().cloned().map(|s| (s, true)).collect();
+        shards[1] = (vec![0; data_len], false);
+        shards[6] = (vec![], false);
+        byte_r
+            .reconstruct_data_unaligned(&mut shards, data_len)
+            .unwrap();
+        assert_eq!(expect[1], shards[1].0);
+        assert!(shards[6].0.is_empty());
+
+        // corruption of the tail is caught
+        let mut corrupt = expect.clone();
+        corrupt[3][data_len - 1] ^= 1;
+        assert!(!byte_r.verify_unaligned(&corrupt).unwrap());
+        let mut corrupt = expect.clone();
+        corrupt[6][parity_len - 1] ^= 1;
+        assert!(!byte_r.verify_unaligned(&corrupt).unwrap());
+    }
+
+    // shard sizes
+    let mut shards = vec![vec![0u8; 3]; 7];
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 4,
+            expected: 4,
+            actual: 3
+        },
+        byte_r.encode_unaligned(&mut shards).unwrap_err()
+    );
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 4,
+            expected: 4,
+            actual: 3
+        },
+        byte_r.verify_unaligned(&shards).unwrap_err()
+    );
+    assert_eq!(
+        Error::EmptyShard,
+        byte_r
+            .encode_unaligned(&mut vec![vec![0u8; 0]; 7])
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewShards,
+        byte_r.verify_unaligned(&shards[1..]).unwrap_err()
+    );
+
+    // nothing is touched on error
+    let mut shards: Vec<Option<Vec<u8>>> = shards.into_iter().map(Some).collect();
+    shards[0] = None;
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 4,
+            expected: 4,
+            actual: 3
+        },
+        byte_r.reconstruct_unaligned(&mut shards, 3).unwrap_err()
+    );
+    assert_eq!(None, shards[0]);
+    for shard in &mut shards[1..4] {
+        *shard = None;
+    }
+    for shard in &mut shards[4..] {
+        *shard = Some(vec![0; 4]);
+    }
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        byte_r.reconstruct_unaligned(&mut shards, 3).unwrap_err()
+    );
+    assert!(shards[..4].iter().all(Option::is_none));
+    assert_eq!(
+        Error::EmptyShard,
+        byte_r.reconstruct_unaligned(&mut shards, 0).unwrap_err()
+    );
+}
+
+#[test]
+fn test_transcode() {
+    use crate::galois_16::transcode;
+    use crate::galois_8;
+
+    let from = galois_8::ReedSolomon::new(6, 3).unwrap();
+    let to = ReedSolomon::new(20, 4).unwrap();
+
+    let mut source = vec![vec![0u8; 101]; 9];
+    for shard in &mut source[..6] {
+        fill_random(shard);
+    }
+    from.encode(&mut source).unwrap();
+
+    let mut shards: Vec<_> = source.iter().cloned().map(Some).collect();
+    shards[2] = None;
+    shards[7] = None;
+    let new_shards = transcode(&from, &to, &mut shards).unwrap();
+    assert_eq!(source, option_shards_into_shards(shards));
+
+    // 606 bytes make 303 elements, split into 20 shards of 16 elements
+    assert_eq!(24, new_shards.len());
+    assert!(new_shards.iter().all(|shard| shard.len() == 16));
+    assert!(to.verify(&new_shards).unwrap());
+
+    let data: Vec<u8> = new_shards[..20].concat().concat();
+    assert_eq!(source[..6].concat(), data[..606]);
+    assert!(data[606..].iter().all(|&x| x == 0));
+
+    // corruption is caught instead of carried over
+    let mut shards: Vec<_> = source.iter().cloned().map(Some).collect();
+    shards[1].as_mut().unwrap()[50] ^= 1;
+    shards[8] = None;
+    assert_eq!(
+        Error::InconsistentShards,
+        transcode(&from, &to, &mut shards).unwrap_err()
+    );
+
+    let mut shards: Vec<Option<Vec<u8>>> = vec![None; 9];
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        transcode(&from, &to, &mut shards).unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewShards,
+        transcode(&from, &to, &mut shards[1..]).unwrap_err()
+    );
+}
+
+#[test]
+fn test_additive_fft_wide() {
+    let r = ReedSolomon::builder(1000, 300)
+        .matrix_kind(MatrixKind::AdditiveFft)
+        .build()
+        .unwrap();
+
+    let mut expect = make_random_shards!(8, 1300);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    for i in (0..1300).step_by(5) {
+        shards[i] = None;
+    }
+    r.reconstruct(&mut shards).unwrap();
+    assert_eq!(expect, option_shards_into_shards(shards));
+}
+
+#[test]
+fn test_platform() {
+    // the additive FFT encodes with the kernels as well at this size
+    for &kind in &[MatrixKind::Vandermonde, MatrixKind::AdditiveFft] {
+        let detected = ReedSolomon::builder(200, 40)
+            .matrix_kind(kind)
+            .build()
+            .unwrap();
+
+        let mut expect = make_random_shards!(1031, 240);
+        detected.encode(&mut expect).unwrap();
+
+        for platform in all_platforms()
+            .into_iter()
+            .filter(|platform| platform.is_supported())
+        {
+            let r = ReedSolomon::builder(200, 40)
+                .matrix_kind(kind)
+                .platform(platform)
+                .build()
+                .unwrap();
+
+            let mut shards = expect.clone();
+            r.encode(&mut shards).unwrap();
+            assert_eq!(expect, shards);
+
+            let mut shards = shards_into_option_shards(expect.clone());
+            for i in (0..240).step_by(6) {
+                shards[i] = None;
+            }
+            r.reconstruct(&mut shards).unwrap();
+            assert_eq!(expect, option_shards_into_shards(shards));
+        }
+    }
+}
+
+#[test]
+fn test_corrector() {
+    let r = ReedSolomon::new(300, 20).unwrap();
+    let corrector = Corrector::new(&r).unwrap();
+
+    let mut expect = make_random_shards!(50, 320);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[3] = None;
+    shards[310] = None;
+    for &i in &[0, 150, 299, 300, 319] {
+        shards[i].as_mut().unwrap()[7][1] ^= 0x81;
+    }
+    shards[150].as_mut().unwrap()[49] = [0xff, 0xff];
+
+    assert_eq!(
+        vec![0, 150, 299, 300, 319],
+        corrector.correct(&mut shards).unwrap()
+    );
+    assert_eq!(expect, option_shards_into_shards(shards));
+}
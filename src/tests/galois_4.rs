@@ -0,0 +1,95 @@
+use super::{fill_random, option_shards_into_shards, shards_into_option_shards};
+use crate::galois_4::{PackedReedSolomon, ReedSolomon, MAX_SHARDS};
+use crate::Error;
+
+macro_rules! make_random_shards {
+    ($per_shard:expr, $size:expr, $mask:expr) => {{
+        let mut shards = Vec::with_capacity(20);
+        for _ in 0..$size {
+            shards.push(vec![0u8; $per_shard]);
+        }
+
+        for s in shards.iter_mut() {
+            fill_random(s);
+            for x in s.iter_mut() {
+                *x &= $mask;
+            }
+        }
+
+        shards
+    }};
+}
+
+#[test]
+fn correct_field_order_restriction() {
+    assert_eq!(
+        Err(Error::TooManyShards { max: MAX_SHARDS }),
+        ReedSolomon::new(MAX_SHARDS, 1)
+    );
+    assert_eq!(
+        Err(Error::TooManyShards { max: MAX_SHARDS }),
+        PackedReedSolomon::new(1, MAX_SHARDS)
+    );
+
+    assert!(ReedSolomon::new(MAX_SHARDS - 1, 1).is_ok());
+    assert!(PackedReedSolomon::new(1, MAX_SHARDS - 1).is_ok());
+}
+
+#[test]
+fn test_encode_reconstruct() {
+    let r = ReedSolomon::new(10, 5).unwrap();
+
+    let mut expect = make_random_shards!(100, 15, 0xf);
+    r.encode(&mut expect).unwrap();
+    assert!(r.verify(&expect).unwrap());
+    assert!(expect.iter().flatten().all(|&x| x < 16));
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    for &i in &[0, 4, 9, 10, 14] {
+        shards[i] = None;
+    }
+    r.reconstruct(&mut shards).unwrap();
+    assert_eq!(expect, option_shards_into_shards(shards));
+}
+
+#[test]
+fn test_packed_encode_reconstruct() {
+    let r = PackedReedSolomon::new(10, 5).unwrap();
+
+    let mut expect = make_random_shards!(100, 15, 0xff);
+    r.encode(&mut expect).unwrap();
+    assert!(r.verify(&expect).unwrap());
+
+    for i in 0..15 {
+        let mut shards = shards_into_option_shards(expect.clone());
+        for j in 0..5 {
+            shards[(i + j * 3) % 15] = None;
+        }
+        r.reconstruct(&mut shards).unwrap();
+        assert_eq!(expect, option_shards_into_shards(shards));
+    }
+
+    let mut corrupted = expect.clone();
+    corrupted[3][50] ^= 0x10;
+    assert!(!r.verify(&corrupted).unwrap());
+}
+
+#[test]
+fn test_packed_matches_unpacked() {
+    let r = ReedSolomon::new(4, 3).unwrap();
+    let packed = PackedReedSolomon::new(4, 3).unwrap();
+
+    let mut shards = make_random_shards!(20, 7, 0xff);
+    packed.encode(&mut shards).unwrap();
+
+    for &(shift, mask) in &[(0, 0xf), (4, 0xf)] {
+        let mut nibbles: Vec<Vec<u8>> = shards
+            .iter()
+            .map(|shard| shard.iter().map(|x| (x >> shift) & mask).collect())
+            .collect();
+        let expect = nibbles.clone();
+
+        r.encode(&mut nibbles).unwrap();
+        assert_eq!(expect, nibbles);
+    }
+}
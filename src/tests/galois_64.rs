@@ -0,0 +1,87 @@
+use super::{fill_random, option_shards_into_shards, shards_into_option_shards};
+use crate::galois_64::{ReedSolomon, MAX_SHARDS};
+use crate::Error;
+
+macro_rules! make_random_shards {
+    ($per_shard:expr, $size:expr) => {{
+        let mut shards = Vec::with_capacity(20);
+        for _ in 0..$size {
+            shards.push(vec![0u64; $per_shard]);
+        }
+
+        for s in shards.iter_mut() {
+            fill_random(s);
+        }
+
+        shards
+    }};
+}
+
+#[test]
+fn correct_field_order_restriction() {
+    assert_eq!(
+        Err(Error::TooManyShards { max: MAX_SHARDS }),
+        ReedSolomon::new(MAX_SHARDS, 1)
+    );
+    assert_eq!(
+        Err(Error::TooManyShards { max: MAX_SHARDS }),
+        ReedSolomon::new(1, MAX_SHARDS)
+    );
+
+    // more shards than GF(2^16) allows would be too slow to build
+    assert!(ReedSolomon::new(250, 20).is_ok());
+}
+
+#[test]
+fn test_encode_reconstruct() {
+    let r = ReedSolomon::new(10, 4).unwrap();
+
+    let mut expect = make_random_shards!(100, 14);
+    r.encode(&mut expect).unwrap();
+    assert!(r.verify(&expect).unwrap());
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[0] = None;
+    shards[5] = None;
+    shards[9] = None;
+    shards[13] = None;
+    r.reconstruct(&mut shards).unwrap();
+    assert_eq!(expect, option_shards_into_shards(shards));
+
+    let mut corrupted = expect.clone();
+    corrupted[3][50] ^= 1;
+    assert!(!r.verify(&corrupted).unwrap());
+}
+
+#[test]
+fn test_more_shards_than_galois_8() {
+    let r = ReedSolomon::new(250, 20).unwrap();
+
+    let mut expect = make_random_shards!(4, 270);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    for i in (0..270).step_by(14) {
+        shards[i] = None;
+    }
+    r.reconstruct_data(&mut shards).unwrap();
+    for i in 0..250 {
+        assert_eq!(Some(&expect[i]), shards[i].as_ref());
+    }
+}
+
+#[test]
+fn test_inversion_cache() {
+    let r = ReedSolomon::new(10, 4).unwrap();
+    let mut expect = make_random_shards!(10, 14);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = shards_into_option_shards(expect);
+    shards[1] = None;
+    r.reconstruct(&mut shards).unwrap();
+
+    let exported = r.export_inversion_cache();
+    let fresh = ReedSolomon::new(10, 4).unwrap();
+    assert_eq!(Ok(()), fresh.import_inversion_cache(&exported));
+    assert_eq!(exported, fresh.export_inversion_cache());
+}
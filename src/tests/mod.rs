@@ -1,12 +1,19 @@
 #![allow(dead_code)]
 
-use super::{galois_8, Error, SBSError};
-use rand::{self, thread_rng, Rng};
+use super::{
+    galois_8, Error, Executor, InversionCachePolicy, MatrixKind, Platform, RangeFetch, SBSError,
+    ScopedThreads,
+};
+use crate::correction::Corrector;
+use rand::{self, seq::SliceRandom, thread_rng, Rng};
 
 mod galois_16;
+mod galois_4;
+mod galois_64;
 
 type ReedSolomon = crate::ReedSolomon<galois_8::Field>;
 type ShardByShard<'a> = crate::ShardByShard<'a, galois_8::Field>;
+type StreamDecoder<'a> = crate::StreamDecoder<'a, galois_8::Field>;
 
 macro_rules! make_random_shards {
     ($per_shard:expr, $size:expr) => {{
@@ -89,6 +96,13 @@ fn option_shards_into_shards<T>(shards: Vec<Option<Vec<T>>>) -> Vec<Vec<T>> {
     result
 }
 
+// The number of decode matrices cached in the inversion tree of `r`.
+fn cached_count(r: &ReedSolomon) -> usize {
+    r.parse_inversion_cache(&r.export_inversion_cache())
+        .unwrap()
+        .len()
+}
+
 #[test]
 fn test_no_data_shards() {
     assert_eq!(Error::TooFewDataShards, ReedSolomon::new(0, 1).unwrap_err());
@@ -105,7 +119,7 @@ fn test_no_parity_shards() {
 #[test]
 fn test_too_many_shards() {
     assert_eq!(
-        Error::TooManyShards,
+        Error::TooManyShards { max: 256 },
         ReedSolomon::new(129, 128).unwrap_err()
     );
 }
@@ -154,7 +168,11 @@ fn test_encoding() {
     let mut bad_shards = make_random_shards!(per_shard, 13);
     bad_shards[0] = vec![0_u8];
     assert_eq!(
-        Error::IncorrectShardSize,
+        Error::IncorrectShardSize {
+            shard: 1,
+            expected: 1,
+            actual: 50000
+        },
         r.encode(&mut bad_shards).unwrap_err()
     );
 }
@@ -952,7 +970,11 @@ fn test_verify_shards_with_buffer_incorrect_buffer_sizes() {
         buffer[1] = vec![0; 99];
 
         assert_eq!(
-            Error::IncorrectShardSize,
+            Error::IncorrectShardSize {
+                shard: 1,
+                expected: 100,
+                actual: 99
+            },
             r.verify_with_buffer(&shards, &mut buffer).unwrap_err()
         );
     }
@@ -1070,13 +1092,19 @@ fn test_slices_or_shards_count_check() {
     {
         let mut shards = make_random_shards!(10, 6);
 
-        assert_eq!(Error::TooManyShards, r.encode(&mut shards).unwrap_err());
-        assert_eq!(Error::TooManyShards, r.verify(&shards).unwrap_err());
+        assert_eq!(
+            Error::TooManyShards { max: 5 },
+            r.encode(&mut shards).unwrap_err()
+        );
+        assert_eq!(
+            Error::TooManyShards { max: 5 },
+            r.verify(&shards).unwrap_err()
+        );
 
         let mut option_shards = shards_to_option_shards(&shards);
 
         assert_eq!(
-            Error::TooManyShards,
+            Error::TooManyShards { max: 5 },
             r.reconstruct(&mut option_shards).unwrap_err()
         );
     }
@@ -1090,15 +1118,30 @@ fn test_check_slices_or_shards_size() {
         let mut shards = shards!([0, 0, 0], [0, 1], [1, 2, 3], [0, 0, 0]);
 
         assert_eq!(
-            Error::IncorrectShardSize,
+            Error::IncorrectShardSize {
+                shard: 1,
+                expected: 3,
+                actual: 2
+            },
             r.encode(&mut shards).unwrap_err()
         );
-        assert_eq!(Error::IncorrectShardSize, r.verify(&shards).unwrap_err());
+        assert_eq!(
+            Error::IncorrectShardSize {
+                shard: 1,
+                expected: 3,
+                actual: 2
+            },
+            r.verify(&shards).unwrap_err()
+        );
 
         let mut option_shards = shards_to_option_shards(&shards);
 
         assert_eq!(
-            Error::IncorrectShardSize,
+            Error::IncorrectShardSize {
+                shard: 1,
+                expected: 3,
+                actual: 2
+            },
             r.reconstruct(&mut option_shards).unwrap_err()
         );
     }
@@ -1106,15 +1149,30 @@ fn test_check_slices_or_shards_size() {
         let mut shards = shards!([0, 1], [0, 1], [1, 2, 3], [0, 0, 0]);
 
         assert_eq!(
-            Error::IncorrectShardSize,
+            Error::IncorrectShardSize {
+                shard: 2,
+                expected: 2,
+                actual: 3
+            },
             r.encode(&mut shards).unwrap_err()
         );
-        assert_eq!(Error::IncorrectShardSize, r.verify(&shards).unwrap_err());
+        assert_eq!(
+            Error::IncorrectShardSize {
+                shard: 2,
+                expected: 2,
+                actual: 3
+            },
+            r.verify(&shards).unwrap_err()
+        );
 
         let mut option_shards = shards_to_option_shards(&shards);
 
         assert_eq!(
-            Error::IncorrectShardSize,
+            Error::IncorrectShardSize {
+                shard: 2,
+                expected: 2,
+                actual: 3
+            },
             r.reconstruct(&mut option_shards).unwrap_err()
         );
     }
@@ -1122,15 +1180,30 @@ fn test_check_slices_or_shards_size() {
         let mut shards = shards!([0, 1], [0, 1, 4], [1, 2, 3], [0, 0, 0]);
 
         assert_eq!(
-            Error::IncorrectShardSize,
+            Error::IncorrectShardSize {
+                shard: 1,
+                expected: 2,
+                actual: 3
+            },
             r.encode(&mut shards).unwrap_err()
         );
-        assert_eq!(Error::IncorrectShardSize, r.verify(&shards).unwrap_err());
+        assert_eq!(
+            Error::IncorrectShardSize {
+                shard: 1,
+                expected: 2,
+                actual: 3
+            },
+            r.verify(&shards).unwrap_err()
+        );
 
         let mut option_shards = shards_to_option_shards(&shards);
 
         assert_eq!(
-            Error::IncorrectShardSize,
+            Error::IncorrectShardSize {
+                shard: 1,
+                expected: 2,
+                actual: 3
+            },
             r.reconstruct(&mut option_shards).unwrap_err()
         );
     }
@@ -1654,14 +1727,22 @@ fn shardbyshard_encode_error_handling() {
             assert_eq!(0, sbs.cur_input_index());
 
             assert_eq!(
-                SBSError::RSError(Error::IncorrectShardSize),
+                SBSError::RSError(Error::IncorrectShardSize {
+                    shard: 1,
+                    expected: 100,
+                    actual: 99
+                }),
                 sbs.encode(&mut slice_refs).unwrap_err()
             );
 
             assert_eq!(0, sbs.cur_input_index());
 
             assert_eq!(
-                SBSError::RSError(Error::IncorrectShardSize),
+                SBSError::RSError(Error::IncorrectShardSize {
+                    shard: 1,
+                    expected: 100,
+                    actual: 99
+                }),
                 sbs.encode(&mut slice_refs).unwrap_err()
             );
 
@@ -1750,14 +1831,22 @@ fn shardbyshard_encode_shard_error_handling() {
             assert_eq!(0, sbs.cur_input_index());
 
             assert_eq!(
-                SBSError::RSError(Error::IncorrectShardSize),
+                SBSError::RSError(Error::IncorrectShardSize {
+                    shard: 1,
+                    expected: 100,
+                    actual: 99
+                }),
                 sbs.encode(&mut shards).unwrap_err()
             );
 
             assert_eq!(0, sbs.cur_input_index());
 
             assert_eq!(
-                SBSError::RSError(Error::IncorrectShardSize),
+                SBSError::RSError(Error::IncorrectShardSize {
+                    shard: 1,
+                    expected: 100,
+                    actual: 99
+                }),
                 sbs.encode(&mut shards).unwrap_err()
             );
 
@@ -1950,14 +2039,22 @@ fn shardbyshard_encode_sep_error_handling() {
                 assert_eq!(0, sbs.cur_input_index());
 
                 assert_eq!(
-                    SBSError::RSError(Error::IncorrectShardSize),
+                    SBSError::RSError(Error::IncorrectShardSize {
+                        shard: 1,
+                        expected: 100,
+                        actual: 99
+                    }),
                     sbs.encode_sep(&data_refs, &mut parity_refs).unwrap_err()
                 );
 
                 assert_eq!(0, sbs.cur_input_index());
 
                 assert_eq!(
-                    SBSError::RSError(Error::IncorrectShardSize),
+                    SBSError::RSError(Error::IncorrectShardSize {
+                        shard: 1,
+                        expected: 100,
+                        actual: 99
+                    }),
                     sbs.encode_sep(&data_refs, &mut parity_refs).unwrap_err()
                 );
 
@@ -1989,14 +2086,22 @@ fn shardbyshard_encode_sep_error_handling() {
                 assert_eq!(0, sbs.cur_input_index());
 
                 assert_eq!(
-                    SBSError::RSError(Error::IncorrectShardSize),
+                    SBSError::RSError(Error::IncorrectShardSize {
+                        shard: 1,
+                        expected: 100,
+                        actual: 99
+                    }),
                     sbs.encode_sep(&data_refs, &mut parity_refs).unwrap_err()
                 );
 
                 assert_eq!(0, sbs.cur_input_index());
 
                 assert_eq!(
-                    SBSError::RSError(Error::IncorrectShardSize),
+                    SBSError::RSError(Error::IncorrectShardSize {
+                        shard: 1,
+                        expected: 100,
+                        actual: 99
+                    }),
                     sbs.encode_sep(&data_refs, &mut parity_refs).unwrap_err()
                 );
 
@@ -2138,14 +2243,22 @@ fn shardbyshard_encode_shard_sep_error_handling() {
                 assert_eq!(0, sbs.cur_input_index());
 
                 assert_eq!(
-                    SBSError::RSError(Error::IncorrectShardSize),
+                    SBSError::RSError(Error::IncorrectShardSize {
+                        shard: 1,
+                        expected: 100,
+                        actual: 99
+                    }),
                     sbs.encode_sep(data, parity).unwrap_err()
                 );
 
                 assert_eq!(0, sbs.cur_input_index());
 
                 assert_eq!(
-                    SBSError::RSError(Error::IncorrectShardSize),
+                    SBSError::RSError(Error::IncorrectShardSize {
+                        shard: 1,
+                        expected: 100,
+                        actual: 99
+                    }),
                     sbs.encode_sep(data, parity).unwrap_err()
                 );
 
@@ -2171,14 +2284,22 @@ fn shardbyshard_encode_shard_sep_error_handling() {
                 assert_eq!(0, sbs.cur_input_index());
 
                 assert_eq!(
-                    SBSError::RSError(Error::IncorrectShardSize),
+                    SBSError::RSError(Error::IncorrectShardSize {
+                        shard: 1,
+                        expected: 100,
+                        actual: 99
+                    }),
                     sbs.encode_sep(data, parity).unwrap_err()
                 );
 
                 assert_eq!(0, sbs.cur_input_index());
 
                 assert_eq!(
-                    SBSError::RSError(Error::IncorrectShardSize),
+                    SBSError::RSError(Error::IncorrectShardSize {
+                        shard: 1,
+                        expected: 100,
+                        actual: 99
+                    }),
                     sbs.encode_sep(data, parity).unwrap_err()
                 );
 
@@ -2196,6 +2317,351 @@ fn shardbyshard_encode_shard_sep_error_handling() {
     }
 }
 
+#[test]
+fn shardbyshard_digest() {
+    let r = ReedSolomon::new(3, 2).unwrap();
+    let mut sbs = ShardByShard::with_digest(&r);
+
+    // CRC-32C check value of "123456789"
+    let mut shards = shards!(
+        [b'1', b'2', b'3'],
+        [b'4', b'5', b'6'],
+        [b'7', b'8', b'9'],
+        [0, 0, 0],
+        [0, 0, 0]
+    );
+
+    assert_eq!(
+        SBSError::LeftoverShards,
+        sbs.finalize_with_digest().unwrap_err()
+    );
+
+    for _ in 0..3 {
+        sbs.encode(&mut shards).unwrap();
+    }
+    assert!(r.verify(&shards).unwrap());
+    assert_eq!(0xe306_9283, sbs.finalize_with_digest().unwrap());
+    assert_eq!(0, sbs.cur_input_index());
+
+    // without a digest, the stripe is left as is
+    let mut plain = ShardByShard::new(&r);
+    for _ in 0..3 {
+        plain.encode(&mut shards).unwrap();
+    }
+    assert_eq!(
+        SBSError::NoDigest,
+        plain.finalize_with_digest().unwrap_err()
+    );
+    assert!(plain.parity_ready());
+
+    // the checksum restarts with each stripe
+    let mut shards = make_random_shards!(1000, 5);
+    let expect = shards.clone();
+    let (data, parity) = shards.split_at_mut(3);
+    for _ in 0..3 {
+        sbs.encode_sep(data, parity).unwrap();
+    }
+    assert!(r.verify(&shards).unwrap());
+
+    let mut other = ShardByShard::with_digest(&r);
+    let mut shards_copy = expect;
+    for _ in 0..3 {
+        other.encode(&mut shards_copy).unwrap();
+    }
+    assert_eq!(
+        other.finalize_with_digest().unwrap(),
+        sbs.finalize_with_digest().unwrap()
+    );
+
+    // resetting discards the checksum of the shards encoded so far
+    let digest = {
+        for _ in 0..3 {
+            other.encode(&mut shards).unwrap();
+        }
+        other.finalize_with_digest().unwrap()
+    };
+    sbs.encode(&mut shards_copy).unwrap();
+    sbs.reset_force();
+    for _ in 0..3 {
+        sbs.encode(&mut shards).unwrap();
+    }
+    assert_eq!(digest, sbs.finalize_with_digest().unwrap());
+}
+
+#[test]
+fn shardbyshard_typed() {
+    use crate::{SbsDone, SbsFed, SbsNotStarted};
+
+    type Fed<'a> = SbsFed<'a, galois_8::Field>;
+
+    fn feed_rest<'a>(mut fed: Fed<'a>, data: &[Vec<u8>], parity: &mut [Vec<u8>]) -> Fed<'a> {
+        while let SbsFed::Feeding(feeding) = fed {
+            let i = feeding.next_index();
+            fed = feeding.feed(&data[i], parity).unwrap();
+        }
+        fed
+    }
+
+    fn done(fed: Fed) -> SbsDone<galois_8::Field> {
+        match fed {
+            SbsFed::Done(done) => done,
+            SbsFed::Feeding(feeding) => panic!("not done at {}", feeding.next_index()),
+        }
+    }
+
+    let r = ReedSolomon::new(3, 2).unwrap();
+
+    let mut shards = make_random_shards!(1000, 5);
+    let mut expect = shards.clone();
+    let (data, parity) = shards.split_at_mut(3);
+
+    let fed = SbsNotStarted::with_digest(&r)
+        .feed(&data[0], parity)
+        .unwrap();
+    let stripe = done(feed_rest(fed, data, parity));
+    assert!(r.verify(&shards).unwrap());
+
+    // same parity and checksum as ShardByShard
+    let mut sbs = ShardByShard::with_digest(&r);
+    for _ in 0..3 {
+        sbs.encode(&mut expect).unwrap();
+    }
+    assert_eq!(expect, shards);
+    assert_eq!(sbs.finalize_with_digest().ok(), stripe.digest());
+
+    // rejected shards hand the state back untouched
+    let mut shards = make_random_shards!(1000, 5);
+    let (data, parity) = shards.split_at_mut(3);
+    let not_started = stripe.reset();
+    let (not_started, e) = not_started.feed(&data[0], &mut parity[..1]).unwrap_err();
+    assert_eq!(Error::TooFewParityShards, e);
+    let (not_started, e) = not_started.feed(&data[0][..10], parity).unwrap_err();
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 0,
+            expected: 1000,
+            actual: 10
+        },
+        e
+    );
+    let feeding = match not_started.feed(&data[0], parity).unwrap() {
+        SbsFed::Feeding(feeding) => feeding,
+        SbsFed::Done(_) => panic!("done after 1 data shard"),
+    };
+    let (feeding, e) = feeding.feed(&[], parity).unwrap_err();
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 1,
+            expected: 1000,
+            actual: 0
+        },
+        e
+    );
+    assert_eq!(1, feeding.next_index());
+    let stripe = done(feed_rest(SbsFed::Feeding(feeding), data, parity));
+    assert!(r.verify(&shards).unwrap());
+
+    // aborting restarts the stripe, overwriting the partial parity
+    let mut shards = make_random_shards!(1000, 5);
+    let (data, parity) = shards.split_at_mut(3);
+    let feeding = match stripe.reset().feed(&data[1], parity).unwrap() {
+        SbsFed::Feeding(feeding) => feeding,
+        SbsFed::Done(_) => panic!("done after 1 data shard"),
+    };
+    let fed = feeding.abort().feed(&data[0], parity).unwrap();
+    done(feed_rest(fed, data, parity));
+    assert!(r.verify(&shards).unwrap());
+
+    // no checksum unless asked for
+    let mut parity = [[0u8; 4]];
+    let r = ReedSolomon::new(1, 1).unwrap();
+    let stripe = done(
+        SbsNotStarted::new(&r)
+            .feed(&[1, 2, 3, 4], &mut parity)
+            .unwrap(),
+    );
+    assert_eq!(None, stripe.digest());
+    assert_eq!([1, 2, 3, 4], parity[0]);
+}
+
+/// Reader handing out at most 1000 bytes per call, like a socket.
+struct Trickle<'a>(&'a [u8]);
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.0.len()).min(1000);
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn shardbyshard_encode_from_reader() {
+    use std::io::ErrorKind;
+
+    let r = ReedSolomon::new(4, 2).unwrap();
+
+    // longer than a read chunk
+    let mut expect = make_random_shards!(100_000, 6);
+    r.encode(&mut expect).unwrap();
+
+    let mut sbs = ShardByShard::with_digest(&r);
+    let mut parity = vec![vec![0u8; 100_000]; 2];
+    for shard in &expect[..4] {
+        sbs.encode_from_reader(Trickle(shard), &mut parity).unwrap();
+    }
+    assert!(sbs.parity_ready());
+    assert_eq!(&expect[4..], &parity[..]);
+
+    let mut other = ShardByShard::with_digest(&r);
+    let mut shards = expect.clone();
+    for _ in 0..4 {
+        other.encode(&mut shards).unwrap();
+    }
+    assert_eq!(
+        other.finalize_with_digest().unwrap(),
+        sbs.finalize_with_digest().unwrap()
+    );
+
+    // the data shard is read up to the length of the parity shards
+    let mut sbs = ShardByShard::new(&r);
+    let mut reader = &expect[..4].concat()[..];
+    for _ in 0..4 {
+        sbs.encode_from_reader(&mut reader, &mut parity).unwrap();
+    }
+    assert!(reader.is_empty());
+    assert_eq!(&expect[4..], &parity[..]);
+
+    let err = sbs
+        .encode_from_reader(&expect[0][..], &mut parity)
+        .unwrap_err();
+    assert_eq!(ErrorKind::InvalidInput, err.kind());
+    assert_eq!(
+        Some(&SBSError::TooManyCalls),
+        err.get_ref().and_then(|e| e.downcast_ref())
+    );
+
+    sbs.reset().unwrap();
+    let err = sbs
+        .encode_from_reader(&expect[0][..], &mut parity[1..])
+        .unwrap_err();
+    assert_eq!(
+        Some(&SBSError::RSError(Error::TooFewParityShards)),
+        err.get_ref().and_then(|e| e.downcast_ref())
+    );
+    assert_eq!(0, sbs.cur_input_index());
+
+    let err = sbs
+        .encode_from_reader(&expect[0][1..], &mut parity)
+        .unwrap_err();
+    assert_eq!(ErrorKind::UnexpectedEof, err.kind());
+    assert_eq!(0, sbs.cur_input_index());
+}
+
+#[test]
+fn shardbyshard_encode_data_shard_out_of_order() {
+    let r = ReedSolomon::new(4, 3).unwrap();
+
+    let mut expect = make_random_shards!(100, 7);
+    r.encode(&mut expect).unwrap();
+
+    let mut sbs = ShardByShard::new(&r);
+    let mut parity = vec![vec![0xffu8; 100]; 3];
+
+    for _ in 0..2 {
+        for (n, &i) in [2, 0, 3, 1].iter().enumerate() {
+            assert!(!sbs.is_applied(i));
+            sbs.encode_data_shard(i, &expect[i], &mut parity).unwrap();
+            assert!(sbs.is_applied(i));
+            assert_eq!(n == 3, sbs.parity_ready());
+        }
+        assert_eq_shards(&expect[4..], &parity);
+
+        assert_eq!(
+            SBSError::TooManyCalls,
+            sbs.encode_data_shard(0, &expect[0], &mut parity)
+                .unwrap_err()
+        );
+        sbs.reset().unwrap();
+        assert!(!sbs.is_applied(2));
+    }
+
+    // encode and encode_sep pick up the lowest data shard not applied yet
+    let mut shards = expect.clone();
+    for shard in &mut shards[4..] {
+        for x in shard.iter_mut() {
+            *x = 0;
+        }
+    }
+    sbs.encode_data_shard(1, &expect[1], &mut shards[4..])
+        .unwrap();
+    assert_eq!(0, sbs.cur_input_index());
+    sbs.encode(&mut shards).unwrap();
+    assert_eq!(2, sbs.cur_input_index());
+    sbs.encode_data_shard(3, &expect[3], &mut shards[4..])
+        .unwrap();
+    assert_eq!(2, sbs.cur_input_index());
+    assert_eq!(SBSError::LeftoverShards, sbs.reset().unwrap_err());
+    {
+        let (data, parity) = shards.split_at_mut(4);
+        sbs.encode_sep(data, parity).unwrap();
+    }
+    assert!(sbs.parity_ready());
+    assert_eq_shards(&expect, &shards);
+    sbs.reset().unwrap();
+
+    // error handling
+    sbs.encode_data_shard(2, &expect[2], &mut parity).unwrap();
+    assert_eq!(
+        SBSError::RSError(Error::InvalidIndex),
+        sbs.encode_data_shard(2, &expect[2], &mut parity)
+            .unwrap_err()
+    );
+    assert_eq!(
+        SBSError::RSError(Error::InvalidIndex),
+        sbs.encode_data_shard(4, &expect[4], &mut parity)
+            .unwrap_err()
+    );
+    assert_eq!(
+        SBSError::RSError(Error::TooFewParityShards),
+        sbs.encode_data_shard(0, &expect[0], &mut parity[1..])
+            .unwrap_err()
+    );
+    assert_eq!(
+        SBSError::RSError(Error::IncorrectShardSize {
+            shard: 0,
+            expected: 100,
+            actual: 99
+        }),
+        sbs.encode_data_shard(0, &expect[0][1..], &mut parity)
+            .unwrap_err()
+    );
+    assert!(!sbs.is_applied(0));
+    sbs.reset_force();
+
+    // the digest is of the data shards in order
+    let mut sbs = ShardByShard::with_digest(&r);
+    assert_eq!(
+        SBSError::OutOfOrder,
+        sbs.encode_data_shard(1, &expect[1], &mut parity)
+            .unwrap_err()
+    );
+    for i in 0..4 {
+        sbs.encode_data_shard(i, &expect[i], &mut parity).unwrap();
+    }
+    assert_eq_shards(&expect[4..], &parity);
+
+    let mut other = ShardByShard::with_digest(&r);
+    for _ in 0..4 {
+        other.encode(&mut expect).unwrap();
+    }
+    assert_eq!(
+        other.finalize_with_digest().unwrap(),
+        sbs.finalize_with_digest().unwrap()
+    );
+}
+
 #[test]
 fn test_encode_single_sep() {
     let r = ReedSolomon::new(10, 3).unwrap();
@@ -2612,3 +3078,4193 @@ fn test_encode_single_error_handling() {
         );
     }
 }
+
+#[test]
+fn test_encode_sep_vectored() {
+    let r = ReedSolomon::new(10, 3).unwrap();
+
+    let mut shards = make_random_shards!(1000, 13);
+    r.encode(&mut shards).unwrap();
+
+    let (data, parity) = shards.split_at(10);
+
+    // split every parity shard into chunks of varying sizes, including an empty one
+    let mut buffers: Vec<Vec<Vec<u8>>> = (0..3)
+        .map(|_| vec![vec![0; 100], vec![0; 0], vec![0; 450], vec![0; 450]])
+        .collect();
+
+    r.encode_sep_vectored(data, &mut buffers).unwrap();
+
+    for (expect, chunks) in parity.iter().zip(buffers.iter()) {
+        assert_eq!(expect, &chunks.concat());
+    }
+
+    // works with `IoSliceMut`s carved out of one buffer as well
+    let mut packet = vec![0u8; 3000];
+    {
+        let mut io_slices: Vec<Vec<std::io::IoSliceMut>> = packet
+            .chunks_mut(1000)
+            .map(|shard| {
+                let (a, b) = shard.split_at_mut(333);
+                vec![std::io::IoSliceMut::new(a), std::io::IoSliceMut::new(b)]
+            })
+            .collect();
+
+        r.encode_sep_vectored(data, &mut io_slices).unwrap();
+    }
+    assert_eq!(parity.concat(), packet);
+}
+
+#[test]
+fn test_encode_sep_vectored_error_handling() {
+    let r = ReedSolomon::new(10, 3).unwrap();
+
+    let shards = make_random_shards!(1000, 13);
+    let (data, _) = shards.split_at(10);
+
+    {
+        let mut buffers: Vec<Vec<Vec<u8>>> = vec![vec![vec![0; 1000]]; 3];
+        buffers[1] = vec![vec![0; 500], vec![0; 499]];
+
+        assert_eq!(
+            Error::IncorrectShardSize {
+                shard: 1,
+                expected: 1000,
+                actual: 999
+            },
+            r.encode_sep_vectored(data, &mut buffers).unwrap_err()
+        );
+        assert!(buffers[0][0].iter().all(|&x| x == 0));
+    }
+    {
+        let mut buffers: Vec<Vec<Vec<u8>>> = vec![vec![vec![0; 1000]]; 4];
+
+        assert_eq!(
+            Error::TooManyParityShards,
+            r.encode_sep_vectored(data, &mut buffers).unwrap_err()
+        );
+    }
+    {
+        let mut buffers: Vec<Vec<Vec<u8>>> = vec![vec![vec![0; 1000]]; 3];
+
+        assert_eq!(
+            Error::TooFewDataShards,
+            r.encode_sep_vectored(&data[..9], &mut buffers).unwrap_err()
+        );
+    }
+}
+
+#[test]
+fn test_encode_sep_padded_same_as_zero_padded_encode() {
+    let r = ReedSolomon::new(5, 3).unwrap();
+
+    for &last_len in &[0, 1, 999, 1000] {
+        let mut shards = make_random_shards!(1000, 8);
+        for x in &mut shards[4][last_len..] {
+            *x = 0;
+        }
+        r.encode(&mut shards).unwrap();
+
+        let mut data: Vec<Vec<u8>> = shards[..5].to_vec();
+        data[4].truncate(last_len);
+        let mut parity = make_random_shards!(1000, 3);
+
+        assert_eq!(last_len, r.encode_sep_padded(&data, &mut parity).unwrap());
+        assert_eq_shards(&shards[5..], &parity);
+
+        let mut padded = data.clone();
+        padded.extend(parity.iter().cloned());
+        assert!(r.verify_padded(&padded, last_len).unwrap());
+
+        padded[6][0] ^= 1;
+        assert!(!r.verify_padded(&padded, last_len).unwrap());
+    }
+}
+
+#[test]
+fn test_reconstruct_padded() {
+    let r = ReedSolomon::new(5, 3).unwrap();
+
+    let mut data = make_random_shards!(1000, 5);
+    data[4].truncate(345);
+    let mut parity = make_random_shards!(1000, 3);
+    let last_len = r.encode_sep_padded(&data, &mut parity).unwrap();
+
+    let mut expect = data;
+    expect.extend(parity);
+
+    {
+        let mut shards = shards_to_option_shards(&expect);
+        shards[4] = None;
+        shards[0] = None;
+        shards[7] = None;
+
+        r.reconstruct_padded(&mut shards, last_len).unwrap();
+        assert_eq!(345, shards[4].as_ref().unwrap().len());
+        assert_eq_shards(&expect, &option_shards_into_shards(shards));
+    }
+    {
+        let mut shards = shards_to_option_shards(&expect);
+        shards[1] = None;
+        shards[6] = None;
+
+        r.reconstruct_data_padded(&mut shards, last_len).unwrap();
+        assert_eq!(345, shards[4].as_ref().unwrap().len());
+        assert_eq!(expect[1], *shards[1].as_ref().unwrap());
+        assert!(shards[6].is_none());
+    }
+    {
+        let mut shards = shards_to_option_shards(&expect);
+        shards[0] = None;
+        shards[1] = None;
+        shards[2] = None;
+        shards[3] = None;
+
+        assert_eq!(
+            Error::TooFewShardsPresent,
+            r.reconstruct_padded(&mut shards, last_len).unwrap_err()
+        );
+        assert_eq!(345, shards[4].as_ref().unwrap().len());
+        assert!(shards[0].is_none());
+    }
+    {
+        let mut shards = shards_to_option_shards(&expect);
+        shards[0] = None;
+
+        assert_eq!(
+            Error::IncorrectShardSize {
+                shard: 4,
+                expected: 344,
+                actual: 345
+            },
+            r.reconstruct_padded(&mut shards, 344).unwrap_err()
+        );
+        assert_eq!(
+            Error::IncorrectShardSize {
+                shard: 4,
+                expected: 1000,
+                actual: 1001
+            },
+            r.reconstruct_padded(&mut shards, 1001).unwrap_err()
+        );
+    }
+}
+
+#[test]
+fn test_encode_sep_padded_error_handling() {
+    let r = ReedSolomon::new(5, 3).unwrap();
+
+    let mut data = make_random_shards!(1000, 5);
+    let mut parity = make_random_shards!(1000, 3);
+
+    data[4].push(0);
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 4,
+            expected: 1000,
+            actual: 1001
+        },
+        r.encode_sep_padded(&data, &mut parity).unwrap_err()
+    );
+
+    data[4].truncate(10);
+    data[3].truncate(10);
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 3,
+            expected: 1000,
+            actual: 10
+        },
+        r.encode_sep_padded(&data, &mut parity).unwrap_err()
+    );
+
+    assert_eq!(
+        Error::TooFewDataShards,
+        r.encode_sep_padded(&data[..4], &mut parity).unwrap_err()
+    );
+}
+
+#[test]
+fn test_freeze_thaw() {
+    let r = ReedSolomon::new(10, 3).unwrap();
+    let frozen = r.freeze();
+
+    assert_eq!(10, frozen.data_shard_count());
+    assert_eq!(3, frozen.parity_shard_count());
+    assert_eq!(13, frozen.total_shard_count());
+    assert_eq!(frozen, frozen.clone());
+
+    let thawed = frozen.thaw();
+    assert_eq!(r, thawed);
+
+    let mut expect = make_random_shards!(1000, 13);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = expect.clone();
+    thawed.encode(&mut shards).unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    let mut shards = shards_into_option_shards(shards);
+    shards[0] = None;
+    shards[11] = None;
+    thawed.reconstruct(&mut shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+    // snapshots can be sent to other threads and thawed there
+    let handle = std::thread::spawn(move || {
+        let worker_codec = frozen.thaw();
+        worker_codec.verify(&expect).unwrap()
+    });
+    assert!(handle.join().unwrap());
+}
+
+#[test]
+fn test_builder() {
+    assert_eq!(
+        Error::TooFewDataShards,
+        ReedSolomon::builder(0, 1).build().unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewParityShards,
+        ReedSolomon::builder(1, 0).build().unwrap_err()
+    );
+    assert_eq!(
+        Error::TooManyShards { max: 256 },
+        ReedSolomon::builder(129, 128).build().unwrap_err()
+    );
+
+    let r = ReedSolomon::builder(10, 3)
+        .inversion_cache_limit(2)
+        .build()
+        .unwrap();
+    assert_eq!(ReedSolomon::new(10, 3).unwrap(), r);
+    assert_eq!(r, r.clone());
+
+    // decoding still works while the cache keeps evicting entries
+    let mut expect = make_random_shards!(100, 13);
+    r.encode(&mut expect).unwrap();
+    for i in 0..13 {
+        let mut shards = shards_to_option_shards(&expect);
+        shards[i] = None;
+        shards[(i + 5) % 13] = None;
+        r.reconstruct(&mut shards).unwrap();
+        assert_eq_shards(&expect, &option_shards_into_shards(shards));
+    }
+}
+
+#[test]
+fn test_weighted_inversion_cache() {
+    let mut expect = make_random_shards!(100, 13);
+    ReedSolomon::new(10, 3)
+        .unwrap()
+        .encode(&mut expect)
+        .unwrap();
+
+    for (budget_bytes, cached) in [(0, 0..=0), (4096, 1..=12), (1 << 20, 13..=13)] {
+        let r = ReedSolomon::builder(10, 3)
+            .inversion_cache_policy(InversionCachePolicy::Weighted { budget_bytes })
+            .build()
+            .unwrap();
+
+        for i in 0..13 {
+            let mut shards = shards_to_option_shards(&expect);
+            shards[i] = None;
+            shards[(i + 5) % 13] = None;
+            r.reconstruct(&mut shards).unwrap();
+            assert_eq_shards(&expect, &option_shards_into_shards(shards));
+        }
+        assert!(cached.contains(&cached_count(&r)));
+
+        // the policy carries over to derived codecs
+        let r2 = r.clone();
+        let mut shards = shards_to_option_shards(&expect);
+        shards[0] = None;
+        r2.reconstruct(&mut shards).unwrap();
+        assert_eq!(usize::from(budget_bytes != 0), cached_count(&r2));
+        let r3 = r.grow_parity(4).unwrap();
+        let mut shards = shards_to_option_shards(&expect);
+        shards.push(None);
+        shards[0] = None;
+        r3.reconstruct_data(&mut shards).unwrap();
+        assert_eq!(usize::from(budget_bytes != 0), cached_count(&r3));
+    }
+}
+
+#[test]
+fn test_lru_inversion_cache() {
+    let mut expect = make_random_shards!(100, 13);
+    ReedSolomon::new(10, 3)
+        .unwrap()
+        .encode(&mut expect)
+        .unwrap();
+
+    for max_entries in [0, 1, 4, 100] {
+        let r = ReedSolomon::builder(10, 3)
+            .inversion_cache_policy(InversionCachePolicy::LeastRecentlyUsed { max_entries })
+            .build()
+            .unwrap();
+
+        for i in 0..13 {
+            let mut shards = shards_to_option_shards(&expect);
+            shards[i] = None;
+            shards[(i + 5) % 13] = None;
+            r.reconstruct(&mut shards).unwrap();
+            assert_eq_shards(&expect, &option_shards_into_shards(shards));
+        }
+
+        // the cache is full of the most recent patterns
+        let exported = r.export_inversion_cache();
+        let mut count = [0; 4];
+        count.copy_from_slice(&exported[13..17]);
+        assert_eq!(max_entries.min(13), u32::from_le_bytes(count) as usize);
+    }
+}
+
+#[test]
+fn test_disabled_inversion_cache() {
+    let r = ReedSolomon::builder(10, 3)
+        .inversion_cache_policy(InversionCachePolicy::Weighted { budget_bytes: 4096 })
+        .inversion_cache(false)
+        .build()
+        .unwrap();
+    let empty_cache = r.export_inversion_cache();
+
+    let mut expect = make_random_shards!(100, 13);
+    r.encode(&mut expect).unwrap();
+    for i in 0..13 {
+        let mut shards = shards_to_option_shards(&expect);
+        shards[i] = None;
+        shards[(i + 5) % 13] = None;
+        r.reconstruct(&mut shards).unwrap();
+        assert_eq_shards(&expect, &option_shards_into_shards(shards));
+    }
+    assert_eq!(empty_cache, r.export_inversion_cache());
+
+    // derived codecs do not cache either
+    let r2 = r.grow_parity(4).unwrap();
+    let mut shards = shards_to_option_shards(&expect);
+    shards.push(None);
+    shards[0] = None;
+    r2.reconstruct_data(&mut shards).unwrap();
+    assert_eq!(
+        ReedSolomon::builder(10, 4)
+            .inversion_cache(false)
+            .build()
+            .unwrap()
+            .export_inversion_cache(),
+        r2.export_inversion_cache()
+    );
+}
+
+#[test]
+fn test_precomputed_erasures() {
+    // all single and double erasures of 13 shards, and no more than the
+    // parity shard count
+    for (max_erasures, cached) in [(0, 0), (1, 13), (2, 13 + 78), (5, 13 + 78 + 286)] {
+        let r = ReedSolomon::builder(10, 3)
+            .precomputed_erasures(max_erasures)
+            .inversion_cache_limit(usize::MAX)
+            .build()
+            .unwrap();
+        assert_eq!(cached, cached_count(&r));
+    }
+
+    let r = ReedSolomon::builder(10, 3)
+        .precomputed_erasures(2)
+        .build()
+        .unwrap();
+    let mut expect = make_random_shards!(100, 13);
+    r.encode(&mut expect).unwrap();
+    let mut shards = shards_to_option_shards(&expect);
+    shards[3] = None;
+    shards[11] = None;
+    r.reconstruct(&mut shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+    // clones and thawed codecs precompute the same matrices
+    let r = ReedSolomon::builder(10, 3)
+        .precomputed_erasures(1)
+        .build()
+        .unwrap();
+    let clone = r.clone();
+    assert_eq!(13, cached_count(&r.freeze().thaw()));
+    assert_eq!(13, cached_count(&clone));
+
+    let r = ReedSolomon::builder(10, 3)
+        .precomputed_erasures(2)
+        .inversion_cache(false)
+        .build()
+        .unwrap();
+    assert_eq!(0, cached_count(&r));
+}
+
+#[test]
+fn test_small_decoder() {
+    let codecs = [
+        ReedSolomon::new(2, 1).unwrap(),
+        ReedSolomon::new(2, 2).unwrap(),
+        ReedSolomon::new(4, 2).unwrap(),
+        ReedSolomon::new(4, 4).unwrap(),
+        ReedSolomon::builder(3, 5)
+            .matrix_kind(MatrixKind::Cauchy)
+            .build()
+            .unwrap(),
+        ReedSolomon::builder(4, 2)
+            .systematic(false)
+            .build()
+            .unwrap(),
+    ];
+
+    for r in &codecs {
+        let total = r.total_shard_count();
+        let empty_cache = r.export_inversion_cache();
+
+        let data = make_random_shards!(20, r.data_shard_count());
+        let mut expect = vec![vec![0u8; 20]; total];
+        r.encode_data(&data, &mut expect).unwrap();
+
+        // every erasure pattern which can be recovered from
+        for missing in 1u32..1 << total {
+            if missing.count_ones() as usize > r.parity_shard_count() {
+                continue;
+            }
+
+            let mut shards = shards_to_option_shards(&expect);
+            for (i, shard) in shards.iter_mut().enumerate() {
+                if missing & (1 << i) != 0 {
+                    *shard = None;
+                }
+            }
+            r.reconstruct(&mut shards).unwrap();
+            assert_eq_shards(&expect, &option_shards_into_shards(shards));
+        }
+
+        // the inversion tree is not involved
+        assert_eq!(empty_cache, r.export_inversion_cache());
+        assert_eq!(empty_cache, r.clone().export_inversion_cache());
+    }
+
+    // larger codecs still use the inversion tree
+    let r = ReedSolomon::new(5, 2).unwrap();
+    let empty_cache = r.export_inversion_cache();
+    let mut shards = make_random_shards!(20, 7);
+    r.encode(&mut shards).unwrap();
+    let mut shards = shards_to_option_shards(&shards);
+    shards[0] = None;
+    r.reconstruct(&mut shards).unwrap();
+    assert_ne!(empty_cache, r.export_inversion_cache());
+}
+
+#[test]
+fn test_inversion_cache_export_import() {
+    let r = ReedSolomon::new(10, 3).unwrap();
+
+    let mut expect = make_random_shards!(100, 13);
+    r.encode(&mut expect).unwrap();
+    for &(i, j) in &[(0, 5), (2, 11), (10, 12)] {
+        let mut shards = shards_to_option_shards(&expect);
+        shards[i] = None;
+        shards[j] = None;
+        r.reconstruct(&mut shards).unwrap();
+    }
+
+    let exported = r.export_inversion_cache();
+
+    let r2 = ReedSolomon::new(10, 3).unwrap();
+    r2.import_inversion_cache(&exported).unwrap();
+    assert_eq!(exported, r2.export_inversion_cache());
+
+    // importing again does not duplicate entries
+    r2.import_inversion_cache(&exported).unwrap();
+    assert_eq!(exported, r2.export_inversion_cache());
+
+    let mut shards = shards_to_option_shards(&expect);
+    shards[2] = None;
+    shards[11] = None;
+    r2.reconstruct(&mut shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+    // configuration mismatch
+    assert_eq!(
+        Error::InvalidInversionCache,
+        ReedSolomon::new(10, 4)
+            .unwrap()
+            .import_inversion_cache(&exported)
+            .unwrap_err()
+    );
+
+    // truncated and trailing data
+    let r3 = ReedSolomon::new(10, 3).unwrap();
+    assert_eq!(
+        Error::InvalidInversionCache,
+        r3.import_inversion_cache(&exported[..exported.len() - 1])
+            .unwrap_err()
+    );
+    let mut trailing = exported;
+    trailing.push(0);
+    assert_eq!(
+        Error::InvalidInversionCache,
+        r3.import_inversion_cache(&trailing).unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidInversionCache,
+        r3.import_inversion_cache(&[]).unwrap_err()
+    );
+    assert_eq!(
+        ReedSolomon::new(10, 3).unwrap().export_inversion_cache(),
+        r3.export_inversion_cache()
+    );
+}
+
+#[test]
+fn test_cauchy_matrix() {
+    let r = ReedSolomon::builder(10, 4)
+        .matrix_kind(MatrixKind::Cauchy)
+        .build()
+        .unwrap();
+    assert_ne!(ReedSolomon::new(10, 4).unwrap(), r);
+    assert_eq!(r, r.clone());
+
+    let mut expect = make_random_shards!(100, 14);
+    r.encode(&mut expect).unwrap();
+    assert!(r.verify(&expect).unwrap());
+    assert!(!ReedSolomon::new(10, 4).unwrap().verify(&expect).unwrap());
+
+    // every combination of 4 missing shards can be recovered
+    for a in 0..14 {
+        for b in a + 1..14 {
+            for c in b + 1..14 {
+                for d in c + 1..14 {
+                    let mut shards = shards_to_option_shards(&expect);
+                    shards[a] = None;
+                    shards[b] = None;
+                    shards[c] = None;
+                    shards[d] = None;
+                    r.reconstruct(&mut shards).unwrap();
+                    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_audit_sample() {
+    let r = ReedSolomon::new(10, 3).unwrap();
+
+    let mut shards = make_random_shards!(100, 13);
+    r.encode(&mut shards).unwrap();
+
+    assert!(r.audit_sample(&shards, &[]).unwrap());
+    assert!(r.audit_sample(&shards, &[0, 17, 99]).unwrap());
+
+    assert_eq!(
+        Error::InvalidSamplePosition,
+        r.audit_sample(&shards, &[0, 100]).unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewShards,
+        r.audit_sample(&shards[1..], &[0]).unwrap_err()
+    );
+
+    // corruption in a data shard and in a parity shard
+    for &i in &[4, 11] {
+        let mut corrupted = shards.clone();
+        corrupted[i][17] ^= 1;
+        assert!(r.audit_sample(&corrupted, &[3, 18]).unwrap());
+        assert!(!r.audit_sample(&corrupted, &[17]).unwrap());
+        assert!(!r.audit_sample(&corrupted, &[0, 17, 99]).unwrap());
+    }
+}
+
+#[test]
+fn test_verify_punctured() {
+    let r = ReedSolomon::new(4, 3).unwrap();
+
+    let mut shards = make_random_shards!(100, 7);
+    r.encode(&mut shards).unwrap();
+
+    let punctured = [false, false, false, false, true, false, true];
+    let mut transmitted: Vec<_> = shards
+        .iter()
+        .zip(&punctured)
+        .filter(|(_, &x)| !x)
+        .map(|(shard, _)| shard.clone())
+        .collect();
+
+    assert!(r.verify_punctured(&transmitted, &punctured).unwrap());
+    assert!(r.verify_punctured(&shards, &[false; 7]).unwrap());
+    assert!(r
+        .verify_punctured(
+            &shards[..4],
+            &[false, false, false, false, true, true, true]
+        )
+        .unwrap());
+
+    // the transmitted parity shard is the second one
+    transmitted[4][0] ^= 1;
+    assert!(!r.verify_punctured(&transmitted, &punctured).unwrap());
+    transmitted[4][0] ^= 1;
+    transmitted[0][99] ^= 1;
+    assert!(!r.verify_punctured(&transmitted, &punctured).unwrap());
+
+    // a missing shard is not a punctured one
+    assert_eq!(
+        Error::TooFewShards,
+        r.verify_punctured(&transmitted[1..], &punctured)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::TooManyShards { max: 5 },
+        r.verify_punctured(&shards, &punctured).unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidShardFlags,
+        r.verify_punctured(&transmitted, &punctured[1..])
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidPuncturing,
+        r.verify_punctured(
+            &transmitted,
+            &[true, false, false, false, false, false, true]
+        )
+        .unwrap_err()
+    );
+}
+
+#[test]
+fn test_new_with_matrix() {
+    // encoding the identity yields the parity rows of the encoding matrix
+    let r = ReedSolomon::new(10, 3).unwrap();
+    let mut shards = vec![vec![0u8; 10]; 13];
+    for (i, shard) in shards.iter_mut().take(10).enumerate() {
+        shard[i] = 1;
+    }
+    r.encode(&mut shards).unwrap();
+    let parity_rows = &shards[10..];
+
+    let r2 = ReedSolomon::new_with_matrix(10, 3, parity_rows).unwrap();
+    assert_eq!(r, r2);
+
+    let mut expect = make_random_shards!(100, 13);
+    r2.encode(&mut expect).unwrap();
+    assert!(r.verify(&expect).unwrap());
+
+    let mut shards = shards_to_option_shards(&expect);
+    shards[1] = None;
+    shards[4] = None;
+    shards[12] = None;
+    r2.reconstruct(&mut shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+    assert_eq!(
+        Error::TooFewParityShards,
+        ReedSolomon::new_with_matrix(10, 3, &parity_rows[1..]).unwrap_err()
+    );
+    assert_eq!(
+        Error::TooManyParityShards,
+        ReedSolomon::new_with_matrix(10, 2, parity_rows).unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewDataShards,
+        ReedSolomon::new_with_matrix(0, 3, parity_rows).unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidMatrix,
+        ReedSolomon::new_with_matrix(9, 3, parity_rows).unwrap_err()
+    );
+
+    // a zero coefficient means the data shard cannot be recovered
+    // from that parity shard alone
+    let mut singular = parity_rows.to_vec();
+    singular[0][3] = 0;
+    assert_eq!(
+        Error::InvalidMatrix,
+        ReedSolomon::new_with_matrix(10, 3, &singular).unwrap_err()
+    );
+
+    // two equal parity rows
+    let mut singular = parity_rows.to_vec();
+    singular[2] = singular[1].clone();
+    assert_eq!(
+        Error::InvalidMatrix,
+        ReedSolomon::new_with_matrix(10, 3, &singular).unwrap_err()
+    );
+    let unchecked = ReedSolomon::new_with_matrix_unchecked(10, 3, &singular).unwrap();
+    let mut shards = shards_to_option_shards(&expect);
+    for &i in &[0, 1, 10] {
+        shards[i] = None;
+    }
+    assert_eq!(
+        Error::InvalidMatrix,
+        unchecked.reconstruct(&mut shards).unwrap_err()
+    );
+    assert!(shards[0].is_none());
+
+    // C(18, 10) = 43758 submatrices are checked, C(19, 10) = 92378 are not
+    let r = ReedSolomon::new(10, 9).unwrap();
+    ReedSolomon::new_with_matrix(10, 8, &r.parity_matrix()[..8]).unwrap();
+    assert_eq!(
+        Error::TooManyShards { max: 18 },
+        ReedSolomon::new_with_matrix(10, 9, &r.parity_matrix()).unwrap_err()
+    );
+    assert_eq!(
+        r,
+        ReedSolomon::new_with_matrix_unchecked(10, 9, &r.parity_matrix()).unwrap()
+    );
+
+    let r = ReedSolomon::new(200, 56).unwrap();
+    assert_eq!(
+        Error::TooManyShards { max: 202 },
+        ReedSolomon::new_with_matrix(200, 56, &r.parity_matrix()).unwrap_err()
+    );
+    assert_eq!(
+        r,
+        ReedSolomon::new_with_matrix_unchecked(200, 56, &r.parity_matrix()).unwrap()
+    );
+}
+
+#[test]
+fn test_encoding_matrix() {
+    let identity: Vec<_> = (0..10)
+        .map(|i| {
+            let mut shard = vec![0u8; 10];
+            shard[i] = 1;
+            shard
+        })
+        .collect();
+
+    let r = ReedSolomon::new(10, 3).unwrap();
+    let parity_rows = r.parity_matrix();
+    assert_eq!(3, parity_rows.len());
+    assert_eq!(
+        r,
+        ReedSolomon::new_with_matrix(10, 3, &parity_rows).unwrap()
+    );
+
+    let matrix = r.encoding_matrix();
+    assert_eq!(identity, matrix[..10]);
+    assert_eq!(parity_rows, matrix[10..]);
+
+    // the shards of the identity are the rows of the encoding matrix
+    for r in &[
+        ReedSolomon::builder(10, 3)
+            .matrix_kind(MatrixKind::Cauchy)
+            .build()
+            .unwrap(),
+        ReedSolomon::builder(10, 3)
+            .systematic(false)
+            .build()
+            .unwrap(),
+    ] {
+        let mut shards = vec![vec![0u8; 10]; 13];
+        r.encode_data(&identity, &mut shards).unwrap();
+        assert_eq!(shards, r.encoding_matrix());
+    }
+
+    // the parity rows of a non-systematic codec apply to the mixed data shards
+    let r = ReedSolomon::builder(10, 3)
+        .systematic(false)
+        .build()
+        .unwrap();
+    let matrix = r.encoding_matrix();
+    let mut shards = matrix.clone();
+    for shard in &mut shards[10..] {
+        for x in shard.iter_mut() {
+            *x = 0;
+        }
+    }
+    ReedSolomon::new_with_matrix(10, 3, &r.parity_matrix())
+        .unwrap()
+        .encode(&mut shards)
+        .unwrap();
+    assert_eq!(matrix, shards);
+}
+
+#[test]
+fn test_config() {
+    use crate::{FieldKind, ReedSolomonConfig};
+
+    for r in &[
+        ReedSolomon::new(10, 4).unwrap(),
+        ReedSolomon::builder(10, 4)
+            .matrix_kind(MatrixKind::Cauchy)
+            .build()
+            .unwrap(),
+        ReedSolomon::builder(10, 4)
+            .systematic(false)
+            .build()
+            .unwrap(),
+    ] {
+        let config = r.config().unwrap();
+        assert_eq!(FieldKind::Galois8, config.field);
+        assert_eq!(galois_8::POLYNOMIAL, config.polynomial);
+        assert_eq!(10, config.data_shards);
+        assert_eq!(4, config.parity_shards);
+        assert_eq!(*r, config.build::<galois_8::Field>().unwrap());
+    }
+
+    let config = ReedSolomon::builder(3, 2)
+        .matrix_kind(MatrixKind::Cauchy)
+        .build()
+        .unwrap()
+        .config()
+        .unwrap();
+    assert_eq!(
+        ReedSolomonConfig {
+            field: FieldKind::Galois8,
+            polynomial: 0x11d,
+            data_shards: 3,
+            parity_shards: 2,
+            matrix_kind: MatrixKind::Cauchy,
+            systematic: true,
+        },
+        config
+    );
+
+    let r16 = crate::galois_16::ReedSolomon::new(3, 2).unwrap();
+    assert_eq!(FieldKind::Galois16, r16.config().unwrap().field);
+    assert_eq!(
+        Error::IncompatibleField,
+        config.build::<crate::galois_16::Field>().unwrap_err()
+    );
+    assert_eq!(
+        Error::IncompatibleField,
+        ReedSolomonConfig {
+            polynomial: 0x11b,
+            ..config
+        }
+        .build::<galois_8::Field>()
+        .unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewParityShards,
+        ReedSolomonConfig {
+            parity_shards: 0,
+            ..config
+        }
+        .build::<galois_8::Field>()
+        .unwrap_err()
+    );
+
+    // custom matrices have no configuration
+    let r = ReedSolomon::new(10, 4).unwrap();
+    assert_eq!(
+        Error::InvalidMatrix,
+        r.shorten(&[3]).unwrap().config().unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidMatrix,
+        ReedSolomon::new_with_matrix(2, 1, &[[1, 2]])
+            .unwrap()
+            .config()
+            .unwrap_err()
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_config_serde() {
+    use crate::ReedSolomonConfig;
+
+    let r = ReedSolomon::builder(10, 4)
+        .matrix_kind(MatrixKind::Cauchy)
+        .build()
+        .unwrap();
+
+    let json = serde_json::to_string(&r.config().unwrap()).unwrap();
+    assert_eq!(
+        r#"{"field":"Galois8","polynomial":285,"data_shards":10,"parity_shards":4,"matrix_kind":"Cauchy","systematic":true}"#,
+        json
+    );
+
+    let config: ReedSolomonConfig = serde_json::from_str(&json).unwrap();
+    assert_eq!(r, config.build().unwrap());
+}
+
+#[test]
+fn test_non_systematic() {
+    assert_eq!(
+        Error::TooManyShards { max: 255 },
+        ReedSolomon::builder(128, 128)
+            .systematic(false)
+            .build()
+            .unwrap_err()
+    );
+    ReedSolomon::builder(128, 127)
+        .systematic(false)
+        .build()
+        .unwrap();
+    assert_eq!(
+        Error::TooFewDataShards,
+        ReedSolomon::builder(1, 3)
+            .systematic(false)
+            .build()
+            .unwrap_err()
+    );
+    ReedSolomon::builder(2, 3)
+        .systematic(false)
+        .build()
+        .unwrap();
+
+    let r = ReedSolomon::builder(10, 3)
+        .systematic(false)
+        .build()
+        .unwrap();
+    assert_ne!(ReedSolomon::new(10, 3).unwrap(), r);
+    assert_eq!(r, r.freeze().thaw());
+
+    let data = make_random_shards!(100, 10);
+    let mut expect = vec![vec![0u8; 100]; 13];
+    r.encode_data(&data, &mut expect).unwrap();
+    assert!(r.verify(&expect).unwrap());
+
+    // no shard holds a data shard in plain
+    for shard in &expect {
+        for d in &data {
+            assert_ne!(shard, d);
+        }
+    }
+
+    let mut decoded = vec![vec![0u8; 100]; 10];
+    let mut shards = shards_to_option_shards(&expect);
+    r.decode_data(&mut shards, &mut decoded).unwrap();
+    assert_eq_shards(&data, &decoded);
+
+    let mut decoded = vec![vec![0u8; 100]; 10];
+    let mut shards = shards_to_option_shards(&expect);
+    shards[0] = None;
+    shards[7] = None;
+    shards[12] = None;
+    r.decode_data(&mut shards, &mut decoded).unwrap();
+    assert_eq_shards(&data, &decoded);
+
+    assert_eq!(
+        Error::TooFewDataShards,
+        r.encode_data(&data[1..], &mut expect).unwrap_err()
+    );
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 0,
+            expected: 100,
+            actual: 99
+        },
+        r.decode_data(
+            &mut shards_to_option_shards(&expect),
+            &mut vec![vec![0u8; 99]; 10]
+        )
+        .unwrap_err()
+    );
+}
+
+#[test]
+fn test_encode_data_systematic() {
+    let r = ReedSolomon::new(10, 3).unwrap();
+
+    let data = make_random_shards!(100, 10);
+    let mut shards = vec![vec![0u8; 100]; 13];
+    r.encode_data(&data, &mut shards).unwrap();
+    assert_eq_shards(&data, &shards[..10]);
+
+    let mut expect = shards.clone();
+    r.encode(&mut expect).unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    let mut decoded = vec![vec![0u8; 100]; 10];
+    let mut shards = shards_to_option_shards(&shards);
+    shards[3] = None;
+    r.decode_data(&mut shards, &mut decoded).unwrap();
+    assert_eq_shards(&data, &decoded);
+}
+
+#[test]
+fn test_reshard() {
+    let r = ReedSolomon::new(6, 3).unwrap();
+    let target = ReedSolomon::new(10, 4).unwrap();
+
+    let mut shards = make_random_shards!(100, 9);
+    r.encode(&mut shards).unwrap();
+    let data: Vec<u8> = shards[..6].concat();
+
+    let mut option_shards = shards_to_option_shards(&shards);
+    option_shards[2] = None;
+    option_shards[7] = None;
+
+    let new_shards = r.reshard(&mut option_shards, &target).unwrap();
+    assert_eq!(14, new_shards.len());
+    assert!(target.verify(&new_shards).unwrap());
+
+    // 600 bytes of data in 10 shards of 60 bytes
+    assert_eq!(data, new_shards[..10].concat());
+
+    // the data is padded with zeros
+    let target = ReedSolomon::new(7, 2).unwrap();
+    let new_shards = r
+        .reshard(&mut shards_to_option_shards(&shards), &target)
+        .unwrap();
+    assert!(target.verify(&new_shards).unwrap());
+    assert_eq!(86, new_shards[0].len());
+    assert_eq!(&data[..], &new_shards[..7].concat()[..600]);
+    assert!(new_shards[6][84..].iter().all(|&x| x == 0));
+
+    // only the parity shard count changes, so the present parity shards are
+    // copied, which is observable when one of them is corrupted
+    let target = ReedSolomon::new(6, 4).unwrap();
+    let mut corrupted = shards.clone();
+    corrupted[6][0] ^= 1;
+    let new_shards = r
+        .reshard(&mut shards_to_option_shards(&corrupted), &target)
+        .unwrap();
+    assert_eq!(corrupted[6], new_shards[6]);
+    assert!(!target.verify(&new_shards).unwrap());
+
+    let mut option_shards = shards_to_option_shards(&corrupted);
+    option_shards[6] = None;
+    let new_shards = r.reshard(&mut option_shards, &target).unwrap();
+    assert!(target.verify(&new_shards).unwrap());
+    assert_eq_shards(&shards, &new_shards[..9]);
+
+    // non-systematic codecs
+    let mixed = ReedSolomon::builder(4, 2)
+        .systematic(false)
+        .build()
+        .unwrap();
+    let new_shards = r
+        .reshard(&mut shards_to_option_shards(&shards), &mixed)
+        .unwrap();
+    let mut decoded = vec![vec![0u8; 150]; 4];
+    mixed
+        .decode_data(&mut shards_to_option_shards(&new_shards), &mut decoded)
+        .unwrap();
+    assert_eq!(data, decoded.concat());
+
+    let new_shards = mixed
+        .reshard(&mut shards_to_option_shards(&new_shards), &r)
+        .unwrap();
+    assert_eq_shards(&shards, &new_shards);
+
+    let mut option_shards = shards_to_option_shards(&shards);
+    for shard in &mut option_shards[..4] {
+        *shard = None;
+    }
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reshard(&mut option_shards, &target).unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewShards,
+        r.reshard(&mut option_shards[1..], &target).unwrap_err()
+    );
+}
+
+#[test]
+fn test_grow_parity() {
+    let r = ReedSolomon::new(5, 2).unwrap();
+    let grown = r.grow_parity(4).unwrap();
+    assert_eq!(4, grown.parity_shard_count());
+    assert_eq!(ReedSolomon::new(5, 4).unwrap(), grown);
+
+    let mut shards = make_random_shards!(100, 7);
+    r.encode(&mut shards).unwrap();
+
+    shards.extend(vec![vec![0u8; 100]; 2]);
+    {
+        let (data, parity) = shards.split_at_mut(5);
+        grown.encode_parity_from(2, data, &mut parity[2..]).unwrap();
+    }
+    assert!(grown.verify(&shards).unwrap());
+
+    let mut option_shards = shards_to_option_shards(&shards);
+    for i in &[0, 1, 3, 6] {
+        option_shards[*i] = None;
+    }
+    grown.reconstruct(&mut option_shards).unwrap();
+    assert_eq_shards(&shards, &option_shards_to_shards(&option_shards));
+
+    let (data, parity) = shards.split_at_mut(5);
+    assert_eq!(
+        Error::TooFewParityShards,
+        grown
+            .encode_parity_from(2, data, &mut parity[3..])
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::TooManyParityShards,
+        grown
+            .encode_parity_from(3, data, &mut parity[2..])
+            .unwrap_err()
+    );
+
+    let cauchy = ReedSolomon::builder(5, 2)
+        .matrix_kind(MatrixKind::Cauchy)
+        .build()
+        .unwrap();
+    assert_eq!(
+        ReedSolomon::builder(5, 3)
+            .matrix_kind(MatrixKind::Cauchy)
+            .build()
+            .unwrap(),
+        cauchy.grow_parity(3).unwrap()
+    );
+
+    let mixed = ReedSolomon::builder(5, 2)
+        .systematic(false)
+        .build()
+        .unwrap();
+    assert_eq!(
+        ReedSolomon::builder(5, 3)
+            .systematic(false)
+            .build()
+            .unwrap(),
+        mixed.grow_parity(3).unwrap()
+    );
+
+    let custom = ReedSolomon::new_with_matrix(2, 1, &[[1, 1]]).unwrap();
+    assert_eq!(Error::InvalidMatrix, custom.grow_parity(2).unwrap_err());
+
+    assert_eq!(Error::TooFewParityShards, r.grow_parity(1).unwrap_err());
+    assert_eq!(
+        Error::TooManyShards { max: 256 },
+        r.grow_parity(252).unwrap_err()
+    );
+}
+
+#[test]
+fn test_shorten() {
+    let r = ReedSolomon::new(10, 4).unwrap();
+    let shortened = r.shorten(&[9, 3, 7]).unwrap();
+    assert_eq!(7, shortened.data_shard_count());
+    assert_eq!(4, shortened.parity_shard_count());
+
+    let mut shards = make_random_shards!(100, 11);
+    shortened.encode(&mut shards).unwrap();
+    assert!(shortened.verify(&shards).unwrap());
+
+    // the parity is the one of the full codec with zeros in place of the
+    // absent data shards
+    let mut full = vec![vec![0u8; 100]; 14];
+    for (&i, shard) in [0, 1, 2, 4, 5, 6, 8].iter().zip(&shards) {
+        full[i] = shard.clone();
+    }
+    r.encode(&mut full).unwrap();
+    assert_eq_shards(&full[10..], &shards[7..]);
+
+    let mut option_shards = shards_to_option_shards(&shards);
+    for i in &[0, 5, 6, 9] {
+        option_shards[*i] = None;
+    }
+    shortened.reconstruct(&mut option_shards).unwrap();
+    assert_eq_shards(&shards, &option_shards_to_shards(&option_shards));
+
+    assert_eq!(r, r.shorten(&[]).unwrap());
+
+    assert_eq!(Error::InvalidIndex, r.shorten(&[10]).unwrap_err());
+    assert_eq!(Error::InvalidIndex, r.shorten(&[2, 2]).unwrap_err());
+    assert_eq!(
+        Error::TooFewDataShards,
+        r.shorten(&(0..10).collect::<Vec<_>>()).unwrap_err()
+    );
+
+    let mixed = ReedSolomon::builder(10, 4)
+        .systematic(false)
+        .build()
+        .unwrap();
+    assert_eq!(Error::InvalidMatrix, mixed.shorten(&[0]).unwrap_err());
+}
+
+#[test]
+fn test_progress() {
+    use crate::Progress;
+
+    const MB: usize = 1024 * 1024;
+
+    let r = ReedSolomon::new(3, 2).unwrap();
+
+    let mut expect = make_random_shards!(2 * MB + 100, 5);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = expect.clone();
+    for shard in &mut shards[3..] {
+        for x in shard.iter_mut() {
+            *x = 0;
+        }
+    }
+    let mut events = Vec::new();
+    r.encode_with_progress(&mut shards, |x| events.push(x))
+        .unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    let total = 3 * (2 * MB + 100) as u64;
+    assert_eq!(
+        vec![
+            Progress::Bytes {
+                processed: 3 * MB as u64,
+                total
+            },
+            Progress::Bytes {
+                processed: 6 * MB as u64,
+                total
+            },
+            Progress::Bytes {
+                processed: total,
+                total
+            },
+            Progress::ShardCompleted(3),
+            Progress::ShardCompleted(4),
+        ],
+        events
+    );
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[1] = None;
+    shards[4] = None;
+    let mut events = Vec::new();
+    r.reconstruct_with_progress(&mut shards, |x| events.push(x))
+        .unwrap();
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+    assert_eq!(5, events.len());
+    assert_eq!(
+        Progress::Bytes {
+            processed: total,
+            total
+        },
+        events[2]
+    );
+    assert_eq!(
+        &[Progress::ShardCompleted(1), Progress::ShardCompleted(4)],
+        &events[3..]
+    );
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[1] = None;
+    shards[4] = None;
+    let mut events = Vec::new();
+    r.reconstruct_data_with_progress(&mut shards, |x| events.push(x))
+        .unwrap();
+    assert_eq!(expect[1], *shards[1].as_ref().unwrap());
+    assert!(shards[4].is_none());
+    assert_eq!(Progress::ShardCompleted(1), events[3]);
+    assert_eq!(4, events.len());
+
+    // nothing to do
+    let mut shards = shards_into_option_shards(expect.clone());
+    let mut events = Vec::new();
+    r.reconstruct_with_progress(&mut shards, |x| events.push(x))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // nothing is reported or touched on error
+    let mut shards = shards_into_option_shards(expect);
+    for shard in &mut shards[..3] {
+        *shard = None;
+    }
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_with_progress(&mut shards, |_| panic!())
+            .unwrap_err()
+    );
+    assert!(shards[..3].iter().all(Option::is_none));
+    assert_eq!(
+        Error::TooFewShards,
+        r.encode_with_progress(vec![vec![0u8]; 4], |_| panic!())
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_cancellation() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let r = ReedSolomon::new(3, 2).unwrap();
+    let cancel = AtomicBool::new(false);
+
+    let mut expect = make_random_shards!(1000, 5);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = expect.clone();
+    for shard in &mut shards[3..] {
+        for x in shard.iter_mut() {
+            *x = 0;
+        }
+    }
+    r.encode_cancellable(&mut shards, &cancel).unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[0] = None;
+    shards[3] = None;
+    r.reconstruct_cancellable(&mut shards, &cancel).unwrap();
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[0] = None;
+    shards[3] = None;
+    r.reconstruct_data_cancellable(&mut shards, &cancel)
+        .unwrap();
+    assert_eq!(expect[0], *shards[0].as_ref().unwrap());
+    assert!(shards[3].is_none());
+
+    // nothing is touched once cancelled
+    cancel.store(true, Ordering::Relaxed);
+
+    let mut shards = expect.clone();
+    for shard in &mut shards[3..] {
+        for x in shard.iter_mut() {
+            *x = 0;
+        }
+    }
+    assert_eq!(
+        Error::Cancelled,
+        r.encode_cancellable(&mut shards, &cancel).unwrap_err()
+    );
+    assert!(shards[3..].iter().flatten().all(|&x| x == 0));
+
+    let mut shards = shards_into_option_shards(expect);
+    shards[0] = None;
+    assert_eq!(
+        Error::Cancelled,
+        r.reconstruct_cancellable(&mut shards, &cancel).unwrap_err()
+    );
+    assert_eq!(
+        Error::Cancelled,
+        r.reconstruct_data_cancellable(&mut shards, &cancel)
+            .unwrap_err()
+    );
+    assert!(shards[0].is_none());
+
+    // other errors take precedence
+    shards[1] = None;
+    shards[2] = None;
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_cancellable(&mut shards, &cancel).unwrap_err()
+    );
+}
+
+#[test]
+fn test_chunked() {
+    let r = ReedSolomon::new(3, 2).unwrap();
+
+    let mut expect = make_random_shards!(1000, 5);
+    r.encode(&mut expect).unwrap();
+
+    // chunks which divide the shards or not, and ones too small
+    // to hold a byte of each shard
+    for &chunk_bytes in &[0, 1, 5, 35, 5000, 1 << 20] {
+        let mut shards = expect.clone();
+        for shard in &mut shards[3..] {
+            for x in shard.iter_mut() {
+                *x = 0;
+            }
+        }
+        r.encode_chunked(&mut shards, chunk_bytes).unwrap();
+        assert_eq_shards(&expect, &shards);
+
+        let mut shards = shards_into_option_shards(expect.clone());
+        shards[0] = None;
+        shards[3] = None;
+        r.reconstruct_chunked(&mut shards, chunk_bytes).unwrap();
+        assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+        let mut shards = shards_into_option_shards(expect.clone());
+        shards[1] = None;
+        shards[4] = None;
+        r.reconstruct_data_chunked(&mut shards, chunk_bytes)
+            .unwrap();
+        assert_eq!(expect[1], *shards[1].as_ref().unwrap());
+        assert!(shards[4].is_none());
+    }
+
+    let mut shards = shards_into_option_shards(expect);
+    shards[0] = None;
+    shards[1] = None;
+    shards[2] = None;
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_chunked(&mut shards, 64).unwrap_err()
+    );
+    assert!(shards[0].is_none());
+}
+
+fn all_platforms() -> Vec<Platform> {
+    vec![
+        Platform::Portable,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Platform::SSE3,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Platform::AVX2,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Platform::AVX512,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Platform::AVX512VBMI,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Platform::GFNI,
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        Platform::NEON,
+        #[cfg(target_arch = "aarch64")]
+        Platform::SVE,
+        #[cfg(target_arch = "powerpc64")]
+        Platform::VSX,
+        #[cfg(target_arch = "s390x")]
+        Platform::ZVECTOR,
+        #[cfg(target_arch = "loongarch64")]
+        Platform::LSX,
+        #[cfg(target_arch = "loongarch64")]
+        Platform::LASX,
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        Platform::SIMD128,
+    ]
+}
+
+#[test]
+fn test_platform() {
+    let detected = ReedSolomon::new(3, 2).unwrap();
+    assert_eq!(None, detected.platform());
+
+    // an odd length leaves a tail for the vectorized kernels
+    let mut expect = make_random_shards!(1001, 5);
+    detected.encode(&mut expect).unwrap();
+
+    for platform in all_platforms() {
+        let result = ReedSolomon::builder(3, 2).platform(platform).build();
+        if !platform.is_supported() {
+            assert_eq!(Error::UnsupportedPlatform, result.unwrap_err());
+            continue;
+        }
+        let r = result.unwrap();
+        assert_eq!(Some(platform), r.platform());
+
+        let mut shards = expect.clone();
+        for shard in &mut shards[3..] {
+            shard.fill(0);
+        }
+        r.encode(&mut shards).unwrap();
+        assert_eq_shards(&expect, &shards);
+        assert!(r.verify(&expect).unwrap());
+
+        let mut shards = shards_into_option_shards(expect.clone());
+        shards[0] = None;
+        shards[4] = None;
+        r.reconstruct(&mut shards).unwrap();
+        assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+        // the platform is kept by derived codecs
+        assert_eq!(Some(platform), r.freeze().thaw().platform());
+        assert_eq!(Some(platform), r.grow_parity(3).unwrap().platform());
+        assert_eq!(Some(platform), r.shorten(&[1]).unwrap().platform());
+    }
+
+    assert!(Platform::Portable.is_supported());
+    assert!(Platform::detect().is_supported());
+}
+
+#[test]
+fn test_calibrate_platform() {
+    let supported = Platform::supported();
+    assert_eq!(Platform::detect(), supported[0]);
+    assert!(supported.contains(&Platform::Portable));
+    for platform in all_platforms() {
+        assert_eq!(platform.is_supported(), supported.contains(&platform));
+    }
+
+    let r = ReedSolomon::builder(3, 2)
+        .calibrate_platform(true)
+        .build()
+        .unwrap();
+    let platform = r.platform().unwrap();
+    assert!(supported.contains(&platform));
+
+    let mut expect = make_random_shards!(100, 5);
+    ReedSolomon::new(3, 2).unwrap().encode(&mut expect).unwrap();
+    assert!(r.verify(&expect).unwrap());
+
+    // a forced platform takes precedence
+    let r = ReedSolomon::builder(3, 2)
+        .platform(Platform::Portable)
+        .calibrate_platform(true)
+        .build()
+        .unwrap();
+    assert_eq!(Some(Platform::Portable), r.platform());
+
+    assert!(Platform::calibrate::<crate::galois_16::Field>().is_supported());
+    assert!(Platform::calibrate::<crate::galois_64::Field>().is_supported());
+}
+
+#[test]
+fn test_encode_cache_blocks() {
+    // long enough for several cache blocks and a partial last one
+    let r = ReedSolomon::new(10, 4).unwrap();
+    let mut expect = make_random_shards!(100_003, 14);
+    r.encode(&mut expect).unwrap();
+
+    // each short window is coded without blocking
+    let mut parity = vec![vec![0; 100_003]; 4];
+    let mut offset = 0;
+    while offset < 100_003 {
+        let end = 100_003.min(offset + 1000);
+        let data: Vec<&[u8]> = expect[..10].iter().map(|x| &x[offset..end]).collect();
+        let mut parity: Vec<&mut [u8]> = parity.iter_mut().map(|x| &mut x[offset..end]).collect();
+        r.encode_sep(&data, &mut parity).unwrap();
+        offset = end;
+    }
+    assert_eq_shards(&expect[10..], &parity);
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    for i in 0..4 {
+        shards[i * 3] = None;
+    }
+    r.reconstruct(&mut shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+}
+
+#[test]
+fn test_max_working_memory() {
+    let unlimited = ReedSolomon::new(3, 2).unwrap();
+
+    let mut expect = make_random_shards!(1000, 5);
+    unlimited.encode(&mut expect).unwrap();
+
+    // budgets which divide the shards or not, ones too small to hold
+    // a byte of each shard, and ones larger than the stripe
+    for &bytes in &[0, 1, 5, 35, 5000, 1 << 20] {
+        let r = ReedSolomon::builder(3, 2)
+            .max_working_memory(bytes)
+            .build()
+            .unwrap();
+
+        let mut shards = expect.clone();
+        for shard in &mut shards[3..] {
+            for x in shard.iter_mut() {
+                *x = 0;
+            }
+        }
+        r.encode(&mut shards).unwrap();
+        assert_eq_shards(&expect, &shards);
+
+        let mut parity = vec![vec![0; 1000]; 2];
+        r.encode_sep(&expect[..3], &mut parity).unwrap();
+        assert_eq_shards(&expect[3..], &parity);
+
+        let mut shards = shards_into_option_shards(expect.clone());
+        shards[0] = None;
+        shards[3] = None;
+        r.reconstruct(&mut shards).unwrap();
+        assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+        let mut shards = shards_into_option_shards(expect.clone());
+        shards[1] = None;
+        shards[4] = None;
+        r.reconstruct_data(&mut shards).unwrap();
+        assert_eq!(expect[1], *shards[1].as_ref().unwrap());
+        assert!(shards[4].is_none());
+
+        // the limit is kept by derived codecs
+        let grown = r.grow_parity(3).unwrap().freeze().thaw();
+        let mut shards = expect.clone();
+        shards.push(vec![0; 1000]);
+        grown.encode(&mut shards).unwrap();
+        assert_eq_shards(&expect, &shards[..5]);
+        assert!(grown.verify(&shards).unwrap());
+    }
+
+    let r = ReedSolomon::builder(3, 2)
+        .max_working_memory(64)
+        .build()
+        .unwrap();
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[0] = None;
+    shards[1] = None;
+    shards[2] = None;
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct(&mut shards).unwrap_err()
+    );
+    assert!(shards[0].is_none());
+
+    let mut shards = shards_into_option_shards(expect);
+    shards[0] = None;
+    shards[1] = Some(vec![0; 999]);
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 2,
+            expected: 999,
+            actual: 1000,
+        },
+        r.reconstruct(&mut shards).unwrap_err()
+    );
+    assert!(shards[0].is_none());
+}
+
+#[test]
+fn test_reconstruct_with_report() {
+    use crate::ReconstructReport;
+
+    let r = ReedSolomon::new(4, 3).unwrap();
+
+    let mut expect = make_random_shards!(100, 7);
+    r.encode(&mut expect).unwrap();
+
+    // nothing missing, nothing used
+    let mut shards = shards_to_option_shards(&expect);
+    assert_eq!(
+        ReconstructReport::default(),
+        r.reconstruct_with_report(&mut shards).unwrap()
+    );
+
+    let mut shards = shards_to_option_shards(&expect);
+    shards[1] = None;
+    shards[2] = None;
+    shards[5] = None;
+    assert_eq!(
+        ReconstructReport {
+            used: vec![0, 3, 4, 6],
+            rebuilt: vec![1, 2, 5],
+        },
+        r.reconstruct_with_report(&mut shards).unwrap()
+    );
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+    // missing parity shards are left alone
+    let mut shards = shards_to_option_shards(&expect);
+    shards[0] = None;
+    shards[4] = None;
+    assert_eq!(
+        ReconstructReport {
+            used: vec![1, 2, 3, 5],
+            rebuilt: vec![0],
+        },
+        r.reconstruct_data_with_report(&mut shards).unwrap()
+    );
+    assert_eq!(None, shards[4]);
+    assert_eq_shards(
+        &expect[..4],
+        &option_shards_into_shards(shards[..4].to_vec()),
+    );
+
+    let mut shards = shards_to_option_shards(&expect);
+    shards[5] = None;
+    assert_eq!(
+        ReconstructReport::default(),
+        r.reconstruct_data_with_report(&mut shards).unwrap()
+    );
+
+    let mut shards = shards_to_option_shards(&expect);
+    shards[0] = None;
+    shards[1] = None;
+    shards[2] = None;
+    shards[3] = None;
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_with_report(&mut shards).unwrap_err()
+    );
+}
+
+#[test]
+fn test_reconstruct_with_scratch() {
+    let r = ReedSolomon::new(10, 4).unwrap();
+    let mut scratch = galois_8::ReconstructScratch::new(&r);
+
+    let mut expect = make_random_shards!(1000, 14);
+    r.encode(&mut expect).unwrap();
+
+    let mut rng = thread_rng();
+    for _ in 0..50 {
+        let mut shards = shards_to_option_shards(&expect);
+        for _ in 0..rng.gen_range(0..=4) {
+            shards[rng.gen_range(0..14)] = None;
+        }
+        let mut data_shards = shards.clone();
+
+        r.reconstruct_with_scratch(&mut shards, &mut scratch)
+            .unwrap();
+        assert_eq_shards(&expect, &option_shards_to_shards(&shards));
+
+        let parity_present: Vec<_> = data_shards[10..].iter().map(Option::is_some).collect();
+        r.reconstruct_data_with_scratch(&mut data_shards, &mut scratch)
+            .unwrap();
+        assert_eq_shards(&expect[..10], &option_shards_to_shards(&data_shards[..10]));
+        for (shard, &present) in data_shards[10..].iter().zip(&parity_present) {
+            assert_eq!(present, shard.is_some());
+        }
+    }
+
+    // the scratch follows the codec it is used with
+    let cauchy = ReedSolomon::builder(10, 4)
+        .matrix_kind(MatrixKind::Cauchy)
+        .build()
+        .unwrap();
+    let small = ReedSolomon::new(3, 2).unwrap();
+    for codec in &[&cauchy, &small, &r] {
+        let mut expect = make_random_shards!(100, codec.total_shard_count());
+        codec.encode(&mut expect).unwrap();
+
+        let mut shards = shards_to_option_shards(&expect);
+        shards[0] = None;
+        shards[codec.data_shard_count()] = None;
+        codec
+            .reconstruct_with_scratch(&mut shards, &mut scratch)
+            .unwrap();
+        assert_eq_shards(&expect, &option_shards_to_shards(&shards));
+    }
+
+    let mut shards = shards_to_option_shards(&expect);
+    for shard in &mut shards[..5] {
+        *shard = None;
+    }
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_with_scratch(&mut shards, &mut scratch)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewShards,
+        r.reconstruct_with_scratch(&mut shards[1..], &mut scratch)
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_decode_plan() {
+    let r = ReedSolomon::new(10, 4).unwrap();
+
+    let mut present = [true; 14];
+    present[2] = false;
+    present[7] = false;
+    present[12] = false;
+    let plan = r.decode_plan(&present).unwrap();
+    assert_eq!(&present[..], plan.present());
+
+    // the same plan for many stripes
+    for _ in 0..3 {
+        let mut expect = make_random_shards!(1_000, 14);
+        r.encode(&mut expect).unwrap();
+
+        let mut shards = expect.clone();
+        for (shard, &present) in shards.iter_mut().zip(&present) {
+            if !present {
+                fill_random(shard);
+            }
+        }
+        plan.reconstruct(&mut shards).unwrap();
+        assert_eq_shards(&expect, &shards);
+
+        let mut shards = expect.clone();
+        shards[2] = vec![0; 1_000];
+        shards[12] = vec![0; 1_000];
+        plan.reconstruct_data(&mut shards).unwrap();
+        assert_eq!(expect[2], shards[2]);
+        assert_eq!(vec![0; 1_000], shards[12]);
+    }
+
+    // nothing to do
+    let mut expect = make_random_shards!(10, 14);
+    r.encode(&mut expect).unwrap();
+    let mut shards = expect.clone();
+    r.decode_plan(&[true; 14])
+        .unwrap()
+        .reconstruct(&mut shards)
+        .unwrap();
+    assert_eq!(expect, shards);
+
+    assert_eq!(
+        Error::InvalidShardFlags,
+        r.decode_plan(&[true; 13]).unwrap_err()
+    );
+    let mut present = [false; 14];
+    present[..9].fill(true);
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.decode_plan(&present).unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewShards,
+        plan.reconstruct(&mut shards[1..]).unwrap_err()
+    );
+    shards[5].pop();
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 5,
+            expected: 10,
+            actual: 9
+        },
+        plan.reconstruct(&mut shards).unwrap_err()
+    );
+}
+
+#[test]
+fn test_reconstruct_parallel() {
+    let r = ReedSolomon::new(20, 10).unwrap();
+
+    let mut expect = make_random_shards!(10_001, 30);
+    r.encode(&mut expect).unwrap();
+
+    for &threads in &[0, 1, 4, 7] {
+        let mut shards = shards_to_option_shards(&expect);
+        for i in &[0, 3, 7, 12, 19, 20, 22, 25, 28, 29] {
+            shards[*i] = None;
+        }
+        r.reconstruct_parallel(&mut shards, threads).unwrap();
+        assert_eq_shards(&expect, &option_shards_to_shards(&shards));
+    }
+
+    let mut shards = shards_to_option_shards(&expect);
+    shards[5] = None;
+    shards[21] = None;
+    r.reconstruct_data_parallel(&mut shards, 3).unwrap();
+    assert_eq!(Some(&expect[5]), shards[5].as_ref());
+    assert_eq!(None, shards[21]);
+
+    // more threads than elements
+    let r = ReedSolomon::new(3, 2).unwrap();
+    let mut expect = make_random_shards!(3, 5);
+    r.encode(&mut expect).unwrap();
+    let mut shards = shards_to_option_shards(&expect);
+    shards[1] = None;
+    shards[4] = None;
+    r.reconstruct_parallel(&mut shards, 8).unwrap();
+    assert_eq_shards(&expect, &option_shards_to_shards(&shards));
+
+    shards[0] = None;
+    shards[1] = None;
+    shards[2] = None;
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_parallel(&mut shards, 2).unwrap_err()
+    );
+    shards[0] = Some(vec![0; 2]);
+    shards[1] = Some(vec![0; 3]);
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 1,
+            expected: 2,
+            actual: 3
+        },
+        r.reconstruct_parallel(&mut shards, 2).unwrap_err()
+    );
+}
+
+#[test]
+fn test_reconstruct_parallel_in() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // counts the jobs and runs them on the calling thread
+    struct Inline(AtomicUsize);
+
+    impl Executor for Inline {
+        fn execute<'a>(&self, jobs: Vec<Box<dyn FnOnce() + Send + 'a>>) {
+            self.0.fetch_add(jobs.len(), Ordering::SeqCst);
+            for job in jobs {
+                job();
+            }
+        }
+    }
+
+    let r = ReedSolomon::new(10, 4).unwrap();
+
+    let mut expect = make_random_shards!(1_000, 14);
+    r.encode(&mut expect).unwrap();
+
+    let executor = Inline(AtomicUsize::new(0));
+    let mut shards = shards_to_option_shards(&expect);
+    shards[2] = None;
+    shards[11] = None;
+    r.reconstruct_parallel_in(&mut shards, 4, &executor)
+        .unwrap();
+    assert_eq_shards(&expect, &option_shards_to_shards(&shards));
+    assert_eq!(4, executor.0.load(Ordering::SeqCst));
+
+    shards[2] = None;
+    shards[11] = None;
+    r.reconstruct_data_parallel_in(&mut shards, 3, &ScopedThreads)
+        .unwrap();
+    assert_eq!(Some(&expect[2]), shards[2].as_ref());
+    assert_eq!(None, shards[11]);
+
+    // the errors of the jobs are returned
+    let mut shards = shards_to_option_shards(&expect);
+    for shard in &mut shards[..5] {
+        *shard = None;
+    }
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_parallel_in(&mut shards, 2, &executor)
+            .unwrap_err()
+    );
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_reconstruct_parallel_rayon() {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .build()
+        .unwrap();
+    let r = ReedSolomon::new(10, 4).unwrap();
+
+    let mut expect = make_random_shards!(10_000, 14);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = shards_to_option_shards(&expect);
+    shards[0] = None;
+    shards[7] = None;
+    shards[13] = None;
+    r.reconstruct_parallel_in(&mut shards, 8, &pool).unwrap();
+    assert_eq_shards(&expect, &option_shards_to_shards(&shards));
+}
+
+#[test]
+fn test_reconstruct_range() {
+    let r = ReedSolomon::new(5, 3).unwrap();
+
+    let mut expect = make_random_shards!(1000, 8);
+    r.encode(&mut expect).unwrap();
+
+    let mut present = [true; 8];
+    present[0] = false;
+    present[3] = false;
+    present[6] = false;
+
+    let mut shards = expect.clone();
+    for (shard, &present) in shards.iter_mut().zip(&present) {
+        if !present {
+            *shard = vec![0; 1000];
+        }
+    }
+
+    // only the range of the missing shards is written
+    r.reconstruct_range(&mut shards, &present, 100..150)
+        .unwrap();
+    for i in 0..8 {
+        assert_eq!(expect[i][100..150], shards[i][100..150]);
+        if !present[i] {
+            assert!(shards[i][..100].iter().all(|&x| x == 0));
+            assert!(shards[i][150..].iter().all(|&x| x == 0));
+        }
+    }
+
+    let mut shards_data = shards.clone();
+    r.reconstruct_data_range(&mut shards_data, &present, 990..1000)
+        .unwrap();
+    assert_eq!(expect[0][990..], shards_data[0][990..]);
+    assert_eq!(expect[3][990..], shards_data[3][990..]);
+    assert!(shards_data[6][990..].iter().all(|&x| x == 0));
+
+    // only the range of the present shards is read
+    let mut garbled = shards.clone();
+    for (shard, &present) in garbled.iter_mut().zip(&present) {
+        if present {
+            shard[..500].iter_mut().for_each(|x| *x = !*x);
+        }
+    }
+    r.reconstruct_range(&mut garbled, &present, 500..1000)
+        .unwrap();
+    assert_eq!(expect[6][500..], garbled[6][500..]);
+
+    assert_eq!(
+        Error::InvalidRange,
+        r.reconstruct_range(&mut shards, &present, 900..1001)
+            .unwrap_err()
+    );
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = 20..10;
+    assert_eq!(
+        Error::InvalidRange,
+        r.reconstruct_range(&mut shards, &present, reversed)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::EmptyShard,
+        r.reconstruct_range(&mut shards, &present, 10..10)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidShardFlags,
+        r.reconstruct_range(&mut shards, &present[1..], 0..10)
+            .unwrap_err()
+    );
+
+    let mut too_few = present;
+    too_few[1] = false;
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_range(&mut shards, &too_few, 0..10)
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_reconstruct_interleaved() {
+    let r = ReedSolomon::new(4, 2).unwrap();
+
+    let mut expect = make_random_shards!(100, 6);
+    r.encode(&mut expect).unwrap();
+
+    // three shards hit, but never more than two in the same sub-stripe
+    let erasures = [(0, 0..50), (1, 50..100), (2, 30..35), (5, 99..100)];
+
+    let mut shards = expect.clone();
+    for (i, range) in erasures.iter().cloned() {
+        for x in &mut shards[i][range] {
+            *x = !*x;
+        }
+    }
+
+    r.reconstruct_interleaved(&mut shards, 10, &erasures)
+        .unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    // segments of 34, 34 and 32 elements
+    let mut shards = expect.clone();
+    shards[3][33] = !shards[3][33];
+    shards[4][34] = !shards[4][34];
+    r.reconstruct_interleaved(&mut shards, 3, &[(3, 33..34), (4, 34..35), (0, 68..68)])
+        .unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    let mut shards = expect.clone();
+    shards[2][0] = !shards[2][0];
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_interleaved(&mut shards, 10, &[(0, 0..10), (1, 5..6), (2, 0..1)])
+            .unwrap_err()
+    );
+    assert_ne!(expect[2][0], shards[2][0]);
+
+    assert_eq!(
+        Error::InvalidInterleaving,
+        r.reconstruct_interleaved(&mut shards, 0, &[]).unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidInterleaving,
+        r.reconstruct_interleaved(&mut shards, 10, &[(6, 0..1)])
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidInterleaving,
+        r.reconstruct_interleaved(&mut shards, 10, &[(0, 90..101)])
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewShards,
+        r.reconstruct_interleaved(&mut shards[1..], 10, &[])
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_update_parity() {
+    let r = ReedSolomon::new(5, 3).unwrap();
+
+    let mut old = make_random_shards!(100, 8);
+    r.encode(&mut old).unwrap();
+
+    let mut new = old.clone();
+    fill_random(&mut new[2]);
+    fill_random(&mut new[4]);
+
+    let mut next_parity = vec![vec![0u8; 100]; 3];
+    r.update_parity(2, &old[2], &new[2], &old[5..], &mut next_parity)
+        .unwrap();
+    let mut new_parity = vec![vec![0u8; 100]; 3];
+    r.update_parity(4, &old[4], &new[4], &next_parity, &mut new_parity)
+        .unwrap();
+
+    new[5..].clone_from_slice(&new_parity);
+    assert!(r.verify(&new).unwrap());
+
+    let mut expect = new.clone();
+    r.encode(&mut expect).unwrap();
+    assert_eq_shards(&expect, &new);
+
+    // the old version is still intact until committed
+    assert!(r.verify(&old).unwrap());
+    let mut option_shards = shards_to_option_shards(&old);
+    option_shards[0] = None;
+    option_shards[6] = None;
+    r.reconstruct(&mut option_shards).unwrap();
+    assert_eq_shards(&old, &option_shards_to_shards(&option_shards));
+
+    assert_eq!(
+        Error::InvalidIndex,
+        r.update_parity(5, &old[2], &new[2], &old[5..], &mut new_parity)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewParityShards,
+        r.update_parity(2, &old[2], &new[2], &old[6..], &mut new_parity)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 2,
+            expected: 100,
+            actual: 99
+        },
+        r.update_parity(2, &old[2][1..], &new[2][1..], &old[5..], &mut new_parity)
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_update_range() {
+    use crate::RangeStore;
+    use std::io;
+
+    struct Store {
+        stripes: Vec<Vec<Vec<u8>>>,
+        failing: Option<usize>,
+        read: usize,
+    }
+
+    impl RangeStore<galois_8::Field> for Store {
+        fn shard_len(&mut self, stripe: usize) -> io::Result<usize> {
+            Ok(self.stripes[stripe][0].len())
+        }
+
+        fn read(
+            &mut self,
+            stripe: usize,
+            shard: usize,
+            offset: usize,
+            len: usize,
+        ) -> io::Result<Vec<u8>> {
+            if self.failing == Some(shard) {
+                return Err(io::Error::other("failing shard"));
+            }
+            self.read += len;
+            Ok(self.stripes[stripe][shard][offset..offset + len].to_vec())
+        }
+
+        fn write(
+            &mut self,
+            stripe: usize,
+            shard: usize,
+            offset: usize,
+            data: &[u8],
+        ) -> io::Result<()> {
+            self.stripes[stripe][shard][offset..offset + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    let r = ReedSolomon::new(4, 2).unwrap();
+
+    let mut stripes = vec![make_random_shards!(100, 6), make_random_shards!(100, 6)];
+    for stripe in &mut stripes {
+        r.encode(stripe).unwrap();
+    }
+    let mut store = Store {
+        stripes,
+        failing: None,
+        read: 0,
+    };
+
+    // within a shard, across two shards, and over a whole shard
+    for &(offset, len) in &[(10, 20), (190, 30), (250, 150), (0, 400)] {
+        let new_data: Vec<u8> = (0..len).map(|_| rand::random()).collect();
+
+        let mut expect = store.stripes[1].clone();
+        for (i, &x) in new_data.iter().enumerate() {
+            expect[(offset + i) / 100][(offset + i) % 100] = x;
+        }
+        r.encode(&mut expect).unwrap();
+
+        let other = store.stripes[0].clone();
+        r.update_range(1, offset, &new_data, &mut store).unwrap();
+        assert_eq_shards(&expect, &store.stripes[1]);
+        assert_eq!(other, store.stripes[0]);
+    }
+
+    // only the range and the parity under it are read
+    store.read = 0;
+    r.update_range(1, 10, &[1, 2, 3], &mut store).unwrap();
+    assert_eq!(3 + 2 * 3, store.read);
+
+    // nothing is written if a read fails
+    let before = store.stripes.clone();
+    store.failing = Some(5);
+    let err = r.update_range(0, 0, &[1, 2, 3], &mut store).unwrap_err();
+    assert_eq!(io::ErrorKind::Other, err.kind());
+    assert_eq!(before, store.stripes);
+    store.failing = None;
+
+    let error = |offset, new_data: &[u8], store: &mut Store| {
+        let err = r.update_range(0, offset, new_data, store).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        *err.get_ref().unwrap().downcast_ref::<Error>().unwrap()
+    };
+    assert_eq!(Error::InvalidRange, error(399, &[1, 2], &mut store));
+    assert_eq!(Error::InvalidRange, error(usize::MAX, &[1], &mut store));
+    assert_eq!(Error::EmptyShard, error(0, &[], &mut store));
+    assert_eq!(before, store.stripes);
+}
+
+#[test]
+fn test_encode_batch() {
+    let r = ReedSolomon::new(4, 2).unwrap();
+
+    let mut stripes: Vec<_> = (0..100).map(|i| make_random_shards!(10 + i, 6)).collect();
+    let mut expect = stripes.clone();
+    for stripe in &mut expect {
+        r.encode(stripe).unwrap();
+    }
+
+    r.encode_batch(&mut stripes).unwrap();
+    assert_eq!(expect, stripes);
+
+    let mut empty: Vec<Vec<Vec<u8>>> = vec![];
+    r.encode_batch(&mut empty).unwrap();
+
+    // an invalid stripe leaves the others untouched
+    let mut stripes: Vec<_> = (0..3).map(|_| make_random_shards!(10, 6)).collect();
+    stripes[2][5] = vec![0; 9];
+    let copy = stripes.clone();
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 5,
+            expected: 10,
+            actual: 9
+        },
+        r.encode_batch(&mut stripes).unwrap_err()
+    );
+    assert_eq!(copy, stripes);
+
+    stripes[2].pop();
+    assert_eq!(
+        Error::TooFewShards,
+        r.encode_batch(&mut stripes).unwrap_err()
+    );
+}
+
+#[test]
+fn test_stream_decoder() {
+    let r = ReedSolomon::new(5, 2).unwrap();
+
+    let mut stripes: Vec<_> = (0..4).map(|_| make_random_shards!(50, 7)).collect();
+    for stripe in &mut stripes {
+        r.encode(stripe).unwrap();
+    }
+
+    // fragments in shuffled order, with two shards of each stripe lost
+    let mut fragments: Vec<(usize, Vec<u8>)> = Vec::new();
+    for (i_stripe, stripe) in stripes.iter().enumerate() {
+        for (i_shard, shard) in stripe.iter().enumerate() {
+            if i_shard != i_stripe && i_shard != (i_stripe + 3) % 7 {
+                fragments.push((i_stripe * 7 + i_shard, shard.clone()));
+            }
+        }
+    }
+    fragments.reverse();
+    fragments.swap(1, 12);
+
+    let mut decoder = StreamDecoder::new(&r);
+    let mut blocks = Vec::new();
+    for (index, shard) in fragments {
+        decoder.push(index, shard).unwrap();
+        while let Some(block) = decoder.next_block() {
+            blocks.push(block);
+        }
+    }
+
+    assert_eq!(4, blocks.len());
+    for (block, stripe) in blocks.iter().zip(&stripes) {
+        assert_eq!(&stripe[..5].concat(), block);
+    }
+    assert_eq!(4, decoder.next_stripe_index());
+    assert_eq!(0, decoder.pending_stripe_count());
+
+    // late fragments of handed out stripes are ignored
+    decoder.push(3, vec![0; 50]).unwrap();
+    assert_eq!(0, decoder.pending_stripe_count());
+
+    assert_eq!(Error::EmptyShard, decoder.push(28, vec![]).unwrap_err());
+    decoder.push(28, vec![0; 50]).unwrap();
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 1,
+            expected: 50,
+            actual: 49
+        },
+        decoder.push(29, vec![0; 49]).unwrap_err()
+    );
+    assert_eq!(None, decoder.next_block());
+    assert_eq!(1, decoder.pending_stripe_count());
+}
+
+#[test]
+fn test_stream_decoder_duplicates() {
+    let r = ReedSolomon::new(2, 2).unwrap();
+
+    let mut stripe = make_random_shards!(10, 4);
+    r.encode(&mut stripe).unwrap();
+    let expect = stripe[..2].concat();
+
+    let mut corrupted = stripe[0].clone();
+    corrupted[3] ^= 1;
+
+    // identical copies are ignored
+    let mut decoder = StreamDecoder::new(&r);
+    decoder.push(0, stripe[0].clone()).unwrap();
+    decoder.push(0, stripe[0].clone()).unwrap();
+    assert_eq!(None, decoder.next_block());
+    decoder.push(2, stripe[2].clone()).unwrap();
+    assert_eq!(Some(expect.clone()), decoder.next_block());
+
+    // conflicting unverified copies are treated as missing
+    let mut decoder = StreamDecoder::new(&r);
+    decoder.push(0, corrupted.clone()).unwrap();
+    decoder.push(0, stripe[0].clone()).unwrap();
+    decoder.push(1, stripe[1].clone()).unwrap();
+    assert_eq!(None, decoder.next_block());
+    decoder.push(0, stripe[0].clone()).unwrap();
+    assert_eq!(None, decoder.next_block());
+    decoder.push(3, stripe[3].clone()).unwrap();
+    assert_eq!(Some(expect.clone()), decoder.next_block());
+
+    // unless a verified copy arrives
+    let mut decoder = StreamDecoder::new(&r);
+    decoder.push(0, corrupted.clone()).unwrap();
+    decoder.push(0, stripe[0].clone()).unwrap();
+    decoder.push_verified(0, stripe[0].clone()).unwrap();
+    decoder.push(1, stripe[1].clone()).unwrap();
+    assert_eq!(Some(expect.clone()), decoder.next_block());
+
+    // verified copies replace unverified ones and are kept over later ones
+    let mut decoder = StreamDecoder::new(&r);
+    decoder.push(0, corrupted.clone()).unwrap();
+    decoder.push_verified(0, stripe[0].clone()).unwrap();
+    decoder.push(0, corrupted.clone()).unwrap();
+    decoder.push_verified(0, corrupted.clone()).unwrap();
+    decoder.push(1, stripe[1].clone()).unwrap();
+    assert_eq!(Some(expect), decoder.next_block());
+
+    let mut decoder = StreamDecoder::new(&r);
+    decoder.push(0, stripe[0].clone()).unwrap();
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 0,
+            expected: 10,
+            actual: 9
+        },
+        decoder.push_verified(0, vec![0; 9]).unwrap_err()
+    );
+    assert_eq!(
+        Error::EmptyShard,
+        decoder.push_verified(1, vec![]).unwrap_err()
+    );
+}
+
+struct ShardStore {
+    shards: Vec<Vec<u8>>,
+    broken: Vec<usize>,
+    reads: Vec<(usize, usize, usize)>,
+}
+
+impl RangeFetch<galois_8::Field> for ShardStore {
+    fn fetch(&mut self, shard: usize, offset: usize, len: usize) -> Option<Vec<u8>> {
+        self.reads.push((shard, offset, len));
+        if self.broken.contains(&shard) {
+            None
+        } else {
+            Some(self.shards[shard][offset..offset + len].to_vec())
+        }
+    }
+}
+
+#[test]
+fn test_fetch_data_range() {
+    let r = ReedSolomon::new(4, 2).unwrap();
+
+    let mut shards = make_random_shards!(100, 6);
+    r.encode(&mut shards).unwrap();
+    let expect: Vec<Vec<u8>> = shards[..4].iter().map(|s| s[10..30].to_vec()).collect();
+
+    // all data shards available, only their ranges are read
+    let mut store = ShardStore {
+        shards,
+        broken: vec![],
+        reads: vec![],
+    };
+    let data = r.fetch_data_range(&mut store, &[true; 6], 10, 20).unwrap();
+    assert_eq_shards(&expect, &data);
+    assert_eq!(
+        vec![(0, 10, 20), (1, 10, 20), (2, 10, 20), (3, 10, 20)],
+        store.reads
+    );
+
+    // one shard known to be lost and one failing to be read
+    store.reads.clear();
+    store.broken = vec![2];
+    let data = r
+        .fetch_data_range(&mut store, &[false, true, true, true, true, true], 10, 20)
+        .unwrap();
+    assert_eq_shards(&expect, &data);
+    assert_eq!(
+        vec![
+            (1, 10, 20),
+            (2, 10, 20),
+            (3, 10, 20),
+            (4, 10, 20),
+            (5, 10, 20)
+        ],
+        store.reads
+    );
+
+    store.broken = vec![2, 5];
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.fetch_data_range(&mut store, &[false, true, true, true, true, true], 10, 20)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidShardFlags,
+        r.fetch_data_range(&mut store, &[true; 5], 10, 20)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::EmptyShard,
+        r.fetch_data_range(&mut store, &[true; 6], 10, 0)
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_testkit_stripe() {
+    let shards = crate::testkit::stripe(0, 3, 2, 10).unwrap();
+    assert_eq!(5, shards.len());
+    assert!(ReedSolomon::new(3, 2).unwrap().verify(&shards).unwrap());
+
+    // SplitMix64 seeded with 0 first yields 0xe220a8397b1dcdaf
+    assert_eq!(
+        &[0xaf, 0xcd, 0x1d, 0x7b, 0x39, 0xa8, 0x20, 0xe2],
+        &shards[0][..8]
+    );
+
+    assert_eq!(shards, crate::testkit::stripe(0, 3, 2, 10).unwrap());
+    assert_ne!(shards, crate::testkit::stripe(1, 3, 2, 10).unwrap());
+
+    // the parity matches the one of the SIMD code paths
+    let mut expect = crate::testkit::stripe(7, 10, 4, 1000).unwrap();
+    let shards = expect.clone();
+    ReedSolomon::new(10, 4)
+        .unwrap()
+        .encode(&mut expect)
+        .unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    assert_eq!(
+        Error::TooFewParityShards,
+        crate::testkit::stripe(0, 3, 0, 10).unwrap_err()
+    );
+}
+
+#[test]
+fn test_compare_stripes() {
+    use crate::diff::{compare_stripes, ShardDiff};
+
+    let a = crate::testkit::stripe(3, 4, 2, 100).unwrap();
+    let mut b = a.clone();
+
+    assert!(compare_stripes(&a, &b).is_empty());
+
+    b[1][10] ^= 1;
+    b[4][0] ^= 0xff;
+    b[4][50] ^= 2;
+    b[4][99] ^= 4;
+
+    let diff = compare_stripes(&a, &b);
+    assert_eq!(
+        vec![
+            ShardDiff {
+                index: 1,
+                first: 10,
+                last: 10,
+                count: 1
+            },
+            ShardDiff {
+                index: 4,
+                first: 0,
+                last: 99,
+                count: 3
+            },
+        ],
+        diff.shards
+    );
+    assert_eq!(vec![1, 4], diff.indices());
+    assert_eq!(4, diff.count());
+
+    // differing lengths and shard counts
+    let mut b = a.clone();
+    b[2].truncate(90);
+    b.pop();
+
+    let diff = compare_stripes(&a, &b);
+    assert_eq!(
+        vec![
+            ShardDiff {
+                index: 2,
+                first: 90,
+                last: 99,
+                count: 10
+            },
+            ShardDiff {
+                index: 5,
+                first: 0,
+                last: 99,
+                count: 100
+            },
+        ],
+        diff.shards
+    );
+    assert_eq!(diff, compare_stripes(&b, &a));
+}
+
+const_assert_geometry!(1, 1, galois_8::Field);
+const_assert_geometry!(128, 128, galois_8::Field);
+
+#[test]
+fn test_max_shards() {
+    assert_eq!(256, galois_8::MAX_SHARDS);
+    assert_eq!(65536, crate::galois_16::MAX_SHARDS);
+
+    assert_eq!(
+        Error::TooManyShards { max: 256 },
+        ReedSolomon::new(galois_8::MAX_SHARDS, 1).unwrap_err()
+    );
+}
+
+#[test]
+fn test_pack() {
+    use crate::pack::{self, Extent, Packer, StripeFetch};
+
+    struct Store {
+        stripes: Vec<Vec<Vec<u8>>>,
+        lost: Vec<(usize, usize)>,
+        fetched: usize,
+    }
+
+    impl StripeFetch for Store {
+        fn fetch(
+            &mut self,
+            stripe: usize,
+            shard: usize,
+            offset: usize,
+            len: usize,
+        ) -> Option<Vec<u8>> {
+            if self.lost.contains(&(stripe, shard)) {
+                return None;
+            }
+            self.fetched += len;
+            Some(self.stripes[stripe][shard][offset..offset + len].to_vec())
+        }
+    }
+
+    let r = ReedSolomon::new(3, 2).unwrap();
+
+    let objects: Vec<Vec<u8>> = [10, 0, 25, 1, 50, 7]
+        .iter()
+        .map(|&len| (0..len).map(|_| rand::random()).collect())
+        .collect();
+
+    let mut packer = Packer::new(&r, 16).unwrap();
+    for (i, object) in objects.iter().enumerate() {
+        assert_eq!(i, packer.push(object));
+    }
+    let (stripes, manifest) = packer.finish().unwrap();
+
+    // 93 bytes over 48 bytes of data per stripe
+    assert_eq!(2, stripes.len());
+    assert_eq!(2, manifest.stripe_count);
+    for stripe in &stripes {
+        assert!(r.verify(stripe).unwrap());
+    }
+    assert!(manifest.objects[1].is_empty());
+    assert_eq!(
+        vec![
+            Extent {
+                stripe: 0,
+                shard: 0,
+                offset: 10,
+                len: 6,
+            },
+            Extent {
+                stripe: 0,
+                shard: 1,
+                offset: 0,
+                len: 16,
+            },
+            Extent {
+                stripe: 0,
+                shard: 2,
+                offset: 0,
+                len: 3,
+            },
+        ],
+        manifest.objects[2]
+    );
+    assert_eq!(Some(50), manifest.object_len(4));
+    assert_eq!(None, manifest.object_len(6));
+
+    let mut store = Store {
+        stripes,
+        lost: Vec::new(),
+        fetched: 0,
+    };
+    for (i, object) in objects.iter().enumerate() {
+        assert_eq!(
+            *object,
+            pack::read_object(&r, &manifest, i, &mut store).unwrap()
+        );
+    }
+    // only the objects are read while nothing is lost
+    assert_eq!(93, store.fetched);
+
+    // degraded reads decode only the lost extents
+    store.lost = vec![(0, 1), (1, 0)];
+    store.fetched = 0;
+    assert_eq!(
+        objects[2],
+        pack::read_object(&r, &manifest, 2, &mut store).unwrap()
+    );
+    assert_eq!(6 + 3 * 16 + 3, store.fetched);
+    for (i, object) in objects.iter().enumerate() {
+        assert_eq!(
+            *object,
+            pack::read_object(&r, &manifest, i, &mut store).unwrap()
+        );
+    }
+
+    store.lost = vec![(0, 0), (0, 1), (0, 2)];
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        pack::read_object(&r, &manifest, 0, &mut store).unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidIndex,
+        pack::read_object(&r, &manifest, 6, &mut store).unwrap_err()
+    );
+    assert_eq!(Error::EmptyShard, Packer::new(&r, 0).unwrap_err());
+}
+
+#[test]
+fn test_tiering() {
+    use crate::pack::{self, Packer, StripeFetch, Tier, TierPolicy, Tiering};
+    use std::time::Duration;
+
+    struct Store(Vec<Vec<Option<Vec<u8>>>>);
+
+    impl StripeFetch for Store {
+        fn fetch(
+            &mut self,
+            stripe: usize,
+            shard: usize,
+            offset: usize,
+            len: usize,
+        ) -> Option<Vec<u8>> {
+            let shard = self.0[stripe].get(shard)?.as_ref()?;
+            Some(shard[offset..offset + len].to_vec())
+        }
+    }
+
+    let policy = TierPolicy {
+        hot_parity: 4,
+        cold_parity: 2,
+        cold_after: Duration::from_secs(90),
+    };
+    let tiering = Tiering::new(3, policy).unwrap();
+    assert_eq!(Tier::Hot, policy.tier(Duration::from_secs(89)));
+    assert_eq!(Tier::Cold, policy.tier(Duration::from_secs(90)));
+
+    let objects: Vec<Vec<u8>> = [40, 70, 30]
+        .iter()
+        .map(|&len| (0..len).map(|_| rand::random()).collect())
+        .collect();
+    let mut packer = Packer::new(tiering.hot(), 16).unwrap();
+    for object in &objects {
+        packer.push(object);
+    }
+    let (mut stripes, mut manifest) = packer.finish().unwrap();
+    assert_eq!(3, manifest.stripe_count);
+    assert_eq!(vec![4, 4, 4], manifest.parity);
+    let original = stripes.clone();
+
+    let ages = [150, 10, 90].map(Duration::from_secs);
+    assert_eq!(
+        vec![0, 2],
+        tiering
+            .transcode(&mut manifest, &mut stripes, &ages)
+            .unwrap()
+    );
+    assert_eq!(vec![2, 4, 2], manifest.parity);
+    assert_eq!(Some(Tier::Cold), tiering.tier_of(&manifest, 0));
+    assert_eq!(5, stripes[0].len());
+    assert!(tiering.cold().verify(&stripes[0]).unwrap());
+    assert_eq!(original[0][..5], stripes[0][..]);
+
+    // cold stripes are read with the hot codec, without their dropped parity
+    let mut store = Store(
+        stripes
+            .iter()
+            .map(|x| x.iter().cloned().map(Some).collect())
+            .collect(),
+    );
+    store.0[0][0] = None;
+    store.0[0][2] = None;
+    for (i, object) in objects.iter().enumerate() {
+        assert_eq!(
+            *object,
+            pack::read_object(tiering.hot(), &manifest, i, &mut store).unwrap()
+        );
+    }
+    store.0[0][3] = None;
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        pack::read_object(tiering.hot(), &manifest, 0, &mut store).unwrap_err()
+    );
+
+    // back up on demand, recomputing only the dropped parity
+    assert!(tiering
+        .set_tier(&mut manifest, 0, &mut stripes[0], Tier::Hot)
+        .unwrap());
+    assert_eq!(original[0], stripes[0]);
+    assert!(!tiering
+        .set_tier(&mut manifest, 0, &mut stripes[0], Tier::Hot)
+        .unwrap());
+    assert_eq!(vec![4, 4, 2], manifest.parity);
+
+    assert_eq!(
+        Error::TooManyShards { max: 5 },
+        tiering
+            .set_tier(&mut manifest, 2, &mut original[2].clone(), Tier::Hot)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidIndex,
+        tiering
+            .set_tier(&mut manifest, 3, &mut stripes[0], Tier::Hot)
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidIndex,
+        tiering
+            .transcode(&mut manifest, &mut stripes[..2], &ages[..2])
+            .unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewParityShards,
+        Tiering::new(
+            3,
+            TierPolicy {
+                hot_parity: 1,
+                ..policy
+            }
+        )
+        .unwrap_err()
+    );
+}
+
+#[test]
+fn test_galois_facade() {
+    use crate::{galois, galois_16, Field};
+
+    fn check<F: Field>(c: F::Elem, input: &[F::Elem]) {
+        let mut out = vec![F::zero(); input.len()];
+        galois::mul_slice::<F>(c, input, &mut out);
+        for (&a, &o) in input.iter().zip(&out) {
+            assert_eq!(galois::mul::<F>(c, a), o);
+            assert_eq!(F::mul(c, a), o);
+        }
+
+        // adding the products again cancels them out in characteristic 2
+        galois::checked_mul_slice_add::<F>(c, input, &mut out).unwrap();
+        assert!(out.iter().all(|&o| o == F::zero()));
+        assert_eq!(
+            Err(Error::IncorrectShardSize {
+                shard: 0,
+                expected: 10003,
+                actual: 10002
+            }),
+            galois::checked_mul_slice::<F>(c, input, &mut out[1..])
+        );
+
+        assert_eq!(F::one(), galois::exp::<F>(c, 0));
+        assert_eq!(
+            Err(Error::DivisionByZero),
+            galois::checked_div::<F>(c, F::zero())
+        );
+        if c != F::zero() {
+            assert_eq!(F::one(), galois::div::<F>(c, c));
+            assert_eq!(
+                galois::mul::<F>(c, c),
+                galois::add::<F>(galois::exp::<F>(c, 2), F::zero())
+            );
+        }
+    }
+
+    let input: Vec<u8> = (0..10_003).map(|_| rand::random()).collect();
+    for _ in 0..10 {
+        check::<galois_8::Field>(rand::random(), &input);
+    }
+
+    let input: Vec<[u8; 2]> = (0..10_003).map(|_| rand::random()).collect();
+    for _ in 0..10 {
+        check::<galois_16::Field>(rand::random(), &input);
+    }
+}
+
+#[test]
+fn test_content_ids() {
+    use crate::content::{self, ContentHash};
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    struct Sip;
+
+    impl ContentHash for Sip {
+        type Id = u64;
+
+        fn hash(&self, chunks: &[&[u8]]) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            for chunk in chunks {
+                hasher.write(chunk);
+            }
+            hasher.finish()
+        }
+    }
+
+    let r = ReedSolomon::new(3, 2).unwrap();
+    let mut shards = make_random_shards!(100, 5);
+    r.encode(&mut shards).unwrap();
+
+    let ids: Vec<_> = shards
+        .iter()
+        .map(|shard| content::shard_id(&Sip, &r, shard))
+        .collect();
+
+    for (id, shard) in ids.iter().zip(&shards) {
+        assert!(content::verify_shard(&Sip, &r, id, shard));
+    }
+    assert!(!content::verify_shard(&Sip, &r, &ids[0], &shards[1]));
+
+    let mut corrupted = shards[4].clone();
+    corrupted[50] ^= 1;
+    assert!(!content::verify_shard(&Sip, &r, &ids[4], &corrupted));
+
+    // the geometry is part of the ID
+    let fingerprint = content::geometry_fingerprint(&r);
+    assert_eq!(&[1, 3, 0, 0, 0, 2, 0, 0, 0], &fingerprint[..9]);
+    assert_eq!(9 + 6, fingerprint.len());
+
+    for other in &[
+        ReedSolomon::new(3, 3).unwrap(),
+        ReedSolomon::new(2, 2).unwrap(),
+        ReedSolomon::builder(3, 2)
+            .matrix_kind(MatrixKind::Cauchy)
+            .build()
+            .unwrap(),
+    ] {
+        assert_ne!(fingerprint, content::geometry_fingerprint(other));
+        assert!(!content::verify_shard(&Sip, other, &ids[0], &shards[0]));
+    }
+    assert_eq!(
+        fingerprint,
+        content::geometry_fingerprint(&ReedSolomon::new(3, 2).unwrap())
+    );
+}
+
+#[cfg(feature = "shadow")]
+#[test]
+fn test_shadow() {
+    use crate::{Divergence, Shadow, ShadowOperation};
+    use std::sync::{Arc, Mutex};
+
+    // a second codec, flipping a bit of one of its outputs if told to
+    struct Secondary {
+        codec: ReedSolomon,
+        corrupt: Option<usize>,
+        divergences: Mutex<Vec<Divergence>>,
+    }
+
+    impl Shadow<galois_8::Field> for Secondary {
+        fn encode(&self, data: &[&[u8]], parity: &mut [Vec<u8>]) {
+            self.codec.encode_sep(data, parity).unwrap();
+            if let Some(i) = self.corrupt {
+                parity[i - data.len()][0] ^= 1;
+            }
+        }
+
+        fn reconstruct(&self, shards: &mut [Option<Vec<u8>>], data_only: bool) {
+            if data_only {
+                self.codec.reconstruct_data(shards).unwrap();
+            } else {
+                self.codec.reconstruct(shards).unwrap();
+            }
+            if let Some(i) = self.corrupt {
+                if let Some(shard) = &mut shards[i] {
+                    shard[0] ^= 1;
+                }
+            }
+        }
+
+        fn divergence(&self, divergence: &Divergence) {
+            self.divergences.lock().unwrap().push(divergence.clone());
+        }
+    }
+
+    let mut r = ReedSolomon::new(3, 2).unwrap();
+
+    let mut expect = make_random_shards!(100, 5);
+    r.encode(&mut expect).unwrap();
+
+    // identical implementations do not diverge
+    let secondary = Arc::new(Secondary {
+        codec: ReedSolomon::new(3, 2).unwrap(),
+        corrupt: None,
+        divergences: Mutex::new(Vec::new()),
+    });
+    r.set_shadow(Some(secondary.clone()));
+
+    let mut shards = expect.clone();
+    r.encode(&mut shards).unwrap();
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[0] = None;
+    shards[4] = None;
+    r.reconstruct(&mut shards).unwrap();
+    assert!(secondary.divergences.lock().unwrap().is_empty());
+
+    // divergences are reported, the outputs of the codec are kept
+    let secondary = Arc::new(Secondary {
+        codec: ReedSolomon::new(3, 2).unwrap(),
+        corrupt: Some(4),
+        divergences: Mutex::new(Vec::new()),
+    });
+    r.set_shadow(Some(secondary.clone()));
+
+    let mut shards = expect.clone();
+    r.encode(&mut shards).unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[0] = None;
+    shards[4] = None;
+    r.clone().reconstruct_data(&mut shards).unwrap();
+    r.reconstruct(&mut shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+    // only the rebuilt shards are compared
+    let mut shards = shards_into_option_shards(expect.clone());
+    shards[1] = None;
+    r.reconstruct(&mut shards).unwrap();
+
+    assert_eq!(
+        vec![
+            Divergence {
+                operation: ShadowOperation::Encode,
+                shards: vec![4],
+            },
+            Divergence {
+                operation: ShadowOperation::Reconstruct,
+                shards: vec![4],
+            },
+        ],
+        *secondary.divergences.lock().unwrap()
+    );
+    assert_eq!(
+        "Encode diverged from the secondary implementation in shards [4]",
+        secondary.divergences.lock().unwrap()[0].to_string()
+    );
+
+    // no longer compared once unregistered
+    r.set_shadow(None);
+    let mut shards = expect.clone();
+    r.encode(&mut shards).unwrap();
+    assert_eq!(2, secondary.divergences.lock().unwrap().len());
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn test_stats() {
+    use crate::Stats;
+
+    let r = ReedSolomon::new(10, 4).unwrap();
+    assert_eq!(Stats::default(), r.stats());
+
+    let mut shards = make_random_shards!(1024, 14);
+    r.encode(&mut shards).unwrap();
+    assert_eq!(
+        Stats {
+            bytes_read: 10 * 1024,
+            bytes_written: 4 * 1024,
+            mul_ops: 40 * 1024,
+        },
+        r.stats()
+    );
+
+    r.reset_stats();
+    assert_eq!(Stats::default(), r.stats());
+
+    let mut shards: Vec<_> = shards.into_iter().map(Some).collect();
+    shards[3] = None;
+    r.reconstruct_data(&mut shards).unwrap();
+    assert_eq!(
+        Stats {
+            bytes_read: 10 * 1024,
+            bytes_written: 1024,
+            mul_ops: 10 * 1024,
+        },
+        r.stats()
+    );
+}
+
+#[test]
+fn test_work_plan() {
+    use crate::WorkPlan;
+    use std::time::Duration;
+
+    let r = ReedSolomon::new(10, 4).unwrap();
+
+    let plan = r.encode_plan(1024);
+    assert_eq!(
+        WorkPlan {
+            mul_ops: 40 * 1024,
+            bytes_read: 10 * 1024,
+            bytes_written: 4 * 1024,
+            scratch_bytes: 0,
+        },
+        plan
+    );
+    assert_eq!(Duration::from_millis(20), plan.duration(2048 * 1000));
+    assert_eq!(Duration::MAX, plan.duration(0));
+    assert_eq!(Duration::ZERO, WorkPlan::default().duration(0));
+    assert_eq!(Duration::ZERO, WorkPlan::default().duration(1000));
+
+    let mut present = [true; 14];
+    assert_eq!(
+        WorkPlan::default(),
+        r.reconstruct_plan(&present, 1024).unwrap()
+    );
+
+    present[3] = false;
+    present[12] = false;
+    assert_eq!(
+        WorkPlan {
+            mul_ops: 20 * 1024,
+            bytes_read: 20 * 1024,
+            bytes_written: 2 * 1024,
+            scratch_bytes: 400,
+        },
+        r.reconstruct_plan(&present, 1024).unwrap()
+    );
+    assert_eq!(
+        WorkPlan {
+            mul_ops: 10 * 1024,
+            bytes_read: 10 * 1024,
+            bytes_written: 1024,
+            scratch_bytes: 400,
+        },
+        r.reconstruct_data_plan(&present, 1024).unwrap()
+    );
+
+    assert_eq!(
+        Error::InvalidShardFlags,
+        r.reconstruct_plan(&present[1..], 1024).unwrap_err()
+    );
+    for x in &mut present[..5] {
+        *x = false;
+    }
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_plan(&present, 1024).unwrap_err()
+    );
+
+    // the plan matches the work done
+    #[cfg(feature = "metrics")]
+    {
+        use crate::Stats;
+
+        let mut shards = make_random_shards!(1024, 14);
+        r.encode(&mut shards).unwrap();
+        r.reset_stats();
+
+        let mut shards = shards_to_option_shards(&shards);
+        shards[0] = None;
+        shards[7] = None;
+        shards[11] = None;
+        let present: Vec<_> = shards.iter().map(Option::is_some).collect();
+        let plan = r.reconstruct_plan(&present, 1024).unwrap();
+        r.reconstruct(&mut shards).unwrap();
+        assert_eq!(
+            Stats {
+                bytes_read: plan.bytes_read,
+                bytes_written: plan.bytes_written,
+                mul_ops: plan.mul_ops,
+            },
+            r.stats()
+        );
+    }
+}
+
+#[test]
+fn test_repair_plan() {
+    let r = ReedSolomon::new(4, 3).unwrap();
+
+    let mut present = [true; 7];
+    assert_eq!(vec![1, 5], r.repair_plan(&present, &[5, 1, 5]).unwrap());
+    assert!(r.repair_plan(&present, &[]).unwrap().is_empty());
+
+    // data shards are preferred
+    present[1] = false;
+    assert_eq!(vec![0, 2, 3, 4], r.repair_plan(&present, &[1]).unwrap());
+
+    // present wanted shards are part of the read set
+    assert_eq!(vec![0, 2, 3, 6], r.repair_plan(&present, &[1, 6]).unwrap());
+
+    present[0] = false;
+    present[4] = false;
+    assert_eq!(
+        vec![2, 3, 5, 6],
+        r.repair_plan(&present, &[0, 1, 4]).unwrap()
+    );
+
+    // the planned shards are enough to reconstruct
+    let mut expect = make_random_shards!(10, 7);
+    r.encode(&mut expect).unwrap();
+    let plan = r.repair_plan(&present, &[0, 1, 4]).unwrap();
+    let mut shards: Vec<_> = expect
+        .iter()
+        .enumerate()
+        .map(|(i, shard)| plan.contains(&i).then(|| shard.clone()))
+        .collect();
+    r.reconstruct(&mut shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_into_shards(shards));
+
+    present[2] = false;
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.repair_plan(&present, &[0]).unwrap_err()
+    );
+    assert_eq!(vec![3], r.repair_plan(&present, &[3]).unwrap());
+    assert_eq!(
+        Error::InvalidShardFlags,
+        r.repair_plan(&present[1..], &[0]).unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidIndex,
+        r.repair_plan(&[true; 7], &[7]).unwrap_err()
+    );
+}
+
+#[test]
+fn test_incremental_decoder() {
+    use crate::galois_8::IncrementalDecoder;
+
+    let r = ReedSolomon::new(4, 3).unwrap();
+
+    let mut expect = make_random_shards!(100, 7);
+    r.encode(&mut expect).unwrap();
+
+    // rebuild a data shard and a parity shard, receiving in any order
+    let mut present = [true; 7];
+    present[1] = false;
+    present[5] = false;
+    let sources = r.repair_plan(&present, &[1, 5]).unwrap();
+    let mut decoder = IncrementalDecoder::new(&r, &sources, &[5, 1]).unwrap();
+    assert_eq!(&[5, 1], decoder.targets());
+
+    for _ in 0..2 {
+        let mut order = sources.clone();
+        order.reverse();
+        order.swap(0, 2);
+
+        let mut outputs = vec![vec![0u8; 100]; 2];
+        for &i in &order {
+            assert!(!decoder.is_done());
+            decoder.push(i, &expect[i], &mut outputs).unwrap();
+        }
+        assert!(decoder.is_done());
+        assert_eq!(expect[5], outputs[0]);
+        assert_eq!(expect[1], outputs[1]);
+
+        assert_eq!(
+            SBSError::TooManyCalls,
+            decoder.push(0, &expect[0], &mut outputs).unwrap_err()
+        );
+        decoder.reset();
+    }
+
+    // reconstruction from parity shards only
+    let mut decoder = IncrementalDecoder::new(&r, &[6, 4, 5, 3], &[0, 1, 2]).unwrap();
+    let mut outputs = vec![vec![0u8; 100]; 3];
+    for &i in &[3, 6, 5, 4] {
+        decoder.push(i, &expect[i], &mut outputs).unwrap();
+    }
+    assert_eq_shards(&expect[..3], &outputs);
+
+    // invalid geometry
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        IncrementalDecoder::new(&r, &[0, 1, 2], &[3]).unwrap_err()
+    );
+    assert_eq!(
+        Error::TooManyShards { max: 4 },
+        IncrementalDecoder::new(&r, &[0, 1, 2, 3, 4], &[5]).unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidIndex,
+        IncrementalDecoder::new(&r, &[0, 1, 2, 2], &[3]).unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidIndex,
+        IncrementalDecoder::new(&r, &[0, 1, 2, 3], &[3]).unwrap_err()
+    );
+    assert_eq!(
+        Error::InvalidIndex,
+        IncrementalDecoder::new(&r, &[0, 1, 2, 3], &[7]).unwrap_err()
+    );
+
+    // invalid pushes
+    let mut decoder = IncrementalDecoder::new(&r, &[0, 1, 2, 4], &[3]).unwrap();
+    let mut outputs = vec![vec![0u8; 100]];
+    assert_eq!(
+        SBSError::RSError(Error::InvalidIndex),
+        decoder.push(3, &expect[3], &mut outputs).unwrap_err()
+    );
+    decoder.push(0, &expect[0], &mut outputs).unwrap();
+    assert_eq!(
+        SBSError::RSError(Error::InvalidIndex),
+        decoder.push(0, &expect[0], &mut outputs).unwrap_err()
+    );
+    assert_eq!(
+        SBSError::RSError(Error::TooManyBufferShards),
+        decoder
+            .push(1, &expect[1], &mut vec![vec![0u8; 100]; 2])
+            .unwrap_err()
+    );
+    assert_eq!(
+        SBSError::RSError(Error::TooFewBufferShards),
+        decoder
+            .push(1, &expect[1], &mut Vec::<Vec<u8>>::new())
+            .unwrap_err()
+    );
+    assert_eq!(
+        SBSError::RSError(Error::IncorrectShardSize {
+            shard: 0,
+            expected: 100,
+            actual: 99
+        }),
+        decoder.push(1, &expect[1][..99], &mut outputs).unwrap_err()
+    );
+    assert_eq!(
+        SBSError::RSError(Error::EmptyShard),
+        decoder.push(1, &[], &mut outputs).unwrap_err()
+    );
+    for &i in &[1, 2, 4] {
+        decoder.push(i, &expect[i], &mut outputs).unwrap();
+    }
+    assert_eq!(expect[3], outputs[0]);
+}
+
+#[test]
+fn test_verify_with_policy() {
+    use crate::{MismatchPolicy, Resolution};
+
+    let r = ReedSolomon::new(5, 4).unwrap();
+
+    let mut expect = make_random_shards!(100, 9);
+    r.encode(&mut expect).unwrap();
+
+    for &policy in &[
+        MismatchPolicy::ReportOnly,
+        MismatchPolicy::Rederive,
+        MismatchPolicy::Correct,
+        MismatchPolicy::Identify { max_corrupted: 4 },
+    ] {
+        let mut shards = expect.clone();
+        let report = r.verify_with_policy(&mut shards, policy).unwrap();
+        assert_eq!(Resolution::Consistent, report.resolution);
+        assert!(report.is_consistent());
+        assert!(report.mismatched_parity.is_empty());
+    }
+
+    // a corrupted data shard mismatches all of the parity shards
+    let mut shards = expect.clone();
+    shards[2][10] ^= 0xff;
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::ReportOnly)
+        .unwrap();
+    assert_eq!(Resolution::Reported, report.resolution);
+    assert!(!report.is_consistent());
+    assert_eq!(vec![5, 6, 7, 8], report.mismatched_parity);
+    assert!(report.candidates.is_empty());
+    assert!(!r.verify(&shards).unwrap());
+
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::Rederive)
+        .unwrap();
+    assert_eq!(Resolution::Corrected, report.resolution);
+    assert!(report.is_consistent());
+    assert_eq!(vec![vec![2]], report.candidates);
+    assert_eq_shards(&expect, &shards);
+
+    // a corrupted parity shard only mismatches itself
+    let mut shards = expect.clone();
+    shards[7][0] ^= 1;
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::Rederive)
+        .unwrap();
+    assert_eq!(vec![7], report.mismatched_parity);
+    assert_eq!(Resolution::Corrected, report.resolution);
+    assert_eq_shards(&expect, &shards);
+
+    // two corrupted shards need the full error-correction search
+    let mut shards = expect.clone();
+    shards[0][1] ^= 1;
+    shards[6][99] ^= 1;
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::Rederive)
+        .unwrap();
+    assert_eq!(Resolution::Uncorrectable, report.resolution);
+    assert!(report.candidates.is_empty());
+    assert_eq!(shards[0][1], expect[0][1] ^ 1);
+
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::Correct)
+        .unwrap();
+    assert_eq!(Resolution::Corrected, report.resolution);
+    assert_eq!(vec![vec![0, 6]], report.candidates);
+    assert_eq_shards(&expect, &shards);
+
+    // three corrupted shards can only be identified
+    let mut shards = expect.clone();
+    shards[1][5] ^= 1;
+    shards[4][0] ^= 1;
+    shards[8][50] ^= 1;
+    let corrupt = shards.clone();
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::Correct)
+        .unwrap();
+    assert_eq!(Resolution::Uncorrectable, report.resolution);
+
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::Identify { max_corrupted: 4 })
+        .unwrap();
+    assert_eq!(Resolution::Identified, report.resolution);
+    assert!(!report.is_consistent());
+    assert_eq!(vec![vec![1, 4, 8]], report.candidates);
+    assert_eq!(corrupt, shards);
+
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::Identify { max_corrupted: 2 })
+        .unwrap();
+    assert_eq!(Resolution::Uncorrectable, report.resolution);
+
+    // with a single parity shard any shard could be the corrupted one
+    let r = ReedSolomon::new(3, 1).unwrap();
+    let mut expect = make_random_shards!(10, 4);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = expect.clone();
+    shards[1][0] ^= 1;
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::Rederive)
+        .unwrap();
+    assert_eq!(Resolution::Ambiguous, report.resolution);
+    assert_eq!(vec![vec![0], vec![1], vec![2], vec![3]], report.candidates);
+    assert_eq!(shards[1][0], expect[1][0] ^ 1);
+
+    let report = r
+        .verify_with_policy(&mut shards, MismatchPolicy::Correct)
+        .unwrap();
+    assert_eq!(Resolution::Uncorrectable, report.resolution);
+
+    assert_eq!(
+        Error::TooFewShards,
+        r.verify_with_policy(&mut shards[1..], MismatchPolicy::Correct)
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_reconstruct_verified() {
+    let r = ReedSolomon::new(5, 4).unwrap();
+
+    let mut expect = make_random_shards!(100, 9);
+    r.encode(&mut expect).unwrap();
+    let expect = shards_into_option_shards(expect);
+
+    // consistent shards are only reconstructed
+    let mut shards = expect.clone();
+    shards[1] = None;
+    shards[6] = None;
+    assert!(r.reconstruct_verified(&mut shards).unwrap().is_empty());
+    assert_eq!(expect, shards);
+
+    // a corrupted shard is left out, then overwritten
+    let mut shards = expect.clone();
+    shards[1] = None;
+    shards[3].as_mut().unwrap()[7] ^= 1;
+    assert_eq!(vec![3], r.reconstruct_verified(&mut shards).unwrap());
+    assert_eq!(expect, shards);
+
+    let mut shards = expect.clone();
+    shards[8] = None;
+    shards[0].as_mut().unwrap()[0] ^= 1;
+    shards[7].as_mut().unwrap()[99] ^= 1;
+    assert_eq!(vec![0, 7], r.reconstruct_verified(&mut shards).unwrap());
+    assert_eq!(expect, shards);
+
+    // no subset to check against is left
+    let mut shards = expect.clone();
+    shards[0] = None;
+    shards[1] = None;
+    shards[2] = None;
+    shards[4].as_mut().unwrap()[0] ^= 1;
+    let corrupt = shards.clone();
+    assert_eq!(
+        Error::InconsistentShards,
+        r.reconstruct_verified(&mut shards).unwrap_err()
+    );
+    assert_eq!(corrupt, shards);
+
+    // nothing to check against, so the corruption spreads
+    shards[3] = None;
+    assert!(r.reconstruct_verified(&mut shards).unwrap().is_empty());
+    assert_ne!(expect, shards);
+
+    let mut shards = expect;
+    for shard in &mut shards[..5] {
+        *shard = None;
+    }
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_verified(&mut shards).unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewShards,
+        r.reconstruct_verified(&mut shards[1..]).unwrap_err()
+    );
+}
+
+#[test]
+fn test_checksums() {
+    use crate::crc32c::Crc32c;
+
+    let r = ReedSolomon::new(10, 4).unwrap();
+
+    // spanning several cache blocks
+    let mut expect = make_random_shards!(100_000, 14);
+    let mut shards = expect.clone();
+    r.encode(&mut expect).unwrap();
+
+    let checksums = r.encode_with_checksums(&mut shards).unwrap();
+    assert_eq!(expect, shards);
+    let second_pass: Vec<_> = expect
+        .iter()
+        .map(|shard| {
+            let mut crc = Crc32c::new();
+            crc.update(shard);
+            crc.value()
+        })
+        .collect();
+    assert_eq!(second_pass, checksums);
+
+    let expect = shards_into_option_shards(expect);
+
+    let mut shards = expect.clone();
+    assert!(r
+        .reconstruct_with_checksums(&mut shards, &checksums)
+        .unwrap()
+        .is_empty());
+    assert_eq!(expect, shards);
+
+    // corrupted shards are rebuilt along with the missing ones
+    let mut shards = expect.clone();
+    shards[2] = None;
+    shards[12] = None;
+    shards[0].as_mut().unwrap()[99_999] ^= 1;
+    shards[11].as_mut().unwrap()[0] ^= 1;
+    assert_eq!(
+        vec![0, 11],
+        r.reconstruct_with_checksums(&mut shards, &checksums)
+            .unwrap()
+    );
+    assert_eq!(expect, shards);
+
+    let mut shards = expect;
+    shards[2] = None;
+    shards[12] = None;
+    shards[13] = None;
+    shards[0].as_mut().unwrap()[0] ^= 1;
+    shards[1].as_mut().unwrap()[0] ^= 1;
+    let corrupt = shards.clone();
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        r.reconstruct_with_checksums(&mut shards, &checksums)
+            .unwrap_err()
+    );
+    assert_eq!(corrupt, shards);
+    assert_eq!(
+        Error::TooFewShards,
+        r.reconstruct_with_checksums(&mut shards, &checksums[1..])
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_format() {
+    use crate::content;
+    use crate::format::{self, Format, Header, Support};
+
+    let formats = [
+        Format::Shards,
+        Format::InversionCache,
+        Format::GeometryFingerprint,
+        Format::Config,
+    ];
+    for &format in &formats {
+        let header = Header::current(format);
+        assert_eq!(Support::Current, format::support(header));
+        assert!(format::is_compatible(header));
+        assert!(format.min_supported_version() <= format.version());
+
+        // versions from the future are not readable
+        let newer = Header {
+            version: format.version() + 1,
+            ..header
+        };
+        assert_eq!(Support::Unsupported, format::support(newer));
+        assert!(!format::is_compatible(newer));
+
+        let removed = Header {
+            version: format.min_supported_version() - 1,
+            ..header
+        };
+        assert_eq!(Support::Unsupported, format::support(removed));
+    }
+
+    let r = ReedSolomon::new(3, 2).unwrap();
+    assert_eq!(
+        Some(Header::current(Format::InversionCache)),
+        Header::read(Format::InversionCache, &r.export_inversion_cache())
+    );
+    assert_eq!(
+        Some(Header::current(Format::GeometryFingerprint)),
+        Header::read(
+            Format::GeometryFingerprint,
+            &content::geometry_fingerprint(&r)
+        )
+    );
+    assert_eq!(None, Header::read(Format::InversionCache, &[]));
+    assert_eq!(None, Header::read(Format::Shards, &[1]));
+    assert_eq!(None, Header::read(Format::Config, &[1]));
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_mmap_files() {
+    use crate::mmap;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{ErrorKind, Write};
+
+    let dir = std::env::temp_dir().join(format!("rse-mmap-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let open = |i: usize| {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(i.to_string()))
+            .unwrap()
+    };
+    let truncate = |file: &File| file.set_len(0).unwrap();
+
+    let r = ReedSolomon::new(3, 2).unwrap();
+    let mut expect = make_random_shards!(5000, 5);
+    r.encode(&mut expect).unwrap();
+
+    let files: Vec<File> = (0..5).map(open).collect();
+    for (file, shard) in files.iter().zip(&expect[..3]) {
+        (&*file).write_all(shard).unwrap();
+    }
+
+    mmap::encode_files(&r, &files[..3], &files[3..]).unwrap();
+    let read_all = || -> Vec<Vec<u8>> {
+        (0..5)
+            .map(|i| fs::read(dir.join(i.to_string())).unwrap())
+            .collect()
+    };
+    assert_eq_shards(&expect, &read_all());
+
+    truncate(&files[1]);
+    truncate(&files[4]);
+    mmap::reconstruct_data_files(&r, &files, &[true, false, true, true, false]).unwrap();
+    let shards = read_all();
+    assert_eq!(expect[1], shards[1]);
+    assert!(shards[4].is_empty());
+
+    mmap::reconstruct_files(&r, &files, &[true, true, true, true, false]).unwrap();
+    assert_eq_shards(&expect, &read_all());
+
+    // nothing is touched on error
+    truncate(&files[0]);
+    truncate(&files[1]);
+    truncate(&files[2]);
+    let err = mmap::reconstruct_files(&r, &files, &[false, false, false, true, true]).unwrap_err();
+    assert_eq!(ErrorKind::InvalidInput, err.kind());
+    assert_eq!(
+        Some(&Error::TooFewShardsPresent),
+        err.get_ref().and_then(|x| x.downcast_ref())
+    );
+    assert!(read_all()[..3].iter().all(Vec::is_empty));
+
+    let err = mmap::encode_files(&r, &files[..3], &files[3..]).unwrap_err();
+    assert_eq!(
+        Some(&Error::EmptyShard),
+        err.get_ref().and_then(|x| x.downcast_ref())
+    );
+    let err = mmap::encode_files(&r, &files[..2], &files[2..]).unwrap_err();
+    assert_eq!(
+        Some(&Error::TooFewDataShards),
+        err.get_ref().and_then(|x| x.downcast_ref())
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "gpu")]
+#[test]
+fn test_gpu() {
+    use crate::gpu::GpuCodec;
+
+    let r = ReedSolomon::new(10, 4).unwrap();
+    let gpu = match GpuCodec::new(r.clone()) {
+        Some(gpu) => gpu.min_stripe_bytes(0),
+        None => return,
+    };
+
+    // a length which does not fill the last word
+    let mut expect = make_random_shards!(10_003, 14);
+    r.encode(&mut expect).unwrap();
+    let mut shards = expect.clone();
+    for shard in &mut shards[10..] {
+        shard.fill(0);
+    }
+    gpu.encode(&mut shards).unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    let mut option_shards = shards_to_option_shards(&shards);
+    for &i in &[0, 4, 9, 12] {
+        option_shards[i] = None;
+    }
+    gpu.reconstruct_data(&mut option_shards).unwrap();
+    assert_eq!(expect[4], *option_shards[4].as_ref().unwrap());
+    assert!(option_shards[12].is_none());
+    gpu.reconstruct(&mut option_shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_to_shards(&option_shards));
+
+    // the parity and data shards rebuilt in one pass
+    let mut option_shards = shards_to_option_shards(&shards);
+    for &i in &[1, 10, 11, 13] {
+        option_shards[i] = None;
+    }
+    gpu.reconstruct(&mut option_shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_to_shards(&option_shards));
+
+    // small stripes stay on the CPU, with the same results
+    let cpu = GpuCodec::new(r).unwrap();
+    let mut option_shards = shards_to_option_shards(&shards);
+    option_shards[3] = None;
+    cpu.reconstruct(&mut option_shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_to_shards(&option_shards));
+
+    let mut option_shards = shards_to_option_shards(&shards);
+    for shard in &mut option_shards[..5] {
+        *shard = None;
+    }
+    assert_eq!(
+        Err(Error::TooFewShardsPresent),
+        gpu.reconstruct(&mut option_shards)
+    );
+    assert_eq!(Err(Error::TooFewShards), gpu.encode(&mut shards[..13]));
+    shards[2].pop();
+    assert_eq!(
+        Err(Error::IncorrectShardSize {
+            shard: 2,
+            expected: 10_003,
+            actual: 10_002
+        }),
+        gpu.encode(&mut shards)
+    );
+}
+
+#[cfg(any(feature = "gpu", feature = "cuda"))]
+#[test]
+fn test_offload_fallback() {
+    use crate::offload::{self, Offload};
+
+    // a device which fails halfway through
+    struct Failing;
+
+    impl Offload for Failing {
+        fn code(&self, _: &[Vec<u8>], _: &[&[u8]], outputs: &mut [&mut [u8]]) -> bool {
+            outputs[0].fill(0xff);
+            false
+        }
+    }
+
+    let r = ReedSolomon::new(10, 4).unwrap();
+    let mut expect = make_random_shards!(1_000, 14);
+    r.encode(&mut expect).unwrap();
+
+    let mut shards = expect.clone();
+    for shard in &mut shards[10..] {
+        shard.fill(0);
+    }
+    offload::encode(&r, 0, &Failing, &mut shards).unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    let mut option_shards = shards_to_option_shards(&shards);
+    for &i in &[1, 10, 11, 13] {
+        option_shards[i] = None;
+    }
+    offload::reconstruct(&r, 0, &Failing, &mut option_shards, false).unwrap();
+    assert_eq_shards(&expect, &option_shards_to_shards(&option_shards));
+}
+
+#[cfg(feature = "cuda")]
+#[test]
+fn test_cuda() {
+    use crate::cuda::CudaCodec;
+
+    let r = ReedSolomon::new(10, 4).unwrap();
+    let cuda = match CudaCodec::new(r.clone()) {
+        Some(cuda) => cuda.min_stripe_bytes(0),
+        None => return,
+    };
+
+    let mut expect = make_random_shards!(10_003, 14);
+    r.encode(&mut expect).unwrap();
+    let mut shards = expect.clone();
+    for shard in &mut shards[10..] {
+        shard.fill(0);
+    }
+    cuda.encode(&mut shards).unwrap();
+    assert_eq_shards(&expect, &shards);
+
+    let mut option_shards = shards_to_option_shards(&shards);
+    for &i in &[1, 4, 10, 13] {
+        option_shards[i] = None;
+    }
+    cuda.reconstruct_data(&mut option_shards).unwrap();
+    assert_eq!(expect[4], *option_shards[4].as_ref().unwrap());
+    assert!(option_shards[10].is_none());
+    cuda.reconstruct(&mut option_shards).unwrap();
+    assert_eq_shards(&expect, &option_shards_to_shards(&option_shards));
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn test_ffi() {
+    use crate::ffi::*;
+    use std::ptr;
+
+    let mut expect = make_random_shards!(100, 5);
+    ReedSolomon::new(3, 2).unwrap().encode(&mut expect).unwrap();
+
+    unsafe {
+        assert!(rse_codec_new(0, 1).is_null());
+        let codec = rse_codec_new(3, 2);
+        assert!(!codec.is_null());
+
+        let mut shards = expect.clone();
+        for x in shards[3..].iter_mut().flatten() {
+            *x = 0;
+        }
+        let mut ptrs: Vec<*mut u8> = shards.iter_mut().map(Vec::as_mut_ptr).collect();
+        assert_eq!(RseStatus::Ok, rse_encode(codec, ptrs.as_ptr(), 5, 100));
+        assert_eq_shards(&expect, &shards);
+
+        let mut correct = false;
+        let const_ptrs: Vec<*const u8> = shards.iter().map(Vec::as_ptr).collect();
+        assert_eq!(
+            RseStatus::Ok,
+            rse_verify(
+                codec,
+                const_ptrs.as_ptr(),
+                5,
+                100,
+                ptr::addr_of_mut!(correct)
+            )
+        );
+        assert!(correct);
+
+        shards[0] = vec![0; 100];
+        shards[4] = vec![0; 100];
+        ptrs = shards.iter_mut().map(Vec::as_mut_ptr).collect();
+        let present = [false, true, true, true, false];
+        assert_eq!(
+            RseStatus::Ok,
+            rse_reconstruct_data(codec, ptrs.as_ptr(), present.as_ptr(), 5, 100)
+        );
+        assert_eq!(expect[0], shards[0]);
+        assert_eq!(vec![0; 100], shards[4]);
+        assert_eq!(
+            RseStatus::Ok,
+            rse_reconstruct(codec, ptrs.as_ptr(), present.as_ptr(), 5, 100)
+        );
+        assert_eq_shards(&expect, &shards);
+
+        assert_eq!(
+            RseStatus::TooFewShards,
+            rse_encode(codec, ptrs.as_ptr(), 4, 100)
+        );
+        assert_eq!(
+            RseStatus::NullPointer,
+            rse_encode(codec, ptr::null(), 5, 100)
+        );
+        assert_eq!(
+            RseStatus::NullPointer,
+            rse_encode(ptr::null(), ptrs.as_ptr(), 5, 100)
+        );
+
+        let mut buf = [0 as std::os::raw::c_char; 8];
+        let message = Error::TooFewShards.to_string();
+        assert_eq!(
+            message.len(),
+            rse_status_message(RseStatus::TooFewShards as u32, buf.as_mut_ptr(), buf.len())
+        );
+        let truncated: Vec<u8> = buf.iter().map(|&x| x as u8).collect();
+        assert_eq!(&message.as_bytes()[..7], &truncated[..7]);
+        assert_eq!(0, truncated[7]);
+        assert_eq!(
+            "Success".len(),
+            rse_status_message(RseStatus::Ok as u32, ptr::null_mut(), 0)
+        );
+        assert_eq!(
+            "Unknown status".len(),
+            rse_status_message(99, ptr::null_mut(), 0)
+        );
+        assert_eq!(
+            Error::UnsupportedPlatform.to_string().len(),
+            rse_status_message(RseStatus::UnsupportedPlatform as u32, ptr::null_mut(), 0)
+        );
+        assert_eq!(
+            "The call panicked".len(),
+            rse_status_message(RseStatus::Panicked as u32, ptr::null_mut(), 0)
+        );
+
+        rse_codec_free(codec);
+        rse_codec_free(ptr::null_mut());
+    }
+}
+
+#[test]
+#[cfg(feature = "kvdemo")]
+fn test_kvdemo() {
+    use crate::kvdemo::{KvStore, MemoryBackend, ShardBackend};
+    use std::io::ErrorKind;
+
+    let mut store = KvStore::new(ReedSolomon::new(3, 2).unwrap(), MemoryBackend::default());
+
+    let values: Vec<Vec<u8>> = [0, 1, 2, 3, 100, 1000]
+        .iter()
+        .map(|&len| (0..len).map(|_| rand::random()).collect())
+        .collect();
+    for (i, value) in values.iter().enumerate() {
+        store.put(&[i as u8], value).unwrap();
+    }
+    assert_eq!(values.len() * 5, store.backend().len());
+    assert_eq!(
+        (0..values.len() as u8).map(|i| vec![i]).collect::<Vec<_>>(),
+        store.keys().map(<[u8]>::to_vec).collect::<Vec<_>>()
+    );
+
+    for (i, value) in values.iter().enumerate() {
+        assert_eq!(Some(value), store.get(&[i as u8]).unwrap().as_ref());
+    }
+    assert_eq!(None, store.get(b"missing").unwrap());
+
+    // lose a data shard and corrupt a parity shard of each value
+    for i in 0..values.len() as u8 {
+        let stripe = store.manifest(&[i]).unwrap().stripe;
+        store.backend_mut().delete(stripe, 1).unwrap();
+        store.backend_mut().shard_mut(stripe, 3).unwrap()[0] ^= 1;
+    }
+    for (i, value) in values.iter().enumerate() {
+        assert_eq!(Some(value), store.get(&[i as u8]).unwrap().as_ref());
+    }
+
+    // lose too many shards of one value
+    let stripe = store.manifest(&[5]).unwrap().stripe;
+    store.backend_mut().delete(stripe, 0).unwrap();
+    let e = store.get(&[5]).unwrap_err();
+    assert_eq!(ErrorKind::InvalidInput, e.kind());
+    assert_eq!(
+        Some(&Error::TooFewShardsPresent),
+        e.get_ref().and_then(|e| e.downcast_ref::<Error>())
+    );
+
+    let report = store.repair_all();
+    assert_eq!(
+        (0..5).map(|i| (vec![i], vec![1, 3])).collect::<Vec<_>>(),
+        report.repaired
+    );
+    assert_eq!(1, report.failed.len());
+    assert_eq!(vec![5], report.failed[0].0);
+
+    assert!(store.repair(&[0]).unwrap().is_empty());
+    for i in 0..5 {
+        let stripe = store.manifest(&[i]).unwrap().stripe;
+        for shard in 0..5 {
+            store.backend_mut().delete(stripe, shard).unwrap();
+            assert_eq!(Some(&values[i as usize]), store.get(&[i]).unwrap().as_ref());
+            assert_eq!(vec![shard], store.repair(&[i]).unwrap());
+        }
+    }
+
+    // replacing and deleting values drops their old shards
+    store.put(&[0], b"new").unwrap();
+    assert_eq!(Some(b"new".to_vec()), store.get(&[0]).unwrap());
+    // the two lost shards of value 5 are still missing
+    assert_eq!(values.len() * 5 - 2, store.backend().len());
+
+    assert!(store.delete(&[5]).unwrap());
+    assert!(!store.delete(&[5]).unwrap());
+    assert_eq!(None, store.get(&[5]).unwrap());
+    assert_eq!(
+        ErrorKind::InvalidInput,
+        store.repair(&[5]).unwrap_err().kind()
+    );
+    assert_eq!((values.len() - 1) * 5, store.backend().len());
+}
+
+#[test]
+fn test_additive_fft() {
+    let fft = |data_shards, parity_shards| {
+        ReedSolomon::builder(data_shards, parity_shards)
+            .matrix_kind(MatrixKind::AdditiveFft)
+            .build()
+    };
+
+    // the same code as the Vandermonde construction
+    // with a power of 2 of data shards
+    assert_eq!(ReedSolomon::new(16, 5).unwrap(), fft(16, 5).unwrap());
+    assert_ne!(ReedSolomon::new(10, 4).unwrap(), fft(10, 4).unwrap());
+
+    // small codecs use the encoding matrix, large ones the FFT
+    for &(data_shards, parity_shards) in &[(10, 4), (3, 9), (100, 100), (128, 128), (20, 200)] {
+        let r = fft(data_shards, parity_shards).unwrap();
+        let total_shards = data_shards + parity_shards;
+
+        let mut expect = make_random_shards!(30, total_shards);
+        r.encode(&mut expect).unwrap();
+        // `verify` always uses the encoding matrix
+        assert!(r.verify(&expect).unwrap());
+
+        // few and many missing shards, including data shards
+        for &missing in &[1, parity_shards / 2, parity_shards] {
+            let mut shards = shards_to_option_shards(&expect);
+            for i in 0..missing {
+                shards[i * total_shards / parity_shards % total_shards] = None;
+            }
+            r.reconstruct(&mut shards).unwrap();
+            assert_eq_shards(&expect, &option_shards_to_shards(&shards));
+
+            let mut shards = shards_to_option_shards(&expect);
+            for shard in shards.iter_mut().rev().take(missing - 1) {
+                *shard = None;
+            }
+            shards[0] = None;
+            r.reconstruct_data(&mut shards).unwrap();
+            assert_eq!(Some(&expect[0]), shards[0].as_ref());
+        }
+    }
+
+    // 100 data shards are padded to 128
+    assert!(fft(100, 128).is_ok());
+    assert_eq!(
+        Error::TooManyShards { max: 228 },
+        fft(100, 129).unwrap_err()
+    );
+    assert_eq!(Error::TooManyShards { max: 200 }, fft(200, 1).unwrap_err());
+    // non-systematic codecs ignore the matrix kind
+    assert!(ReedSolomon::builder(200, 1)
+        .matrix_kind(MatrixKind::AdditiveFft)
+        .systematic(false)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn test_additive_fft_config_grow_parity() {
+    let r = ReedSolomon::builder(100, 50)
+        .matrix_kind(MatrixKind::AdditiveFft)
+        .build()
+        .unwrap();
+
+    let config = r.config().unwrap();
+    assert_eq!(MatrixKind::AdditiveFft, config.matrix_kind);
+    assert_eq!(r, config.build::<galois_8::Field>().unwrap());
+
+    let grown = r.grow_parity(100).unwrap();
+    assert_eq!(MatrixKind::AdditiveFft, grown.config().unwrap().matrix_kind);
+    assert_eq!(
+        Error::TooManyShards { max: 228 },
+        r.grow_parity(129).unwrap_err()
+    );
+
+    let mut shards = make_random_shards!(10, 150);
+    r.encode(&mut shards).unwrap();
+    shards.extend(vec![vec![0u8; 10]; 50]);
+    {
+        let (data, parity) = shards.split_at_mut(100);
+        grown
+            .encode_parity_from(50, data, &mut parity[50..])
+            .unwrap();
+    }
+    assert!(grown.verify(&shards).unwrap());
+
+    let frozen = grown.freeze().thaw();
+    let mut option_shards = shards_to_option_shards(&shards);
+    for shard in option_shards.iter_mut().take(100) {
+        *shard = None;
+    }
+    frozen.reconstruct(&mut option_shards).unwrap();
+    assert_eq_shards(&shards, &option_shards_to_shards(&option_shards));
+}
+
+#[test]
+fn test_corrector() {
+    let codecs = [
+        ReedSolomon::new(10, 6).unwrap(),
+        ReedSolomon::new(1, 4).unwrap(),
+        ReedSolomon::new(100, 30).unwrap(),
+        ReedSolomon::builder(10, 6)
+            .matrix_kind(MatrixKind::Cauchy)
+            .build()
+            .unwrap(),
+        ReedSolomon::builder(10, 6)
+            .systematic(false)
+            .build()
+            .unwrap(),
+        ReedSolomon::builder(8, 6)
+            .matrix_kind(MatrixKind::AdditiveFft)
+            .build()
+            .unwrap(),
+    ];
+
+    let mut rng = thread_rng();
+    for r in &codecs {
+        let corrector = Corrector::new(r).unwrap();
+        let total = r.total_shard_count();
+        let parity = r.parity_shard_count();
+
+        for missing in 0..=parity {
+            let errors = corrector.max_errors(missing);
+            assert_eq!((parity - missing) / 2, errors);
+
+            let mut expect = make_random_shards!(100, total);
+            r.encode(&mut expect).unwrap();
+
+            let mut indices: Vec<usize> = (0..total).collect();
+            indices.shuffle(&mut rng);
+            let mut corrupted = indices[missing..missing + errors].to_vec();
+            corrupted.sort_unstable();
+
+            let mut shards: Vec<_> = expect.iter().cloned().map(Some).collect();
+            for &i in &indices[..missing] {
+                shards[i] = None;
+            }
+            for &i in &corrupted {
+                let shard = shards[i].as_mut().unwrap();
+                // at least one position of each shard, some shared
+                shard[0] ^= 1;
+                for _ in 0..10 {
+                    shard[rng.gen_range(0..100)] ^= rng.gen_range(1..=255);
+                }
+            }
+
+            assert_eq!(corrupted, corrector.correct(&mut shards).unwrap());
+            let shards: Vec<_> = shards.into_iter().flatten().collect();
+            assert_eq_shards(&expect, &shards);
+        }
+    }
+}
+
+#[test]
+fn test_corrector_errors() {
+    let r = ReedSolomon::new(4, 2).unwrap();
+    let corrector = Corrector::new(&r).unwrap();
+
+    let mut expect = make_random_shards!(100, 6);
+    r.encode(&mut expect).unwrap();
+    let expect: Vec<_> = expect.into_iter().map(Some).collect();
+
+    // nothing to correct
+    let mut shards = expect.clone();
+    assert!(corrector.correct(&mut shards).unwrap().is_empty());
+    assert_eq!(expect, shards);
+
+    // too many errors are either detected, leaving the shards untouched,
+    // or miscorrected into a consistent stripe
+    let mut rng = thread_rng();
+    for _ in 0..20 {
+        let mut shards = expect.clone();
+        for shard in &mut shards[..2] {
+            shard.as_mut().unwrap()[0] ^= rng.gen_range(1..=255);
+        }
+        let corrupt = shards.clone();
+
+        match corrector.correct(&mut shards) {
+            Ok(corrected) => {
+                assert_eq!(1, corrected.len());
+                let shards: Vec<_> = shards.into_iter().flatten().collect();
+                assert!(r.verify(&shards).unwrap());
+            }
+            Err(e) => {
+                assert_eq!(Error::InconsistentShards, e);
+                assert_eq!(corrupt, shards);
+            }
+        }
+    }
+
+    let mut shards = expect.clone();
+    shards[0] = None;
+    shards[1] = None;
+    shards[2] = None;
+    assert_eq!(
+        Error::TooFewShardsPresent,
+        corrector.correct(&mut shards).unwrap_err()
+    );
+    assert_eq!(
+        Error::TooFewShards,
+        corrector.correct(&mut expect.clone()[1..]).unwrap_err()
+    );
+    let mut shards = expect;
+    shards[3].as_mut().unwrap().pop();
+    assert_eq!(
+        Error::IncorrectShardSize {
+            shard: 3,
+            expected: 100,
+            actual: 99
+        },
+        corrector.correct(&mut shards).unwrap_err()
+    );
+
+    // not a Reed-Solomon code at the points of the shards
+    let r = ReedSolomon::builder(5, 3)
+        .matrix_kind(MatrixKind::AdditiveFft)
+        .build()
+        .unwrap();
+    assert_eq!(Error::InvalidMatrix, Corrector::new(&r).unwrap_err());
+    let r = ReedSolomon::new_with_matrix(3, 2, &[[1, 1, 1], [1, 2, 3]]).unwrap();
+    assert_eq!(Error::InvalidMatrix, Corrector::new(&r).unwrap_err());
+    let r = ReedSolomon::new(200, 56).unwrap();
+    assert_eq!(
+        Error::TooManyShards { max: 255 },
+        Corrector::new(&r).unwrap_err()
+    );
+}
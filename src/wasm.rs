@@ -0,0 +1,190 @@
+//! JavaScript interface over GF(2^8), enabled by the `wasm` feature, for
+//! modules built with wasm-bindgen.
+//!
+//! Shards are passed as an `Array` of `Uint8Array`s of equal length. The
+//! memory of JavaScript is not shared with the module, so each shard the
+//! codec reads is copied in once, and each shard it produces is copied out
+//! once, whereas shards the codec does not need are never copied.
+//!
+//! Errors of the codec are thrown as `Error`s named `ReedSolomonError`
+//! with the message of the `Error`, and shards which are not `Uint8Array`s
+//! as `TypeError`s.
+//!
+//! # Example
+//!
+//! ```js
+//! const codec = new Codec(3, 2);
+//! const shards = [...Array(5)].map(() => new Uint8Array(1024));
+//! // fill the data shards shards[0..3]
+//! codec.encode(shards);
+//!
+//! shards[0] = null;
+//! shards[4] = undefined;
+//! codec.reconstruct(shards);
+//! ```
+
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::galois_8::ReedSolomon;
+use crate::Error;
+
+fn error(e: Error) -> JsValue {
+    let error = js_sys::Error::new(&e.to_string());
+    error.set_name("ReedSolomonError");
+    error.into()
+}
+
+// Shard `i` of `shards`, or `None` if it is `null` or `undefined`.
+fn shard(shards: &Array, i: u32) -> Result<Option<Uint8Array>, JsValue> {
+    let shard = shards.get(i);
+    if shard.is_null() || shard.is_undefined() {
+        return Ok(None);
+    }
+
+    shard
+        .dyn_into::<Uint8Array>()
+        .map(Some)
+        .map_err(|_| js_sys::TypeError::new(&format!("Shard {i} is not a Uint8Array")).into())
+}
+
+/// A codec over GF(2^8), see `ReedSolomon`.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct Codec(ReedSolomon);
+
+#[wasm_bindgen]
+impl Codec {
+    /// Creates a codec with `data_shards` data shards and `parity_shards`
+    /// parity shards, see `ReedSolomon::new`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self, JsValue> {
+        ReedSolomon::new(data_shards, parity_shards)
+            .map(Self)
+            .map_err(error)
+    }
+
+    #[wasm_bindgen(getter, js_name = dataShardCount)]
+    pub fn data_shard_count(&self) -> usize {
+        self.0.data_shard_count()
+    }
+
+    #[wasm_bindgen(getter, js_name = parityShardCount)]
+    pub fn parity_shard_count(&self) -> usize {
+        self.0.parity_shard_count()
+    }
+
+    #[wasm_bindgen(getter, js_name = totalShardCount)]
+    pub fn total_shard_count(&self) -> usize {
+        self.0.total_shard_count()
+    }
+
+    // The shards of `shards`, which must match the codec.
+    fn shards(&self, shards: &Array) -> Result<Vec<Option<Uint8Array>>, JsValue> {
+        let count = shards.length() as usize;
+        if count < self.0.total_shard_count() {
+            return Err(error(Error::TooFewShards));
+        }
+        if count > self.0.total_shard_count() {
+            return Err(error(Error::TooManyShards {
+                max: self.0.total_shard_count(),
+            }));
+        }
+
+        (0..shards.length()).map(|i| shard(shards, i)).collect()
+    }
+
+    /// Constructs the parity shards of `shards` in place, see
+    /// `ReedSolomon::encode`. Parity shards which are `null` or `undefined`
+    /// are replaced with new `Uint8Array`s.
+    ///
+    /// Only the data shards are copied into the module.
+    pub fn encode(&self, shards: &Array) -> Result<(), JsValue> {
+        let slots = self.shards(shards)?;
+        let (data, parity) = slots.split_at(self.0.data_shard_count());
+
+        let data = data
+            .iter()
+            .map(|shard| shard.as_ref().map(Uint8Array::to_vec))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| error(Error::TooFewShardsPresent))?;
+        let shard_len = data[0].len();
+        for (i, shard) in parity.iter().enumerate() {
+            if let Some(len) = shard.as_ref().map(|shard| shard.length() as usize) {
+                if len != shard_len {
+                    return Err(error(Error::IncorrectShardSize {
+                        shard: data.len() + i,
+                        expected: shard_len,
+                        actual: len,
+                    }));
+                }
+            }
+        }
+
+        let mut output = vec![vec![0; shard_len]; parity.len()];
+        self.0.encode_sep(&data, &mut output).map_err(error)?;
+
+        for (i, (shard, output)) in parity.iter().zip(&output).enumerate() {
+            match shard {
+                Some(shard) => shard.copy_from(output),
+                None => shards.set(
+                    (data.len() + i) as u32,
+                    Uint8Array::from(&output[..]).into(),
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the parity shards of `shards` are correct, see
+    /// `ReedSolomon::verify`.
+    pub fn verify(&self, shards: &Array) -> Result<bool, JsValue> {
+        let shards = self
+            .shards(shards)?
+            .into_iter()
+            .map(|shard| shard.as_ref().map(Uint8Array::to_vec))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| error(Error::TooFewShardsPresent))?;
+
+        self.0.verify(&shards).map_err(error)
+    }
+
+    /// Reconstructs the shards of `shards` which are `null` or `undefined`,
+    /// replacing them with new `Uint8Array`s, see `ReedSolomon::reconstruct`.
+    pub fn reconstruct(&self, shards: &Array) -> Result<(), JsValue> {
+        self.reconstruct_internal(shards, false)
+    }
+
+    /// Reconstructs the data shards of `shards` which are `null` or
+    /// `undefined`, see `reconstruct`. Missing parity shards are left as is.
+    #[wasm_bindgen(js_name = reconstructData)]
+    pub fn reconstruct_data(&self, shards: &Array) -> Result<(), JsValue> {
+        self.reconstruct_internal(shards, true)
+    }
+
+    fn reconstruct_internal(&self, shards: &Array, data_only: bool) -> Result<(), JsValue> {
+        let mut slots: Vec<Option<Vec<u8>>> = self
+            .shards(shards)?
+            .iter()
+            .map(|shard| shard.as_ref().map(Uint8Array::to_vec))
+            .collect();
+        let missing: Vec<bool> = slots.iter().map(Option::is_none).collect();
+
+        let result = if data_only {
+            self.0.reconstruct_data(&mut slots)
+        } else {
+            self.0.reconstruct(&mut slots)
+        };
+        result.map_err(error)?;
+
+        for (i, slot) in slots.iter().enumerate() {
+            if let (true, Some(shard)) = (missing[i], slot) {
+                shards.set(i as u32, Uint8Array::from(&shard[..]).into());
+            }
+        }
+
+        Ok(())
+    }
+}